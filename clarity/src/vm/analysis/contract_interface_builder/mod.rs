@@ -18,6 +18,7 @@ use std::collections::{BTreeMap, BTreeSet};
 
 use stacks_common::types::StacksEpochId;
 
+use crate::vm::analysis::cost_bound_checker::CostBound;
 use crate::vm::analysis::types::ContractAnalysis;
 use crate::vm::analysis::CheckResult;
 use crate::vm::types::signatures::CallableSubtype;
@@ -49,6 +50,7 @@ pub fn build_contract_interface(
         contract_identifier: _,
         type_map: _,
         cost_track: _,
+        diagnostics: _,
         contract_interface: _,
         is_cost_contract_eligible: _,
     } = contract_analysis;
@@ -261,6 +263,9 @@ pub struct ContractInterfaceFunction {
     pub access: ContractInterfaceFunctionAccess,
     pub args: Vec<ContractInterfaceFunctionArg>,
     pub outputs: ContractInterfaceFunctionOutput,
+    /// The worst-case cost of calling a public or read-only function, if it was computed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_bound: Option<CostBound>,
 }
 
 impl ContractInterfaceFunction {
@@ -273,6 +278,7 @@ impl ContractInterfaceFunction {
                 Ok(ContractInterfaceFunction {
                     name: name.clone().into(),
                     access: access.to_owned(),
+                    cost_bound: None,
                     outputs: ContractInterfaceFunctionOutput {
                         type_f: match function_type {
                             FunctionType::Fixed(FixedFunction { returns, .. }) => {
@@ -400,6 +406,13 @@ impl ContractInterface {
         }
     }
 
+    /// Report the worst-case cost of each function that has a bound in `cost_bounds`
+    pub fn set_cost_bounds(&mut self, cost_bounds: &BTreeMap<ClarityName, CostBound>) {
+        for function in self.functions.iter_mut() {
+            function.cost_bound = cost_bounds.get(function.name.as_str()).cloned();
+        }
+    }
+
     pub fn serialize(&self) -> CheckResult<String> {
         serde_json::to_string(self).map_err(|_| {
             CheckErrors::Expects("Failed to serialize contract interface".into()).into()
@@ -0,0 +1,679 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use hashbrown::HashMap;
+use stacks_common::types::StacksEpochId;
+
+use super::type_checker::contexts::TypeMap;
+use crate::vm::analysis::types::ContractAnalysis;
+use crate::vm::callables::CallableType;
+use crate::vm::costs::cost_functions::ClarityCostFunction;
+use crate::vm::costs::{CostErrors, CostTracker, ExecutionCost};
+use crate::vm::functions::define::DefineFunctionsParsed;
+use crate::vm::functions::{lookup_reserved_functions, NativeFunctions};
+use crate::vm::representations::{ClarityName, SymbolicExpression, SymbolicExpressionType};
+use crate::vm::types::signatures::{SequenceSubtype, StringSubtype};
+use crate::vm::types::{FixedFunction, FunctionArg, FunctionType, TypeSignature, Value};
+use crate::vm::variables::NativeVariables;
+
+#[cfg(test)]
+mod tests;
+
+///
+/// A static-analysis pass that bounds the cost of calling each public and
+///  read-only function, using the same cost functions as the interpreter.
+/// Every list, buffer and string is assumed to be as long as its type allows,
+///  and every branch to take its most expensive arm, so the bound depends only
+///  on the declared max-lengths of the function's arguments and data.  It is a
+///  single cost, not a function of the lengths of the arguments actually passed:
+///  a call with shorter arguments may cost much less than its bound.
+/// This pass is not part of consensus: it never rejects a contract, and it is
+///  not run when a contract is deployed.  The bounds are computed on demand, by
+///  `clarity-cli check --cost_bounds` and the contract interface RPC endpoint.
+///
+pub struct CostBoundChecker<'a, T: CostTracker> {
+    contract_analysis: &'a ContractAnalysis,
+    type_map: &'a TypeMap,
+    cost_tracker: &'a mut T,
+    /// The arguments and body of each function the contract defines
+    functions: HashMap<ClarityName, (&'a [FunctionArg], &'a SymbolicExpression)>,
+    bounds: HashMap<ClarityName, CostBound>,
+    /// Cost function results, by function and input
+    costs: HashMap<(ClarityCostFunction, u64), ExecutionCost>,
+    /// The contract calls made by the function being bounded
+    unbounded_calls: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostBound {
+    /// The most one call to the function can cost in each dimension.  This does not include
+    ///  loading the contract, which is charged before the function is called.
+    pub worst_case: ExecutionCost,
+    /// The `contract-call?`s whose callees' costs aren't included in `worst_case`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unbounded_calls: Vec<String>,
+}
+
+fn add_cost(total: &mut ExecutionCost, cost: &ExecutionCost) {
+    total.runtime = total.runtime.saturating_add(cost.runtime);
+    total.read_count = total.read_count.saturating_add(cost.read_count);
+    total.read_length = total.read_length.saturating_add(cost.read_length);
+    total.write_count = total.write_count.saturating_add(cost.write_count);
+    total.write_length = total.write_length.saturating_add(cost.write_length);
+}
+
+fn multiply_cost(cost: &ExecutionCost, times: u64) -> ExecutionCost {
+    ExecutionCost {
+        runtime: cost.runtime.saturating_mul(times),
+        read_count: cost.read_count.saturating_mul(times),
+        read_length: cost.read_length.saturating_mul(times),
+        write_count: cost.write_count.saturating_mul(times),
+        write_length: cost.write_length.saturating_mul(times),
+    }
+}
+
+/// The cost function of a comparison or boolean operator, which are special functions that can
+///  also be passed to `map`, `filter` and `fold`
+fn operator_cost_function(function: NativeFunctions) -> Option<ClarityCostFunction> {
+    match function {
+        NativeFunctions::CmpGeq => Some(ClarityCostFunction::Geq),
+        NativeFunctions::CmpLeq => Some(ClarityCostFunction::Leq),
+        NativeFunctions::CmpLess => Some(ClarityCostFunction::Le),
+        NativeFunctions::CmpGreater => Some(ClarityCostFunction::Ge),
+        NativeFunctions::And => Some(ClarityCostFunction::And),
+        NativeFunctions::Or => Some(ClarityCostFunction::Or),
+        _ => None,
+    }
+}
+
+/// The most elements `sequence_type` can hold
+fn max_len(sequence_type: &TypeSignature) -> u64 {
+    match sequence_type {
+        TypeSignature::SequenceType(SequenceSubtype::ListType(list)) => list.get_max_len().into(),
+        TypeSignature::SequenceType(SequenceSubtype::BufferType(len))
+        | TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::ASCII(len))) => {
+            u32::from(len).into()
+        }
+        TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::UTF8(len))) => {
+            u32::from(len).into()
+        }
+        _ => 0,
+    }
+}
+
+fn size_of(type_signature: &TypeSignature) -> u64 {
+    type_signature.size().map_or(0, u64::from)
+}
+
+impl<'a, T: CostTracker> CostBoundChecker<'a, T> {
+    /// Bound each public and read-only function in `contract_analysis`, which must have been
+    ///  type checked with a type map.  `cost_tracker` only evaluates cost functions: nothing
+    ///  is charged to it.  Functions whose costs can't be computed are left out.
+    pub fn run(
+        contract_analysis: &'a ContractAnalysis,
+        cost_tracker: &'a mut T,
+    ) -> BTreeMap<ClarityName, CostBound> {
+        let Some(type_map) = contract_analysis.type_map.as_ref() else {
+            return BTreeMap::new();
+        };
+
+        let mut functions = HashMap::new();
+        for expr in contract_analysis.expressions.iter() {
+            let (signature, body, function_types) =
+                match DefineFunctionsParsed::try_parse(expr).ok().flatten() {
+                    Some(DefineFunctionsParsed::PrivateFunction { signature, body }) => {
+                        (signature, body, &contract_analysis.private_function_types)
+                    }
+                    Some(DefineFunctionsParsed::PublicFunction { signature, body }) => {
+                        (signature, body, &contract_analysis.public_function_types)
+                    }
+                    Some(DefineFunctionsParsed::ReadOnlyFunction { signature, body }) => {
+                        (signature, body, &contract_analysis.read_only_function_types)
+                    }
+                    _ => continue,
+                };
+            let Some(name) = signature.first().and_then(|name| name.match_atom()) else {
+                continue;
+            };
+            if let Some(FunctionType::Fixed(FixedFunction { args, .. })) = function_types.get(name)
+            {
+                functions.insert(name.clone(), (args.as_slice(), body));
+            }
+        }
+
+        let mut checker = CostBoundChecker {
+            contract_analysis,
+            type_map,
+            cost_tracker,
+            functions,
+            bounds: HashMap::new(),
+            costs: HashMap::new(),
+            unbounded_calls: BTreeSet::new(),
+        };
+
+        contract_analysis
+            .public_function_types
+            .keys()
+            .chain(contract_analysis.read_only_function_types.keys())
+            .filter_map(|name| {
+                let bound = checker.function_bound(name).ok()?;
+                Some((name.clone(), bound))
+            })
+            .collect()
+    }
+
+    fn cost(
+        &mut self,
+        function: ClarityCostFunction,
+        input: u64,
+    ) -> Result<ExecutionCost, CostErrors> {
+        if let Some(cost) = self.costs.get(&(function, input)) {
+            return Ok(cost.clone());
+        }
+        let cost = self.cost_tracker.compute_cost(function, &[input])?;
+        self.costs.insert((function, input), cost.clone());
+        Ok(cost)
+    }
+
+    fn type_of(&self, expr: &SymbolicExpression) -> Option<&'a TypeSignature> {
+        self.type_map.get_type_expected(expr)
+    }
+
+    /// The largest size a value of `expr`'s type can have
+    fn size(&self, expr: &SymbolicExpression) -> u64 {
+        self.type_of(expr).map_or(0, size_of)
+    }
+
+    fn function_bound(&mut self, name: &ClarityName) -> Result<CostBound, CostErrors> {
+        if let Some(bound) = self.bounds.get(name) {
+            return Ok(bound.clone());
+        }
+        let (args, body) = *self
+            .functions
+            .get(name)
+            .ok_or_else(|| CostErrors::Expect(format!("No such function: {name}")))?;
+        if self.type_of(body).is_none() {
+            return Err(CostErrors::Expect(format!(
+                "Function body has no type annotation: {name}"
+            )));
+        }
+
+        let caller_calls = std::mem::take(&mut self.unbounded_calls);
+        let worst_case = self.function_cost(args, body);
+        let unbounded_calls = std::mem::replace(&mut self.unbounded_calls, caller_calls);
+
+        let bound = CostBound {
+            worst_case: worst_case?,
+            unbounded_calls: unbounded_calls.into_iter().collect(),
+        };
+        self.bounds.insert(name.clone(), bound.clone());
+        Ok(bound)
+    }
+
+    /// The cost of applying a function, as `DefinedFunction::execute_apply` charges it
+    fn function_cost(
+        &mut self,
+        args: &[FunctionArg],
+        body: &'a SymbolicExpression,
+    ) -> Result<ExecutionCost, CostErrors> {
+        let mut cost = self.cost(
+            ClarityCostFunction::UserFunctionApplication,
+            args.len() as u64,
+        )?;
+        for arg in args.iter() {
+            let type_check = self.cost(
+                ClarityCostFunction::InnerTypeCheckCost,
+                size_of(&arg.signature),
+            )?;
+            add_cost(&mut cost, &type_check);
+        }
+        add_cost(&mut cost, &self.expr_cost(body, 0)?);
+        Ok(cost)
+    }
+
+    /// Call `name` from another function, adding its contract calls to the caller's
+    fn call_cost(&mut self, name: &ClarityName) -> Result<ExecutionCost, CostErrors> {
+        let callee = self.function_bound(name)?;
+        self.unbounded_calls.extend(callee.unbounded_calls);
+        Ok(callee.worst_case)
+    }
+
+    fn exprs_cost(
+        &mut self,
+        exprs: &'a [SymbolicExpression],
+        depth: u64,
+    ) -> Result<ExecutionCost, CostErrors> {
+        let mut cost = ExecutionCost::zero();
+        for expr in exprs.iter() {
+            add_cost(&mut cost, &self.expr_cost(expr, depth)?);
+        }
+        Ok(cost)
+    }
+
+    /// The most evaluating `expr` can cost, in a local context `depth` deep
+    fn expr_cost(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        depth: u64,
+    ) -> Result<ExecutionCost, CostErrors> {
+        match &expr.expr {
+            SymbolicExpressionType::Atom(name) => self.variable_cost(name, expr, depth),
+            SymbolicExpressionType::List(list) => self.application_cost(list, depth),
+            SymbolicExpressionType::AtomValue(_)
+            | SymbolicExpressionType::LiteralValue(_)
+            | SymbolicExpressionType::Field(_)
+            | SymbolicExpressionType::TraitReference(..) => Ok(ExecutionCost::zero()),
+        }
+    }
+
+    fn variable_cost(
+        &mut self,
+        name: &ClarityName,
+        expr: &SymbolicExpression,
+        depth: u64,
+    ) -> Result<ExecutionCost, CostErrors> {
+        let analysis = self.contract_analysis;
+        let version = &analysis.clarity_version;
+        if let Some(variable) = NativeVariables::lookup_by_name_at_version(name, version) {
+            return match variable {
+                NativeVariables::BlockHeight
+                | NativeVariables::BurnBlockHeight
+                | NativeVariables::TotalLiquidMicroSTX => {
+                    self.cost(ClarityCostFunction::FetchVar, 1)
+                }
+                _ => Ok(ExecutionCost::zero()),
+            };
+        }
+        let mut cost = self.cost(ClarityCostFunction::LookupVariableDepth, depth)?;
+        let size = self.size(expr);
+        add_cost(
+            &mut cost,
+            &self.cost(ClarityCostFunction::LookupVariableSize, size)?,
+        );
+        Ok(cost)
+    }
+
+    fn application_cost(
+        &mut self,
+        list: &'a [SymbolicExpression],
+        depth: u64,
+    ) -> Result<ExecutionCost, CostErrors> {
+        let Some((name, args)) = list.split_first() else {
+            return Ok(ExecutionCost::zero());
+        };
+        let Some(name) = name.match_atom() else {
+            return Ok(ExecutionCost::zero());
+        };
+
+        let mut cost = self.cost(ClarityCostFunction::LookupFunction, 0)?;
+        let analysis = self.contract_analysis;
+        let version = &analysis.clarity_version;
+        if let Some(function) = NativeFunctions::lookup_by_name_at_version(name, version) {
+            match lookup_reserved_functions(name, version) {
+                Some(CallableType::NativeFunction(_, _, cost_function)) => {
+                    add_cost(&mut cost, &self.exprs_cost(args, depth)?);
+                    add_cost(&mut cost, &self.cost(cost_function, args.len() as u64)?);
+                }
+                Some(CallableType::NativeFunction205(_, _, cost_function, _)) => {
+                    add_cost(&mut cost, &self.exprs_cost(args, depth)?);
+                    let input = if analysis.epoch >= StacksEpochId::Epoch2_05 {
+                        args.iter().map(|arg| self.size(arg)).sum()
+                    } else {
+                        args.len() as u64
+                    };
+                    add_cost(&mut cost, &self.cost(cost_function, input)?);
+                }
+                _ => add_cost(&mut cost, &self.special_cost(function, args, depth)?),
+            }
+        } else if self.functions.contains_key(name) {
+            add_cost(&mut cost, &self.exprs_cost(args, depth)?);
+            add_cost(&mut cost, &self.call_cost(name)?);
+        } else {
+            add_cost(&mut cost, &self.exprs_cost(args, depth)?);
+        }
+        Ok(cost)
+    }
+
+    /// The cost of a special function, which evaluates its own arguments
+    fn special_cost(
+        &mut self,
+        function: NativeFunctions,
+        args: &'a [SymbolicExpression],
+        depth: u64,
+    ) -> Result<ExecutionCost, CostErrors> {
+        use crate::vm::functions::NativeFunctions::*;
+
+        let rest = args.get(1..).unwrap_or(&[]);
+        let mut cost = ExecutionCost::zero();
+        match function {
+            If => {
+                add_cost(&mut cost, &self.cost(ClarityCostFunction::If, 0)?);
+                add_cost(
+                    &mut cost,
+                    &self.exprs_cost(&args[..args.len().min(1)], depth)?,
+                );
+                add_cost(&mut cost, &self.branches_cost(rest, depth)?);
+            }
+            Match => {
+                add_cost(&mut cost, &self.cost(ClarityCostFunction::Match, 0)?);
+                add_cost(
+                    &mut cost,
+                    &self.exprs_cost(&args[..args.len().min(1)], depth)?,
+                );
+                // `(match opt some-name some-branch none-branch)` or
+                //  `(match res ok-name ok-branch err-name err-branch)`
+                let branches: Vec<_> = match args {
+                    [_, _, some_branch, none_branch] => vec![some_branch, none_branch],
+                    [_, _, ok_branch, _, err_branch] => vec![ok_branch, err_branch],
+                    _ => vec![],
+                };
+                let mut worst_branch = ExecutionCost::zero();
+                for branch in branches {
+                    let branch_cost = self.expr_cost(branch, depth + 1)?;
+                    worst_branch = ExecutionCost::max_cost(worst_branch, branch_cost);
+                }
+                add_cost(&mut cost, &worst_branch);
+            }
+            Let => {
+                let bindings = args.first().and_then(|arg| arg.match_list()).unwrap_or(&[]);
+                add_cost(
+                    &mut cost,
+                    &self.cost(ClarityCostFunction::Let, bindings.len() as u64)?,
+                );
+                for binding in bindings.iter() {
+                    if let Some([_, value]) = binding.match_list() {
+                        add_cost(&mut cost, &self.expr_cost(value, depth + 1)?);
+                    }
+                }
+                add_cost(&mut cost, &self.exprs_cost(rest, depth + 1)?);
+            }
+            TupleCons => {
+                add_cost(
+                    &mut cost,
+                    &self.cost(ClarityCostFunction::TupleCons, args.len() as u64)?,
+                );
+                for binding in args.iter() {
+                    if let Some([_, value]) = binding.match_list() {
+                        add_cost(&mut cost, &self.expr_cost(value, depth)?);
+                    }
+                }
+            }
+            Map | Filter | Fold => {
+                add_cost(&mut cost, &self.iteration_cost(function, args, depth)?);
+            }
+            ContractCall => {
+                add_cost(&mut cost, &self.cost(ClarityCostFunction::ContractCall, 0)?);
+                add_cost(
+                    &mut cost,
+                    &self.exprs_cost(args.get(2..).unwrap_or(&[]), depth)?,
+                );
+                if let [contract, callee, ..] = args {
+                    let contract = match &contract.expr {
+                        SymbolicExpressionType::LiteralValue(Value::Principal(principal)) => {
+                            principal.to_string()
+                        }
+                        SymbolicExpressionType::Atom(name) => {
+                            add_cost(&mut cost, &self.variable_cost(name, contract, depth)?);
+                            name.to_string()
+                        }
+                        _ => "?".into(),
+                    };
+                    let callee = callee.match_atom().map_or("?", |callee| callee.as_str());
+                    self.unbounded_calls.insert(format!("{contract}.{callee}"));
+                }
+            }
+            _ => {
+                let (cost_function, input, evaluated) = self.special_charge(function, args);
+                if let Some(cost_function) = cost_function {
+                    add_cost(&mut cost, &self.cost(cost_function, input)?);
+                }
+                add_cost(&mut cost, &self.exprs_cost(evaluated, depth)?);
+            }
+        }
+        Ok(cost)
+    }
+
+    /// The most expensive of `branches`
+    fn branches_cost(
+        &mut self,
+        branches: &'a [SymbolicExpression],
+        depth: u64,
+    ) -> Result<ExecutionCost, CostErrors> {
+        let mut worst_branch = ExecutionCost::zero();
+        for branch in branches.iter() {
+            let branch_cost = self.expr_cost(branch, depth)?;
+            worst_branch = ExecutionCost::max_cost(worst_branch, branch_cost);
+        }
+        Ok(worst_branch)
+    }
+
+    /// The cost function and input a special function which doesn't branch or iterate charges,
+    ///  and the arguments it evaluates.  Each input is the largest the interpreter could pass.
+    fn special_charge(
+        &self,
+        function: NativeFunctions,
+        args: &'a [SymbolicExpression],
+    ) -> (Option<ClarityCostFunction>, u64, &'a [SymbolicExpression]) {
+        use crate::vm::functions::NativeFunctions::*;
+
+        let analysis = self.contract_analysis;
+        let rest = args.get(1..).unwrap_or(&[]);
+        let size = |i: usize| args.get(i).map_or(0, |arg| self.size(arg));
+        let name = args.first().and_then(|arg| arg.match_atom());
+        let variable_size = name
+            .and_then(|name| analysis.persisted_variable_types.get(name))
+            .map_or(0, size_of);
+        let (key_size, value_size) = name
+            .and_then(|name| analysis.map_types.get(name))
+            .map_or((0, 0), |(key, value)| (size_of(key), size_of(value)));
+        let nft_size = name
+            .and_then(|name| analysis.non_fungible_tokens.get(name))
+            .map_or(0, size_of);
+
+        let (cost_function, input, evaluated) = match function {
+            CmpGeq | CmpLeq | CmpLess | CmpGreater | And | Or => {
+                let input = if matches!(function, And | Or) {
+                    args.len() as u64
+                } else {
+                    (args.len() as u64).max(size(0).min(size(1)))
+                };
+                (operator_cost_function(function), input, args)
+            }
+            Asserts => (Some(ClarityCostFunction::Asserts), 0, args),
+            FetchVar => (Some(ClarityCostFunction::FetchVar), variable_size, &[][..]),
+            SetVar => (Some(ClarityCostFunction::SetVar), variable_size, rest),
+            FetchEntry => (
+                Some(ClarityCostFunction::FetchEntry),
+                key_size + value_size,
+                rest,
+            ),
+            SetEntry | InsertEntry => (
+                Some(ClarityCostFunction::SetEntry),
+                key_size + value_size,
+                rest,
+            ),
+            DeleteEntry => (Some(ClarityCostFunction::SetEntry), key_size, rest),
+            IsStandard => (Some(ClarityCostFunction::IsStandard), 0, args),
+            PrincipalDestruct => (Some(ClarityCostFunction::PrincipalDestruct), 0, args),
+            PrincipalConstruct => (Some(ClarityCostFunction::PrincipalConstruct), 0, args),
+            Concat => (Some(ClarityCostFunction::Concat), size(0) + size(1), args),
+            AsMaxLen => (Some(ClarityCostFunction::AsMaxLen), 0, args),
+            Append => {
+                let entry_size = match args.first().and_then(|list| self.type_of(list)) {
+                    Some(TypeSignature::SequenceType(sequence)) => {
+                        sequence.unit_type().map_or(0, |entry| size_of(&entry))
+                    }
+                    _ => 0,
+                };
+                (
+                    Some(ClarityCostFunction::Append),
+                    entry_size.max(size(1)),
+                    args,
+                )
+            }
+            Slice => (Some(ClarityCostFunction::Slice), size(0), args),
+            ReplaceAt => (Some(ClarityCostFunction::ReplaceAt), size(0), args),
+            ListCons => (
+                Some(ClarityCostFunction::ListCons),
+                (0..args.len()).map(size).sum(),
+                args,
+            ),
+            TupleGet => {
+                // `get` also takes an optional tuple
+                let tuple_type = match args.get(1).and_then(|tuple| self.type_of(tuple)) {
+                    Some(TypeSignature::OptionalType(inner)) => Some(inner.as_ref()),
+                    tuple_type => tuple_type,
+                };
+                let fields = match tuple_type {
+                    Some(TypeSignature::TupleType(tuple)) => tuple.len(),
+                    _ => 0,
+                };
+                (Some(ClarityCostFunction::TupleGet), fields, rest)
+            }
+            Secp256k1Recover => (Some(ClarityCostFunction::Secp256k1recover), 0, args),
+            Secp256k1Verify => (Some(ClarityCostFunction::Secp256k1verify), 0, args),
+            Print => (Some(ClarityCostFunction::Print), size(0), args),
+            AsContract => (Some(ClarityCostFunction::AsContract), 0, args),
+            ContractOf => (Some(ClarityCostFunction::ContractOf), 0, &[][..]),
+            PrincipalOf => (Some(ClarityCostFunction::PrincipalOf), 0, args),
+            GetBlockInfo => (Some(ClarityCostFunction::BlockInfo), 0, rest),
+            GetBurnBlockInfo => (Some(ClarityCostFunction::GetBurnBlockInfo), 0, rest),
+            MintAsset => (Some(ClarityCostFunction::NftMint), nft_size, rest),
+            TransferAsset => (Some(ClarityCostFunction::NftTransfer), nft_size, rest),
+            GetAssetOwner => (Some(ClarityCostFunction::NftOwner), nft_size, rest),
+            BurnAsset => (Some(ClarityCostFunction::NftBurn), nft_size, rest),
+            MintToken => (Some(ClarityCostFunction::FtMint), 0, rest),
+            TransferToken => (Some(ClarityCostFunction::FtTransfer), 0, rest),
+            GetTokenBalance => (Some(ClarityCostFunction::FtBalance), 0, rest),
+            GetTokenSupply => (Some(ClarityCostFunction::FtSupply), 0, rest),
+            BurnToken => (Some(ClarityCostFunction::FtBurn), 0, rest),
+            AtBlock => (Some(ClarityCostFunction::AtBlock), 0, args),
+            GetStxBalance => (Some(ClarityCostFunction::StxBalance), 0, args),
+            StxTransfer | StxBurn => (Some(ClarityCostFunction::StxTransfer), 0, args),
+            StxTransferMemo => (Some(ClarityCostFunction::StxTransferMemo), 0, args),
+            StxGetAccount => (Some(ClarityCostFunction::StxGetAccount), 0, args),
+            GetInferResult => (Some(ClarityCostFunction::InferResult), 0, args),
+            // the first argument is the type to deserialize to
+            FromConsensusBuff => (
+                Some(ClarityCostFunction::FromConsensusBuff),
+                args.get(1)
+                    .and_then(|buff| self.type_of(buff))
+                    .map_or(0, max_len),
+                rest,
+            ),
+            _ => (None, 0, args),
+        };
+        (cost_function, input, evaluated)
+    }
+
+    /// The cost of `map`, `filter` or `fold`, applying their function to every element of the
+    ///  longest sequences their arguments' types allow
+    fn iteration_cost(
+        &mut self,
+        function: NativeFunctions,
+        args: &'a [SymbolicExpression],
+        depth: u64,
+    ) -> Result<ExecutionCost, CostErrors> {
+        let Some((applied, rest)) = args.split_first() else {
+            return Ok(ExecutionCost::zero());
+        };
+        let sequences = match function {
+            // `(fold f sequence initial)`
+            NativeFunctions::Fold => &rest[..rest.len().min(1)],
+            _ => rest,
+        };
+
+        let mut cost = match function {
+            NativeFunctions::Map => self.cost(ClarityCostFunction::Map, args.len() as u64)?,
+            NativeFunctions::Filter => self.cost(ClarityCostFunction::Filter, 0)?,
+            _ => self.cost(ClarityCostFunction::Fold, 0)?,
+        };
+        add_cost(
+            &mut cost,
+            &self.cost(ClarityCostFunction::LookupFunction, 0)?,
+        );
+        add_cost(&mut cost, &self.exprs_cost(rest, depth)?);
+
+        // `map` stops at the end of its shortest sequence
+        let mut iterations = None;
+        let mut arg_sizes = vec![];
+        for sequence in sequences.iter() {
+            let sequence_type = self.type_of(sequence);
+            let len = sequence_type.map_or(0, max_len);
+            iterations = Some(iterations.map_or(len, |iterations: u64| iterations.min(len)));
+            let entry_size = match sequence_type {
+                Some(TypeSignature::SequenceType(sequence)) => {
+                    sequence.unit_type().map_or(0, |entry| size_of(&entry))
+                }
+                _ => 0,
+            };
+            arg_sizes.push(entry_size);
+        }
+        if function == NativeFunctions::Fold {
+            // the accumulator, whose type is the type of the result
+            arg_sizes.push(rest.get(1).map_or(0, |initial| self.size(initial)));
+        }
+
+        let application = self.apply_cost(applied, &arg_sizes)?;
+        add_cost(
+            &mut cost,
+            &multiply_cost(&application, iterations.unwrap_or(0)),
+        );
+        Ok(cost)
+    }
+
+    /// The cost of applying the function named by `applied` to values of `arg_sizes`
+    fn apply_cost(
+        &mut self,
+        applied: &SymbolicExpression,
+        arg_sizes: &[u64],
+    ) -> Result<ExecutionCost, CostErrors> {
+        let Some(name) = applied.match_atom() else {
+            return Ok(ExecutionCost::zero());
+        };
+        let analysis = self.contract_analysis;
+        let version = &analysis.clarity_version;
+        let Some(function) = NativeFunctions::lookup_by_name_at_version(name, version) else {
+            return if self.functions.contains_key(name) {
+                self.call_cost(name)
+            } else {
+                Ok(ExecutionCost::zero())
+            };
+        };
+        match lookup_reserved_functions(name, version) {
+            Some(CallableType::NativeFunction(_, _, cost_function)) => {
+                self.cost(cost_function, arg_sizes.len() as u64)
+            }
+            Some(CallableType::NativeFunction205(_, _, cost_function, _)) => {
+                let input = if analysis.epoch >= StacksEpochId::Epoch2_05 {
+                    arg_sizes.iter().sum()
+                } else {
+                    arg_sizes.len() as u64
+                };
+                self.cost(cost_function, input)
+            }
+            _ => match operator_cost_function(function) {
+                Some(cost_function) => {
+                    let smallest = arg_sizes.iter().min().cloned().unwrap_or(0);
+                    self.cost(cost_function, (arg_sizes.len() as u64).max(smallest))
+                }
+                None => Ok(ExecutionCost::zero()),
+            },
+        }
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use stacks_common::types::StacksEpochId;
+
+use crate::vm::analysis::cost_bound_checker::{CostBound, CostBoundChecker};
+use crate::vm::analysis::mem_type_check;
+use crate::vm::costs::cost_functions::ClarityCostFunction;
+use crate::vm::costs::{CostErrors, CostTracker, ExecutionCost};
+use crate::vm::types::QualifiedContractIdentifier;
+use crate::vm::{ClarityName, ClarityVersion};
+
+/// A tracker whose cost functions are all linear in their input, and where only
+///  fetches read from storage
+struct LinearCostTracker;
+
+impl CostTracker for LinearCostTracker {
+    fn compute_cost(
+        &mut self,
+        cost_function: ClarityCostFunction,
+        input: &[u64],
+    ) -> Result<ExecutionCost, CostErrors> {
+        let input = input.iter().sum::<u64>();
+        let read_count = match cost_function {
+            ClarityCostFunction::FetchVar | ClarityCostFunction::FetchEntry => 1,
+            _ => 0,
+        };
+        Ok(ExecutionCost {
+            runtime: 1 + input,
+            read_count,
+            read_length: 0,
+            write_count: 0,
+            write_length: 0,
+        })
+    }
+    fn add_cost(&mut self, _cost: ExecutionCost) -> Result<(), CostErrors> {
+        Ok(())
+    }
+    fn add_memory(&mut self, _memory: u64) -> Result<(), CostErrors> {
+        Ok(())
+    }
+    fn drop_memory(&mut self, _memory: u64) -> Result<(), CostErrors> {
+        Ok(())
+    }
+    fn reset_memory(&mut self) {}
+    fn short_circuit_contract_call(
+        &mut self,
+        _contract: &QualifiedContractIdentifier,
+        _function: &ClarityName,
+        _input: &[u64],
+    ) -> Result<bool, CostErrors> {
+        Ok(false)
+    }
+}
+
+fn bounds(contract: &str) -> BTreeMap<ClarityName, CostBound> {
+    let (_, analysis) =
+        mem_type_check(contract, ClarityVersion::Clarity2, StacksEpochId::Epoch21).unwrap();
+    CostBoundChecker::run(&analysis, &mut LinearCostTracker)
+}
+
+fn bound<'a>(bounds: &'a BTreeMap<ClarityName, CostBound>, name: &str) -> &'a CostBound {
+    bounds
+        .get(name)
+        .unwrap_or_else(|| panic!("no bound for {name}"))
+}
+
+#[test]
+fn test_public_and_read_only_only() {
+    let bounds = bounds(
+        "
+(define-private (double (x uint)) (* x u2))
+(define-read-only (quadruple (x uint)) (double (double x)))
+(define-public (pay (amount uint)) (ok (double amount)))
+",
+    );
+    assert_eq!(bounds.len(), 2);
+    assert!(bounds.get("double").is_none());
+    assert!(bound(&bounds, "quadruple").worst_case.runtime > 0);
+    assert!(bound(&bounds, "pay").worst_case.runtime > 0);
+}
+
+#[test]
+fn test_private_calls_are_included() {
+    let bounds = bounds(
+        "
+(define-private (double (x uint)) (* x u2))
+(define-read-only (once (x uint)) (double x))
+(define-read-only (twice (x uint)) (double (double x)))
+",
+    );
+    assert!(bound(&bounds, "twice").worst_case.runtime > bound(&bounds, "once").worst_case.runtime);
+}
+
+#[test]
+fn test_list_length_scales_bound() {
+    let bounds = bounds(
+        "
+(define-private (add (i uint) (acc uint)) (+ acc i))
+(define-read-only (sum-10 (items (list 10 uint))) (fold add items u0))
+(define-read-only (sum-20 (items (list 20 uint))) (fold add items u0))
+(define-read-only (sum-40 (items (list 40 uint))) (fold add items u0))
+",
+    );
+    let sum_10 = bound(&bounds, "sum-10").worst_case.runtime;
+    let sum_20 = bound(&bounds, "sum-20").worst_case.runtime;
+    let sum_40 = bound(&bounds, "sum-40").worst_case.runtime;
+    assert!(sum_10 < sum_20);
+    assert!(sum_20 < sum_40);
+    // every element adds the same cost, so twenty more cost more than ten more
+    assert!(sum_40 - sum_20 > sum_20 - sum_10);
+}
+
+#[test]
+fn test_branches_take_most_expensive_arm() {
+    let bounds = bounds(
+        "
+(define-private (add (i uint) (acc uint)) (+ acc i))
+(define-read-only (then-arm (flag bool) (items (list 50 uint)))
+  (if flag (fold add items u0) u0))
+(define-read-only (else-arm (flag bool) (items (list 50 uint)))
+  (if flag u0 (fold add items u0)))
+(define-read-only (cheap (flag bool) (items (list 50 uint)))
+  (if flag u0 u1))
+",
+    );
+    let then_arm = &bound(&bounds, "then-arm").worst_case;
+    let else_arm = &bound(&bounds, "else-arm").worst_case;
+    assert_eq!(then_arm, else_arm);
+    assert!(then_arm.runtime > bound(&bounds, "cheap").worst_case.runtime);
+}
+
+#[test]
+fn test_reads_are_counted() {
+    let bounds = bounds(
+        "
+(define-data-var counter uint u0)
+(define-map balances principal uint)
+(define-read-only (get-counter) (var-get counter))
+(define-read-only (get-both (who principal))
+  (+ (var-get counter) (default-to u0 (map-get? balances who))))
+(define-read-only (get-nothing) u0)
+",
+    );
+    assert_eq!(bound(&bounds, "get-counter").worst_case.read_count, 1);
+    assert_eq!(bound(&bounds, "get-both").worst_case.read_count, 2);
+    assert_eq!(bound(&bounds, "get-nothing").worst_case.read_count, 0);
+}
+
+#[test]
+fn test_contract_calls_are_unbounded() {
+    let bounds = bounds(
+        "
+(define-trait payer ((pay (uint) (response uint uint))))
+(define-private (forward (to <payer>) (amount uint))
+  (contract-call? to pay amount))
+(define-public (pay-twice (first <payer>) (second <payer>) (amount uint))
+  (begin
+    (try! (forward first amount))
+    (contract-call? second pay amount)))
+(define-public (pay-none (amount uint))
+  (ok amount))
+",
+    );
+    assert_eq!(
+        bound(&bounds, "pay-twice").unbounded_calls,
+        vec!["second.pay".to_string(), "to.pay".to_string()]
+    );
+    assert!(bound(&bounds, "pay-none").unbounded_calls.is_empty());
+}
+
+#[test]
+fn test_analysis_does_not_bound_costs() {
+    // the bounds aren't part of consensus, so only callers that ask for them compute them
+    let (_, analysis) = mem_type_check(
+        "(define-read-only (quadruple (x uint)) (* x u4))",
+        ClarityVersion::Clarity2,
+        StacksEpochId::Epoch21,
+    )
+    .unwrap();
+    let mut interface = analysis.contract_interface.clone().unwrap();
+    assert!(interface.functions[0].cost_bound.is_none());
+
+    interface.set_cost_bounds(&CostBoundChecker::run(&analysis, &mut LinearCostTracker));
+    assert!(interface.functions[0].cost_bound.is_some());
+}
@@ -0,0 +1,361 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use hashbrown::HashSet;
+use stacks_common::types::StacksEpochId;
+
+use super::errors::CheckResult;
+use super::AnalysisDatabase;
+use crate::vm::analysis::types::{AnalysisPass, ContractAnalysis};
+use crate::vm::diagnostic::{DiagnosableError, Diagnostic, Level};
+use crate::vm::functions::define::DefineFunctionsParsed;
+use crate::vm::functions::NativeFunctions;
+use crate::vm::representations::{ClarityName, Span, SymbolicExpression};
+use crate::vm::ClarityVersion;
+
+#[cfg(test)]
+mod tests;
+
+///
+/// A static-analysis pass that looks for common mistakes in a contract
+///  which already type checks, and reports each one as a warning.
+/// This pass is not part of consensus: it never rejects a contract, and
+///  `run_analysis` does not run it.
+///
+pub struct LintChecker<'a> {
+    clarity_version: &'a ClarityVersion,
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum LintWarning {
+    /// The response returned by a function that can fail is discarded
+    UncheckedResponse(String),
+    /// `tx-sender` is compared for equality outside of `as-contract`
+    TxSenderAuthorization,
+    UnusedPrivateFunction(ClarityName),
+    UnusedDataVar(ClarityName),
+    UnusedConstant(ClarityName),
+    UnusedBinding(ClarityName),
+    /// A branch of an `if` whose condition is the literal `true` or `false`
+    UnreachableBranch(bool),
+    /// An expression that follows `(asserts! false ...)`
+    UnreachableExpression,
+}
+
+impl DiagnosableError for LintWarning {
+    fn message(&self) -> String {
+        match self {
+            LintWarning::UncheckedResponse(function) => {
+                format!("the response returned by `{function}` is never checked")
+            }
+            LintWarning::TxSenderAuthorization => {
+                "`tx-sender` is compared in what looks like an authorization check".into()
+            }
+            LintWarning::UnusedPrivateFunction(name) => {
+                format!("private function `{name}` is never called")
+            }
+            LintWarning::UnusedDataVar(name) => format!("data-var `{name}` is never used"),
+            LintWarning::UnusedConstant(name) => format!("constant `{name}` is never used"),
+            LintWarning::UnusedBinding(name) => format!("let binding `{name}` is never used"),
+            LintWarning::UnreachableBranch(condition) => {
+                format!("this branch is never taken: its `if` condition is always `{condition}`")
+            }
+            LintWarning::UnreachableExpression => {
+                "this expression is never evaluated: the `(asserts! false ...)` before it always exits".into()
+            }
+        }
+    }
+
+    fn suggestion(&self) -> Option<String> {
+        match self {
+            LintWarning::UncheckedResponse(_) => Some(
+                "use `try!`, `unwrap!` or `asserts!` so that a failure aborts the transaction"
+                    .into(),
+            ),
+            LintWarning::TxSenderAuthorization => Some(
+                "`tx-sender` is still the original sender when the call comes through another contract; compare `contract-caller` to only allow direct calls"
+                    .into(),
+            ),
+            _ => None,
+        }
+    }
+
+    fn level(&self) -> Level {
+        Level::Warning
+    }
+}
+
+/// Functions which return a response that says whether they did what was asked
+fn is_fallible_call(function: &NativeFunctions) -> bool {
+    matches!(
+        function,
+        NativeFunctions::ContractCall
+            | NativeFunctions::StxTransfer
+            | NativeFunctions::StxTransferMemo
+            | NativeFunctions::StxBurn
+    )
+}
+
+fn is_literal_bool(expr: &SymbolicExpression) -> Option<bool> {
+    match expr.match_atom().map(|atom| atom.as_str()) {
+        Some("true") => Some(true),
+        Some("false") => Some(false),
+        _ => None,
+    }
+}
+
+/// Add the name of every atom in `expr` to `names`
+fn collect_atoms(expr: &SymbolicExpression, names: &mut HashSet<ClarityName>) {
+    if let Some(name) = expr.match_atom() {
+        names.insert(name.clone());
+    } else if let Some(list) = expr.match_list() {
+        for child in list.iter() {
+            collect_atoms(child, names);
+        }
+    }
+}
+
+impl<'a> AnalysisPass for LintChecker<'a> {
+    fn run_pass(
+        _epoch: &StacksEpochId,
+        contract_analysis: &mut ContractAnalysis,
+        _analysis_db: &mut AnalysisDatabase,
+    ) -> CheckResult<()> {
+        let warnings = LintChecker::run(contract_analysis);
+        contract_analysis.diagnostics.extend(warnings);
+        Ok(())
+    }
+}
+
+impl<'a> LintChecker<'a> {
+    /// Lint the contract, and return a warning for each problem found
+    pub fn run(contract_analysis: &ContractAnalysis) -> Vec<Diagnostic> {
+        let mut checker = LintChecker {
+            clarity_version: &contract_analysis.clarity_version,
+            diagnostics: vec![],
+        };
+        checker.check_contract(&contract_analysis.expressions);
+        checker.diagnostics
+    }
+
+    fn warn(&mut self, warning: LintWarning, expr: &SymbolicExpression) {
+        let mut diagnostic = Diagnostic::new(&warning);
+        let span = expr.span();
+        if *span != Span::ZERO {
+            diagnostic.spans.push(span.clone());
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn check_contract(&mut self, expressions: &[SymbolicExpression]) {
+        use crate::vm::functions::define::DefineFunctionsParsed::*;
+
+        // definitions which must be referenced somewhere, and everything that is
+        let mut definitions = vec![];
+        let mut referenced = HashSet::new();
+
+        for expr in expressions.iter() {
+            let Ok(define_type) = DefineFunctionsParsed::try_parse(expr) else {
+                continue;
+            };
+            match define_type {
+                Some(Constant { name, value }) => {
+                    definitions.push((name, LintWarning::UnusedConstant(name.clone()), expr));
+                    collect_atoms(value, &mut referenced);
+                    self.check_expression(value, true, false);
+                }
+                Some(PersistedVariable { name, initial, .. }) => {
+                    definitions.push((name, LintWarning::UnusedDataVar(name.clone()), expr));
+                    collect_atoms(initial, &mut referenced);
+                    self.check_expression(initial, true, false);
+                }
+                Some(PrivateFunction { signature, body }) => {
+                    if let Some(name) = signature.first().and_then(|name| name.match_atom()) {
+                        definitions.push((
+                            name,
+                            LintWarning::UnusedPrivateFunction(name.clone()),
+                            expr,
+                        ));
+                    }
+                    collect_atoms(body, &mut referenced);
+                    self.check_expression(body, true, false);
+                }
+                Some(ReadOnlyFunction { body, .. }) | Some(PublicFunction { body, .. }) => {
+                    collect_atoms(body, &mut referenced);
+                    self.check_expression(body, true, false);
+                }
+                Some(BoundedFungibleToken { max_supply, .. }) => {
+                    collect_atoms(max_supply, &mut referenced);
+                }
+                Some(_) => {}
+                None => {
+                    // the value of a top-level expression is thrown away
+                    collect_atoms(expr, &mut referenced);
+                    self.check_expression(expr, false, false);
+                }
+            }
+        }
+
+        for (name, warning, expr) in definitions.into_iter() {
+            if !referenced.contains(name) {
+                self.warn(warning, expr);
+            }
+        }
+    }
+
+    /// Check `expr`, whose value is discarded unless `used`.  Inside `as-contract`, `tx-sender`
+    /// is the contract itself.
+    fn check_expression(&mut self, expr: &SymbolicExpression, used: bool, in_as_contract: bool) {
+        let Some((function_name, args)) = expr.match_list().and_then(|list| list.split_first())
+        else {
+            return;
+        };
+        let Some(function) = function_name.match_atom().and_then(|name| {
+            NativeFunctions::lookup_by_name_at_version(name, self.clarity_version)
+        }) else {
+            // a call of a function defined in the contract: every argument is used
+            for arg in args.iter() {
+                self.check_expression(arg, true, in_as_contract);
+            }
+            return;
+        };
+
+        match function {
+            NativeFunctions::Begin => self.check_sequence(args, used, in_as_contract),
+            NativeFunctions::Let => self.check_let(args, used, in_as_contract),
+            NativeFunctions::If => {
+                if let [condition, then_branch, else_branch] = args {
+                    match is_literal_bool(condition) {
+                        Some(true) => self.warn(LintWarning::UnreachableBranch(true), else_branch),
+                        Some(false) => {
+                            self.warn(LintWarning::UnreachableBranch(false), then_branch)
+                        }
+                        None => {}
+                    }
+                    self.check_expression(condition, true, in_as_contract);
+                    self.check_expression(then_branch, used, in_as_contract);
+                    self.check_expression(else_branch, used, in_as_contract);
+                }
+            }
+            // these hand back (something derived from) their argument, so it's only checked if
+            //  their own result is
+            NativeFunctions::IsOkay | NativeFunctions::IsErr | NativeFunctions::Print => {
+                for arg in args.iter() {
+                    self.check_expression(arg, used, in_as_contract);
+                }
+            }
+            NativeFunctions::AsContract => {
+                for arg in args.iter() {
+                    self.check_expression(arg, used, true);
+                }
+            }
+            NativeFunctions::Equals => {
+                let is_atom = |arg: &SymbolicExpression, name: &str| {
+                    arg.match_atom().map_or(false, |atom| atom.as_str() == name)
+                };
+                // `(is-eq tx-sender contract-caller)` checks that the call is direct
+                if !in_as_contract
+                    && args.iter().any(|arg| is_atom(arg, "tx-sender"))
+                    && !args.iter().any(|arg| is_atom(arg, "contract-caller"))
+                {
+                    self.warn(LintWarning::TxSenderAuthorization, expr);
+                }
+                for arg in args.iter() {
+                    self.check_expression(arg, true, in_as_contract);
+                }
+            }
+            _ => {
+                if !used && is_fallible_call(&function) {
+                    self.warn(LintWarning::UncheckedResponse(function.get_name()), expr);
+                }
+                for arg in args.iter() {
+                    self.check_expression(arg, true, in_as_contract);
+                }
+            }
+        }
+    }
+
+    /// Check a sequence of expressions, such as the body of a `begin`, of which only the last
+    /// value is kept
+    fn check_sequence(&mut self, exprs: &[SymbolicExpression], used: bool, in_as_contract: bool) {
+        let mut exits_before = false;
+        for (i, expr) in exprs.iter().enumerate() {
+            if exits_before {
+                self.warn(LintWarning::UnreachableExpression, expr);
+                exits_before = false;
+            }
+            let is_last = i + 1 == exprs.len();
+            self.check_expression(expr, used && is_last, in_as_contract);
+            if self.always_exits(expr) {
+                exits_before = true;
+            }
+        }
+    }
+
+    fn always_exits(&self, expr: &SymbolicExpression) -> bool {
+        let Some((function_name, args)) = expr.match_list().and_then(|list| list.split_first())
+        else {
+            return false;
+        };
+        let is_asserts = function_name.match_atom().and_then(|name| {
+            NativeFunctions::lookup_by_name_at_version(name, self.clarity_version)
+        }) == Some(NativeFunctions::Asserts);
+        is_asserts && args.first().and_then(is_literal_bool) == Some(false)
+    }
+
+    fn check_let(&mut self, args: &[SymbolicExpression], used: bool, in_as_contract: bool) {
+        let Some((bindings, body)) = args.split_first() else {
+            return;
+        };
+        let Some(bindings) = bindings.match_list() else {
+            return;
+        };
+        for (i, binding) in bindings.iter().enumerate() {
+            let Some([name, value]) = binding.match_list() else {
+                continue;
+            };
+            let Some(name) = name.match_atom() else {
+                continue;
+            };
+            // a binding can be used by the bindings after it, as well as by the body
+            let mut later_references = HashSet::new();
+            for later in bindings[i + 1..].iter().chain(body.iter()) {
+                collect_atoms(later, &mut later_references);
+            }
+            let binding_used = later_references.contains(name);
+            if !binding_used {
+                let is_fallible = value
+                    .match_list()
+                    .and_then(|list| list.first())
+                    .and_then(|function_name| function_name.match_atom())
+                    .and_then(|function_name| {
+                        NativeFunctions::lookup_by_name_at_version(
+                            function_name,
+                            self.clarity_version,
+                        )
+                    })
+                    .map_or(false, |function| is_fallible_call(&function));
+                // an unchecked response is the more pressing of the two problems
+                if !is_fallible {
+                    self.warn(LintWarning::UnusedBinding(name.clone()), binding);
+                }
+            }
+            self.check_expression(value, binding_used, in_as_contract);
+        }
+        self.check_sequence(body, used, in_as_contract);
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use stacks_common::types::StacksEpochId;
+
+use crate::vm::analysis::lint_checker::{LintChecker, LintWarning};
+use crate::vm::analysis::types::AnalysisPass;
+use crate::vm::analysis::{mem_type_check, ContractAnalysis};
+use crate::vm::database::MemoryBackingStore;
+use crate::vm::diagnostic::{DiagnosableError, Level};
+use crate::vm::ClarityVersion;
+
+fn analyze(contract: &str) -> ContractAnalysis {
+    mem_type_check(contract, ClarityVersion::Clarity2, StacksEpochId::Epoch21)
+        .unwrap()
+        .1
+}
+
+/// The messages of the warnings for `contract`, which must type check
+fn lint(contract: &str) -> Vec<String> {
+    let diagnostics = LintChecker::run(&analyze(contract));
+    assert!(diagnostics.iter().all(|d| d.level == Level::Warning));
+    diagnostics.into_iter().map(|d| d.message).collect()
+}
+
+#[test]
+fn test_clean_contract() {
+    let contract = "
+(define-data-var owner principal tx-sender)
+(define-private (double (x uint)) (* x u2))
+(define-public (pay (amount uint))
+  (begin
+    (asserts! (is-eq contract-caller (var-get owner)) (err u1))
+    (try! (stx-transfer? (double amount) tx-sender (var-get owner)))
+    (ok amount)))
+";
+    assert_eq!(lint(contract), Vec::<String>::new());
+}
+
+#[test]
+fn test_unchecked_responses() {
+    let contract = "
+(define-public (pay (amount uint))
+  (let ((result (stx-transfer? amount tx-sender 'S1G2081040G2081040G2081040G208105NK8PE5)))
+    (ok amount)))
+(define-public (burn (amount uint))
+  (begin
+    (print (is-ok (stx-burn? amount tx-sender)))
+    (ok amount)))
+(define-public (checked (amount uint))
+  (begin
+    (try! (stx-transfer? amount tx-sender 'S1G2081040G2081040G2081040G208105NK8PE5))
+    (asserts! (is-ok (stx-burn? amount tx-sender)) (err u2))
+    (unwrap! (stx-burn? amount tx-sender) (err u3))
+    (ok amount)))
+";
+    assert_eq!(
+        lint(contract),
+        vec![
+            LintWarning::UncheckedResponse("stx-transfer?".into()).message(),
+            LintWarning::UncheckedResponse("stx-burn?".into()).message(),
+        ]
+    );
+}
+
+#[test]
+fn test_tx_sender_authorization() {
+    let contract = "
+(define-data-var owner principal tx-sender)
+(define-public (set-owner (new-owner principal))
+  (begin
+    (asserts! (is-eq tx-sender (var-get owner)) (err u1))
+    (ok (var-set owner new-owner))))
+(define-public (direct-only)
+  (begin
+    (asserts! (is-eq tx-sender contract-caller) (err u2))
+    (ok true)))
+(define-read-only (owned-by-self)
+  (as-contract (is-eq tx-sender (var-get owner))))
+";
+    let diagnostics = LintChecker::run(&analyze(contract));
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].message,
+        LintWarning::TxSenderAuthorization.message()
+    );
+    assert!(diagnostics[0]
+        .suggestion
+        .as_ref()
+        .unwrap()
+        .contains("contract-caller"));
+    #[cfg(feature = "developer-mode")]
+    assert_eq!(diagnostics[0].spans[0].start_line, 5);
+}
+
+#[test]
+fn test_unused_definitions() {
+    let contract = "
+(define-constant limit u10)
+(define-constant unused-limit u20)
+(define-data-var total uint u0)
+(define-data-var unused-total uint u0)
+(define-private (double (x uint)) (* x u2))
+(define-private (triple (x uint)) (* x u3))
+(define-private (unused-helper (x uint)) (+ x u1))
+(define-read-only (doubled (xs (list 10 uint))) (map double xs))
+(define-public (add (x uint))
+  (let ((tripled (triple x)) (ignored (+ x limit)))
+    (ok (var-set total (+ (var-get total) tripled)))))
+";
+    assert_eq!(
+        lint(contract),
+        vec![
+            "let binding `ignored` is never used",
+            "constant `unused-limit` is never used",
+            "data-var `unused-total` is never used",
+            "private function `unused-helper` is never called",
+        ]
+    );
+}
+
+#[test]
+fn test_unreachable_code() {
+    let contract = "
+(define-read-only (pick (x uint))
+  (if true x u0))
+(define-public (fail (x uint))
+  (begin
+    (asserts! false (err u1))
+    (ok x)))
+";
+    assert_eq!(
+        lint(contract),
+        vec![
+            LintWarning::UnreachableBranch(true).message(),
+            LintWarning::UnreachableExpression.message(),
+        ]
+    );
+}
+
+#[test]
+fn test_lint_pass() {
+    let contract = "
+(define-constant unused u1)
+(define-read-only (get-one) u1)
+";
+    let mut analysis = analyze(contract);
+    assert!(analysis.diagnostics.is_empty());
+
+    let mut marf = MemoryBackingStore::new();
+    let mut db = marf.as_analysis_db();
+    LintChecker::run_pass(&StacksEpochId::Epoch21, &mut analysis, &mut db).unwrap();
+    assert_eq!(analysis.diagnostics.len(), 1);
+    assert_eq!(analysis.diagnostics[0].level, Level::Warning);
+    assert!(analysis.diagnostics[0].to_string().starts_with("Warning"));
+}
@@ -17,8 +17,10 @@
 pub mod analysis_db;
 pub mod arithmetic_checker;
 pub mod contract_interface_builder;
+pub mod cost_bound_checker;
 #[allow(clippy::result_large_err)]
 pub mod errors;
+pub mod lint_checker;
 pub mod read_only_checker;
 pub mod trait_checker;
 pub mod type_checker;
@@ -773,7 +773,7 @@ impl TypedNativeFunction {
             | StringToUInt | IntToAscii | IntToUtf8 | GetBurnBlockInfo | StxTransferMemo
             | StxGetAccount | BitwiseAnd | BitwiseOr | BitwiseNot | BitwiseLShift
             | BitwiseRShift | BitwiseXor2 | Slice | ToConsensusBuff | FromConsensusBuff
-            | ReplaceAt => {
+            | ReplaceAt | GetInferResult => {
                 return Err(CheckErrors::Expects(
                     "Clarity 2 keywords should not show up in 2.05".into(),
                 )
@@ -26,11 +26,12 @@ use crate::vm::costs::{
     analysis_typecheck_cost, cost_functions, runtime_cost, CostErrors, CostOverflowingMath,
     CostTracker,
 };
+use crate::vm::database::INFER_RESULT_MAX_OUTPUT_LEN;
 use crate::vm::errors::{Error as InterpError, RuntimeErrorType};
 use crate::vm::functions::{handle_binding_list, NativeFunctions};
 use crate::vm::types::signatures::{
-    CallableSubtype, FunctionArgSignature, FunctionReturnsSignature, SequenceSubtype, ASCII_40,
-    UTF8_40,
+    CallableSubtype, FunctionArgSignature, FunctionReturnsSignature, SequenceSubtype,
+    StringSubtype, StringUTF8Length, ASCII_40, UTF8_40,
 };
 use crate::vm::types::TypeSignature::SequenceType;
 use crate::vm::types::{
@@ -699,6 +700,41 @@ fn check_get_burn_block_info(
     )?)
 }
 
+/// Type check an `infer-result?` call, which reads the committed result of an Infer transaction.
+/// The committed results are only recorded from epoch 3.0 on, so this is a Clarity 3 keyword.
+// Errors:
+// - `CheckErrors::UnknownFunction` before Clarity 3.
+// - `CheckErrors::IncorrectArgumentCount` if there isn't exactly one argument.
+// - `CheckErrors::TypeError` if `args[0]` is not a `(buff 32)`.
+fn check_special_get_infer_result(
+    checker: &mut TypeChecker,
+    args: &[SymbolicExpression],
+    context: &TypingContext,
+) -> TypeResult {
+    if context.clarity_version < ClarityVersion::Clarity3 {
+        return Err(CheckErrors::UnknownFunction("infer-result?".to_string()).into());
+    }
+    check_argument_count(1, args)?;
+
+    checker.type_check_expects(&args[0], context, &BUFF_32)?;
+
+    Ok(TypeSignature::new_option(
+        TupleTypeSignature::try_from(vec![
+            ("output-hash".into(), BUFF_32.clone()),
+            (
+                "output".into(),
+                TypeSignature::new_option(SequenceType(SequenceSubtype::StringType(
+                    StringSubtype::UTF8(StringUTF8Length::try_from(INFER_RESULT_MAX_OUTPUT_LEN)?),
+                )))?,
+            ),
+        ])
+        .map_err(|_| {
+            CheckErrors::Expects("FAIL: GetInferResult failed to initialize type signature".into())
+        })?
+        .into(),
+    )?)
+}
+
 impl TypedNativeFunction {
     pub fn type_check_application(
         &self,
@@ -958,6 +994,7 @@ impl TypedNativeFunction {
                 })?
                 .into(),
             }))),
+            GetInferResult => Special(SpecialNativeFunction(&check_special_get_infer_result)),
             StxBurn => Simple(SimpleNativeFunction(FunctionType::Fixed(FixedFunction {
                 args: vec![
                     FunctionArg::new(
@@ -329,6 +329,45 @@ fn test_get_burn_block_info() {
     }
 }
 
+#[test]
+fn test_get_infer_result() {
+    let type_check_v3 = |exp: &str| {
+        mem_run_analysis(exp, ClarityVersion::Clarity3, StacksEpochId::Epoch30)
+            .map(|(type_sig_opt, _)| type_sig_opt.unwrap())
+    };
+
+    let good =
+        ["(infer-result? 0x0000000000000000000000000000000000000000000000000000000000000000)"];
+    let expected =
+        ["(optional (tuple (output (optional (string-utf8 1024))) (output-hash (buff 32))))"];
+
+    let bad = [
+        "(infer-result?)",
+        "(infer-result? 0x00 0x00)",
+        "(infer-result? u1)",
+    ];
+    let bad_expected = [
+        CheckErrors::IncorrectArgumentCount(1, 0),
+        CheckErrors::IncorrectArgumentCount(1, 2),
+        CheckErrors::TypeError(BUFF_32.clone(), UIntType),
+    ];
+
+    for (good_test, expected) in good.iter().zip(expected.iter()) {
+        assert_eq!(expected, &format!("{}", type_check_v3(good_test).unwrap()));
+        // `infer-result?` is a Clarity 3 keyword
+        assert_eq!(
+            CheckErrors::UnknownFunction("infer-result?".to_string()),
+            mem_run_analysis(good_test, ClarityVersion::Clarity2, StacksEpochId::Epoch30)
+                .unwrap_err()
+                .err
+        );
+    }
+
+    for (bad_test, expected) in bad.iter().zip(bad_expected.iter()) {
+        assert_eq!(expected, &type_check_v3(bad_test).unwrap_err().err);
+    }
+}
+
 #[apply(test_clarity_versions)]
 fn test_define_trait(#[case] version: ClarityVersion, #[case] epoch: StacksEpochId) {
     let good = [
@@ -24,6 +24,7 @@ use crate::vm::analysis::contract_interface_builder::ContractInterface;
 use crate::vm::analysis::errors::{CheckErrors, CheckResult};
 use crate::vm::analysis::type_checker::contexts::TypeMap;
 use crate::vm::costs::{CostTracker, ExecutionCost, LimitedCostTracker};
+use crate::vm::diagnostic::Diagnostic;
 use crate::vm::types::signatures::FunctionSignature;
 use crate::vm::types::{FunctionType, QualifiedContractIdentifier, TraitIdentifier, TypeSignature};
 use crate::vm::{ClarityName, ClarityVersion, SymbolicExpression};
@@ -64,6 +65,9 @@ pub struct ContractAnalysis {
     pub type_map: Option<TypeMap>,
     #[serde(skip)]
     pub cost_track: Option<LimitedCostTracker>,
+    /// Warnings from non-consensus passes, such as `LintChecker`
+    #[serde(skip)]
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl ContractAnalysis {
@@ -90,6 +94,7 @@ impl ContractAnalysis {
             fungible_tokens: BTreeSet::new(),
             non_fungible_tokens: BTreeMap::new(),
             cost_track: Some(cost_track),
+            diagnostics: vec![],
             is_cost_contract_eligible: false,
             epoch,
             clarity_version,
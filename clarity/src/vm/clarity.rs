@@ -9,7 +9,7 @@ use crate::vm::contexts::{AssetMap, Environment, OwnedEnvironment};
 use crate::vm::costs::{ExecutionCost, LimitedCostTracker};
 use crate::vm::database::ClarityDatabase;
 use crate::vm::errors::Error as InterpreterError;
-use crate::vm::events::StacksTransactionEvent;
+use crate::vm::events::{InferSessionLink, StacksTransactionEvent};
 use crate::vm::types::{BuffData, PrincipalData, QualifiedContractIdentifier};
 use crate::vm::{analysis, ast, ClarityVersion, ContractContext, SymbolicExpression, Value};
 
@@ -276,15 +276,19 @@ pub trait TransactionConnection: ClarityConnection {
     }
 
     /// Execute a infer in the current block.
+    /// Will throw an error if `payment` spends STX that the 'from' principal doesn't have.
     fn run_stx_infer(
         &mut self,
         from: &PrincipalData,
         infer_output_hash: &BuffData,
+        cost_units: u64,
+        payment: Option<(&PrincipalData, u128)>,
+        session: Option<&InferSessionLink>,
     ) -> Result<(Value, AssetMap, Vec<StacksTransactionEvent>), Error> {
         self.with_abort_callback(
             |vm_env| {
                 vm_env
-                    .stx_infer(from, infer_output_hash)
+                    .stx_infer(from, infer_output_hash, cost_units, payment, session)
                     .map_err(Error::from)
             },
             |_, _| false,
@@ -731,9 +731,12 @@ impl<'a, 'hooks> OwnedEnvironment<'a, 'hooks> {
         &mut self,
         from: &PrincipalData,
         infer_output_hash: &BuffData,
+        cost_units: u64,
+        payment: Option<(&PrincipalData, u128)>,
+        session: Option<&InferSessionLink>,
     ) -> Result<(Value, AssetMap, Vec<StacksTransactionEvent>)> {
         self.execute_in_env(from.clone(), None, None, |exec_env| {
-            exec_env.stx_infer(from,infer_output_hash)
+            exec_env.stx_infer(from, infer_output_hash, cost_units, payment, session)
         })
     }
 
@@ -1365,13 +1368,20 @@ impl<'a, 'b, 'hooks> Environment<'a, 'b, 'hooks> {
         }
     }
 
+    /// Top-level inference commitment, invoked by Infer transactions.
+    /// Like `stx_transfer()`, only commits if the inner stx_infer_consolidated() returns an
+    /// (ok ..) value, i.e. if the sender could afford the payment (if any).
     pub fn stx_infer(
         &mut self,
         from: &PrincipalData,
         infer_out_hash: &BuffData,
+        cost_units: u64,
+        payment: Option<(&PrincipalData, u128)>,
+        session: Option<&InferSessionLink>,
     ) -> Result<Value> {
         self.global_context.begin();
-        let result = stx_infer_consolidated(self, from, infer_out_hash);
+        let result =
+            stx_infer_consolidated(self, from, infer_out_hash, cost_units, payment, session);
         match result {
             Ok(value) => match value.clone().expect_result()? {
                 Ok(_) => {
@@ -1460,10 +1470,12 @@ impl<'a, 'b, 'hooks> Environment<'a, 'b, 'hooks> {
         &mut self,
         sender: PrincipalData,
         infer_out_hash: BuffData,
+        session: Option<InferSessionLink>,
     ) -> Result<()> {
         let event_data = STXInferEventData {
             sender,
             infer_out_hash,
+            session,
         };
         let event = StacksTransactionEvent::STXEvent(STXEventType::STXInferEvent(event_data));
 
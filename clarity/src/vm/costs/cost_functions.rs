@@ -154,5 +154,7 @@ define_named_enum!(ClarityCostFunction {
     BitwiseNot("cost_bitwise_not"),
     BitwiseLShift("cost_bitwise_left_shift"),
     BitwiseRShift("cost_bitwise_right_shift"),
+    StxInfer("cost_stx_infer"),
+    InferResult("cost_infer_result"),
     Unimplemented("cost_unimplemented"),
 });
@@ -7,11 +7,103 @@ use serde_json::Value as JsonValue;
 
 use super::functions::define::DefineFunctionsParsed;
 use super::EvalHook;
+use crate::vm::contexts::ContractContext;
+use crate::vm::errors::Error;
 use crate::vm::types::QualifiedContractIdentifier;
-use crate::vm::SymbolicExpression;
+use crate::vm::{ClarityName, SymbolicExpression, Value};
 
 pub struct CoverageReporter {
     executed_lines: HashMap<QualifiedContractIdentifier, HashMap<u32, u64>>,
+    /// How often each arm of each branch was taken, keyed by the branch's line and column and
+    ///  the arm's index
+    executed_branches: HashMap<QualifiedContractIdentifier, HashMap<(u32, u32, u32), u64>>,
+    executed_functions: HashMap<QualifiedContractIdentifier, HashMap<ClarityName, u64>>,
+    /// Where each function's body starts, along with how many functions the contract had when
+    ///  this was worked out: functions are defined one by one while a contract is launched
+    #[cfg(feature = "developer-mode")]
+    function_bodies:
+        HashMap<QualifiedContractIdentifier, (usize, HashMap<(u32, u32), ClarityName>)>,
+    /// The branching expressions being evaluated, innermost last
+    open_branches: Vec<OpenBranch>,
+}
+
+/// The expressions that take one of two arms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum BranchKind {
+    If,
+    Match,
+    Asserts,
+    Unwrap,
+    UnwrapErr,
+    Try,
+}
+
+impl BranchKind {
+    /// The kind of branch `expr` is, if any, and its arguments
+    fn of(expr: &SymbolicExpression) -> Option<(BranchKind, &[SymbolicExpression])> {
+        let (function, args) = expr.match_list()?.split_first()?;
+        let kind = match function.match_atom()?.as_str() {
+            "if" => BranchKind::If,
+            "match" => BranchKind::Match,
+            "asserts!" => BranchKind::Asserts,
+            "unwrap!" => BranchKind::Unwrap,
+            "unwrap-err!" => BranchKind::UnwrapErr,
+            "try!" => BranchKind::Try,
+            _ => return None,
+        };
+        Some((kind, args))
+    }
+
+    /// The arms of an `if` or `match`, which are taken by evaluating them.  The other kinds of
+    ///  branch take their second arm by exiting early.
+    #[cfg(feature = "developer-mode")]
+    fn evaluated_arms(self, args: &[SymbolicExpression]) -> Option<[&SymbolicExpression; 2]> {
+        match (self, args) {
+            (BranchKind::If, [_, then_branch, else_branch]) => Some([then_branch, else_branch]),
+            (BranchKind::Match, [_, _, some_branch, none_branch]) => {
+                Some([some_branch, none_branch])
+            }
+            (BranchKind::Match, [_, _, ok_branch, _, err_branch]) => Some([ok_branch, err_branch]),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            BranchKind::If => "if",
+            BranchKind::Match => "match",
+            BranchKind::Asserts => "asserts!",
+            BranchKind::Unwrap => "unwrap!",
+            BranchKind::UnwrapErr => "unwrap-err!",
+            BranchKind::Try => "try!",
+        }
+    }
+
+    fn arm_names(self) -> [&'static str; 2] {
+        match self {
+            BranchKind::If => ["then", "else"],
+            BranchKind::Match => ["some/ok", "none/err"],
+            BranchKind::Asserts => ["passed", "failed"],
+            BranchKind::Unwrap | BranchKind::UnwrapErr => ["unwrapped", "thrown"],
+            BranchKind::Try => ["unwrapped", "returned"],
+        }
+    }
+}
+
+/// A branching expression whose arm hasn't been recorded yet.  These are only opened with the
+///  `developer-mode` spans.
+#[cfg_attr(not(feature = "developer-mode"), allow(dead_code))]
+struct OpenBranch {
+    id: u64,
+    contract: QualifiedContractIdentifier,
+    position: (u32, u32),
+    /// Where each arm starts, if the branch takes an arm by evaluating it
+    arms: Option<[(u32, u32); 2]>,
+}
+
+fn position(expr: &SymbolicExpression) -> (u32, u32) {
+    let span = expr.span();
+    (span.start_line, span.start_column)
 }
 
 #[derive(Serialize, Deserialize)]
@@ -19,17 +111,125 @@ struct ContractFileInfo {
     contract: String,
     src_file: String,
     executable_lines: Vec<u32>,
+    #[serde(default)]
+    branches: Vec<BranchInfo>,
+    #[serde(default)]
+    functions: Vec<FunctionInfo>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BranchInfo {
+    line: u32,
+    column: u32,
+    kind: BranchKind,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FunctionInfo {
+    name: String,
+    line: u32,
 }
 
 #[derive(Serialize, Deserialize)]
 struct CoverageFileInfo {
     coverage: HashMap<String, Vec<(u32, u64)>>,
+    /// (line, column, arm, count) for each branch arm taken
+    #[serde(default)]
+    branches: HashMap<String, Vec<(u32, u32, u32, u64)>>,
+    #[serde(default)]
+    functions: HashMap<String, Vec<(String, u64)>>,
+}
+
+/// The coverage of one contract, summed over every run
+struct ContractCoverage {
+    info: ContractFileInfo,
+    lines: BTreeMap<u32, u64>,
+    branches: BTreeMap<(u32, u32, u32), u64>,
+    functions: BTreeMap<String, u64>,
 }
 
+impl ContractCoverage {
+    fn arm_counts(&self, branch: &BranchInfo) -> [u64; 2] {
+        [0, 1].map(|arm| {
+            self.branches
+                .get(&(branch.line, branch.column, arm))
+                .cloned()
+                .unwrap_or(0)
+        })
+    }
+
+    fn function_count(&self, function: &FunctionInfo) -> u64 {
+        self.functions.get(&function.name).cloned().unwrap_or(0)
+    }
+
+    fn lines_hit(&self) -> usize {
+        self.info
+            .executable_lines
+            .iter()
+            .filter(|line| self.lines.get(*line).map_or(false, |count| *count > 0))
+            .count()
+    }
+
+    fn functions_hit(&self) -> usize {
+        self.info
+            .functions
+            .iter()
+            .filter(|function| self.function_count(function) > 0)
+            .count()
+    }
+
+    fn arms_hit(&self) -> usize {
+        self.info
+            .branches
+            .iter()
+            .flat_map(|branch| self.arm_counts(branch))
+            .filter(|count| *count > 0)
+            .count()
+    }
+}
+
+fn percent(hit: usize, total: usize) -> String {
+    if total == 0 {
+        return "-".into();
+    }
+    format!(
+        "{:.1}% ({}/{})",
+        100.0 * hit as f64 / total as f64,
+        hit,
+        total
+    )
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const HTML_STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { padding: 0 0.6em; text-align: left; }
+table.summary td, table.summary th, table.functions td, table.functions th { border-bottom: 1px solid #ddd; }
+table.source { font-family: monospace; width: 100%; }
+table.source td { white-space: pre; vertical-align: top; }
+td.num { color: #888; text-align: right; }
+td.branches { color: #555; font-size: 90%; }
+tr.hit { background: #dfd; }
+tr.miss { background: #fdd; }
+tr.partial { background: #ffd; }
+";
+
 impl CoverageReporter {
     pub fn new() -> CoverageReporter {
         CoverageReporter {
             executed_lines: HashMap::new(),
+            executed_branches: HashMap::new(),
+            executed_functions: HashMap::new(),
+            #[cfg(feature = "developer-mode")]
+            function_bodies: HashMap::new(),
+            open_branches: vec![],
         }
     }
 
@@ -70,6 +270,109 @@ impl CoverageReporter {
         }
     }
 
+    #[cfg(not(feature = "developer-mode"))]
+    fn report_branches(&mut self, _contract_context: &ContractContext, _expr: &SymbolicExpression) {
+    }
+
+    /// Record function calls and evaluated branch arms, and start tracking `expr` if it's a
+    ///  branch.  These are matched up by source position, so need the `developer-mode` spans.
+    #[cfg(feature = "developer-mode")]
+    fn report_branches(&mut self, contract_context: &ContractContext, expr: &SymbolicExpression) {
+        let contract = &contract_context.contract_identifier;
+        let expr_position = position(expr);
+
+        let taken_arm = self
+            .open_branches
+            .last()
+            .filter(|branch| branch.contract == *contract)
+            .and_then(|branch| {
+                let arms = branch.arms?;
+                let arm = arms.iter().position(|arm| *arm == expr_position)?;
+                Some((branch.position, arm as u32))
+            });
+        if let Some((branch_position, arm)) = taken_arm {
+            self.record_branch(contract, branch_position, arm);
+        }
+
+        let functions = &contract_context.functions;
+        let (function_count, bodies) = self
+            .function_bodies
+            .entry(contract.clone())
+            .or_insert_with(|| (0, HashMap::new()));
+        if *function_count != functions.len() {
+            *bodies = functions
+                .iter()
+                .map(|(name, function)| {
+                    let span = function.get_span();
+                    ((span.start_line, span.start_column), name.clone())
+                })
+                .collect();
+            *function_count = functions.len();
+        }
+        if let Some(name) = bodies.get(&expr_position) {
+            *self
+                .executed_functions
+                .entry(contract.clone())
+                .or_default()
+                .entry(name.clone())
+                .or_insert(0) += 1;
+        }
+
+        if let Some((kind, args)) = BranchKind::of(expr) {
+            self.open_branches.push(OpenBranch {
+                id: expr.id,
+                contract: contract.clone(),
+                position: expr_position,
+                arms: kind.evaluated_arms(args).map(|arms| arms.map(position)),
+            });
+        }
+    }
+
+    /// Finish tracking the branch `expr`, if it is one, and record which arm an early exit
+    ///  decided.
+    fn report_branch_result(
+        &mut self,
+        contract: &QualifiedContractIdentifier,
+        expr: &SymbolicExpression,
+        res: &Result<Value, Error>,
+    ) {
+        // an expression that failed early may not have reported finishing, so unwind to this one
+        let Some(index) = self
+            .open_branches
+            .iter()
+            .rposition(|branch| branch.id == expr.id && branch.contract == *contract)
+        else {
+            return;
+        };
+        self.open_branches.truncate(index + 1);
+        let Some(branch) = self.open_branches.pop() else {
+            return;
+        };
+        if branch.arms.is_some() {
+            return;
+        }
+        let arm = match res {
+            Ok(_) => 0,
+            Err(Error::ShortReturn(_)) => 1,
+            Err(_) => return,
+        };
+        self.record_branch(contract, branch.position, arm);
+    }
+
+    fn record_branch(
+        &mut self,
+        contract: &QualifiedContractIdentifier,
+        (line, column): (u32, u32),
+        arm: u32,
+    ) {
+        *self
+            .executed_branches
+            .entry(contract.clone())
+            .or_default()
+            .entry((line, column, arm))
+            .or_insert(0) += 1;
+    }
+
     pub fn to_file<P: AsRef<std::path::Path> + Copy>(&self, filename: P) -> std::io::Result<()> {
         let f = File::create(filename)?;
         let iter = self.executed_lines.iter();
@@ -85,7 +388,31 @@ impl CoverageReporter {
             coverage.insert(contract.to_string(), executed_lines);
         }
 
-        let out = CoverageFileInfo { coverage };
+        let mut branches = HashMap::with_capacity(self.executed_branches.len());
+        for (contract, execution_map) in self.executed_branches.iter() {
+            let mut executed_arms = execution_map
+                .iter()
+                .map(|((line, column, arm), count)| (*line, *column, *arm, *count))
+                .collect::<Vec<_>>();
+            executed_arms.sort();
+            branches.insert(contract.to_string(), executed_arms);
+        }
+
+        let mut functions = HashMap::with_capacity(self.executed_functions.len());
+        for (contract, execution_map) in self.executed_functions.iter() {
+            let mut executed_functions = execution_map
+                .iter()
+                .map(|(name, count)| (name.to_string(), *count))
+                .collect::<Vec<_>>();
+            executed_functions.sort();
+            functions.insert(contract.to_string(), executed_functions);
+        }
+
+        let out = CoverageFileInfo {
+            coverage,
+            branches,
+            functions,
+        };
         if let Err(e) = serde_json::to_writer(f, &out) {
             error!(
                 "Failed to serialize JSON to coverage file {}: {}",
@@ -98,60 +425,69 @@ impl CoverageReporter {
         Ok(())
     }
 
-    fn executable_lines(exprs: &[SymbolicExpression]) -> Vec<u32> {
-        let mut lines = vec![];
-        let mut lines_seen = HashSet::new();
-        for expression in exprs.iter() {
-            let mut frontier = vec![expression];
-            while let Some(cur_expr) = frontier.pop() {
-                // handle defines: the `define-` atom is non executable, and neither are any of the type arguments,
-                //  but the bodies of functions, the value of a constant, initial values for variables, and the
-                //  max supply of FTs
-                if let Some(define_expr) = DefineFunctionsParsed::try_parse(cur_expr).ok().flatten()
-                {
-                    match define_expr {
-                        DefineFunctionsParsed::Constant { name: _, value } => {
-                            frontier.push(value);
-                        }
-                        DefineFunctionsParsed::PrivateFunction { signature: _, body }
-                        | DefineFunctionsParsed::PublicFunction { signature: _, body }
-                        | DefineFunctionsParsed::ReadOnlyFunction { signature: _, body } => {
-                            frontier.push(body);
-                        }
-                        DefineFunctionsParsed::BoundedFungibleToken {
-                            name: _,
-                            max_supply,
-                        } => {
-                            frontier.push(max_supply);
-                        }
-                        DefineFunctionsParsed::PersistedVariable {
-                            name: _,
-                            data_type: _,
-                            initial,
-                        } => {
-                            frontier.push(initial);
-                        }
-                        DefineFunctionsParsed::NonFungibleToken { .. } => {}
-                        DefineFunctionsParsed::UnboundedFungibleToken { .. } => {}
-                        DefineFunctionsParsed::Map { .. } => {}
-                        DefineFunctionsParsed::Trait { .. } => {}
-                        DefineFunctionsParsed::UseTrait { .. } => {}
-                        DefineFunctionsParsed::ImplTrait { .. } => {}
+    /// Every expression that is evaluated when the contract runs, outermost first: the
+    ///  `define-` atoms and the type arguments of definitions are skipped.
+    fn executable_exprs(exprs: &[SymbolicExpression]) -> Vec<&SymbolicExpression> {
+        let mut executable = vec![];
+        let mut frontier: Vec<&SymbolicExpression> = exprs.iter().rev().collect();
+        while let Some(cur_expr) = frontier.pop() {
+            // handle defines: the `define-` atom is non executable, and neither are any of the type arguments,
+            //  but the bodies of functions, the value of a constant, initial values for variables, and the
+            //  max supply of FTs
+            if let Some(define_expr) = DefineFunctionsParsed::try_parse(cur_expr).ok().flatten() {
+                match define_expr {
+                    DefineFunctionsParsed::Constant { name: _, value } => {
+                        frontier.push(value);
                     }
-
-                    continue;
-                }
-
-                if let Some(children) = cur_expr.match_list() {
-                    // don't count list expressions as a whole, just their children
-                    frontier.extend(children);
-                } else {
-                    let line = cur_expr.span().start_line;
-                    if !lines_seen.contains(&line) {
-                        lines_seen.insert(line);
-                        lines.push(line);
+                    DefineFunctionsParsed::PrivateFunction { signature: _, body }
+                    | DefineFunctionsParsed::PublicFunction { signature: _, body }
+                    | DefineFunctionsParsed::ReadOnlyFunction { signature: _, body } => {
+                        frontier.push(body);
+                    }
+                    DefineFunctionsParsed::BoundedFungibleToken {
+                        name: _,
+                        max_supply,
+                    } => {
+                        frontier.push(max_supply);
+                    }
+                    DefineFunctionsParsed::PersistedVariable {
+                        name: _,
+                        data_type: _,
+                        initial,
+                    } => {
+                        frontier.push(initial);
                     }
+                    DefineFunctionsParsed::NonFungibleToken { .. } => {}
+                    DefineFunctionsParsed::UnboundedFungibleToken { .. } => {}
+                    DefineFunctionsParsed::Map { .. } => {}
+                    DefineFunctionsParsed::Trait { .. } => {}
+                    DefineFunctionsParsed::UseTrait { .. } => {}
+                    DefineFunctionsParsed::ImplTrait { .. } => {}
                 }
+
+                continue;
+            }
+
+            executable.push(cur_expr);
+            if let Some(children) = cur_expr.match_list() {
+                frontier.extend(children.iter().rev());
+            }
+        }
+        executable
+    }
+
+    fn executable_lines(exprs: &[SymbolicExpression]) -> Vec<u32> {
+        let mut lines = vec![];
+        let mut lines_seen = HashSet::new();
+        for cur_expr in CoverageReporter::executable_exprs(exprs) {
+            // don't count list expressions as a whole, just their children
+            if cur_expr.match_list().is_some() {
+                continue;
+            }
+            let line = cur_expr.span().start_line;
+            if !lines_seen.contains(&line) {
+                lines_seen.insert(line);
+                lines.push(line);
             }
         }
 
@@ -159,6 +495,38 @@ impl CoverageReporter {
         lines
     }
 
+    fn executable_branches(exprs: &[SymbolicExpression]) -> Vec<BranchInfo> {
+        let mut branches: Vec<BranchInfo> = CoverageReporter::executable_exprs(exprs)
+            .into_iter()
+            .filter_map(|expr| {
+                let (kind, _) = BranchKind::of(expr)?;
+                let (line, column) = position(expr);
+                Some(BranchInfo { line, column, kind })
+            })
+            .collect();
+        branches.sort_by_key(|branch| (branch.line, branch.column));
+        branches
+    }
+
+    fn functions(exprs: &[SymbolicExpression]) -> Vec<FunctionInfo> {
+        exprs
+            .iter()
+            .filter_map(|expr| {
+                let signature = match DefineFunctionsParsed::try_parse(expr).ok().flatten()? {
+                    DefineFunctionsParsed::PrivateFunction { signature, .. }
+                    | DefineFunctionsParsed::PublicFunction { signature, .. }
+                    | DefineFunctionsParsed::ReadOnlyFunction { signature, .. } => signature,
+                    _ => return None,
+                };
+                let name = signature.first()?.match_atom()?;
+                Some(FunctionInfo {
+                    name: name.to_string(),
+                    line: expr.span().start_line,
+                })
+            })
+            .collect()
+    }
+
     pub fn register_src_file<P: AsRef<std::path::Path> + Copy>(
         contract: &QualifiedContractIdentifier,
         src_file_name: &str,
@@ -173,6 +541,8 @@ impl CoverageReporter {
             contract: contract.to_string(),
             src_file: src_file_name.to_string(),
             executable_lines,
+            branches: CoverageReporter::executable_branches(ast),
+            functions: CoverageReporter::functions(ast),
         };
 
         if let Err(e) = serde_json::to_writer(f, &json) {
@@ -186,43 +556,241 @@ impl CoverageReporter {
         Ok(())
     }
 
-    pub fn produce_lcov<P: AsRef<std::path::Path>>(
-        out_filename: &str,
+    /// Sum the coverage in `coverage_files` for each contract registered in `register_files`
+    fn merge_coverage<P: AsRef<std::path::Path>>(
         register_files: &[P],
         coverage_files: &[P],
-    ) -> std::io::Result<()> {
-        let mut out = File::create(out_filename)?;
+    ) -> std::io::Result<Vec<ContractCoverage>> {
+        let mut runs = Vec::with_capacity(coverage_files.len());
+        for coverage_filename in coverage_files.iter() {
+            let cov_reader = File::open(coverage_filename)?;
+            let coverage: CoverageFileInfo = serde_json::from_reader(cov_reader)?;
+            runs.push(coverage);
+        }
 
+        let mut merged = vec![];
         for contract_filename in register_files.iter() {
             let reader = File::open(contract_filename)?;
             let info: ContractFileInfo = serde_json::from_reader(reader)?;
-            let mut summed_coverage = BTreeMap::new();
-            for coverage_filename in coverage_files.iter() {
-                let cov_reader = File::open(coverage_filename)?;
-                let coverage: CoverageFileInfo = serde_json::from_reader(cov_reader)?;
-                if let Some(contract_coverage) = coverage.coverage.get(&info.contract) {
-                    for (line, count) in contract_coverage.iter() {
-                        if let Some(line_count) = summed_coverage.get_mut(line) {
-                            *line_count += *count;
-                        } else {
-                            summed_coverage.insert(*line, *count);
-                        }
-                    }
+            let mut lines = BTreeMap::new();
+            let mut branches = BTreeMap::new();
+            let mut functions = BTreeMap::new();
+            for run in runs.iter() {
+                for (line, count) in run.coverage.get(&info.contract).into_iter().flatten() {
+                    *lines.entry(*line).or_insert(0) += *count;
+                }
+                for (line, column, arm, count) in
+                    run.branches.get(&info.contract).into_iter().flatten()
+                {
+                    *branches.entry((*line, *column, *arm)).or_insert(0) += *count;
+                }
+                for (name, count) in run.functions.get(&info.contract).into_iter().flatten() {
+                    *functions.entry(name.clone()).or_insert(0) += *count;
                 }
             }
+            merged.push(ContractCoverage {
+                info,
+                lines,
+                branches,
+                functions,
+            });
+        }
+        Ok(merged)
+    }
+
+    pub fn produce_lcov<P: AsRef<std::path::Path>>(
+        out_filename: &str,
+        register_files: &[P],
+        coverage_files: &[P],
+    ) -> std::io::Result<()> {
+        let mut out = File::create(out_filename)?;
+
+        for coverage in CoverageReporter::merge_coverage(register_files, coverage_files)? {
+            let info = &coverage.info;
             writeln!(out, "TN:{}", &info.contract)?;
             writeln!(out, "SF:{}", &info.src_file)?;
+
+            for function in info.functions.iter() {
+                writeln!(out, "FN:{},{}", function.line, function.name)?;
+            }
+            for function in info.functions.iter() {
+                writeln!(
+                    out,
+                    "FNDA:{},{}",
+                    coverage.function_count(function),
+                    function.name
+                )?;
+            }
+            writeln!(out, "FNF:{}", info.functions.len())?;
+            writeln!(out, "FNH:{}", coverage.functions_hit())?;
+
+            for (block, branch) in info.branches.iter().enumerate() {
+                let counts = coverage.arm_counts(branch);
+                // a branch which was never reached has no arm counts at all
+                let reached = counts.iter().any(|count| *count > 0);
+                for (arm, count) in counts.iter().enumerate() {
+                    if reached {
+                        writeln!(out, "BRDA:{},{},{},{}", branch.line, block, arm, count)?;
+                    } else {
+                        writeln!(out, "BRDA:{},{},{},-", branch.line, block, arm)?;
+                    }
+                }
+            }
+            writeln!(out, "BRF:{}", 2 * info.branches.len())?;
+            writeln!(out, "BRH:{}", coverage.arms_hit())?;
+
             for line in info.executable_lines.iter() {
-                let count = summed_coverage.get(line).cloned().unwrap_or(0);
+                let count = coverage.lines.get(line).cloned().unwrap_or(0);
                 writeln!(out, "DA:{},{}", line, count)?;
             }
-            writeln!(out, "LH:{}", summed_coverage.len())?;
+            writeln!(out, "LH:{}", coverage.lines.len())?;
             writeln!(out, "LF:{}", &info.executable_lines.len())?;
             writeln!(out, "end_of_record")?;
         }
 
         Ok(())
     }
+
+    /// Write a self-contained HTML report: a summary of every contract, then each contract's
+    ///  functions and its source annotated with line and branch counts.
+    pub fn produce_html<P: AsRef<std::path::Path>>(
+        out_filename: &str,
+        register_files: &[P],
+        coverage_files: &[P],
+    ) -> std::io::Result<()> {
+        let contracts = CoverageReporter::merge_coverage(register_files, coverage_files)?;
+        let mut out = File::create(out_filename)?;
+
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html><head><meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>Clarity coverage</title>")?;
+        writeln!(out, "<style>{}</style>", HTML_STYLE)?;
+        writeln!(out, "</head><body>")?;
+        writeln!(out, "<h1>Clarity coverage</h1>")?;
+
+        writeln!(out, "<table class=\"summary\">")?;
+        writeln!(
+            out,
+            "<tr><th>Contract</th><th>Lines</th><th>Functions</th><th>Branches</th></tr>"
+        )?;
+        for (i, coverage) in contracts.iter().enumerate() {
+            let info = &coverage.info;
+            writeln!(
+                out,
+                "<tr><td><a href=\"#contract-{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                i,
+                html_escape(&info.contract),
+                percent(coverage.lines_hit(), info.executable_lines.len()),
+                percent(coverage.functions_hit(), info.functions.len()),
+                percent(coverage.arms_hit(), 2 * info.branches.len()),
+            )?;
+        }
+        writeln!(out, "</table>")?;
+
+        for (i, coverage) in contracts.iter().enumerate() {
+            let info = &coverage.info;
+            writeln!(
+                out,
+                "<h2 id=\"contract-{}\">{}</h2>\n<p>{}</p>",
+                i,
+                html_escape(&info.contract),
+                html_escape(&info.src_file)
+            )?;
+
+            if !info.functions.is_empty() {
+                writeln!(out, "<table class=\"functions\">")?;
+                writeln!(out, "<tr><th>Function</th><th>Line</th><th>Calls</th></tr>")?;
+                for function in info.functions.iter() {
+                    let count = coverage.function_count(function);
+                    writeln!(
+                        out,
+                        "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td></tr>",
+                        if count > 0 { "hit" } else { "miss" },
+                        html_escape(&function.name),
+                        function.line,
+                        count
+                    )?;
+                }
+                writeln!(out, "</table>")?;
+            }
+
+            let source = match std::fs::read_to_string(&info.src_file) {
+                Ok(source) => source,
+                Err(e) => {
+                    writeln!(
+                        out,
+                        "<p>Could not read the source file: {}</p>",
+                        html_escape(&e.to_string())
+                    )?;
+                    continue;
+                }
+            };
+
+            let mut branches_by_line: BTreeMap<u32, Vec<&BranchInfo>> = BTreeMap::new();
+            for branch in info.branches.iter() {
+                branches_by_line
+                    .entry(branch.line)
+                    .or_default()
+                    .push(branch);
+            }
+            let executable: HashSet<u32> = info.executable_lines.iter().cloned().collect();
+
+            writeln!(out, "<table class=\"source\">")?;
+            for (index, text) in source.lines().enumerate() {
+                let line = index as u32 + 1;
+                let count = coverage.lines.get(&line).cloned().unwrap_or(0);
+                let branches = branches_by_line.get(&line);
+                let branch_counts: Vec<(&BranchInfo, [u64; 2])> = branches
+                    .into_iter()
+                    .flatten()
+                    .map(|branch| (*branch, coverage.arm_counts(branch)))
+                    .collect();
+
+                let class = if !executable.contains(&line) {
+                    ""
+                } else if count == 0 {
+                    "miss"
+                } else if branch_counts.iter().any(|(_, counts)| counts.contains(&0)) {
+                    "partial"
+                } else {
+                    "hit"
+                };
+                let count_text = if executable.contains(&line) {
+                    count.to_string()
+                } else {
+                    String::new()
+                };
+                let branch_text: Vec<String> = branch_counts
+                    .iter()
+                    .map(|(branch, counts)| {
+                        let [first, second] = branch.kind.arm_names();
+                        format!(
+                            "{}: {} {}, {} {}",
+                            branch.kind.name(),
+                            first,
+                            counts[0],
+                            second,
+                            counts[1]
+                        )
+                    })
+                    .collect();
+
+                writeln!(
+                    out,
+                    "<tr class=\"{}\"><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"branches\">{}</td><td>{}</td></tr>",
+                    class,
+                    line,
+                    count_text,
+                    html_escape(&branch_text.join("; ")),
+                    html_escape(text)
+                )?;
+            }
+            writeln!(out, "</table>")?;
+        }
+
+        writeln!(out, "</body></html>")?;
+        Ok(())
+    }
 }
 
 impl EvalHook for CoverageReporter {
@@ -233,15 +801,17 @@ impl EvalHook for CoverageReporter {
         expr: &SymbolicExpression,
     ) {
         self.report_eval(expr, &env.contract_context.contract_identifier);
+        self.report_branches(env.contract_context, expr);
     }
 
     fn did_finish_eval(
         &mut self,
-        _env: &mut crate::vm::Environment,
+        env: &mut crate::vm::Environment,
         _context: &crate::vm::LocalContext,
-        _expr: &SymbolicExpression,
-        _res: &core::result::Result<crate::vm::Value, crate::vm::errors::Error>,
+        expr: &SymbolicExpression,
+        res: &core::result::Result<crate::vm::Value, crate::vm::errors::Error>,
     ) {
+        self.report_branch_result(&env.contract_context.contract_identifier, expr, res);
     }
 
     fn did_complete(
@@ -250,3 +820,128 @@ impl EvalHook for CoverageReporter {
     ) {
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rand::Rng;
+
+    use super::*;
+
+    fn write_tmp(name: &str, content: &str) -> String {
+        let path = format!("/tmp/{}_{}", rand::thread_rng().gen::<u32>(), name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    /// A contract with `(define-public (check (x uint)) (if (> x u1) (ok x) (err u2)))` on line 2,
+    ///  its branch on line 3, and a private function that's never called
+    fn register_file(src_file: &str) -> String {
+        write_tmp(
+            "check.clarcovref",
+            &json!({
+                "contract": "S1G2081040G2081040G2081040G208105NK8PE5.check",
+                "src_file": src_file,
+                "executable_lines": [3, 4, 5, 6],
+                "branches": [{ "line": 3, "column": 3, "kind": "If" }],
+                "functions": [
+                    { "name": "check", "line": 2 },
+                    { "name": "unused", "line": 6 },
+                ],
+            })
+            .to_string(),
+        )
+    }
+
+    #[test]
+    fn test_merged_lcov() {
+        let register = register_file("check.clar");
+        let first_run = write_tmp(
+            "first.clarcov",
+            &json!({
+                "coverage": { "S1G2081040G2081040G2081040G208105NK8PE5.check": [[3, 1], [4, 1]] },
+                "branches": { "S1G2081040G2081040G2081040G208105NK8PE5.check": [[3, 3, 0, 1]] },
+                "functions": { "S1G2081040G2081040G2081040G208105NK8PE5.check": [["check", 1]] },
+            })
+            .to_string(),
+        );
+        // files written before branch and function coverage have line counts only
+        let second_run = write_tmp(
+            "second.clarcov",
+            &json!({
+                "coverage": { "S1G2081040G2081040G2081040G208105NK8PE5.check": [[3, 2], [5, 1]] },
+            })
+            .to_string(),
+        );
+        let lcov = format!("/tmp/{}_coverage.lcov", rand::thread_rng().gen::<u32>());
+
+        CoverageReporter::produce_lcov(
+            &lcov,
+            &[register.as_str()],
+            &[first_run.as_str(), second_run.as_str()],
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(&lcov).unwrap(),
+            "TN:S1G2081040G2081040G2081040G208105NK8PE5.check
+SF:check.clar
+FN:2,check
+FN:6,unused
+FNDA:1,check
+FNDA:0,unused
+FNF:2
+FNH:1
+BRDA:3,0,0,1
+BRDA:3,0,1,0
+BRF:2
+BRH:1
+DA:3,3
+DA:4,1
+DA:5,1
+DA:6,0
+LH:3
+LF:4
+end_of_record
+"
+        );
+    }
+
+    #[test]
+    fn test_html_report() {
+        let src_file = write_tmp(
+            "check.clar",
+            "
+(define-public (check (x uint))
+  (if (> x u1)
+    (ok x)
+    (err u2)))
+(define-private (unused) (< u1 u2))
+",
+        );
+        let register = register_file(&src_file);
+        let run = write_tmp(
+            "run.clarcov",
+            &json!({
+                "coverage": { "S1G2081040G2081040G2081040G208105NK8PE5.check": [[3, 1], [4, 1]] },
+                "branches": { "S1G2081040G2081040G2081040G208105NK8PE5.check": [[3, 3, 0, 1]] },
+                "functions": { "S1G2081040G2081040G2081040G208105NK8PE5.check": [["check", 1]] },
+            })
+            .to_string(),
+        );
+        let html = format!("/tmp/{}_coverage.html", rand::thread_rng().gen::<u32>());
+
+        CoverageReporter::produce_html(&html, &[register.as_str()], &[run.as_str()]).unwrap();
+        let report = fs::read_to_string(&html).unwrap();
+        assert!(report.contains("<td>50.0% (2/4)</td><td>50.0% (1/2)</td><td>50.0% (1/2)</td>"));
+        assert!(report.contains("<tr class=\"miss\"><td>unused</td><td>6</td><td>0</td></tr>"));
+        // the `if` only took its `then` arm
+        assert!(report.contains(
+            "<tr class=\"partial\"><td class=\"num\">3</td><td class=\"num\">1</td><td class=\"branches\">if: then 1, else 0</td><td>  (if (&gt; x u1)</td></tr>"
+        ));
+        assert!(report.contains(
+            "<tr class=\"miss\"><td class=\"num\">5</td><td class=\"num\">0</td><td class=\"branches\"></td><td>    (err u2)))</td></tr>"
+        ));
+        assert!(report.contains("(define-private (unused) (&lt; u1 u2))"));
+    }
+}
@@ -38,8 +38,8 @@ use crate::vm::contracts::Contract;
 use crate::vm::costs::{CostOverflowingMath, ExecutionCost};
 use crate::vm::database::structures::{
     ClarityDeserializable, ClaritySerializable, ContractMetadata, DataMapMetadata,
-    DataVariableMetadata, FungibleTokenMetadata, NonFungibleTokenMetadata, STXBalance,
-    STXBalanceSnapshot, SimmedBlock,
+    DataVariableMetadata, FungibleTokenMetadata, InferResultRecord, NonFungibleTokenMetadata,
+    STXBalance, STXBalanceSnapshot, SimmedBlock,
 };
 use crate::vm::database::{ClarityBackingStore, RollbackWrapper};
 use crate::vm::errors::{
@@ -1146,6 +1146,26 @@ impl<'a> ClarityDatabase<'a> {
     }
 }
 
+// inference results
+
+impl<'a> ClarityDatabase<'a> {
+    pub fn make_key_for_infer_result(txid: &[u8; 32]) -> String {
+        format!("vm-infer::{}", to_hex(txid))
+    }
+
+    /// Record the result of the Infer transaction `txid`
+    pub fn set_infer_result(&mut self, txid: &[u8; 32], record: &InferResultRecord) -> Result<()> {
+        let key = ClarityDatabase::make_key_for_infer_result(txid);
+        self.put_data(&key, record)
+    }
+
+    /// The result of the Infer transaction `txid`, if it was committed in this fork
+    pub fn get_infer_result(&mut self, txid: &[u8; 32]) -> Result<Option<InferResultRecord>> {
+        let key = ClarityDatabase::make_key_for_infer_result(txid);
+        self.get_data(&key)
+    }
+}
+
 // poison-microblock
 
 impl<'a> ClarityDatabase<'a> {
@@ -25,7 +25,8 @@ pub use self::key_value_wrapper::{RollbackWrapper, RollbackWrapperPersistedLog};
 pub use self::sqlite::SqliteConnection;
 pub use self::structures::{
     ClarityDeserializable, ClaritySerializable, DataMapMetadata, DataVariableMetadata,
-    FungibleTokenMetadata, NonFungibleTokenMetadata, STXBalance,
+    FungibleTokenMetadata, InferResultRecord, NonFungibleTokenMetadata, STXBalance,
+    INFER_RESULT_MAX_OUTPUT_LEN, INFER_RESULT_MAX_SERIALIZED_LEN,
 };
 
 pub mod clarity_db;
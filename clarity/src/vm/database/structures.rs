@@ -120,6 +120,85 @@ pub struct SimmedBlock {
 
 clarity_serializable!(SimmedBlock);
 
+/// The longest inference output (in characters) that is kept on-chain, and so readable with
+/// `infer-result?`.  Longer outputs are only committed by their hash.
+pub const INFER_RESULT_MAX_OUTPUT_LEN: u32 = 1024;
+
+/// The longest an `InferResultRecord` can be once serialized: JSON escapes a control character
+/// in the output as `\uXXXX` (6 bytes), plus room for the hex-encoded hashes and field names.
+/// The costs of writing and reading a record are bounded by this length.
+pub const INFER_RESULT_MAX_SERIALIZED_LEN: u32 = 6 * INFER_RESULT_MAX_OUTPUT_LEN + 256;
+
+/// The result of an Infer transaction, as recorded on-chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InferResultRecord {
+    #[serde(
+        serialize_with = "infer_hash_serialize",
+        deserialize_with = "infer_hash_deserialize"
+    )]
+    pub output_hash: Vec<u8>,
+    pub output: Option<String>,
+    /// The inference's session hash (see `InferSessionLink`), recorded from epoch 3.0 on
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "infer_hash_opt_serialize",
+        deserialize_with = "infer_hash_opt_deserialize"
+    )]
+    pub session_hash: Option<Vec<u8>>,
+}
+
+fn infer_hash_serialize<S: serde::Serializer>(
+    hash: &[u8],
+    s: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    s.serialize_str(&to_hex(hash))
+}
+
+fn infer_hash_deserialize<'de, D: serde::Deserializer<'de>>(
+    d: D,
+) -> std::result::Result<Vec<u8>, D::Error> {
+    let hash_hex = String::deserialize(d)?;
+    hex_bytes(&hash_hex).map_err(serde::de::Error::custom)
+}
+
+fn infer_hash_opt_serialize<S: serde::Serializer>(
+    hash: &Option<Vec<u8>>,
+    s: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match hash {
+        Some(hash) => infer_hash_serialize(hash, s),
+        None => s.serialize_none(),
+    }
+}
+
+fn infer_hash_opt_deserialize<'de, D: serde::Deserializer<'de>>(
+    d: D,
+) -> std::result::Result<Option<Vec<u8>>, D::Error> {
+    let hash_hex: Option<String> = Option::deserialize(d)?;
+    hash_hex
+        .map(|hash_hex| hex_bytes(&hash_hex).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+impl InferResultRecord {
+    /// Record `output`, keeping it only if it's short enough
+    pub fn new(output_hash: Vec<u8>, output: &str) -> InferResultRecord {
+        let output = if output.chars().count() <= INFER_RESULT_MAX_OUTPUT_LEN as usize {
+            Some(output.to_string())
+        } else {
+            None
+        };
+        InferResultRecord {
+            output_hash,
+            output,
+            session_hash: None,
+        }
+    }
+}
+
+clarity_serializable!(InferResultRecord);
+
 clarity_serializable!(PrincipalData);
 clarity_serializable!(i128);
 clarity_serializable!(u128);
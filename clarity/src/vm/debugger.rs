@@ -0,0 +1,839 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! An interactive step debugger for Clarity, driven by `EvalHook`.
+//!
+//! The debugger pauses before evaluating a function application when it reaches a breakpoint
+//! or finishes a step, and then reads commands until told to resume.  While paused, it can show
+//! the local variables in scope, the contract's data-vars and maps, and the value of any
+//! expression evaluated in the paused frame.
+
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use super::EvalHook;
+use crate::vm::ast::{self, ASTRules};
+use crate::vm::contexts::{Environment, LocalContext};
+use crate::vm::errors::{Error, RuntimeErrorType};
+use crate::vm::types::QualifiedContractIdentifier;
+use crate::vm::{eval, ExecutionResult, SymbolicExpression, Value};
+
+/// Longest rendering of an expression shown when pausing on it
+const MAX_EXPR_DISPLAY_LEN: usize = 120;
+
+const HELP: &str = "Commands:
+  s, step              step into the next expression
+  n, next              step over the current expression
+  f, finish            step out of the enclosing expression
+  c, continue          run until the next breakpoint
+  b, break <spec>      break at <line>, <contract>:<line>, or <function-name>
+  d, delete <id>       delete a breakpoint
+  w, watch <expr>      evaluate <expr> every time execution pauses
+  unwatch <id>         stop watching an expression
+  i, info              list breakpoints and watch expressions
+  l, locals            show the local variables in scope
+  vars                 show the contract's data-vars
+  maps                 show the contract's maps
+  map <name> <key>     look up <key> in the map <name>
+  p, print <expr>      evaluate <expr> in the current frame
+  bt, backtrace        show the function calls leading here
+  q, quit              stop debugging and run to completion
+  h, help              show this message
+An empty line repeats the last command.";
+
+/// Where a breakpoint stops execution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BreakpointSpec {
+    /// Before the first expression on a source line, in the named contract or in any contract.
+    /// Line numbers are only known when the VM is built with the `developer-mode` feature.
+    Line { contract: Option<String>, line: u32 },
+    /// Before every application of the named function
+    Function(String),
+}
+
+impl FromStr for BreakpointSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() || s.contains(char::is_whitespace) {
+            return Err(format!("invalid breakpoint '{s}'"));
+        }
+        if let Ok(line) = s.parse::<u32>() {
+            return Ok(BreakpointSpec::Line {
+                contract: None,
+                line,
+            });
+        }
+        if let Some((contract, line)) = s.rsplit_once(':') {
+            let line = line
+                .parse::<u32>()
+                .map_err(|_| format!("invalid line number in breakpoint '{s}'"))?;
+            return Ok(BreakpointSpec::Line {
+                contract: Some(contract.to_string()),
+                line,
+            });
+        }
+        Ok(BreakpointSpec::Function(s.to_string()))
+    }
+}
+
+impl fmt::Display for BreakpointSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BreakpointSpec::Line {
+                contract: None,
+                line,
+            } => write!(f, "line {line}"),
+            BreakpointSpec::Line {
+                contract: Some(contract),
+                line,
+            } => write!(f, "line {contract}:{line}"),
+            BreakpointSpec::Function(name) => write!(f, "function {name}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Breakpoint {
+    id: usize,
+    spec: BreakpointSpec,
+}
+
+/// How far execution runs before the debugger pauses again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepMode {
+    /// Pause at the next expression
+    StepIn,
+    /// Pause at the next expression no deeper than this
+    StepOver(usize),
+    /// Pause at the next expression shallower than this
+    StepOut(usize),
+    /// Pause only at breakpoints
+    Continue,
+    /// Never pause again
+    Detached,
+}
+
+/// A function application being evaluated
+struct Frame {
+    id: u64,
+    contract: QualifiedContractIdentifier,
+    line: Option<u32>,
+    function: String,
+    /// Whether `function` is defined by the contract, rather than native
+    defined: bool,
+}
+
+enum DebuggerInput {
+    Stdin,
+    Reader(Box<dyn BufRead>),
+}
+
+pub struct Debugger {
+    input: DebuggerInput,
+    output: Box<dyn Write>,
+    breakpoints: Vec<Breakpoint>,
+    next_breakpoint_id: usize,
+    watches: Vec<String>,
+    mode: StepMode,
+    stack: Vec<Frame>,
+    /// The contract and line of the last function application begun, so that a line
+    /// breakpoint pauses once per visit to its line rather than once per expression on it
+    last_line: Option<(QualifiedContractIdentifier, u32)>,
+    /// The expression (id and depth) whose result to show when it finishes
+    report_result_of: Option<(u64, usize)>,
+    last_command: String,
+}
+
+impl Debugger {
+    /// A debugger that reads commands from `input` and writes to `output`.  It pauses at the
+    /// first expression evaluated.
+    pub fn new(input: Box<dyn BufRead>, output: Box<dyn Write>) -> Debugger {
+        Debugger::with_input(DebuggerInput::Reader(input), output)
+    }
+
+    /// A debugger that reads commands from stdin and writes to stderr, so it doesn't interleave
+    /// with a program's output on stdout.  Stdin is only read while paused, so it can be shared
+    /// with a REPL.
+    pub fn stdio() -> Debugger {
+        Debugger::with_input(DebuggerInput::Stdin, Box::new(io::stderr()))
+    }
+
+    fn with_input(input: DebuggerInput, output: Box<dyn Write>) -> Debugger {
+        Debugger {
+            input,
+            output,
+            breakpoints: vec![],
+            next_breakpoint_id: 1,
+            watches: vec![],
+            mode: StepMode::StepIn,
+            stack: vec![],
+            last_line: None,
+            report_result_of: None,
+            last_command: String::new(),
+        }
+    }
+
+    /// Don't pause at the first expression; run until a breakpoint instead
+    pub fn run_to_breakpoint(&mut self) {
+        self.mode = StepMode::Continue;
+    }
+
+    /// Add a breakpoint, and return its id
+    pub fn add_breakpoint(&mut self, spec: &str) -> Result<usize, String> {
+        let spec = spec.parse()?;
+        let id = self.next_breakpoint_id;
+        self.next_breakpoint_id += 1;
+        self.breakpoints.push(Breakpoint { id, spec });
+        Ok(id)
+    }
+
+    /// Evaluate `expr` every time execution pauses
+    pub fn add_watch(&mut self, expr: &str) {
+        self.watches.push(expr.trim().to_string());
+    }
+
+    fn read_command(&mut self) -> Option<String> {
+        let _ = write!(self.output, "(debug) ");
+        let _ = self.output.flush();
+        let mut line = String::new();
+        let read = match self.input {
+            DebuggerInput::Stdin => io::stdin().read_line(&mut line),
+            DebuggerInput::Reader(ref mut reader) => reader.read_line(&mut line),
+        };
+        match read {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim().to_string()),
+        }
+    }
+
+    /// Why execution should pause before `expr`, if it should
+    fn pause_reason(&self, frame: &Frame, depth: usize) -> Option<String> {
+        for breakpoint in self.breakpoints.iter() {
+            let hit = match breakpoint.spec {
+                BreakpointSpec::Function(ref name) => frame.function == *name,
+                BreakpointSpec::Line { ref contract, line } => {
+                    frame.line == Some(line)
+                        && contract.as_ref().map_or(true, |contract| {
+                            *contract == frame.contract.to_string()
+                                || *contract == frame.contract.name.as_str()
+                        })
+                        && self.last_line.as_ref() != Some(&(frame.contract.clone(), line))
+                }
+            };
+            if hit {
+                return Some(format!("breakpoint {}", breakpoint.id));
+            }
+        }
+        let stepped = match self.mode {
+            StepMode::StepIn => true,
+            StepMode::StepOver(max_depth) => depth <= max_depth,
+            StepMode::StepOut(max_depth) => depth < max_depth,
+            StepMode::Continue | StepMode::Detached => false,
+        };
+        if stepped {
+            return Some("step".to_string());
+        }
+        None
+    }
+
+    fn pause(
+        &mut self,
+        env: &mut Environment,
+        context: &LocalContext,
+        expr: &SymbolicExpression,
+        reason: &str,
+    ) {
+        self.report_result_of = None;
+        let _ = writeln!(self.output, "[{}] {}", reason, self.location());
+        let _ = writeln!(self.output, "  {}", display_expr(expr));
+        self.show_watches(env, context);
+
+        loop {
+            let Some(mut command) = self.read_command() else {
+                // nobody left to ask
+                self.mode = StepMode::Detached;
+                return;
+            };
+            if command.is_empty() {
+                command = self.last_command.clone();
+            } else {
+                self.last_command = command.clone();
+            }
+            let (verb, arg) = match command.split_once(char::is_whitespace) {
+                Some((verb, arg)) => (verb, arg.trim()),
+                None => (command.as_str(), ""),
+            };
+            let depth = self.stack.len();
+            match verb {
+                "s" | "step" => {
+                    self.mode = StepMode::StepIn;
+                    return;
+                }
+                "n" | "next" => {
+                    self.mode = StepMode::StepOver(depth);
+                    self.report_result_of = Some((expr.id, depth));
+                    return;
+                }
+                "f" | "finish" => {
+                    self.mode = StepMode::StepOut(depth);
+                    self.report_result_of = depth
+                        .checked_sub(2)
+                        .and_then(|parent| self.stack.get(parent))
+                        .map(|frame| (frame.id, depth - 1));
+                    return;
+                }
+                "c" | "continue" => {
+                    self.mode = StepMode::Continue;
+                    return;
+                }
+                "q" | "quit" => {
+                    self.mode = StepMode::Detached;
+                    return;
+                }
+                "b" | "break" => match self.add_breakpoint(arg) {
+                    Ok(id) => {
+                        let _ = writeln!(
+                            self.output,
+                            "Breakpoint {id} at {}",
+                            self.breakpoints
+                                .last()
+                                .expect("BUG: breakpoint just added")
+                                .spec
+                        );
+                    }
+                    Err(e) => {
+                        let _ = writeln!(self.output, "{e}");
+                    }
+                },
+                "d" | "delete" => {
+                    let before = self.breakpoints.len();
+                    if let Ok(id) = arg.parse::<usize>() {
+                        self.breakpoints.retain(|breakpoint| breakpoint.id != id);
+                    }
+                    if self.breakpoints.len() == before {
+                        let _ = writeln!(self.output, "No breakpoint '{arg}'");
+                    }
+                }
+                "w" | "watch" => {
+                    if arg.is_empty() {
+                        let _ = writeln!(self.output, "Usage: watch <expr>");
+                    } else {
+                        self.add_watch(arg);
+                        self.show_watches(env, context);
+                    }
+                }
+                "unwatch" => match arg.parse::<usize>() {
+                    Ok(id) if id >= 1 && id <= self.watches.len() => {
+                        self.watches.remove(id - 1);
+                    }
+                    _ => {
+                        let _ = writeln!(self.output, "No watch expression '{arg}'");
+                    }
+                },
+                "i" | "info" => self.show_info(),
+                "l" | "locals" => self.show_locals(context),
+                "vars" => self.show_data_vars(env),
+                "maps" => self.show_maps(env),
+                "map" => self.show_map_entry(env, context, arg),
+                "p" | "print" => {
+                    let output = match eval_in_frame(env, context, arg) {
+                        Ok(value) => format!("{value}"),
+                        Err(e) => format!("error: {e}"),
+                    };
+                    let _ = writeln!(self.output, "{output}");
+                }
+                "bt" | "backtrace" => self.show_backtrace(),
+                "h" | "help" => {
+                    let _ = writeln!(self.output, "{HELP}");
+                }
+                _ => {
+                    let _ = writeln!(
+                        self.output,
+                        "Unknown command '{verb}'. Type 'help' for a list of commands."
+                    );
+                }
+            }
+        }
+    }
+
+    fn location(&self) -> String {
+        let Some(frame) = self.stack.last() else {
+            return "<top level>".to_string();
+        };
+        match frame.line {
+            Some(line) => format!("{}:{}", frame.contract, line),
+            None => format!("{}", frame.contract),
+        }
+    }
+
+    fn show_watches(&mut self, env: &mut Environment, context: &LocalContext) {
+        for (i, watch) in self.watches.iter().enumerate() {
+            let value = match eval_in_frame(env, context, watch) {
+                Ok(value) => format!("{value}"),
+                Err(e) => format!("error: {e}"),
+            };
+            let _ = writeln!(self.output, "  watch {}: {} = {}", i + 1, watch, value);
+        }
+    }
+
+    fn show_info(&mut self) {
+        if self.breakpoints.is_empty() {
+            let _ = writeln!(self.output, "No breakpoints");
+        }
+        for breakpoint in self.breakpoints.iter() {
+            let _ = writeln!(
+                self.output,
+                "breakpoint {}: {}",
+                breakpoint.id, breakpoint.spec
+            );
+        }
+        for (i, watch) in self.watches.iter().enumerate() {
+            let _ = writeln!(self.output, "watch {}: {}", i + 1, watch);
+        }
+    }
+
+    fn show_locals(&mut self, context: &LocalContext) {
+        let mut shown = vec![];
+        let mut scope = Some(context);
+        while let Some(current) = scope {
+            let mut names: Vec<_> = current
+                .variables
+                .keys()
+                .filter(|name| !shown.contains(*name))
+                .cloned()
+                .collect();
+            names.sort();
+            for name in names {
+                let _ = writeln!(self.output, "{} = {}", name, current.variables[&name]);
+                shown.push(name);
+            }
+            let mut callables: Vec<_> = current
+                .callable_contracts
+                .iter()
+                .filter(|(name, _)| !shown.contains(*name))
+                .collect();
+            callables.sort_by(|a, b| a.0.cmp(b.0));
+            for (name, callable) in callables {
+                let _ = writeln!(self.output, "{} = {}", name, callable.contract_identifier);
+                shown.push(name.clone());
+            }
+            scope = current.parent;
+        }
+        if shown.is_empty() {
+            let _ = writeln!(self.output, "No local variables");
+        }
+    }
+
+    fn show_data_vars(&mut self, env: &mut Environment) {
+        let contract_context = env.contract_context;
+        let epoch = *env.epoch();
+        let mut names: Vec<_> = contract_context.meta_data_var.keys().collect();
+        names.sort();
+        if names.is_empty() {
+            let _ = writeln!(self.output, "No data-vars");
+        }
+        for name in names {
+            let value = env
+                .global_context
+                .database
+                .lookup_variable(
+                    &contract_context.contract_identifier,
+                    name,
+                    &contract_context.meta_data_var[name],
+                    &epoch,
+                )
+                .map(|value| format!("{value}"))
+                .unwrap_or_else(|e| format!("error: {e}"));
+            let _ = writeln!(self.output, "{name} = {value}");
+        }
+    }
+
+    fn show_maps(&mut self, env: &mut Environment) {
+        let contract_context = env.contract_context;
+        let mut names: Vec<_> = contract_context.meta_data_map.keys().collect();
+        names.sort();
+        if names.is_empty() {
+            let _ = writeln!(self.output, "No maps");
+        }
+        for name in names {
+            let metadata = &contract_context.meta_data_map[name];
+            let _ = writeln!(
+                self.output,
+                "{}: {} -> {}",
+                name, metadata.key_type, metadata.value_type
+            );
+        }
+    }
+
+    fn show_map_entry(&mut self, env: &mut Environment, context: &LocalContext, arg: &str) {
+        let contract_context = env.contract_context;
+        let Some((name, key)) = arg.split_once(char::is_whitespace) else {
+            let _ = writeln!(self.output, "Usage: map <name> <key>");
+            return;
+        };
+        let Some(metadata) = contract_context.meta_data_map.get(name) else {
+            let _ = writeln!(self.output, "No map '{name}'");
+            return;
+        };
+        let epoch = *env.epoch();
+        let entry = eval_in_frame(env, context, key).and_then(|key| {
+            env.global_context.database.fetch_entry(
+                &contract_context.contract_identifier,
+                name,
+                &key,
+                metadata,
+                &epoch,
+            )
+        });
+        let output = match entry {
+            Ok(value) => format!("{value}"),
+            Err(e) => format!("error: {e}"),
+        };
+        let _ = writeln!(self.output, "{output}");
+    }
+
+    fn show_backtrace(&mut self) {
+        let depth = self.stack.len();
+        for (i, frame) in self.stack.iter().enumerate().rev() {
+            // only the current expression, and calls into contract-defined functions
+            if !frame.defined && i + 1 != depth {
+                continue;
+            }
+            let location = match frame.line {
+                Some(line) => format!("{}:{}", frame.contract, line),
+                None => format!("{}", frame.contract),
+            };
+            let _ = writeln!(
+                self.output,
+                "#{} ({}) at {}",
+                depth - 1 - i,
+                frame.function,
+                location
+            );
+        }
+    }
+}
+
+/// The source line `expr` starts on, if known
+#[cfg(feature = "developer-mode")]
+fn expr_line(expr: &SymbolicExpression) -> Option<u32> {
+    match expr.span.start_line {
+        0 => None,
+        line => Some(line),
+    }
+}
+
+#[cfg(not(feature = "developer-mode"))]
+fn expr_line(_expr: &SymbolicExpression) -> Option<u32> {
+    None
+}
+
+fn display_expr(expr: &SymbolicExpression) -> String {
+    let mut display = format!("{expr}");
+    if display.len() > MAX_EXPR_DISPLAY_LEN {
+        let mut end = MAX_EXPR_DISPLAY_LEN;
+        while !display.is_char_boundary(end) {
+            end -= 1;
+        }
+        display.truncate(end);
+        display.push_str(" ...");
+    }
+    display
+}
+
+/// Evaluate `program` as if it appeared in the paused frame.  Nothing it does persists, and it
+/// isn't charged to the transaction's costs.
+fn eval_in_frame(
+    env: &mut Environment,
+    context: &LocalContext,
+    program: &str,
+) -> Result<Value, Error> {
+    let contract_context = env.contract_context;
+    let epoch = *env.epoch();
+    env.run_free(|env| {
+        let parsed = ast::build_ast_with_rules(
+            &contract_context.contract_identifier,
+            program,
+            env,
+            *contract_context.get_clarity_version(),
+            epoch,
+            ASTRules::PrecheckSize,
+        )?
+        .expressions;
+        let expr = parsed.first().ok_or_else(|| {
+            Error::from(RuntimeErrorType::ParseError(
+                "Expected an expression".to_string(),
+            ))
+        })?;
+
+        env.global_context.begin();
+        let result = eval(expr, env, context);
+        env.global_context.roll_back()?;
+        result
+    })
+}
+
+impl EvalHook for Debugger {
+    fn will_begin_eval(
+        &mut self,
+        env: &mut Environment,
+        context: &LocalContext,
+        expr: &SymbolicExpression,
+    ) {
+        if self.mode == StepMode::Detached {
+            return;
+        }
+        // only pause at function applications: there's nothing to step into in an atom
+        let Some(function) = expr
+            .match_list()
+            .and_then(|list| list.first())
+            .and_then(|first| first.match_atom())
+        else {
+            return;
+        };
+        let frame = Frame {
+            id: expr.id,
+            contract: env.contract_context.contract_identifier.clone(),
+            line: expr_line(expr),
+            function: function.to_string(),
+            defined: env
+                .contract_context
+                .lookup_function(function.as_str())
+                .is_some(),
+        };
+        let depth = self.stack.len() + 1;
+        let reason = self.pause_reason(&frame, depth);
+        if let Some(line) = frame.line {
+            self.last_line = Some((frame.contract.clone(), line));
+        }
+        self.stack.push(frame);
+
+        if let Some(reason) = reason {
+            self.pause(env, context, expr, &reason);
+        }
+    }
+
+    fn did_finish_eval(
+        &mut self,
+        _env: &mut Environment,
+        _context: &LocalContext,
+        expr: &SymbolicExpression,
+        res: &Result<Value, Error>,
+    ) {
+        if self.mode == StepMode::Detached || expr.match_list().is_none() {
+            return;
+        }
+        let depth = self.stack.len();
+        // an expression that failed early may not have reported finishing, so unwind to this one
+        let Some(index) = self.stack.iter().rposition(|frame| frame.id == expr.id) else {
+            return;
+        };
+        self.stack.truncate(index);
+
+        if self.report_result_of == Some((expr.id, depth)) {
+            self.report_result_of = None;
+            let _ = match res {
+                Ok(value) => writeln!(self.output, "  => {value}"),
+                Err(e) => writeln!(self.output, "  => error: {e}"),
+            };
+        }
+    }
+
+    fn did_complete(&mut self, _result: core::result::Result<&mut ExecutionResult, String>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::io::Cursor;
+    use std::rc::Rc;
+
+    use stacks_common::types::StacksEpochId;
+
+    use super::*;
+    use crate::vm::contexts::OwnedEnvironment;
+    use crate::vm::database::MemoryBackingStore;
+    use crate::vm::types::PrincipalData;
+
+    /// A `Write` whose contents the test can read after handing it to the debugger
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    const CONTRACT: &str = "
+(define-data-var counter int 0)
+(define-map balances principal int)
+(define-private (double (x int))
+  (* x 2))
+(define-public (bump (amount int))
+  (let ((doubled (double amount)))
+    (var-set counter (+ (var-get counter) doubled))
+    (map-set balances tx-sender doubled)
+    (ok (var-get counter))))
+";
+
+    /// Run `(bump 5)` under a debugger fed `commands`, and return what it printed
+    fn debug_bump(commands: &str, configure: impl FnOnce(&mut Debugger)) -> (Value, String) {
+        let output = SharedBuffer::default();
+        let mut debugger = Debugger::new(
+            Box::new(Cursor::new(commands.as_bytes().to_vec())),
+            Box::new(output.clone()),
+        );
+        configure(&mut debugger);
+
+        let mut marf = MemoryBackingStore::new();
+        let mut owned_env = OwnedEnvironment::new(marf.as_clarity_db(), StacksEpochId::Epoch21);
+        let contract_id = QualifiedContractIdentifier::local("counter").unwrap();
+        owned_env
+            .initialize_contract(contract_id.clone(), CONTRACT, None, ASTRules::PrecheckSize)
+            .unwrap();
+
+        owned_env.add_eval_hook(&mut debugger);
+        let sender = PrincipalData::parse("S1G2081040G2081040G2081040G208105NK8PE5").unwrap();
+        let (result, _, _) = owned_env
+            .execute_transaction(
+                sender,
+                None,
+                contract_id,
+                "bump",
+                &[SymbolicExpression::atom_value(Value::Int(5))],
+            )
+            .unwrap();
+        (result, output.contents())
+    }
+
+    #[test]
+    fn test_breakpoint_spec() {
+        assert_eq!(
+            "12".parse::<BreakpointSpec>().unwrap(),
+            BreakpointSpec::Line {
+                contract: None,
+                line: 12
+            }
+        );
+        assert_eq!(
+            "S1G2081040G2081040G2081040G208105NK8PE5.counter:7"
+                .parse::<BreakpointSpec>()
+                .unwrap(),
+            BreakpointSpec::Line {
+                contract: Some("S1G2081040G2081040G2081040G208105NK8PE5.counter".to_string()),
+                line: 7
+            }
+        );
+        assert_eq!(
+            "double".parse::<BreakpointSpec>().unwrap(),
+            BreakpointSpec::Function("double".to_string())
+        );
+        assert!("counter:x".parse::<BreakpointSpec>().is_err());
+        assert!("".parse::<BreakpointSpec>().is_err());
+    }
+
+    #[test]
+    fn test_function_breakpoint_and_inspection() {
+        let (result, output) = debug_bump(
+            "locals\nprint (+ x 1)\nvars\nmaps\nbt\ncontinue\n",
+            |debugger| {
+                debugger.run_to_breakpoint();
+                debugger.add_breakpoint("*").unwrap();
+            },
+        );
+        assert_eq!(result, Value::okay(Value::Int(10)).unwrap());
+
+        // the breakpoint on `*` is inside `double`, where `x` is bound
+        assert!(output.contains("[breakpoint 1]"));
+        assert!(output.contains("x = 5"));
+        assert!(output.contains("(debug) 6\n"));
+        assert!(output.contains("counter = 0"));
+        assert!(output.contains("balances: principal -> int"));
+        assert!(output.contains("#0 (*) at"));
+        assert!(output.contains("#1 (double) at"));
+        // only paused once
+        assert_eq!(output.matches("[breakpoint").count(), 1);
+    }
+
+    #[test]
+    fn test_step_over_and_watch() {
+        // pause at the start, then step over the `let` in one go
+        let (result, output) = debug_bump("watch (var-get counter)\nnext\nnext\nquit\n", |_| {});
+        assert_eq!(result, Value::okay(Value::Int(10)).unwrap());
+
+        assert!(output.starts_with("[step]"));
+        assert!(output.contains("watch 1: (var-get counter) = 0"));
+        assert!(output.contains("=> (ok 10)"));
+    }
+
+    #[test]
+    fn test_step_in_and_out() {
+        let (_, output) = debug_bump(
+            "step\nlocals\nfinish\nmap balances tx-sender\ncontinue\n",
+            |debugger| {
+                debugger.run_to_breakpoint();
+                debugger.add_breakpoint("double").unwrap();
+            },
+        );
+        // stepping in from the call to `double` reaches its body
+        assert!(output.contains("[breakpoint 1]"));
+        assert!(output.contains("x = 5"));
+        // finishing the body reports `double`'s result
+        assert!(output.contains("=> 10"));
+        // nothing's been written to the map yet
+        assert!(output.contains("(debug) none\n"));
+    }
+
+    #[test]
+    fn test_debugger_does_not_change_state() {
+        let (result, output) = debug_bump(
+            "print (var-set counter 100)\nprint (var-get counter)\ncontinue\n",
+            |_| {},
+        );
+        // the debugger's own evaluations are rolled back
+        assert_eq!(result, Value::okay(Value::Int(10)).unwrap());
+        assert!(output.contains("(debug) true\n"));
+        assert!(output.contains("(debug) 0\n"));
+    }
+
+    #[cfg(feature = "developer-mode")]
+    #[test]
+    fn test_line_breakpoint() {
+        let (_, output) = debug_bump("locals\ncontinue\n", |debugger| {
+            debugger.run_to_breakpoint();
+            debugger.add_breakpoint("counter:9").unwrap();
+        });
+        assert!(output.contains("[breakpoint 1]"));
+        assert!(output.contains(".counter:9"));
+        assert!(output.contains("doubled = 10"));
+        assert_eq!(output.matches("[breakpoint").count(), 1);
+    }
+}
@@ -18,8 +18,8 @@ use std::fmt;
 
 use crate::vm::representations::Span;
 
-/// In a near future, we can go further in our static analysis and provide different levels
-/// of diagnostics, such as warnings, hints, best practices, etc.
+/// The severity of a diagnostic.  Analysis errors reject a contract, while warnings, such as
+/// those from the lint pass, only point out likely mistakes.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum Level {
     Note,
@@ -53,6 +53,16 @@ impl Diagnostic {
         }
     }
 
+    /// A diagnostic at the level the error reports for itself
+    pub fn new(error: &dyn DiagnosableError) -> Diagnostic {
+        Diagnostic {
+            spans: vec![],
+            level: error.level(),
+            message: error.message(),
+            suggestion: error.suggestion(),
+        }
+    }
+
     pub fn add_span(&mut self, start_line: u32, start_column: u32, end_line: u32, end_column: u32) {
         self.spans.push(Span {
             start_line,
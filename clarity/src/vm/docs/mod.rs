@@ -2286,6 +2286,27 @@ unlock height for any locked STX, all denominated in microstacks.
 "#,
 };
 
+const INFER_RESULT: SpecialAPI = SpecialAPI {
+    input_type: "(buff 32)",
+    output_type: "(optional (tuple (output (optional (string-utf8 1024))) (output-hash (buff 32))))",
+    snippet: "infer-result? ${1:txid}",
+    signature: "(infer-result? txid)",
+    description: "`infer-result?` looks up the result of the Infer transaction with the given `txid`.
+
+If that transaction committed its inference output in this fork, this function returns a tuple
+with the SHA-256 hash of the output (`output-hash`), and the output itself (`output`) if it is at
+most 1024 characters long; longer outputs are only committed by their hash, and `output` is
+`none`. If there is no such transaction, or it has not committed an output, this function
+returns `none`.
+
+This function is only available in Clarity 3 contracts.  Inference results are only recorded
+from epoch 3.0 on, so Infer transactions mined before then have no result.
+",
+    example: r#"
+(infer-result? 0x0000000000000000000000000000000000000000000000000000000000000000) ;; Returns none
+"#,
+};
+
 const STX_TRANSFER: SpecialAPI = SpecialAPI {
     input_type: "uint, principal, principal, buff",
     snippet: "stx-transfer? ${1:amount} ${2:sender} ${3:recipient}",
@@ -2522,6 +2543,7 @@ pub fn make_api_reference(function: &NativeFunctions) -> FunctionAPI {
         AtBlock => make_for_special(&AT_BLOCK, function),
         GetStxBalance => make_for_simple_native(&STX_GET_BALANCE, &function, name),
         StxGetAccount => make_for_simple_native(&STX_GET_ACCOUNT, &function, name),
+        GetInferResult => make_for_special(&INFER_RESULT, function),
         StxTransfer => make_for_special(&STX_TRANSFER, function),
         StxTransferMemo => make_for_special(&STX_TRANSFER_MEMO, function),
         StxBurn => make_for_simple_native(&STX_BURN, &function, name),
@@ -177,14 +177,34 @@ impl STXTransferEventData {
 pub struct STXInferEventData {
     pub sender: PrincipalData,
     pub infer_out_hash: BuffData,
+    /// Where the inference sits in its session, for inferences committed in epoch 3.0 and later
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<InferSessionLink>,
+}
+
+/// Links an inference to the turns of its session that came before it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferSessionLink {
+    /// The txid of the Infer transaction this one continues, if any
+    pub parent_txid: Option<BuffData>,
+    /// Hash of this turn's input, context and output, chained onto the parent's session hash
+    pub session_hash: BuffData,
 }
 
 impl STXInferEventData {
     pub fn json_serialize(&self) -> serde_json::Value {
-        json!({
+        let mut json = json!({
             "sender": format!("{}",self.sender),
             "infer_out_hash": format!("{}", self.infer_out_hash),
-        })
+        });
+        if let Some(ref session) = self.session {
+            json["session_hash"] = json!(format!("{}", session.session_hash));
+            json["parent_txid"] = match session.parent_txid {
+                Some(ref txid) => json!(format!("{}", txid)),
+                None => serde_json::Value::Null,
+            };
+        }
+        json
     }
 }
 
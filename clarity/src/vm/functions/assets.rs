@@ -23,6 +23,7 @@ use crate::vm::errors::{
     check_argument_count, CheckErrors, Error, InterpreterError, InterpreterResult as Result,
     RuntimeErrorType,
 };
+use crate::vm::events::InferSessionLink;
 use crate::vm::functions::tuples;
 use crate::vm::representations::SymbolicExpression;
 use crate::vm::types::{
@@ -157,11 +158,22 @@ pub fn stx_transfer_consolidated(
     Ok(Value::okay_true())
 }
 
+/// Commit an inference output on behalf of `from`, charging `cost_units` (see
+/// `libllm::InferCost`) against the `cost_stx_infer` cost function and, if `payment` is given,
+/// paying the inference provider.  `session` links the output into its session, if the caller
+/// tracks sessions.
 pub fn stx_infer_consolidated(
     env: &mut Environment,
     from: &PrincipalData,
     infer_out_hash: &BuffData,
+    cost_units: u64,
+    payment: Option<(&PrincipalData, u128)>,
+    session: Option<&InferSessionLink>,
 ) -> Result<Value> {
+    if env.epoch().charges_infer_costs() {
+        runtime_cost(ClarityCostFunction::StxInfer, env, cost_units)?;
+    }
+
     // loading from/to principals and balances
     env.add_memory(TypeSignature::PrincipalType.size()? as u64)?;
     env.add_memory(TypeSignature::PrincipalType.size()? as u64)?;
@@ -171,7 +183,19 @@ pub fn stx_infer_consolidated(
     env.add_memory(STXBalance::unlocked_and_v1_size as u64)?;
     env.add_memory(STXBalance::unlocked_and_v1_size as u64)?;
 
-    env.register_stx_infer_event(from.clone(), infer_out_hash.clone())?;
+    if let Some((to, amount)) = payment {
+        let mut sender_snapshot = env.global_context.database.get_stx_balance_snapshot(from)?;
+        if !sender_snapshot.can_transfer(amount)? {
+            return clarity_ecode!(StxErrorCodes::NOT_ENOUGH_BALANCE);
+        }
+
+        sender_snapshot.transfer_to(to, amount)?;
+
+        env.global_context.log_stx_transfer(from, amount)?;
+        env.register_stx_transfer_event(from.clone(), to.clone(), amount, BuffData::empty())?;
+    }
+
+    env.register_stx_infer_event(from.clone(), infer_out_hash.clone(), session.cloned())?;
     Ok(Value::okay_infer_hash(infer_out_hash))
 }
 
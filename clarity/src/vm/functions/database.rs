@@ -34,7 +34,7 @@ use crate::vm::types::{
     BlockInfoProperty, BuffData, BurnBlockInfoProperty, OptionalData, PrincipalData, SequenceData,
     TupleData, TypeSignature, Value, BUFF_32,
 };
-use crate::vm::{eval, Environment, LocalContext};
+use crate::vm::{eval, ClarityVersion, Environment, LocalContext};
 
 switch_on_global_epoch!(special_fetch_variable(
     special_fetch_variable_v200,
@@ -924,3 +924,53 @@ pub fn special_get_burn_block_info(
         }
     }
 }
+
+/// Handles the `infer-result?` special function.
+/// Interprets `args` as the txid of an Infer transaction, and returns
+/// `(some { output-hash: (buff 32), output: (optional (string-utf8 1024)) })` if that
+/// transaction committed its output in this fork, or `none` otherwise.  The output itself is
+/// only available if it was short enough to be kept on-chain.
+///
+/// # Errors:
+/// - CheckErrors::UndefinedFunction if the contract is older than Clarity 3.
+/// - CheckErrors::IncorrectArgumentCount if there aren't 1 arguments.
+/// - CheckErrors::TypeValueError if `args[0]` isn't a buffer.
+pub fn special_get_infer_result(
+    args: &[SymbolicExpression],
+    env: &mut Environment,
+    context: &LocalContext,
+) -> Result<Value> {
+    if *env.contract_context.get_clarity_version() < ClarityVersion::Clarity3 {
+        return Err(CheckErrors::UndefinedFunction("infer-result?".to_string()).into());
+    }
+    check_argument_count(1, args)?;
+
+    // `cost_infer_result` is only defined from .costs-4 on
+    if env.epoch().charges_infer_costs() {
+        runtime_cost(ClarityCostFunction::InferResult, env, 0)?;
+    }
+
+    let txid = match eval(&args[0], env, context)? {
+        Value::Sequence(SequenceData::Buffer(BuffData { data })) => data,
+        x => return Err(CheckErrors::TypeValueError(BUFF_32.clone(), x).into()),
+    };
+    let Ok(txid) = <[u8; 32]>::try_from(txid.as_slice()) else {
+        return Ok(Value::none());
+    };
+
+    let Some(record) = env.global_context.database.get_infer_result(&txid)? else {
+        return Ok(Value::none());
+    };
+
+    let output = match record.output {
+        Some(output) => Value::some(Value::string_utf8_from_bytes(output.into_bytes())?)?,
+        None => Value::none(),
+    };
+    let result = TupleData::from_data(vec![
+        ("output-hash".into(), Value::buff_from(record.output_hash)?),
+        ("output".into(), output),
+    ])
+    .map_err(|_| InterpreterError::Expect("FATAL: failed to build infer result tuple".into()))?;
+
+    Value::some(Value::Tuple(result))
+}
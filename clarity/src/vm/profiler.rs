@@ -0,0 +1,482 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A per-expression cost profiler for Clarity, driven by `EvalHook`.
+//!
+//! The profiler snapshots the transaction's running `ExecutionCost` before and after every
+//! function application, and charges the difference to the stack of applications that were in
+//! progress.  The result can be written as collapsed stacks (one metric per file, for
+//! `flamegraph.pl` and `inferno`) or as a speedscope file holding all five metrics.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use serde_json::json;
+
+use super::EvalHook;
+use crate::vm::contexts::{Environment, LocalContext};
+use crate::vm::costs::ExecutionCost;
+use crate::vm::errors::Error;
+use crate::vm::{ExecutionResult, SymbolicExpression, Value};
+
+const SPEEDSCOPE_SCHEMA: &str = "https://www.speedscope.app/file-format-schema.json";
+
+/// One dimension of `ExecutionCost`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostMetric {
+    Runtime,
+    ReadCount,
+    ReadLength,
+    WriteCount,
+    WriteLength,
+}
+
+impl CostMetric {
+    pub const ALL: [CostMetric; 5] = [
+        CostMetric::Runtime,
+        CostMetric::ReadCount,
+        CostMetric::ReadLength,
+        CostMetric::WriteCount,
+        CostMetric::WriteLength,
+    ];
+
+    pub fn of(&self, cost: &ExecutionCost) -> u64 {
+        match self {
+            CostMetric::Runtime => cost.runtime,
+            CostMetric::ReadCount => cost.read_count,
+            CostMetric::ReadLength => cost.read_length,
+            CostMetric::WriteCount => cost.write_count,
+            CostMetric::WriteLength => cost.write_length,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CostMetric::Runtime => "runtime",
+            CostMetric::ReadCount => "read_count",
+            CostMetric::ReadLength => "read_length",
+            CostMetric::WriteCount => "write_count",
+            CostMetric::WriteLength => "write_length",
+        }
+    }
+}
+
+impl FromStr for CostMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CostMetric::ALL
+            .into_iter()
+            .find(|metric| metric.name() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown cost metric '{s}': expected one of runtime, read_count, read_length, write_count, write_length"
+                )
+            })
+    }
+}
+
+impl fmt::Display for CostMetric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A function application site: what was called, and where
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct FrameKey {
+    function: String,
+    contract: String,
+    line: Option<u32>,
+}
+
+impl fmt::Display for FrameKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} ({}:{})", self.function, self.contract, line),
+            None => write!(f, "{} ({})", self.function, self.contract),
+        }
+    }
+}
+
+/// A function application that is still being evaluated
+struct Frame {
+    id: u64,
+    key: FrameKey,
+    /// The transaction's total cost when the application began
+    start: ExecutionCost,
+    /// The inclusive cost of the applications it has finished making
+    children: ExecutionCost,
+}
+
+/// Attributes execution costs to the call stacks that incurred them
+#[derive(Default)]
+pub struct CostProfiler {
+    stack: Vec<Frame>,
+    /// Self cost of each distinct call stack, outermost application first
+    stacks: BTreeMap<Vec<FrameKey>, ExecutionCost>,
+}
+
+impl CostProfiler {
+    pub fn new() -> CostProfiler {
+        CostProfiler::default()
+    }
+
+    /// The total cost attributed so far, across all call stacks
+    pub fn total(&self) -> ExecutionCost {
+        self.stacks
+            .values()
+            .fold(ExecutionCost::zero(), |total, cost| cost_sum(&total, cost))
+    }
+
+    /// Render the profile in the collapsed-stack format read by `flamegraph.pl` and `inferno`:
+    /// one line per call stack, with its frames separated by `;` and followed by the stack's
+    /// self cost in `metric`.  Stacks that cost nothing in `metric` are left out.
+    pub fn to_collapsed(&self, metric: CostMetric) -> String {
+        let mut out = String::new();
+        for (path, cost) in self.stacks.iter() {
+            let value = metric.of(cost);
+            if value == 0 {
+                continue;
+            }
+            let frames: Vec<String> = path.iter().map(|key| key.to_string()).collect();
+            out.push_str(&format!("{} {}\n", frames.join(";"), value));
+        }
+        out
+    }
+
+    /// Render the profile as a speedscope file, with one sampled profile per cost metric
+    pub fn to_speedscope(&self, name: &str) -> serde_json::Value {
+        let mut frame_index: BTreeMap<&FrameKey, usize> = BTreeMap::new();
+        let mut frames = vec![];
+        for key in self.stacks.keys().flatten() {
+            if frame_index.contains_key(key) {
+                continue;
+            }
+            frame_index.insert(key, frames.len());
+            let mut frame = json!({
+                "name": key.function,
+                "file": key.contract,
+            });
+            if let Some(line) = key.line {
+                frame["line"] = json!(line);
+            }
+            frames.push(frame);
+        }
+
+        let profiles: Vec<serde_json::Value> = CostMetric::ALL
+            .into_iter()
+            .map(|metric| {
+                let mut samples = vec![];
+                let mut weights = vec![];
+                for (path, cost) in self.stacks.iter() {
+                    let value = metric.of(cost);
+                    if value == 0 {
+                        continue;
+                    }
+                    let sample: Vec<usize> = path.iter().map(|key| frame_index[key]).collect();
+                    samples.push(sample);
+                    weights.push(value);
+                }
+                let end_value: u64 = weights.iter().sum();
+                json!({
+                    "type": "sampled",
+                    "name": metric.name(),
+                    "unit": "none",
+                    "startValue": 0,
+                    "endValue": end_value,
+                    "samples": samples,
+                    "weights": weights,
+                })
+            })
+            .collect();
+
+        json!({
+            "$schema": SPEEDSCOPE_SCHEMA,
+            "name": name,
+            "exporter": "clarity-cli",
+            "activeProfileIndex": 0,
+            "shared": { "frames": frames },
+            "profiles": profiles,
+        })
+    }
+
+    /// Write the profile to `path`: as a speedscope file if its name ends in `.json`, and as
+    /// collapsed stacks of `metric` otherwise.
+    pub fn to_file<P: AsRef<Path>>(&self, path: P, metric: CostMetric) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut out = BufWriter::new(File::create(path)?);
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            serde_json::to_writer(&mut out, &self.to_speedscope(&name))?;
+        } else {
+            out.write_all(self.to_collapsed(metric).as_bytes())?;
+        }
+        out.flush()
+    }
+
+    /// Start an application, as of `now`
+    fn enter(&mut self, id: u64, key: FrameKey, now: ExecutionCost) {
+        self.stack.push(Frame {
+            id,
+            key,
+            start: now,
+            children: ExecutionCost::zero(),
+        });
+    }
+
+    /// Finish every application above `depth` on the stack, as of `now`
+    fn finish_frames(&mut self, depth: usize, now: &ExecutionCost) {
+        while self.stack.len() > depth {
+            let Some(frame) = self.stack.pop() else {
+                return;
+            };
+            let inclusive = cost_delta(now, &frame.start);
+            let own = cost_delta(&inclusive, &frame.children);
+            if let Some(parent) = self.stack.last_mut() {
+                parent.children = cost_sum(&parent.children, &inclusive);
+            }
+
+            let mut path: Vec<FrameKey> = self.stack.iter().map(|f| f.key.clone()).collect();
+            path.push(frame.key);
+            let entry = self.stacks.entry(path).or_insert_with(ExecutionCost::zero);
+            *entry = cost_sum(entry, &own);
+        }
+    }
+}
+
+fn cost_delta(after: &ExecutionCost, before: &ExecutionCost) -> ExecutionCost {
+    ExecutionCost {
+        write_length: after.write_length.saturating_sub(before.write_length),
+        write_count: after.write_count.saturating_sub(before.write_count),
+        read_length: after.read_length.saturating_sub(before.read_length),
+        read_count: after.read_count.saturating_sub(before.read_count),
+        runtime: after.runtime.saturating_sub(before.runtime),
+    }
+}
+
+fn cost_sum(a: &ExecutionCost, b: &ExecutionCost) -> ExecutionCost {
+    ExecutionCost {
+        write_length: a.write_length.saturating_add(b.write_length),
+        write_count: a.write_count.saturating_add(b.write_count),
+        read_length: a.read_length.saturating_add(b.read_length),
+        read_count: a.read_count.saturating_add(b.read_count),
+        runtime: a.runtime.saturating_add(b.runtime),
+    }
+}
+
+#[cfg(feature = "developer-mode")]
+fn expr_line(expr: &SymbolicExpression) -> Option<u32> {
+    match expr.span.start_line {
+        0 => None,
+        line => Some(line),
+    }
+}
+
+#[cfg(not(feature = "developer-mode"))]
+fn expr_line(_expr: &SymbolicExpression) -> Option<u32> {
+    None
+}
+
+impl EvalHook for CostProfiler {
+    fn will_begin_eval(
+        &mut self,
+        env: &mut Environment,
+        _context: &LocalContext,
+        expr: &SymbolicExpression,
+    ) {
+        // atoms are charged to the application that evaluates them
+        let Some(function) = expr
+            .match_list()
+            .and_then(|list| list.first())
+            .and_then(|first| first.match_atom())
+        else {
+            return;
+        };
+        let key = FrameKey {
+            function: function.to_string(),
+            contract: env.contract_context.contract_identifier.to_string(),
+            line: expr_line(expr),
+        };
+        self.enter(expr.id, key, env.global_context.cost_track.get_total());
+    }
+
+    fn did_finish_eval(
+        &mut self,
+        env: &mut Environment,
+        _context: &LocalContext,
+        expr: &SymbolicExpression,
+        _res: &Result<Value, Error>,
+    ) {
+        if expr.match_list().is_none() {
+            return;
+        }
+        // an expression that failed early may not have reported finishing, so unwind to this one
+        let Some(index) = self.stack.iter().rposition(|frame| frame.id == expr.id) else {
+            return;
+        };
+        let now = env.global_context.cost_track.get_total();
+        self.finish_frames(index, &now);
+    }
+
+    fn did_complete(&mut self, _result: core::result::Result<&mut ExecutionResult, String>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use stacks_common::types::StacksEpochId;
+
+    use super::*;
+    use crate::vm::ast::ASTRules;
+    use crate::vm::contexts::OwnedEnvironment;
+    use crate::vm::database::MemoryBackingStore;
+    use crate::vm::types::{PrincipalData, QualifiedContractIdentifier};
+
+    fn key(function: &str, line: u32) -> FrameKey {
+        FrameKey {
+            function: function.to_string(),
+            contract: "c".to_string(),
+            line: Some(line),
+        }
+    }
+
+    fn runtime(runtime: u64) -> ExecutionCost {
+        ExecutionCost {
+            runtime,
+            ..ExecutionCost::zero()
+        }
+    }
+
+    /// `outer` spends 35 in all, 20 of it in `inner`.  Then `inner` runs again and finishes
+    /// without `other`, which it called and which made a write, reporting that it finished.
+    fn sample_profile() -> CostProfiler {
+        let mut profiler = CostProfiler::new();
+        profiler.enter(1, key("outer", 1), runtime(0));
+        profiler.enter(2, key("inner", 2), runtime(10));
+        profiler.finish_frames(1, &runtime(30));
+        profiler.finish_frames(0, &runtime(35));
+
+        profiler.enter(3, key("inner", 2), runtime(35));
+        profiler.enter(4, key("other", 3), runtime(40));
+        profiler.finish_frames(
+            0,
+            &ExecutionCost {
+                runtime: 50,
+                write_count: 1,
+                ..ExecutionCost::zero()
+            },
+        );
+        profiler
+    }
+
+    #[test]
+    fn test_cost_metric() {
+        for metric in CostMetric::ALL {
+            assert_eq!(metric.name().parse::<CostMetric>().unwrap(), metric);
+        }
+        assert!("runtime_ms".parse::<CostMetric>().is_err());
+    }
+
+    #[test]
+    fn test_self_costs() {
+        let profiler = sample_profile();
+        assert_eq!(profiler.total().runtime, 50);
+        assert_eq!(
+            profiler.to_collapsed(CostMetric::Runtime),
+            "inner (c:2) 5\n\
+             inner (c:2);other (c:3) 10\n\
+             outer (c:1) 15\n\
+             outer (c:1);inner (c:2) 20\n"
+        );
+        // the write happened while `other` was running
+        assert_eq!(
+            profiler.to_collapsed(CostMetric::WriteCount),
+            "inner (c:2);other (c:3) 1\n"
+        );
+        assert_eq!(profiler.to_collapsed(CostMetric::ReadLength), "");
+    }
+
+    #[test]
+    fn test_speedscope_output() {
+        let file = sample_profile().to_speedscope("sample");
+        assert_eq!(file["$schema"], SPEEDSCOPE_SCHEMA);
+
+        let frames = file["shared"]["frames"].as_array().unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0]["name"], "inner");
+        assert_eq!(frames[0]["file"], "c");
+        assert_eq!(frames[0]["line"], 2);
+
+        let profiles = file["profiles"].as_array().unwrap();
+        assert_eq!(profiles.len(), CostMetric::ALL.len());
+        assert_eq!(profiles[0]["name"], "runtime");
+        assert_eq!(profiles[0]["endValue"], 50);
+        assert_eq!(profiles[0]["weights"], json!([5, 10, 15, 20]));
+        assert_eq!(profiles[0]["samples"], json!([[0], [0, 1], [2], [2, 0]]));
+        assert_eq!(profiles[3]["name"], "write_count");
+        assert_eq!(profiles[3]["samples"], json!([[0, 1]]));
+    }
+
+    #[test]
+    fn test_profile_records_call_stacks() {
+        const CONTRACT: &str = "
+(define-map balances uint uint)
+(define-private (credit (i uint) (acc uint))
+  (begin
+    (map-set balances i (+ acc i))
+    (+ acc (default-to u0 (map-get? balances i)))))
+(define-public (run)
+  (ok (fold credit (list u1 u2 u3) u0)))
+";
+        let mut profiler = CostProfiler::new();
+        let mut marf = MemoryBackingStore::new();
+        let mut owned_env = OwnedEnvironment::new(marf.as_clarity_db(), StacksEpochId::Epoch21);
+        let contract_id = QualifiedContractIdentifier::local("bank").unwrap();
+        owned_env
+            .initialize_contract(contract_id.clone(), CONTRACT, None, ASTRules::PrecheckSize)
+            .unwrap();
+
+        owned_env.add_eval_hook(&mut profiler);
+        let sender = PrincipalData::parse("S1G2081040G2081040G2081040G208105NK8PE5").unwrap();
+        let (result, _, _) = owned_env
+            .execute_transaction(sender, None, contract_id, "run", &[])
+            .unwrap();
+        assert_eq!(result, Value::okay(Value::UInt(12)).unwrap());
+        drop(owned_env);
+
+        // every application finished, and `map-get?` was reached through `fold` and `credit`
+        assert!(profiler.stack.is_empty());
+        let functions = |path: &Vec<FrameKey>| -> Vec<String> {
+            path.iter().map(|key| key.function.clone()).collect()
+        };
+        assert!(profiler
+            .stacks
+            .keys()
+            .any(|path| functions(path) == ["ok", "fold", "begin", "+", "default-to", "map-get?"]));
+        assert!(profiler
+            .stacks
+            .keys()
+            .flatten()
+            .all(|key| key.contract == "S1G2081040G2081040G2081040G208105NK8PE5.bank"));
+    }
+}
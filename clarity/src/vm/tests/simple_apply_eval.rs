@@ -28,13 +28,16 @@ use crate::vm::ast::{parse, ASTRules};
 use crate::vm::callables::DefinedFunction;
 use crate::vm::contexts::OwnedEnvironment;
 use crate::vm::costs::LimitedCostTracker;
-use crate::vm::database::MemoryBackingStore;
+use crate::vm::database::{
+    ClarityDeserializable, ClaritySerializable, InferResultRecord, MemoryBackingStore,
+    INFER_RESULT_MAX_OUTPUT_LEN, INFER_RESULT_MAX_SERIALIZED_LEN,
+};
 use crate::vm::errors::{CheckErrors, Error, RuntimeErrorType, ShortReturnType};
 use crate::vm::tests::{execute, test_clarity_versions};
 use crate::vm::types::signatures::*;
 use crate::vm::types::{
     ASCIIData, BuffData, CharType, PrincipalData, QualifiedContractIdentifier, SequenceData,
-    StacksAddressExtensions, TypeSignature,
+    StacksAddressExtensions, TupleData, TypeSignature,
 };
 use crate::vm::{
     eval, execute as vm_execute, execute_v2 as vm_execute_v2, execute_with_parameters, CallStack,
@@ -1763,3 +1766,151 @@ fn test_chain_id() {
             )
         });
 }
+
+#[test]
+fn test_get_infer_result() {
+    let txid = [0xab; 32];
+    let mut marf = MemoryBackingStore::new();
+    {
+        let mut db = marf.as_clarity_db();
+        db.begin();
+        db.set_infer_result(&txid, &InferResultRecord::new(vec![0xcd; 32], "Yes."))
+            .unwrap();
+        db.commit().unwrap();
+    }
+
+    let mut env = OwnedEnvironment::new(marf.as_clarity_db(), StacksEpochId::Epoch30);
+    let mut eval_at = |program: &str, version: ClarityVersion| {
+        env.execute_in_env(
+            QualifiedContractIdentifier::transient().issuer.into(),
+            None,
+            Some(ContractContext::new(
+                QualifiedContractIdentifier::transient(),
+                version,
+            )),
+            |exec_env| exec_env.eval_raw(program),
+        )
+        .map(|(value, ..)| value)
+    };
+
+    let expected = Value::some(Value::Tuple(
+        TupleData::from_data(vec![
+            (
+                "output-hash".into(),
+                Value::buff_from(vec![0xcd; 32]).unwrap(),
+            ),
+            (
+                "output".into(),
+                Value::some(Value::string_utf8_from_bytes("Yes.".into()).unwrap()).unwrap(),
+            ),
+        ])
+        .unwrap(),
+    ))
+    .unwrap();
+    let program = format!("(infer-result? 0x{})", to_hex(&txid));
+    assert_eq!(eval_at(&program, ClarityVersion::Clarity3), Ok(expected));
+
+    // not a keyword before Clarity 3
+    assert_eq!(
+        eval_at(&program, ClarityVersion::Clarity2),
+        Err(CheckErrors::UndefinedFunction("infer-result?".to_string()).into())
+    );
+
+    // unknown transactions and malformed txids have no result
+    assert_eq!(
+        eval_at(
+            &format!("(infer-result? 0x{})", to_hex(&[0xef; 32])),
+            ClarityVersion::Clarity3
+        ),
+        Ok(Value::none())
+    );
+    assert_eq!(
+        eval_at("(infer-result? 0xab)", ClarityVersion::Clarity3),
+        Ok(Value::none())
+    );
+}
+
+#[test]
+fn test_infer_result_record_output_limit() {
+    let long_output = "a".repeat(INFER_RESULT_MAX_OUTPUT_LEN as usize + 1);
+    let record = InferResultRecord::new(vec![0xcd; 32], &long_output);
+    assert_eq!(record.output, None);
+
+    let record = InferResultRecord::new(vec![0xcd; 32], &long_output[1..]);
+    assert_eq!(record.output, Some(long_output[1..].to_string()));
+}
+
+#[test]
+fn test_get_largest_infer_result() {
+    // every character of the output is escaped as `\uXXXX` once serialized
+    let txid = [0xab; 32];
+    let output = "\u{1}".repeat(INFER_RESULT_MAX_OUTPUT_LEN as usize);
+    let mut record = InferResultRecord::new(vec![0xcd; 32], &output);
+    record.session_hash = Some(vec![0xef; 32]);
+    assert_eq!(record.output, Some(output.clone()));
+    assert!(record.serialize().len() > 4 * INFER_RESULT_MAX_OUTPUT_LEN as usize);
+    assert!(record.serialize().len() <= INFER_RESULT_MAX_SERIALIZED_LEN as usize);
+
+    let mut marf = MemoryBackingStore::new();
+    {
+        let mut db = marf.as_clarity_db();
+        db.begin();
+        db.set_infer_result(&txid, &record).unwrap();
+        db.commit().unwrap();
+    }
+
+    let mut env = OwnedEnvironment::new(marf.as_clarity_db(), StacksEpochId::Epoch30);
+    let program = format!("(infer-result? 0x{})", to_hex(&txid));
+    let value = env
+        .execute_in_env(
+            QualifiedContractIdentifier::transient().issuer.into(),
+            None,
+            Some(ContractContext::new(
+                QualifiedContractIdentifier::transient(),
+                ClarityVersion::Clarity3,
+            )),
+            |exec_env| exec_env.eval_raw(&program),
+        )
+        .map(|(value, ..)| value)
+        .unwrap();
+    let expected = Value::some(Value::Tuple(
+        TupleData::from_data(vec![
+            (
+                "output-hash".into(),
+                Value::buff_from(vec![0xcd; 32]).unwrap(),
+            ),
+            (
+                "output".into(),
+                Value::some(Value::string_utf8_from_bytes(output.into_bytes()).unwrap()).unwrap(),
+            ),
+        ])
+        .unwrap(),
+    ))
+    .unwrap();
+    assert_eq!(value, expected);
+}
+
+#[test]
+fn test_infer_result_record_serialization() {
+    let mut record = InferResultRecord::new(vec![0xcd; 32], "Yes.");
+    record.session_hash = Some(vec![0xef; 32]);
+
+    // hashes are stored hex-encoded
+    let serialized = record.serialize();
+    assert_eq!(
+        serialized,
+        format!(
+            "{{\"output_hash\":\"{}\",\"output\":\"Yes.\",\"session_hash\":\"{}\"}}",
+            "cd".repeat(32),
+            "ef".repeat(32)
+        )
+    );
+    assert_eq!(InferResultRecord::deserialize(&serialized).unwrap(), record);
+
+    record.session_hash = None;
+    assert!(!record.serialize().contains("session_hash"));
+    assert_eq!(
+        InferResultRecord::deserialize(&record.serialize()).unwrap(),
+        record
+    );
+}
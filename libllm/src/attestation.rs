@@ -0,0 +1,205 @@
+//! Attestations tie an inference output to the node that produced it.
+//!
+//! When a worker finishes a task, the node signs the task's txid, the model and parameters it
+//! ran with, the hash of the output, and the time, with its Stacks private key (the same key it
+//! signs Nakamoto blocks with).  The attestation is stored with the result and served alongside
+//! it, so that anyone who fetches the result -- e.g. a signer checking a miner's block -- can
+//! tell who produced it, and hold them to it.
+
+use serde::{Deserialize, Serialize};
+use stacks_common::types::{PrivateKey, PublicKey};
+use stacks_common::util::hash::Sha512Trunc256Sum;
+use stacks_common::util::secp256k1::{MessageSignature, Secp256k1PrivateKey, Secp256k1PublicKey};
+
+use crate::params::InferParams;
+use crate::InferResult;
+
+/// Domain separator, so that an attestation's signature can't be passed off as any other
+const INFER_ATTESTATION_DOMAIN: &str = "stacks-infer-attestation-v1";
+
+/// A node's signed statement that it produced the output with hash `output_hash` for the
+/// inference task `txid`, by running `model` with `params`, at unix time `timestamp`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferAttestation {
+    pub txid: String,
+    pub model: Option<String>,
+    pub params: InferParams,
+    pub output_hash: String,
+    /// When the output was produced, as a unix time in seconds
+    pub timestamp: u64,
+    /// The attesting node's compressed public key, hex-encoded
+    pub public_key: String,
+    pub signature: MessageSignature,
+}
+
+impl InferAttestation {
+    /// The digest that is signed
+    fn signature_hash(
+        txid: &str,
+        model: &Option<String>,
+        params: &InferParams,
+        output_hash: &str,
+        timestamp: u64,
+    ) -> Result<Sha512Trunc256Sum, String> {
+        let message = serde_json::to_vec(&(
+            INFER_ATTESTATION_DOMAIN,
+            txid,
+            model,
+            params,
+            output_hash,
+            timestamp,
+        ))
+        .map_err(|e| e.to_string())?;
+        Ok(Sha512Trunc256Sum::from_data(&message))
+    }
+
+    /// Attest, with `privk`, that running `params` for the task `txid` produced the output with
+    /// hash `output_hash`
+    pub fn sign(
+        privk: &Secp256k1PrivateKey,
+        txid: &str,
+        params: &InferParams,
+        output_hash: &str,
+        timestamp: u64,
+    ) -> Result<InferAttestation, String> {
+        let sighash =
+            Self::signature_hash(txid, &params.model, params, output_hash, timestamp)?;
+        let signature = privk.sign(sighash.as_bytes())?;
+        let mut public_key = Secp256k1PublicKey::from_private(privk);
+        public_key.set_compressed(true);
+        Ok(InferAttestation {
+            txid: txid.to_string(),
+            model: params.model.clone(),
+            params: params.clone(),
+            output_hash: output_hash.to_string(),
+            timestamp,
+            public_key: public_key.to_hex(),
+            signature,
+        })
+    }
+
+    /// Check the signature, and return the public key that made it
+    pub fn verify(&self) -> Result<Secp256k1PublicKey, String> {
+        let public_key = Secp256k1PublicKey::from_hex(&self.public_key)?;
+        let sighash = Self::signature_hash(
+            &self.txid,
+            &self.model,
+            &self.params,
+            &self.output_hash,
+            self.timestamp,
+        )?;
+        if !public_key.verify(sighash.as_bytes(), &self.signature)? {
+            return Err("signature does not match the public key".to_string());
+        }
+        Ok(public_key)
+    }
+
+    /// Is this a valid attestation of `result`?  Returns the public key that made it.
+    pub fn check_result(&self, result: &InferResult) -> Result<Secp256k1PublicKey, String> {
+        if self.txid != result.txid {
+            return Err(format!("attests to tx {}, not {}", &self.txid, &result.txid));
+        }
+        if self.output_hash != result.output_hash {
+            return Err(format!(
+                "attests to output {}, not {}",
+                &self.output_hash, &result.output_hash
+            ));
+        }
+        if self.model != self.params.model || result.params.as_ref() != Some(&self.params) {
+            return Err(format!(
+                "attests to parameters {:?}, not {:?}",
+                &self.params, &result.params
+            ));
+        }
+        self.verify()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{output_hash, InferStatus};
+
+    fn test_result() -> InferResult {
+        InferResult {
+            txid: "ab".repeat(32),
+            status: InferStatus::Success,
+            input: "Is the Earth round?".to_string(),
+            output: "Yes.".to_string(),
+            output_hash: output_hash("Yes."),
+            params: Some(InferParams {
+                model: Some("mock".to_string()),
+                seed: 42,
+                ..InferParams::default()
+            }),
+            attestation: None,
+            session_context: None,
+            session_turns: None,
+        }
+    }
+
+    fn attest(privk: &Secp256k1PrivateKey, result: &InferResult) -> InferAttestation {
+        InferAttestation::sign(
+            privk,
+            &result.txid,
+            result.params.as_ref().unwrap(),
+            &result.output_hash,
+            1_700_000_000,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let privk = Secp256k1PrivateKey::new();
+        let result = test_result();
+        let attestation = attest(&privk, &result);
+        assert_eq!(attestation.model, Some("mock".to_string()));
+
+        let public_key = attestation.check_result(&result).unwrap();
+        assert_eq!(
+            public_key.to_bytes_compressed(),
+            Secp256k1PublicKey::from_private(&privk).to_bytes_compressed()
+        );
+
+        // survives a round-trip through JSON, as served over RPC
+        let json = serde_json::to_string(&attestation).unwrap();
+        let decoded: InferAttestation = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, attestation);
+        decoded.check_result(&result).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_tampering() {
+        let privk = Secp256k1PrivateKey::new();
+        let result = test_result();
+        let attestation = attest(&privk, &result);
+
+        // the result doesn't match what was attested to
+        let mut other = test_result();
+        other.output_hash = output_hash("No.");
+        assert!(attestation.check_result(&other).is_err());
+        let mut other = test_result();
+        other.txid = "cd".repeat(32);
+        assert!(attestation.check_result(&other).is_err());
+        let mut other = test_result();
+        other.params.as_mut().unwrap().seed = 7;
+        assert!(attestation.check_result(&other).is_err());
+
+        // the attestation was altered after it was signed
+        let mut forged = attestation.clone();
+        forged.output_hash = output_hash("No.");
+        let mut other = test_result();
+        other.output_hash = output_hash("No.");
+        assert!(forged.check_result(&other).is_err());
+
+        let mut forged = attestation.clone();
+        forged.timestamp += 1;
+        assert!(forged.check_result(&result).is_err());
+
+        // ...or claims to be from someone else
+        let mut forged = attestation.clone();
+        forged.public_key = Secp256k1PublicKey::from_private(&Secp256k1PrivateKey::new()).to_hex();
+        assert!(forged.check_result(&result).is_err());
+    }
+}
@@ -0,0 +1,791 @@
+use std::error;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use openai::chat::{ChatCompletion, ChatCompletionMessage, ChatCompletionMessageRole};
+use serde::{Deserialize, Serialize};
+
+use stacks_common::util::hash::Sha256Sum;
+
+use crate::params::InferParams;
+
+pub const DEFAULT_BASE_URL: &str = "http://localhost:11434/v1/";
+pub const DEFAULT_API_KEY: &str = "ollama";
+pub const DEFAULT_MODEL: &str = "llama3.1";
+pub const DEFAULT_TIMEOUT_MS: u64 = 120_000;
+/// How often `SubprocessBackend` checks whether its program exited or its call was cancelled
+pub const SUBPROCESS_POLL_INTERVAL_MS: u64 = 10;
+
+/// A single chat-completion request handed to an `InferenceBackend`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CompletionRequest {
+    pub messages: Vec<ChatCompletionMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+impl CompletionRequest {
+    pub fn new(messages: Vec<ChatCompletionMessage>) -> CompletionRequest {
+        CompletionRequest {
+            messages,
+            temperature: None,
+            seed: None,
+            max_tokens: None,
+        }
+    }
+
+    pub fn with_temperature(mut self, temperature: f32) -> CompletionRequest {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Sample according to `params` (see `InferParams`)
+    pub fn with_params(mut self, params: &InferParams) -> CompletionRequest {
+        self.temperature = Some(params.temperature);
+        self.seed = Some(params.seed);
+        self.max_tokens = Some(params.max_tokens);
+        self
+    }
+
+    /// The content of the last user message in this request, if any.
+    pub fn last_user_content(&self) -> Option<&str> {
+        self.messages
+            .iter()
+            .rev()
+            .find(|msg| msg.role == ChatCompletionMessageRole::User)
+            .and_then(|msg| msg.content.as_deref())
+    }
+}
+
+/// Something that can run a chat completion against a model.
+///
+/// Implementations are shared between the node's inference thread and its other subsystems, so
+/// they must be thread-safe and must not rely on process-wide state.
+pub trait InferenceBackend: Send + Sync {
+    /// Human-readable backend name, used in logs.
+    fn name(&self) -> &str;
+    /// Identifier of the model this backend runs.
+    fn model(&self) -> &str;
+    /// Digest of the model weights this backend runs, if the operator pinned one.
+    fn model_digest(&self) -> Option<&str> {
+        None
+    }
+    /// Run `request` to completion and return the assistant's reply.
+    fn complete(&self, request: &CompletionRequest) -> Result<String, Box<dyn error::Error>>;
+    /// Like `complete`, but hand each piece of the reply to `on_token` as it is generated.  The
+    /// pieces add up to the returned reply.  Backends that can't stream emit the whole reply at
+    /// once.
+    fn complete_streaming(
+        &self,
+        request: &CompletionRequest,
+        on_token: &mut dyn FnMut(&str),
+    ) -> Result<String, Box<dyn error::Error>> {
+        let reply = self.complete(request)?;
+        on_token(&reply);
+        Ok(reply)
+    }
+    /// Like `complete_streaming`, but stop as soon as `cancelled` is set, once whoever asked no
+    /// longer wants the reply.  Backends whose calls can't be interrupted run them to completion.
+    fn complete_cancellable(
+        &self,
+        request: &CompletionRequest,
+        on_token: &mut dyn FnMut(&str),
+        _cancelled: &AtomicBool,
+    ) -> Result<String, Box<dyn error::Error>> {
+        self.complete_streaming(request, on_token)
+    }
+    /// Embed `text` as a vector, for backends that can.
+    fn embed(&self, _text: &str) -> Result<Vec<f32>, Box<dyn error::Error>> {
+        Err(Box::new(Error::new(
+            ErrorKind::Unsupported,
+            format!("EMBEDDINGS_UNSUPPORTED by backend {}", self.name()),
+        )))
+    }
+}
+
+/// Backend that talks to any OpenAI-compatible `/chat/completions` endpoint (OpenAI, Ollama,
+/// vLLM, llama.cpp server, ...).
+pub struct OpenAiBackend {
+    base_url: String,
+    api_key: String,
+    model: String,
+    model_digest: Option<String>,
+    embedding_model: Option<String>,
+    client: reqwest::blocking::Client,
+}
+
+#[derive(Serialize)]
+struct OpenAiChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatCompletionMessage],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+impl<'a> OpenAiChatRequest<'a> {
+    fn new(model: &'a str, request: &'a CompletionRequest) -> OpenAiChatRequest<'a> {
+        OpenAiChatRequest {
+            model,
+            messages: &request.messages,
+            temperature: request.temperature,
+            seed: request.seed,
+            max_tokens: request.max_tokens,
+            stream: false,
+        }
+    }
+
+    /// Ask for the reply as a stream of server-sent events
+    fn streaming(mut self) -> OpenAiChatRequest<'a> {
+        self.stream = true;
+        self
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenAiStreamDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiStreamChoice {
+    delta: OpenAiStreamDelta,
+}
+
+/// One server-sent event of a streamed chat completion
+#[derive(Deserialize)]
+struct OpenAiStreamChunk {
+    choices: Vec<OpenAiStreamChoice>,
+}
+
+/// Read a streamed chat completion from `body`, handing each piece of content to `on_token`, and
+/// return the whole reply.
+fn read_completion_stream<R: BufRead>(
+    body: R,
+    on_token: &mut dyn FnMut(&str),
+) -> Result<String, Box<dyn error::Error>> {
+    let mut reply = String::new();
+    for line in body.lines() {
+        let line = line?;
+        let Some(data) = line.strip_prefix("data:") else {
+            // blank separators, comments, and event names
+            continue;
+        };
+        let data = data.trim();
+        if data == "[DONE]" {
+            break;
+        }
+        let chunk: OpenAiStreamChunk = serde_json::from_str(data)?;
+        for choice in chunk.choices.iter().take(1) {
+            if let Some(content) = choice.delta.content.as_deref() {
+                if !content.is_empty() {
+                    on_token(content);
+                    reply.push_str(content);
+                }
+            }
+        }
+    }
+    if reply.is_empty() {
+        return Err(Box::new(Error::new(
+            ErrorKind::InvalidData,
+            "EMPTY_RESPONSE",
+        )));
+    }
+    Ok(reply)
+}
+
+#[derive(Serialize)]
+struct OpenAiEmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbedding {
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbeddingResponse {
+    data: Vec<OpenAiEmbedding>,
+}
+
+impl OpenAiBackend {
+    pub fn new(base_url: &str, api_key: &str, model: &str, timeout: Duration) -> OpenAiBackend {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("FATAL: failed to build HTTP client");
+        OpenAiBackend {
+            base_url: base_url.to_string(),
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+            model_digest: None,
+            embedding_model: None,
+            client,
+        }
+    }
+
+    /// Declare the digest of the weights served under `model`
+    pub fn with_model_digest(mut self, model_digest: &str) -> OpenAiBackend {
+        self.model_digest = Some(model_digest.to_string());
+        self
+    }
+
+    /// Use `embedding_model` for embeddings, instead of the chat model
+    pub fn with_embedding_model(mut self, embedding_model: &str) -> OpenAiBackend {
+        self.embedding_model = Some(embedding_model.to_string());
+        self
+    }
+
+    fn completions_url(&self) -> String {
+        format!("{}/chat/completions", self.base_url.trim_end_matches('/'))
+    }
+
+    fn embeddings_url(&self) -> String {
+        format!("{}/embeddings", self.base_url.trim_end_matches('/'))
+    }
+}
+
+impl Default for OpenAiBackend {
+    fn default() -> OpenAiBackend {
+        OpenAiBackend::new(
+            DEFAULT_BASE_URL,
+            DEFAULT_API_KEY,
+            DEFAULT_MODEL,
+            Duration::from_millis(DEFAULT_TIMEOUT_MS),
+        )
+    }
+}
+
+impl InferenceBackend for OpenAiBackend {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn model_digest(&self) -> Option<&str> {
+        self.model_digest.as_deref()
+    }
+
+    fn complete(&self, request: &CompletionRequest) -> Result<String, Box<dyn error::Error>> {
+        let body = OpenAiChatRequest::new(&self.model, request);
+        let response = self
+            .client
+            .post(self.completions_url())
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .send()?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().unwrap_or_default();
+            return Err(Box::new(Error::new(
+                ErrorKind::Other,
+                format!("BACKEND_HTTP_ERROR {}: {}", status, text),
+            )));
+        }
+        let completion: ChatCompletion = response.json()?;
+        first_choice_content(&completion)
+    }
+
+    fn complete_streaming(
+        &self,
+        request: &CompletionRequest,
+        on_token: &mut dyn FnMut(&str),
+    ) -> Result<String, Box<dyn error::Error>> {
+        let body = OpenAiChatRequest::new(&self.model, request).streaming();
+        let response = self
+            .client
+            .post(self.completions_url())
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .send()?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().unwrap_or_default();
+            return Err(Box::new(Error::new(
+                ErrorKind::Other,
+                format!("BACKEND_HTTP_ERROR {}: {}", status, text),
+            )));
+        }
+        read_completion_stream(BufReader::new(response), on_token)
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, Box<dyn error::Error>> {
+        let body = OpenAiEmbeddingRequest {
+            model: self.embedding_model.as_deref().unwrap_or(&self.model),
+            input: text,
+        };
+        let response = self
+            .client
+            .post(self.embeddings_url())
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .send()?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().unwrap_or_default();
+            return Err(Box::new(Error::new(
+                ErrorKind::Other,
+                format!("BACKEND_HTTP_ERROR {}: {}", status, text),
+            )));
+        }
+        let embeddings: OpenAiEmbeddingResponse = response.json()?;
+        embeddings
+            .data
+            .into_iter()
+            .next()
+            .map(|data| data.embedding)
+            .ok_or_else(|| Box::new(Error::new(ErrorKind::InvalidData, "EMPTY_RESPONSE")).into())
+    }
+}
+
+fn first_choice_content(completion: &ChatCompletion) -> Result<String, Box<dyn error::Error>> {
+    completion
+        .choices
+        .first()
+        .and_then(|choice| choice.message.content.clone())
+        .ok_or_else(|| Box::new(Error::new(ErrorKind::InvalidData, "EMPTY_RESPONSE")).into())
+}
+
+/// Deterministic backend for tests.
+///
+/// If constructed with a fixed response, it always answers with that; otherwise it echoes a
+/// digest of the last user message, so the same prompt always produces the same output.
+#[derive(Debug, Clone, Default)]
+pub struct MockBackend {
+    response: Option<String>,
+}
+
+impl MockBackend {
+    pub fn new() -> MockBackend {
+        MockBackend { response: None }
+    }
+
+    pub fn with_response(response: &str) -> MockBackend {
+        MockBackend {
+            response: Some(response.to_string()),
+        }
+    }
+}
+
+impl InferenceBackend for MockBackend {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn model(&self) -> &str {
+        "mock"
+    }
+
+    fn complete(&self, request: &CompletionRequest) -> Result<String, Box<dyn error::Error>> {
+        if let Some(ref response) = self.response {
+            return Ok(response.clone());
+        }
+        let input = request.last_user_content().unwrap_or("");
+        Ok(format!(
+            "mock:{}",
+            Sha256Sum::from_data(input.as_bytes()).to_hex()
+        ))
+    }
+
+    /// Emits the reply a word at a time
+    fn complete_streaming(
+        &self,
+        request: &CompletionRequest,
+        on_token: &mut dyn FnMut(&str),
+    ) -> Result<String, Box<dyn error::Error>> {
+        let reply = self.complete(request)?;
+        for word in reply.split_inclusive(' ') {
+            on_token(word);
+        }
+        Ok(reply)
+    }
+
+    /// A bag-of-bytes histogram of `text`, so equal texts embed identically and texts that
+    /// share most of their characters embed close together.
+    fn embed(&self, text: &str) -> Result<Vec<f32>, Box<dyn error::Error>> {
+        let mut embedding = vec![0f32; 64];
+        for byte in text.bytes() {
+            embedding[usize::from(byte) % 64] += 1.0;
+        }
+        Ok(embedding)
+    }
+}
+
+/// Backend that runs a local program once per request.
+///
+/// The request is written to the program's stdin as JSON (`{"model", "messages",
+/// "temperature", "seed", "max_tokens"}`), and its stdout, trimmed, is taken as the reply.  A non-zero exit status is
+/// treated as a failed inference.  A cancelled call kills the program.
+#[derive(Debug, Clone)]
+pub struct SubprocessBackend {
+    command: String,
+    args: Vec<String>,
+    model: String,
+    model_digest: Option<String>,
+}
+
+impl SubprocessBackend {
+    pub fn new(command: &str, args: Vec<String>, model: &str) -> SubprocessBackend {
+        SubprocessBackend {
+            command: command.to_string(),
+            args,
+            model: model.to_string(),
+            model_digest: None,
+        }
+    }
+
+    /// Declare the digest of the weights the program runs
+    pub fn with_model_digest(mut self, model_digest: &str) -> SubprocessBackend {
+        self.model_digest = Some(model_digest.to_string());
+        self
+    }
+}
+
+impl InferenceBackend for SubprocessBackend {
+    fn name(&self) -> &str {
+        "subprocess"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn model_digest(&self) -> Option<&str> {
+        self.model_digest.as_deref()
+    }
+
+    fn complete(&self, request: &CompletionRequest) -> Result<String, Box<dyn error::Error>> {
+        self.complete_cancellable(request, &mut |_| {}, &AtomicBool::new(false))
+    }
+
+    fn complete_cancellable(
+        &self,
+        request: &CompletionRequest,
+        on_token: &mut dyn FnMut(&str),
+        cancelled: &AtomicBool,
+    ) -> Result<String, Box<dyn error::Error>> {
+        let body = serde_json::to_vec(&OpenAiChatRequest::new(&self.model, request))?;
+
+        let mut child = Command::new(&self.command)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        // feed stdin and drain stdout and stderr at the same time, so that the program can't
+        // block on a full pipe while we block on another
+        let stdin = child.stdin.take();
+        let writer = thread::spawn(move || {
+            if let Some(mut stdin) = stdin {
+                // a program may answer without reading the whole request
+                let _ = stdin.write_all(&body);
+            }
+        });
+        let stdout = read_pipe(child.stdout.take());
+        let stderr = read_pipe(child.stderr.take());
+
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if cancelled.load(Ordering::SeqCst) {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(Box::new(Error::new(
+                    ErrorKind::Interrupted,
+                    "BACKEND_PROCESS_CANCELLED",
+                )));
+            }
+            thread::sleep(Duration::from_millis(SUBPROCESS_POLL_INTERVAL_MS));
+        };
+        let _ = writer.join();
+        let stdout = join_pipe(stdout)?;
+        let stderr = join_pipe(stderr)?;
+
+        if !status.success() {
+            return Err(Box::new(Error::new(
+                ErrorKind::Other,
+                format!("BACKEND_PROCESS_FAILED {}: {}", status, stderr.trim()),
+            )));
+        }
+
+        let output = stdout.trim();
+        if output.is_empty() {
+            return Err(Box::new(Error::new(ErrorKind::InvalidData, "EMPTY_RESPONSE")));
+        }
+        on_token(output);
+        Ok(output.to_string())
+    }
+}
+
+/// Read all of `pipe` on a thread of its own
+fn read_pipe<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<Result<String, Error>> {
+    thread::spawn(move || {
+        let mut contents = String::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_string(&mut contents)?;
+        }
+        Ok(contents)
+    })
+}
+
+fn join_pipe(reader: JoinHandle<Result<String, Error>>) -> Result<String, Error> {
+    reader
+        .join()
+        .map_err(|_| Error::new(ErrorKind::Other, "BACKEND_PROCESS_READ_FAILED"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_message(content: &str) -> ChatCompletionMessage {
+        ChatCompletionMessage {
+            role: ChatCompletionMessageRole::User,
+            content: Some(content.to_string()),
+            name: None,
+            function_call: None,
+        }
+    }
+
+    #[test]
+    fn test_mock_backend_is_deterministic() {
+        let backend = MockBackend::new();
+        let request = CompletionRequest::new(vec![user_message("Is the Earth round?")]);
+        let first = backend.complete(&request).unwrap();
+        let second = backend.complete(&request).unwrap();
+        assert_eq!(first, second);
+
+        let other = CompletionRequest::new(vec![user_message("Is the Moon round?")]);
+        assert_ne!(first, backend.complete(&other).unwrap());
+
+        let fixed = MockBackend::with_response("yes");
+        assert_eq!(fixed.complete(&request).unwrap(), "yes");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_subprocess_backend() {
+        let request = CompletionRequest::new(vec![user_message("Is the Earth round?")]);
+
+        let backend = SubprocessBackend::new(
+            "sh",
+            vec!["-c".into(), "cat > /dev/null; echo ' yes '".into()],
+            "local",
+        );
+        assert_eq!(backend.complete(&request).unwrap(), "yes");
+
+        let failing = SubprocessBackend::new("sh", vec!["-c".into(), "exit 3".into()], "local");
+        assert!(failing.complete(&request).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_subprocess_backend_fills_pipes() {
+        // a request bigger than a pipe buffer, from a program that fills stderr before it reads it
+        let request =
+            CompletionRequest::new(vec![user_message(&"Is the Earth round? ".repeat(20_000))]);
+        let backend = SubprocessBackend::new(
+            "sh",
+            vec![
+                "-c".into(),
+                "head -c 200000 /dev/zero >&2; cat > /dev/null; echo yes".into(),
+            ],
+            "local",
+        );
+        assert_eq!(backend.complete(&request).unwrap(), "yes");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_subprocess_backend_cancel() {
+        let request = CompletionRequest::new(vec![user_message("Is the Earth round?")]);
+        let backend = SubprocessBackend::new("sh", vec!["-c".into(), "sleep 30".into()], "local");
+        let cancelled = AtomicBool::new(false);
+        let started = std::time::Instant::now();
+        let result = thread::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(Duration::from_millis(100));
+                cancelled.store(true, Ordering::SeqCst);
+            });
+            backend.complete_cancellable(&request, &mut |_| {}, &cancelled)
+        });
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("BACKEND_PROCESS_CANCELLED"));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_openai_backend_url() {
+        let backend = OpenAiBackend::default();
+        assert_eq!(
+            backend.completions_url(),
+            "http://localhost:11434/v1/chat/completions"
+        );
+        assert_eq!(
+            backend.embeddings_url(),
+            "http://localhost:11434/v1/embeddings"
+        );
+        assert_eq!(backend.model(), DEFAULT_MODEL);
+        assert_eq!(backend.model_digest(), None);
+    }
+
+    #[test]
+    fn test_embeddings() {
+        let backend = MockBackend::new();
+        let first = backend.embed("Is the Earth round?").unwrap();
+        assert_eq!(first, backend.embed("Is the Earth round?").unwrap());
+        assert_ne!(first, backend.embed("Is the Moon round?").unwrap());
+
+        let local = SubprocessBackend::new("true", vec![], "local");
+        assert!(local.embed("Is the Earth round?").is_err());
+    }
+
+    #[test]
+    fn test_request_carries_params() {
+        let params = InferParams {
+            seed: 7,
+            max_tokens: 16,
+            ..InferParams::default()
+        };
+        let request =
+            CompletionRequest::new(vec![user_message("Is the Earth round?")]).with_params(&params);
+        let body = serde_json::to_value(OpenAiChatRequest::new("m", &request)).unwrap();
+        assert_eq!(body["model"], "m");
+        assert_eq!(body["temperature"], 0.0);
+        assert_eq!(body["seed"], 7);
+        assert_eq!(body["max_tokens"], 16);
+
+        let unseeded = CompletionRequest::new(vec![user_message("Is the Earth round?")]);
+        let body = serde_json::to_value(OpenAiChatRequest::new("m", &unseeded)).unwrap();
+        assert!(body.get("seed").is_none());
+        assert!(body.get("max_tokens").is_none());
+        assert!(body.get("stream").is_none());
+
+        let body =
+            serde_json::to_value(OpenAiChatRequest::new("m", &unseeded).streaming()).unwrap();
+        assert_eq!(body["stream"], true);
+    }
+
+    #[test]
+    fn test_mock_backend_streams_words() {
+        let request = CompletionRequest::new(vec![user_message("Is the Earth round?")]);
+        let backend = MockBackend::with_response("Yes, it is.");
+        let mut tokens = vec![];
+        let reply = backend
+            .complete_streaming(&request, &mut |token| tokens.push(token.to_string()))
+            .unwrap();
+        assert_eq!(reply, "Yes, it is.");
+        assert_eq!(tokens, vec!["Yes, ", "it ", "is."]);
+
+        // backends that can't stream emit the whole reply
+        let local = SubprocessBackend::new("sh", vec!["-c".into(), "echo yes".into()], "local");
+        let mut tokens = vec![];
+        let reply = local
+            .complete_streaming(&request, &mut |token| tokens.push(token.to_string()))
+            .unwrap();
+        assert_eq!(tokens, vec![reply]);
+    }
+
+    #[test]
+    fn test_read_completion_stream() {
+        let body = concat!(
+            ": keep-alive\n",
+            "\n",
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n",
+            "\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Yes, \"}}]}\n",
+            "\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"it is.\"}}]}\n",
+            "\n",
+            "data: [DONE]\n",
+            "\n",
+        );
+        let mut tokens = vec![];
+        let reply =
+            read_completion_stream(body.as_bytes(), &mut |token| tokens.push(token.to_string()))
+                .unwrap();
+        assert_eq!(reply, "Yes, it is.");
+        assert_eq!(tokens, vec!["Yes, ", "it is."]);
+
+        assert!(read_completion_stream("data: [DONE]\n".as_bytes(), &mut |_| {}).is_err());
+        assert!(read_completion_stream("data: nope\n".as_bytes(), &mut |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_openai_backend_streams() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            // read the request headers and body
+            let mut reader = BufReader::new(sock.try_clone().unwrap());
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(len) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = len.trim().parse().unwrap();
+                }
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut request = vec![0u8; content_length];
+            reader.read_exact(&mut request).unwrap();
+
+            let events = concat!(
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Yes\"}}]}\n\n",
+                "data: {\"choices\":[{\"delta\":{\"content\":\", it is.\"}}]}\n\n",
+                "data: [DONE]\n\n",
+            );
+            write!(
+                sock,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                events.len(),
+                events
+            )
+            .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&request).unwrap()
+        });
+
+        let backend = OpenAiBackend::new(
+            &format!("http://{}/v1/", addr),
+            "key",
+            "m",
+            Duration::from_secs(10),
+        );
+        let request = CompletionRequest::new(vec![user_message("Is the Earth round?")]);
+        let mut tokens = vec![];
+        let reply = backend
+            .complete_streaming(&request, &mut |token| tokens.push(token.to_string()))
+            .unwrap();
+        assert_eq!(reply, "Yes, it is.");
+        assert_eq!(tokens, vec!["Yes", ", it is."]);
+
+        let body = server.join().unwrap();
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["model"], "m");
+    }
+}
@@ -0,0 +1,64 @@
+//! Cost accounting for inference tasks.
+//!
+//! An Infer transaction is charged by how much text the model has to read and write.  Real
+//! tokenizers differ per model and aren't available to every node, so token counts are
+//! estimated from byte lengths -- what matters for consensus is that every node computes the
+//! same number.
+
+/// Bytes per estimated token
+pub const BYTES_PER_TOKEN: u64 = 4;
+
+/// Generating a token costs this many times as much as reading one
+pub const OUTPUT_TOKEN_WEIGHT: u64 = 4;
+
+/// Estimated number of tokens in `text`
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.len() as u64).div_ceil(BYTES_PER_TOKEN)
+}
+
+/// What an inference task consumed, in estimated tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InferCost {
+    pub input_tokens: u64,
+    pub context_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl InferCost {
+    pub fn new(user_input: &str, context: &str, output: &str) -> InferCost {
+        InferCost {
+            input_tokens: estimate_tokens(user_input),
+            context_tokens: estimate_tokens(context),
+            output_tokens: estimate_tokens(output),
+        }
+    }
+
+    /// The input to the `cost_stx_infer` cost function
+    pub fn cost_units(&self) -> u64 {
+        self.input_tokens
+            .saturating_add(self.context_tokens)
+            .saturating_add(self.output_tokens.saturating_mul(OUTPUT_TOKEN_WEIGHT))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("a"), 1);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+
+    #[test]
+    fn test_cost_units() {
+        let cost = InferCost::new("Is the Earth round?", "", "Yes.");
+        assert_eq!(cost.input_tokens, 5);
+        assert_eq!(cost.context_tokens, 0);
+        assert_eq!(cost.output_tokens, 1);
+        assert_eq!(cost.cost_units(), 5 + OUTPUT_TOKEN_WEIGHT);
+    }
+}
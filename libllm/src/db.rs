@@ -1,13 +1,20 @@
-#![allow(dead_code)]
+//! The `InferDB` stores the inference tasks a node has been asked to run, and their results.
+//!
+//! It lives next to the chainstate (see `InferDB::db_path`), and is opened once by each
+//! subsystem that needs it -- the chainstate, the mempool, and the node's inference thread.
+//! Handles are cheap to clone and share a single connection.
 
 use std::error;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
 
-use rusqlite::Error::QueryReturnedNoRows;
 use rusqlite::types::ToSql;
-use rusqlite::{
-    Connection, NO_PARAMS,
-    params,
-};
+use rusqlite::Error::QueryReturnedNoRows;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Row, Transaction, NO_PARAMS};
+
+use crate::params::InferContext;
 
 #[derive(Debug)]
 pub struct ResultRow {
@@ -19,81 +26,498 @@ pub struct ResultRow {
     pub status: u8,
     pub create_time: String,
     pub start_time: String,
-    pub end_time: String
+    pub end_time: String,
+    /// JSON-encoded `InferParams`: as requested until the task runs, as resolved afterwards
+    pub params: String,
+    /// Fee paid by the Infer transaction; higher-fee tasks run first
+    pub fee: u64,
+    /// How many times this task has been started
+    pub attempts: u32,
+    /// Unix time (in seconds) before which a `Retrying` task won't be started again
+    pub next_attempt_time: u64,
+    /// Why the last attempt failed, if it did
+    pub error: String,
+    /// JSON-encoded `InferAttestation` of a successful task's output, if the node signed it
+    pub attestation: String,
+    /// Highest Stacks block height at which the task's transaction was confirmed, in any fork
+    pub confirm_height: Option<u64>,
+    /// Unix time (in seconds) at which the task's transaction left the mempool unconfirmed, or 0
+    pub evict_time: u64,
 }
 
-pub const LLM_DB_PATH: &str = "./llm.sqlite";
+const RESULT_ROW_COLUMNS: &str = "txid, context, input, output, output_hash, status, create_time, start_time, end_time, params, fee, attempts, next_attempt_time, error, attestation, confirm_height, evict_time";
 
-pub fn initialize_conn(conn: &Connection) -> Result<(), Box<dyn error::Error>> {
-    conn.query_row("PRAGMA journal_mode = WAL;", NO_PARAMS, |_row| Ok(()))?;
+impl ResultRow {
+    fn from_row(row: &Row) -> Result<ResultRow, rusqlite::Error> {
+        Ok(ResultRow {
+            txid: row.get(0)?,
+            context: row.get(1)?,
+            input: row.get(2)?,
+            output: row.get(3)?,
+            output_hash: row.get(4)?,
+            status: row.get(5)?,
+            create_time: row.get(6)?,
+            start_time: row.get(7)?,
+            end_time: row.get(8)?,
+            params: row.get(9)?,
+            fee: row.get::<_, i64>(10)?.max(0) as u64,
+            attempts: row.get(11)?,
+            next_attempt_time: row.get::<_, i64>(12)?.max(0) as u64,
+            error: row.get(13)?,
+            attestation: row.get(14)?,
+            confirm_height: row
+                .get::<_, Option<i64>>(15)?
+                .map(|height| height.max(0) as u64),
+            evict_time: row.get::<_, i64>(16)?.max(0) as u64,
+        })
+    }
+}
 
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS result_table
-                  (txid TEXT PRIMARY KEY, context TEXT default '', input TEXT default '',
-                   output TEXT default '', output_hash TEXT default '',
-                   status INTEGER, create_time TEXT default '',
-                   start_time TEXT default '', end_time TEXT default '')",
-        NO_PARAMS,
-    )?;
+/// A snapshot of a task, taken whenever its status changes
+#[derive(Debug)]
+pub struct TaskEventRow {
+    pub seq: u64,
+    pub txid: String,
+    pub status: u8,
+    pub fee: u64,
+    pub attempts: u32,
+    pub params: String,
+    pub output_hash: String,
+    pub error: String,
+    /// Unix times (in seconds), if the task got that far
+    pub create_time: Option<u64>,
+    pub start_time: Option<u64>,
+    pub end_time: Option<u64>,
+}
 
-    let sql = "SELECT sql FROM sqlite_master WHERE name=?";
-    let _: String = conn
-        .query_row(sql, &["result_table"], |row| row.get(0))?;
+impl TaskEventRow {
+    fn from_row(row: &Row) -> Result<TaskEventRow, rusqlite::Error> {
+        let time = |idx: usize| -> Result<Option<u64>, rusqlite::Error> {
+            Ok(row.get::<_, Option<i64>>(idx)?.map(|time| time.max(0) as u64))
+        };
+        Ok(TaskEventRow {
+            seq: row.get::<_, i64>(0)?.max(0) as u64,
+            txid: row.get(1)?,
+            status: row.get(2)?,
+            fee: row.get::<_, i64>(3)?.max(0) as u64,
+            attempts: row.get(4)?,
+            params: row.get(5)?,
+            output_hash: row.get(6)?,
+            error: row.get(7)?,
+            create_time: time(8)?,
+            start_time: time(9)?,
+            end_time: time(10)?,
+        })
+    }
+}
 
-    Ok(())
+pub const INFER_DB_FILENAME: &str = "llm.sqlite";
+pub const INFERDB_VERSION: &str = "7";
+
+/// How long to wait on a locked database before giving up
+const INFERDB_BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+const INFERDB_INITIAL_SCHEMA: &[&str] = &[
+    r#"
+    CREATE TABLE result_table(
+        txid TEXT PRIMARY KEY,
+        context TEXT default '',
+        input TEXT default '',
+        output TEXT default '',
+        output_hash TEXT default '',
+        status INTEGER,
+        create_time TEXT default '',
+        start_time TEXT default '',
+        end_time TEXT default ''
+    );"#,
+    "CREATE TABLE db_config(version TEXT NOT NULL);",
+];
+
+const INFERDB_SCHEMA_2: &[&str] = &[
+    // sampling parameters the output was produced with
+    "ALTER TABLE result_table ADD COLUMN params TEXT default '';",
+];
+
+const INFERDB_SCHEMA_3: &[&str] = &[
+    // scheduling state for the worker pool
+    "ALTER TABLE result_table ADD COLUMN fee INTEGER default 0;",
+    "ALTER TABLE result_table ADD COLUMN attempts INTEGER default 0;",
+    "ALTER TABLE result_table ADD COLUMN next_attempt_time INTEGER default 0;",
+    "ALTER TABLE result_table ADD COLUMN error TEXT default '';",
+    // older nodes retried failed tasks indefinitely, so give them a fresh set of attempts
+    "UPDATE result_table SET status = 6 WHERE status = 4;",
+];
+
+const INFERDB_SCHEMA_4: &[&str] = &[
+    // lifecycle changes of tasks, waiting to be sent to event observers
+    r#"
+    CREATE TABLE task_events(
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        txid TEXT NOT NULL,
+        status INTEGER NOT NULL,
+        fee INTEGER default 0,
+        attempts INTEGER default 0,
+        params TEXT default '',
+        output_hash TEXT default '',
+        error TEXT default '',
+        create_time INTEGER,
+        start_time INTEGER,
+        end_time INTEGER
+    );"#,
+];
+
+const INFERDB_SCHEMA_5: &[&str] = &[
+    // the node's signed attestation of each output it produced
+    "ALTER TABLE result_table ADD COLUMN attestation TEXT default '';",
+];
+
+const INFERDB_SCHEMA_6: &[&str] = &[
+    // what the retention policy needs to know about each task's transaction
+    "ALTER TABLE result_table ADD COLUMN confirm_height INTEGER default NULL;",
+    "ALTER TABLE result_table ADD COLUMN evict_time INTEGER default 0;",
+];
+
+const INFERDB_SCHEMA_7: &[&str] = &[
+    // the previous turn of each task's session, which must be kept as long as the task is
+    "ALTER TABLE result_table ADD COLUMN parent TEXT default NULL;",
+];
+
+/// How many undelivered task events to keep.  Older ones are dropped, so that a node with no
+/// one draining them doesn't grow the DB without bound.
+const MAX_TASK_EVENTS: i64 = 10_000;
+
+const INFERDB_INDEXES: &[&str] = &[
+    "CREATE INDEX IF NOT EXISTS index_result_status ON result_table(status, create_time);",
+    "CREATE INDEX IF NOT EXISTS index_result_queue ON result_table(status, fee, create_time);",
+    "CREATE INDEX IF NOT EXISTS index_result_retention ON result_table(confirm_height, evict_time);",
+    "CREATE INDEX IF NOT EXISTS index_result_parent ON result_table(parent);",
+];
+
+#[derive(Clone)]
+pub struct InferDB {
+    path: String,
+    conn: Arc<Mutex<Connection>>,
 }
 
-pub fn open(filename: &str) -> Result<Connection, Box<dyn error::Error>> {
-    let llm_db = Connection::open(filename)?;
-    initialize_conn(&llm_db)?;
-    Ok(llm_db)
+impl std::fmt::Debug for InferDB {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "InferDB({})", self.path)
+    }
 }
 
-pub fn sqlite_create(conn: &Connection, txid: &str, context: &str, input: &str, status: u8) -> Result<(), Box<dyn error::Error>> {
-    let params: [&dyn ToSql; 4] = [&txid, &context, &input, &status];
-    conn.execute(
-        "REPLACE INTO result_table (txid, context, input, status, create_time) VALUES (?, ?, ?, ?, datetime('now'))",
+fn table_exists(conn: &Connection, table_name: &str) -> Result<bool, rusqlite::Error> {
+    let sql = "SELECT name FROM sqlite_master WHERE type='table' AND name=?";
+    conn.query_row(sql, &[table_name], |row| row.get::<_, String>(0))
+        .optional()
+        .map(|r| r.is_some())
+}
+
+impl InferDB {
+    /// Path to the inference DB, given the chainstate's root directory
+    pub fn db_path(chainstate_root_path: &str) -> String {
+        let mut path = PathBuf::from(chainstate_root_path);
+        path.push(INFER_DB_FILENAME);
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Open the inference DB at `path`, creating it and applying any pending schema migrations.
+    pub fn open(path: &str) -> Result<InferDB, Box<dyn error::Error>> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE,
+        )?;
+        conn.busy_timeout(INFERDB_BUSY_TIMEOUT)?;
+        conn.query_row("PRAGMA journal_mode = WAL;", NO_PARAMS, |_row| Ok(()))?;
+        Self::check_instantiate_db(path, conn)
+    }
+
+    /// Open an in-memory inference DB (used for testing)
+    pub fn open_memory() -> Result<InferDB, Box<dyn error::Error>> {
+        let conn = Connection::open_in_memory()?;
+        Self::check_instantiate_db(":memory:", conn)
+    }
+
+    fn check_instantiate_db(path: &str, mut conn: Connection) -> Result<InferDB, Box<dyn error::Error>> {
+        let tx = conn.transaction()?;
+        if !table_exists(&tx, "db_config")? {
+            if table_exists(&tx, "result_table")? {
+                // created by an unversioned node, whose schema matches version 1
+                tx.execute_batch(INFERDB_INITIAL_SCHEMA[1])?;
+                Self::set_schema_version(&tx, "1")?;
+            } else {
+                Self::instantiate(&tx)?;
+            }
+        }
+        Self::apply_schema_migrations(&tx)?;
+        for row_text in INFERDB_INDEXES {
+            tx.execute_batch(row_text)?;
+        }
+        tx.commit()?;
+
+        Ok(InferDB {
+            path: path.to_string(),
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    fn instantiate(tx: &Transaction) -> Result<(), Box<dyn error::Error>> {
+        for row_text in INFERDB_INITIAL_SCHEMA {
+            tx.execute_batch(row_text)?;
+        }
+        Self::set_schema_version(tx, "1")
+    }
+
+    fn apply_schema_2(tx: &Transaction) -> Result<(), Box<dyn error::Error>> {
+        for row_text in INFERDB_SCHEMA_2 {
+            tx.execute_batch(row_text)?;
+        }
+        Self::set_schema_version(tx, "2")
+    }
+
+    fn apply_schema_3(tx: &Transaction) -> Result<(), Box<dyn error::Error>> {
+        for row_text in INFERDB_SCHEMA_3 {
+            tx.execute_batch(row_text)?;
+        }
+        Self::set_schema_version(tx, "3")
+    }
+
+    fn apply_schema_4(tx: &Transaction) -> Result<(), Box<dyn error::Error>> {
+        for row_text in INFERDB_SCHEMA_4 {
+            tx.execute_batch(row_text)?;
+        }
+        Self::set_schema_version(tx, "4")
+    }
+
+    fn apply_schema_5(tx: &Transaction) -> Result<(), Box<dyn error::Error>> {
+        for row_text in INFERDB_SCHEMA_5 {
+            tx.execute_batch(row_text)?;
+        }
+        Self::set_schema_version(tx, "5")
+    }
+
+    fn apply_schema_6(tx: &Transaction) -> Result<(), Box<dyn error::Error>> {
+        for row_text in INFERDB_SCHEMA_6 {
+            tx.execute_batch(row_text)?;
+        }
+        Self::set_schema_version(tx, "6")
+    }
+
+    fn apply_schema_7(tx: &Transaction) -> Result<(), Box<dyn error::Error>> {
+        for row_text in INFERDB_SCHEMA_7 {
+            tx.execute_batch(row_text)?;
+        }
+        // the parent is part of the context
+        let contexts = {
+            let mut stmt = tx.prepare("SELECT txid, context FROM result_table")?;
+            let rows = stmt.query_map(NO_PARAMS, |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+        for (txid, context) in contexts {
+            if let Some(parent) = InferContext::parse(&context).parent {
+                tx.execute(
+                    "UPDATE result_table SET parent = ?2 WHERE txid = ?1",
+                    &[&txid, &parent],
+                )?;
+            }
+        }
+        Self::set_schema_version(tx, "7")
+    }
+
+    /// Get the database schema version, given a DB connection
+    fn get_schema_version(conn: &Connection) -> Result<String, Box<dyn error::Error>> {
+        let version = conn.query_row("SELECT MAX(version) FROM db_config", NO_PARAMS, |row| {
+            row.get(0)
+        })?;
+        Ok(version)
+    }
+
+    fn set_schema_version(tx: &Transaction, version: &str) -> Result<(), Box<dyn error::Error>> {
+        tx.execute("DELETE FROM db_config", NO_PARAMS)?;
+        tx.execute("INSERT INTO db_config (version) VALUES (?1)", &[version])?;
+        Ok(())
+    }
+
+    /// Bring an existing DB up to `INFERDB_VERSION`, one schema version at a time.
+    fn apply_schema_migrations(tx: &Transaction) -> Result<(), Box<dyn error::Error>> {
+        loop {
+            let version = Self::get_schema_version(tx)?;
+            match version.as_str() {
+                "1" => Self::apply_schema_2(tx)?,
+                "2" => Self::apply_schema_3(tx)?,
+                "3" => Self::apply_schema_4(tx)?,
+                "4" => Self::apply_schema_5(tx)?,
+                "5" => Self::apply_schema_6(tx)?,
+                "6" => Self::apply_schema_7(tx)?,
+                INFERDB_VERSION => return Ok(()),
+                _ => {
+                    return Err(Box::new(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Unsupported InferDB schema version {}", version),
+                    )))
+                }
+            }
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().expect("FATAL: InferDB connection lock poisoned")
+    }
+}
+
+/// Create task `txid`, replacing any earlier task for it -- unless that one succeeded, since a
+/// successful result may already be committed on-chain.  Returns whether the task was created.
+pub fn sqlite_create(conn: &Connection, txid: &str, context: &str, input: &str, status: u8, infer_params: &str, fee: u64) -> Result<bool, Box<dyn error::Error>> {
+    let fee = i64::try_from(fee).unwrap_or(i64::MAX);
+    let parent = InferContext::parse(context).parent;
+    let params: [&dyn ToSql; 7] = [&txid, &context, &input, &status, &infer_params, &fee, &parent];
+    let created = conn.execute(
+        "REPLACE INTO result_table (txid, context, input, status, params, fee, parent, create_time)
+         SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, datetime('now')
+         WHERE NOT EXISTS (SELECT 1 FROM result_table WHERE txid = ?1 AND status = 3)",
         &params,
     )?;
-    Ok(())
+    if created > 0 {
+        sqlite_record_event(conn, txid)?;
+    }
+    Ok(created > 0)
 }
 
-pub fn sqlite_start_llm(conn: &Connection, txid: &str, status: u8) -> Result<(), Box<dyn error::Error>> {
+/// Mark a queued (`Created` or `Retrying`) task as `status` and count the attempt.  Returns
+/// false if the task is no longer queued.
+pub fn sqlite_start_llm(conn: &Connection, txid: &str, status: u8) -> Result<bool, Box<dyn error::Error>> {
     let params: [&dyn ToSql; 2] = [&txid, &status];
-    conn.execute(
-        "UPDATE result_table SET status = ?2, start_time = datetime('now') WHERE txid = ?1",
+    let updated = conn.execute(
+        "UPDATE result_table SET status = ?2, attempts = attempts + 1, start_time = datetime('now') WHERE txid = ?1 AND status IN (1, 6)",
         &params,
     )?;
-    Ok(())
+    if updated > 0 {
+        sqlite_record_event(conn, txid)?;
+    }
+    Ok(updated > 0)
 }
 
-pub fn sqlite_end_llm(conn: &Connection, txid: &str, output: &str, output_hash: &str, status: u8) -> Result<(), Box<dyn error::Error>> {
-    let params: [&dyn ToSql; 4] = [&txid, &output, &output_hash, &status];
+pub fn sqlite_end_llm(conn: &Connection, txid: &str, output: &str, output_hash: &str, status: u8, infer_params: &str) -> Result<(), Box<dyn error::Error>> {
+    let params: [&dyn ToSql; 5] = [&txid, &output, &output_hash, &status, &infer_params];
     conn.execute(
-        "UPDATE result_table SET output = ?2, output_hash = ?3, status = ?4, end_time = datetime('now') WHERE txid = ?1",
+        "UPDATE result_table SET output = ?2, output_hash = ?3, status = ?4, params = ?5, end_time = datetime('now') WHERE txid = ?1",
+        &params,
+    )?;
+    sqlite_record_event(conn, txid)
+}
+
+/// Like `sqlite_end_llm`, but only if the task is still `InProgress` (i.e. it wasn't cancelled
+/// while it ran), and along with the output's `attestation`, if any.  Returns whether the
+/// result was recorded.
+pub fn sqlite_finish_llm(conn: &Connection, txid: &str, output: &str, output_hash: &str, status: u8, infer_params: &str, err: &str, attestation: &str) -> Result<bool, Box<dyn error::Error>> {
+    let params: [&dyn ToSql; 7] = [&txid, &output, &output_hash, &status, &infer_params, &err, &attestation];
+    let updated = conn.execute(
+        "UPDATE result_table SET output = ?2, output_hash = ?3, status = ?4, params = ?5, error = ?6, attestation = ?7, end_time = datetime('now') WHERE txid = ?1 AND status = 2",
         &params,
     )?;
+    if updated > 0 {
+        sqlite_record_event(conn, txid)?;
+    }
+    Ok(updated > 0)
+}
+
+/// Record the output that attempt number `attempts` at an `InProgress` task has produced so far.
+/// Returns false if the task is no longer `InProgress`, or is on another attempt.
+pub fn sqlite_save_partial_output(conn: &Connection, txid: &str, attempts: u32, output: &str) -> Result<bool, Box<dyn error::Error>> {
+    let params: [&dyn ToSql; 3] = [&txid, &attempts, &output];
+    let updated = conn.execute(
+        "UPDATE result_table SET output = ?3 WHERE txid = ?1 AND status = 2 AND attempts = ?2",
+        &params,
+    )?;
+    Ok(updated > 0)
+}
+
+/// Put an `InProgress` task back in the queue as `Retrying`, not to be started again before
+/// `next_attempt_time`.  Returns false if the task is no longer `InProgress`.
+pub fn sqlite_retry_llm(conn: &Connection, txid: &str, next_attempt_time: u64, err: &str) -> Result<bool, Box<dyn error::Error>> {
+    let next_attempt_time = i64::try_from(next_attempt_time).unwrap_or(i64::MAX);
+    let params: [&dyn ToSql; 3] = [&txid, &next_attempt_time, &err];
+    let updated = conn.execute(
+        "UPDATE result_table SET status = 6, output = '', next_attempt_time = ?2, error = ?3, end_time = datetime('now') WHERE txid = ?1 AND status = 2",
+        &params,
+    )?;
+    if updated > 0 {
+        sqlite_record_event(conn, txid)?;
+    }
+    Ok(updated > 0)
+}
+
+/// Cancel a task that hasn't finished yet.  Returns false if there was nothing to cancel.
+pub fn sqlite_cancel_llm(conn: &Connection, txid: &str) -> Result<bool, Box<dyn error::Error>> {
+    let updated = conn.execute(
+        "UPDATE result_table SET status = 8, output = '', end_time = datetime('now') WHERE txid = ?1 AND status IN (1, 2, 6)",
+        &[&txid],
+    )?;
+    if updated > 0 {
+        sqlite_record_event(conn, txid)?;
+    }
+    Ok(updated > 0)
+}
+
+/// Re-queue tasks left `InProgress` by a node that stopped while running them.
+pub fn sqlite_requeue_in_progress(conn: &Connection) -> Result<usize, Box<dyn error::Error>> {
+    let txids = {
+        let mut stmt = conn.prepare("SELECT txid FROM result_table WHERE status = 2")?;
+        let txids = stmt.query_map(NO_PARAMS, |row| row.get::<_, String>(0))?;
+        txids.collect::<Result<Vec<_>, _>>()?
+    };
+    let updated = conn.execute(
+        "UPDATE result_table SET status = 6, output = '', next_attempt_time = 0 WHERE status = 2",
+        NO_PARAMS,
+    )?;
+    for txid in txids.iter() {
+        sqlite_record_event(conn, txid)?;
+    }
+    Ok(updated)
+}
+
+/// Snapshot task `txid` into the task events, dropping the oldest events past `MAX_TASK_EVENTS`.
+fn sqlite_record_event(conn: &Connection, txid: &str) -> Result<(), Box<dyn error::Error>> {
+    conn.execute(
+        "INSERT INTO task_events (txid, status, fee, attempts, params, output_hash, error, create_time, start_time, end_time)
+         SELECT txid, status, fee, attempts, params, output_hash, error, strftime('%s', create_time), strftime('%s', start_time), strftime('%s', end_time)
+         FROM result_table WHERE txid = ?1",
+        &[&txid],
+    )?;
+    conn.execute(
+        "DELETE FROM task_events WHERE seq <= (SELECT MAX(seq) FROM task_events) - ?1",
+        &[&MAX_TASK_EVENTS],
+    )?;
     Ok(())
 }
 
+/// Remove and return up to `max` of the oldest task events, oldest first.
+pub fn sqlite_take_events(conn: &Connection, max: u32) -> Result<Vec<TaskEventRow>, Box<dyn error::Error>> {
+    let events = {
+        let mut stmt = conn.prepare(
+            "SELECT seq, txid, status, fee, attempts, params, output_hash, error, create_time, start_time, end_time FROM task_events ORDER BY seq LIMIT ?1",
+        )?;
+        let events = stmt.query_map(&[&max], TaskEventRow::from_row)?;
+        events.collect::<Result<Vec<_>, _>>()?
+    };
+    if let Some(last) = events.last() {
+        let last = i64::try_from(last.seq).unwrap_or(i64::MAX);
+        conn.execute("DELETE FROM task_events WHERE seq <= ?1", &[&last])?;
+    }
+    Ok(events)
+}
+
 pub fn sqlite_get(conn: &Connection, txid: &str) -> Result<ResultRow, Box<dyn error::Error>> {
     let params: [&dyn ToSql; 1] = [&txid];
     let result = conn.query_row(
-        "SELECT txid, context, input, output, output_hash, status, create_time, start_time, end_time FROM result_table WHERE txid = ?",
+        &format!("SELECT {} FROM result_table WHERE txid = ?", RESULT_ROW_COLUMNS),
         &params,
-        |row| {
-            Ok( ResultRow {
-                txid: row.get(0)?,
-                context: row.get(1)?,
-                input: row.get(2)?,
-                output: row.get(3)?,
-                output_hash: row.get(4)?,
-                status: row.get(5)?,
-                create_time: row.get(6)?,
-                start_time: row.get(7)?,
-                end_time: row.get(8)?,
-            })
-        }
+        ResultRow::from_row,
     );
     match result {
         Ok(row) => Ok(row),
@@ -107,28 +531,452 @@ pub fn sqlite_get(conn: &Connection, txid: &str) -> Result<ResultRow, Box<dyn er
             create_time: "".to_string(),
             start_time: "".to_string(),
             end_time: "".to_string(),
+            params: "".to_string(),
+            fee: 0,
+            attempts: 0,
+            next_attempt_time: 0,
+            error: "".to_string(),
+            attestation: "".to_string(),
+            confirm_height: None,
+            evict_time: 0,
         }),
         Err(e) => Err(Box::new(e)),
     }
 }
 
-pub fn sqlite_filter_to_infer(conn: &Connection) -> Result<ResultRow, Box<dyn error::Error>> {
+/// The next task to run at unix time `now`: the highest-fee task that is either new, or due for
+/// a retry.  Ties go to the oldest task, then to the lowest txid.
+pub fn sqlite_filter_to_infer(conn: &Connection, now: u64) -> Result<Option<ResultRow>, Box<dyn error::Error>> {
+    let now = i64::try_from(now).unwrap_or(i64::MAX);
     let result = conn.query_row(
-        "SELECT txid, context, input, output, output_hash, status, create_time, start_time, end_time FROM result_table WHERE status = ? OR status = ? ORDER BY create_time",
-        params![1u32, 4u8],
-        |row| {
-            Ok( ResultRow {
-                txid: row.get(0)?,
-                context: row.get(1)?,
-                input: row.get(2)?,
-                output: row.get(3)?,
-                output_hash: row.get(4)?,
-                status: row.get(5)?,
-                create_time: row.get(6)?,
-                start_time: row.get(7)?,
-                end_time: row.get(8)?,
-            })
-        }
-    )?;
+        &format!(
+            "SELECT {} FROM result_table WHERE status = ?1 OR (status = ?2 AND next_attempt_time <= ?3) ORDER BY fee DESC, create_time, txid LIMIT 1",
+            RESULT_ROW_COLUMNS
+        ),
+        params![1u8, 6u8, now],
+        ResultRow::from_row,
+    )
+    .optional()?;
     Ok(result)
-}
\ No newline at end of file
+}
+
+/// How many queued (`Created` or `Retrying`) tasks would be run before the queued task `txid`,
+/// in the order `sqlite_filter_to_infer` picks them.  A `Retrying` task still waiting out its
+/// backoff is counted as if it were due.  Returns None if `txid` isn't queued.
+pub fn sqlite_queue_position(conn: &Connection, txid: &str) -> Result<Option<u64>, Box<dyn error::Error>> {
+    let params: [&dyn ToSql; 1] = [&txid];
+    let position: Option<i64> = conn
+        .query_row(
+            "SELECT (SELECT COUNT(*) FROM result_table AS ahead WHERE ahead.status IN (1, 6) AND
+                        (ahead.fee > task.fee OR (ahead.fee = task.fee AND
+                            (ahead.create_time < task.create_time OR (ahead.create_time = task.create_time AND ahead.txid < task.txid)))))
+             FROM result_table AS task WHERE task.txid = ?1 AND task.status IN (1, 6)",
+            &params,
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(position.map(|position| position.max(0) as u64))
+}
+
+/// How long (in seconds) the task `txid` waited before its latest attempt was started, since it
+/// became due: when it was submitted, or when its retry backoff ran out.  Returns None if the task
+/// hasn't been started.
+pub fn sqlite_queue_wait(conn: &Connection, txid: &str) -> Result<Option<u64>, Box<dyn error::Error>> {
+    let params: [&dyn ToSql; 1] = [&txid];
+    let wait: Option<Option<i64>> = conn
+        .query_row(
+            "SELECT CAST(strftime('%s', start_time) AS INTEGER) - MAX(CAST(strftime('%s', create_time) AS INTEGER), next_attempt_time)
+             FROM result_table WHERE txid = ?1 AND start_time != ''",
+            &params,
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(wait.flatten().map(|wait| wait.max(0) as u64))
+}
+
+/// Up to `max` tasks that succeeded and whose transactions are still in the mempool (neither
+/// confirmed nor evicted), highest fee first.  Ties go to the oldest task, then to the lowest txid.
+pub fn sqlite_list_unconfirmed_successes(conn: &Connection, max: u32) -> Result<Vec<ResultRow>, Box<dyn error::Error>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM result_table WHERE status = 3 AND confirm_height IS NULL AND evict_time = 0 ORDER BY fee DESC, create_time, txid LIMIT ?1",
+        RESULT_ROW_COLUMNS
+    ))?;
+    let rows = stmt.query_map(&[&max], ResultRow::from_row)?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// How many tasks are queued (`Created` or `Retrying`)
+pub fn sqlite_count_queued(conn: &Connection) -> Result<u64, Box<dyn error::Error>> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM result_table WHERE status IN (1, 6)",
+        NO_PARAMS,
+        |row| row.get(0),
+    )?;
+    Ok(count.max(0) as u64)
+}
+
+/// Record that task `txid`'s transaction was confirmed in a block at `height`.  In case of a
+/// reorg, the highest such height is kept.  Returns false if there is no such task.
+pub fn sqlite_confirm(conn: &Connection, txid: &str, height: u64) -> Result<bool, Box<dyn error::Error>> {
+    let height = i64::try_from(height).unwrap_or(i64::MAX);
+    let params: [&dyn ToSql; 2] = [&txid, &height];
+    let updated = conn.execute(
+        "UPDATE result_table SET confirm_height = MAX(IFNULL(confirm_height, 0), ?2), evict_time = 0 WHERE txid = ?1",
+        &params,
+    )?;
+    Ok(updated > 0)
+}
+
+/// Record that task `txid`'s transaction left the mempool at unix time `now`, unless it was
+/// confirmed or already left.  Returns whether anything changed.
+pub fn sqlite_evict(conn: &Connection, txid: &str, now: u64) -> Result<bool, Box<dyn error::Error>> {
+    let now = i64::try_from(now).unwrap_or(i64::MAX).max(1);
+    let params: [&dyn ToSql; 2] = [&txid, &now];
+    let updated = conn.execute(
+        "UPDATE result_table SET evict_time = ?2 WHERE txid = ?1 AND confirm_height IS NULL AND evict_time = 0",
+        &params,
+    )?;
+    Ok(updated > 0)
+}
+
+/// Record that task `txid`'s transaction is (back) in the mempool.
+pub fn sqlite_unevict(conn: &Connection, txid: &str) -> Result<bool, Box<dyn error::Error>> {
+    let updated = conn.execute(
+        "UPDATE result_table SET evict_time = 0 WHERE txid = ?1 AND evict_time != 0",
+        &[&txid],
+    )?;
+    Ok(updated > 0)
+}
+
+/// Delete the tasks whose transactions were confirmed at or below `max_confirm_height`, and the
+/// unconfirmed ones whose transactions left the mempool at or before `max_evict_time`.  Tasks
+/// that are running, and tasks that a kept task continues the session of, are left alone.
+/// Returns how many were deleted.
+pub fn sqlite_prune(conn: &Connection, max_confirm_height: Option<u64>, max_evict_time: Option<u64>) -> Result<usize, Box<dyn error::Error>> {
+    // -1 matches nothing
+    let max_confirm_height = max_confirm_height.map_or(-1, |height| i64::try_from(height).unwrap_or(i64::MAX));
+    let max_evict_time = max_evict_time.map_or(-1, |time| i64::try_from(time).unwrap_or(i64::MAX));
+    let params: [&dyn ToSql; 2] = [&max_confirm_height, &max_evict_time];
+    // a session's turns can only be deleted from its last one back, one turn per pass
+    let mut deleted = 0;
+    loop {
+        let deleted_turns = conn.execute(
+            "DELETE FROM result_table WHERE status != 2 AND
+                ((confirm_height IS NOT NULL AND confirm_height <= ?1) OR
+                 (confirm_height IS NULL AND evict_time > 0 AND evict_time <= ?2)) AND
+                txid NOT IN (SELECT parent FROM result_table WHERE parent IS NOT NULL)",
+            &params,
+        )?;
+        if deleted_turns == 0 {
+            return Ok(deleted);
+        }
+        deleted += deleted_turns;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instantiate_and_reopen() {
+        let path = std::env::temp_dir().join(format!("infer-db-{}.sqlite", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        let db = InferDB::open(&path).unwrap();
+        assert_eq!(InferDB::get_schema_version(&db.conn()).unwrap(), INFERDB_VERSION);
+        sqlite_create(&db.conn(), "01", "", "hello", 1, "", 0).unwrap();
+        drop(db);
+
+        let db = InferDB::open(&path).unwrap();
+        assert_eq!(sqlite_get(&db.conn(), "01").unwrap().input, "hello");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_migrate_unversioned_db() {
+        let conn = Connection::open_in_memory().unwrap();
+        // what pre-versioned nodes created in ./llm.sqlite
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS result_table
+                  (txid TEXT PRIMARY KEY, context TEXT default '', input TEXT default '',
+                   output TEXT default '', output_hash TEXT default '',
+                   status INTEGER, create_time TEXT default '',
+                   start_time TEXT default '', end_time TEXT default '')",
+            NO_PARAMS,
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO result_table (txid, input, status) VALUES ('01', 'hello', 3)",
+            NO_PARAMS,
+        )
+        .unwrap();
+
+        let db = InferDB::check_instantiate_db(":memory:", conn).unwrap();
+        assert_eq!(InferDB::get_schema_version(&db.conn()).unwrap(), INFERDB_VERSION);
+        let row = sqlite_get(&db.conn(), "01").unwrap();
+        assert_eq!(row.status, 3);
+        assert_eq!(row.params, "");
+    }
+
+    #[test]
+    fn test_migrate_schema_6() {
+        let conn = Connection::open_in_memory().unwrap();
+        for row_text in INFERDB_INITIAL_SCHEMA
+            .iter()
+            .chain(INFERDB_SCHEMA_2.iter())
+            .chain(INFERDB_SCHEMA_3.iter())
+            .chain(INFERDB_SCHEMA_4.iter())
+            .chain(INFERDB_SCHEMA_5.iter())
+            .chain(INFERDB_SCHEMA_6.iter())
+        {
+            conn.execute_batch(row_text).unwrap();
+        }
+        conn.execute("INSERT INTO db_config (version) VALUES ('6')", NO_PARAMS)
+            .unwrap();
+        conn.execute(
+            r#"INSERT INTO result_table (txid, context, input, status) VALUES
+                 ('01', '', 'hello', 3),
+                 ('02', '{"parent":"01"}', 'again', 1)"#,
+            NO_PARAMS,
+        )
+        .unwrap();
+
+        let db = InferDB::check_instantiate_db(":memory:", conn).unwrap();
+        assert_eq!(InferDB::get_schema_version(&db.conn()).unwrap(), INFERDB_VERSION);
+
+        // sessions are backfilled
+        let parent = |txid: &str| -> Option<String> {
+            db.conn()
+                .query_row(
+                    "SELECT parent FROM result_table WHERE txid = ?1",
+                    &[txid],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        assert_eq!(parent("01"), None);
+        assert_eq!(parent("02"), Some("01".to_string()));
+    }
+
+    #[test]
+    fn test_migrate_schema_2() {
+        let conn = Connection::open_in_memory().unwrap();
+        for row_text in INFERDB_INITIAL_SCHEMA.iter().chain(INFERDB_SCHEMA_2.iter()) {
+            conn.execute_batch(row_text).unwrap();
+        }
+        conn.execute("INSERT INTO db_config (version) VALUES ('2')", NO_PARAMS)
+            .unwrap();
+        conn.execute(
+            "INSERT INTO result_table (txid, input, status) VALUES ('01', 'hello', 4)",
+            NO_PARAMS,
+        )
+        .unwrap();
+
+        let db = InferDB::check_instantiate_db(":memory:", conn).unwrap();
+        assert_eq!(InferDB::get_schema_version(&db.conn()).unwrap(), INFERDB_VERSION);
+
+        // failed tasks get another chance
+        let row = sqlite_get(&db.conn(), "01").unwrap();
+        assert_eq!(row.status, 6);
+        assert_eq!(row.attempts, 0);
+        assert_eq!(row.fee, 0);
+        assert_eq!(
+            sqlite_filter_to_infer(&db.conn(), 0).unwrap().unwrap().txid,
+            "01"
+        );
+    }
+
+    #[test]
+    fn test_queue_position() {
+        let db = InferDB::open_memory().unwrap();
+        let conn = db.conn();
+        sqlite_create(&conn, "a", "", "low", 1, "", 1).unwrap();
+        sqlite_create(&conn, "b", "", "high", 1, "", 10).unwrap();
+        sqlite_create(&conn, "c", "", "high too", 1, "", 10).unwrap();
+        sqlite_create(&conn, "d", "", "done", 3, "", 100).unwrap();
+
+        assert_eq!(sqlite_queue_position(&conn, "b").unwrap(), Some(0));
+        assert_eq!(sqlite_queue_position(&conn, "c").unwrap(), Some(1));
+        assert_eq!(sqlite_queue_position(&conn, "a").unwrap(), Some(2));
+        assert_eq!(sqlite_queue_position(&conn, "d").unwrap(), None);
+        assert_eq!(sqlite_queue_position(&conn, "e").unwrap(), None);
+        assert_eq!(sqlite_filter_to_infer(&conn, 0).unwrap().unwrap().txid, "b");
+
+        // once the head of the queue starts, everyone moves up
+        assert!(sqlite_start_llm(&conn, "b", 2).unwrap());
+        assert_eq!(sqlite_queue_position(&conn, "b").unwrap(), None);
+        assert_eq!(sqlite_queue_position(&conn, "c").unwrap(), Some(0));
+        assert_eq!(sqlite_queue_position(&conn, "a").unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_save_partial_output() {
+        let db = InferDB::open_memory().unwrap();
+        let conn = db.conn();
+        sqlite_create(&conn, "a", "", "hello", 1, "", 0).unwrap();
+
+        // not started yet
+        assert!(!sqlite_save_partial_output(&conn, "a", 1, "Hel").unwrap());
+
+        assert!(sqlite_start_llm(&conn, "a", 2).unwrap());
+        assert!(sqlite_save_partial_output(&conn, "a", 1, "Hel").unwrap());
+        assert_eq!(sqlite_get(&conn, "a").unwrap().output, "Hel");
+
+        // a retry starts from scratch, and the abandoned attempt can't write to it
+        assert!(sqlite_retry_llm(&conn, "a", 0, "oops").unwrap());
+        assert_eq!(sqlite_get(&conn, "a").unwrap().output, "");
+        assert!(sqlite_start_llm(&conn, "a", 2).unwrap());
+        assert!(!sqlite_save_partial_output(&conn, "a", 1, "Hello").unwrap());
+        assert!(sqlite_save_partial_output(&conn, "a", 2, "Hi").unwrap());
+
+        assert!(sqlite_cancel_llm(&conn, "a").unwrap());
+        assert_eq!(sqlite_get(&conn, "a").unwrap().output, "");
+    }
+
+    #[test]
+    fn test_task_events() {
+        let db = InferDB::open_memory().unwrap();
+        let conn = db.conn();
+        sqlite_create(&conn, "a", "", "hello", 1, "", 5).unwrap();
+        assert!(sqlite_start_llm(&conn, "a", 2).unwrap());
+        assert!(sqlite_retry_llm(&conn, "a", 0, "oops").unwrap());
+        // nothing changed, so nothing to report
+        assert!(!sqlite_retry_llm(&conn, "a", 0, "oops").unwrap());
+        assert!(!sqlite_start_llm(&conn, "b", 2).unwrap());
+
+        let events = sqlite_take_events(&conn, 2).unwrap();
+        assert_eq!(
+            events.iter().map(|e| e.status).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(events[0].txid, "a");
+        assert_eq!(events[0].fee, 5);
+        assert_eq!(events[0].attempts, 0);
+        assert!(events[0].create_time.is_some());
+        assert_eq!(events[0].start_time, None);
+        assert_eq!(events[1].attempts, 1);
+        assert!(events[1].start_time.is_some());
+        assert!(events[1].seq > events[0].seq);
+
+        assert!(sqlite_start_llm(&conn, "a", 2).unwrap());
+        assert!(sqlite_finish_llm(&conn, "a", "hi", "00", 3, "", "", "").unwrap());
+
+        let events = sqlite_take_events(&conn, 10).unwrap();
+        assert_eq!(
+            events.iter().map(|e| e.status).collect::<Vec<_>>(),
+            vec![6, 2, 3]
+        );
+        assert_eq!(events[0].error, "oops");
+        assert!(events[0].end_time.is_some());
+        assert_eq!(events[2].output_hash, "00");
+        assert_eq!(events[2].attempts, 2);
+        assert!(sqlite_take_events(&conn, 10).unwrap().is_empty());
+
+        // tasks interrupted by a restart go back in the queue
+        sqlite_create(&conn, "b", "", "hello", 1, "", 0).unwrap();
+        assert!(sqlite_start_llm(&conn, "b", 2).unwrap());
+        assert_eq!(sqlite_requeue_in_progress(&conn).unwrap(), 1);
+        let events = sqlite_take_events(&conn, 10).unwrap();
+        assert_eq!(events.last().unwrap().txid, "b");
+        assert_eq!(events.last().unwrap().status, 6);
+    }
+
+    #[test]
+    fn test_never_overwrite_success() {
+        let db = InferDB::open_memory().unwrap();
+        let conn = db.conn();
+        sqlite_create(&conn, "a", "", "hello", 1, "", 0).unwrap();
+        assert!(sqlite_start_llm(&conn, "a", 2).unwrap());
+        assert!(sqlite_finish_llm(&conn, "a", "hi", "00", 3, "", "", "").unwrap());
+
+        assert!(!sqlite_create(&conn, "a", "", "goodbye", 1, "", 0).unwrap());
+        let row = sqlite_get(&conn, "a").unwrap();
+        assert_eq!(row.status, 3);
+        assert_eq!(row.input, "hello");
+        assert_eq!(row.output, "hi");
+
+        // anything else can be replaced
+        sqlite_create(&conn, "b", "", "hello", 1, "", 0).unwrap();
+        assert!(sqlite_cancel_llm(&conn, "b").unwrap());
+        assert!(sqlite_create(&conn, "b", "", "goodbye", 1, "", 0).unwrap());
+        let row = sqlite_get(&conn, "b").unwrap();
+        assert_eq!(row.status, 1);
+        assert_eq!(row.input, "goodbye");
+    }
+
+    #[test]
+    fn test_prune() {
+        let db = InferDB::open_memory().unwrap();
+        let conn = db.conn();
+        for txid in ["a", "b", "c", "d", "e"] {
+            sqlite_create(&conn, txid, "", "hello", 1, "", 0).unwrap();
+        }
+        assert!(!sqlite_confirm(&conn, "z", 10).unwrap());
+
+        // confirmed at 10, then again at 12 in another fork
+        assert!(sqlite_confirm(&conn, "a", 10).unwrap());
+        assert!(sqlite_confirm(&conn, "a", 12).unwrap());
+        assert!(sqlite_confirm(&conn, "a", 11).unwrap());
+        assert_eq!(sqlite_get(&conn, "a").unwrap().confirm_height, Some(12));
+        // confirmed, then garbage-collected from the mempool
+        assert!(sqlite_confirm(&conn, "b", 10).unwrap());
+        assert!(!sqlite_evict(&conn, "b", 100).unwrap());
+        // evicted unconfirmed
+        assert!(sqlite_evict(&conn, "c", 100).unwrap());
+        assert!(!sqlite_evict(&conn, "c", 200).unwrap());
+        assert_eq!(sqlite_get(&conn, "c").unwrap().evict_time, 100);
+        // evicted, but came back
+        assert!(sqlite_evict(&conn, "d", 100).unwrap());
+        assert!(sqlite_unevict(&conn, "d").unwrap());
+        // evicted while running
+        assert!(sqlite_start_llm(&conn, "e", 2).unwrap());
+        assert!(sqlite_evict(&conn, "e", 100).unwrap());
+
+        assert_eq!(sqlite_prune(&conn, None, None).unwrap(), 0);
+        assert_eq!(sqlite_prune(&conn, Some(11), Some(99)).unwrap(), 1);
+        assert_eq!(sqlite_get(&conn, "b").unwrap().status, 5);
+        assert_eq!(sqlite_get(&conn, "a").unwrap().status, 1);
+
+        assert_eq!(sqlite_prune(&conn, Some(12), Some(100)).unwrap(), 2);
+        assert_eq!(sqlite_get(&conn, "a").unwrap().status, 5);
+        assert_eq!(sqlite_get(&conn, "c").unwrap().status, 5);
+        assert_eq!(sqlite_get(&conn, "d").unwrap().status, 1);
+        assert_eq!(sqlite_get(&conn, "e").unwrap().status, 2);
+    }
+
+    #[test]
+    fn test_list_unconfirmed_successes() {
+        let db = InferDB::open_memory().unwrap();
+        let conn = db.conn();
+        for (txid, fee) in [("a", 1), ("b", 3), ("c", 2), ("d", 5), ("e", 4), ("f", 6)] {
+            sqlite_create(&conn, txid, "", "hello", 1, "", fee).unwrap();
+        }
+        for txid in ["a", "b", "c", "d", "e"] {
+            sqlite_end_llm(&conn, txid, "world", "00", 3, "").unwrap();
+        }
+        // confirmed, evicted, and still running
+        assert!(sqlite_confirm(&conn, "d", 10).unwrap());
+        assert!(sqlite_evict(&conn, "e", 100).unwrap());
+        assert!(sqlite_start_llm(&conn, "f", 2).unwrap());
+
+        let txids = |max| -> Vec<String> {
+            sqlite_list_unconfirmed_successes(&conn, max)
+                .unwrap()
+                .into_iter()
+                .map(|row| row.txid)
+                .collect()
+        };
+        assert_eq!(txids(10), vec!["b", "c", "a"]);
+        assert_eq!(txids(2), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn test_db_path() {
+        assert_eq!(
+            InferDB::db_path("/var/stacks/chainstate"),
+            "/var/stacks/chainstate/llm.sqlite"
+        );
+    }
+}
@@ -1,24 +1,48 @@
+#[macro_use(slog_log, slog_warn, slog_info, slog_debug)]
+extern crate slog;
+#[macro_use]
+extern crate stacks_common;
 
 use std::error;
 use std::io::{Error, ErrorKind};
+use std::sync::atomic::AtomicBool;
+#[cfg(test)]
 use std::thread;
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
 
 use hex;
-use openai::{
-    chat::{ChatCompletion, ChatCompletionMessage, ChatCompletionMessageRole},
-    set_base_url, set_key,
-};
+use openai::chat::ChatCompletionMessage;
 
 use stacks_common::util::hash::Sha256Sum;
 
-mod db;
-
-pub const INFER_CHECK_SUCCESS: i32 = 1;
-pub const INFER_CHECK_FAIL: i32 = 0;
-
-pub async fn infer(user_input: &str, context_messages: Option<Vec<ChatCompletionMessage>>) -> Result<String, Box<dyn error::Error>> {
+pub mod attestation;
+pub mod backend;
+pub mod cost;
+pub mod db;
+pub mod metrics;
+pub mod mock_server;
+pub mod params;
+pub mod pool;
+pub mod retention;
+pub mod session;
+
+pub use attestation::InferAttestation;
+pub use backend::{
+    CompletionRequest, InferenceBackend, MockBackend, OpenAiBackend, SubprocessBackend,
+};
+pub use cost::InferCost;
+pub use db::InferDB;
+pub use metrics::InferMetrics;
+pub use mock_server::{MockLlmServer, MockReply};
+pub use openai::chat::ChatCompletionMessageRole;
+pub use params::{InferContext, InferParams, InferPayment};
+pub use pool::{InferPoolConfig, InferWorkerPool};
+pub use retention::InferRetentionConfig;
+pub use session::SessionTurn;
+
+fn user_request(user_input: &str, context_messages: Option<Vec<ChatCompletionMessage>>) -> Result<CompletionRequest, Box<dyn error::Error>> {
     if user_input.is_empty() {
         return Err(Box::new(Error::new(ErrorKind::InvalidInput, "EMPTY_USER_INPUT")));
     }
@@ -35,55 +59,38 @@ pub async fn infer(user_input: &str, context_messages: Option<Vec<ChatCompletion
         function_call: None,
     });
 
-    set_key("ollama".to_string());
-    set_base_url("http://localhost:11434/v1/".to_string());
-
-    let chat_completion = ChatCompletion::builder("llama3.1", messages.clone())
-        .create()
-        .await?;
-
-    Ok(chat_completion.choices[0].message.content.clone().expect("ResponseError"))
+    Ok(CompletionRequest::new(messages))
 }
 
-pub async fn infer_check(user_input: &str, output: &str, context_messages: Option<Vec<ChatCompletionMessage>>)  -> Result<i32, Box<dyn error::Error>> {
-    if user_input.is_empty() {
-        return Err(Box::new(Error::new(ErrorKind::InvalidInput, "EMPTY_USER_INPUT")));
-    }
-
-    if output.is_empty() {
-        return Err(Box::new(Error::new(ErrorKind::InvalidInput, "EMPTY_OUTPUT")));
-    }
-
-    let mut messages = Vec::new();
-    if let Some(context_messages) = context_messages {
-        messages.extend(context_messages);
-    }
-
-    messages.push(ChatCompletionMessage {
-        role: ChatCompletionMessageRole::User,
-        content: Some(format!("请评估问题和答案是否匹配，匹配：1，不匹配：0。
-# 说明
-直接提供最终分数，不要解释和说明
-# 问题
-{user_input}
-# 回答
-{output}", user_input=user_input, output=output)),
-        name: None,
-        function_call: None,
-    });
+pub fn infer(backend: &dyn InferenceBackend, user_input: &str, context_messages: Option<Vec<ChatCompletionMessage>>) -> Result<String, Box<dyn error::Error>> {
+    backend.complete(&user_request(user_input, context_messages)?)
+}
 
-    set_key("ollama".to_string());
-    set_base_url("http://localhost:11434/v1/".to_string());
+/// Like `infer`, but sampling with `params`.  This is how on-chain inference tasks are run.
+pub fn infer_with_params(backend: &dyn InferenceBackend, user_input: &str, context_messages: Option<Vec<ChatCompletionMessage>>, params: &InferParams) -> Result<String, Box<dyn error::Error>> {
+    backend.complete(&user_request(user_input, context_messages)?.with_params(params))
+}
 
-    let chat_completion = ChatCompletion::builder("llama3.1", messages.clone())
-        .create()
-        .await?;
+/// The hash committed on-chain for an inference output
+pub fn output_hash(output: &str) -> String {
+    hex::encode(Sha256Sum::from_data(output.as_bytes()))
+}
 
-    Ok(chat_completion.choices[0].message.content.clone().expect("ResponseError").parse::<i32>()?)
+/// Re-run an inference task with the parameters another node recorded for it, and return the
+/// output.
+pub fn rerun_infer(backend: &dyn InferenceBackend, user_input: &str, context: &str, params: &InferParams) -> Result<String, Box<dyn error::Error>> {
+    let context = InferContext::parse(context);
+    let params = params.resolve(backend)?;
+    infer_with_params(backend, user_input, context.context_messages(), &params)
 }
 
+/// Re-run an inference task with the parameters another node recorded for it, and return the
+/// hash of the output.  A validator compares this against the hash the miner committed.
+pub fn recompute_output_hash(backend: &dyn InferenceBackend, user_input: &str, context: &str, params: &InferParams) -> Result<String, Box<dyn error::Error>> {
+    Ok(output_hash(&rerun_infer(backend, user_input, context, params)?))
+}
 
-pub async fn random_question() -> Result<String, Box<dyn error::Error>> {
+pub fn random_question(backend: &dyn InferenceBackend) -> Result<String, Box<dyn error::Error>> {
     let mut messages = Vec::new();
     messages.push(ChatCompletionMessage {
         role: ChatCompletionMessageRole::User,
@@ -92,24 +99,40 @@ pub async fn random_question() -> Result<String, Box<dyn error::Error>> {
         function_call: None,
     });
 
-    set_key("ollama".to_string());
-    set_base_url("http://localhost:11434/v1/".to_string());
-
-    let chat_completion = ChatCompletion::builder("llama3.1", messages.clone())
-        .temperature(0.9)
-        .create()
-        .await?;
-
-    Ok(chat_completion.choices[0].message.content.clone().expect("ResponseError"))
+    backend.complete(&CompletionRequest::new(messages).with_temperature(0.9))
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum InferStatus {
+    /// Queued, and not yet attempted
     Created = 1,
+    /// Being run by a worker
     InProgress = 2,
     Success = 3,
+    /// Gave up after the last attempt failed
     Failure = 4,
     NotFound = 5,
+    /// An attempt failed; queued to be retried after a backoff
+    Retrying = 6,
+    /// Gave up after the last attempt ran out of time
+    TimedOut = 7,
+    /// The transaction left the mempool before the task finished
+    Cancelled = 8,
+}
+
+impl InferStatus {
+    /// Whether this node is done with the task: it succeeded, was given up on, or isn't queued
+    /// at all
+    pub fn is_finished(&self) -> bool {
+        match self {
+            InferStatus::Created | InferStatus::InProgress | InferStatus::Retrying => false,
+            InferStatus::Success
+            | InferStatus::Failure
+            | InferStatus::NotFound
+            | InferStatus::TimedOut
+            | InferStatus::Cancelled => true,
+        }
+    }
 }
 
 impl From<u8> for InferStatus {
@@ -120,6 +143,9 @@ impl From<u8> for InferStatus {
             3 => InferStatus::Success,
             4 => InferStatus::Failure,
             5 => InferStatus::NotFound,
+            6 => InferStatus::Retrying,
+            7 => InferStatus::TimedOut,
+            8 => InferStatus::Cancelled,
             _ => panic!("UNKNOWN_VALUE {}", value),
         }
     }
@@ -134,31 +160,153 @@ pub struct InferResult {
     pub input: String,
     pub output: String,
     pub output_hash: String,
+    /// The parameters the output was (or will be) produced with
+    #[serde(default)]
+    pub params: Option<InferParams>,
+    /// The producing node's signed attestation of the output, once the task succeeds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<InferAttestation>,
+    /// If the task continues a session, the context it was run with: its own, preceded by the
+    /// conversation so far
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_context: Option<String>,
+    /// If the task continues a session, the session's earlier turns, oldest first, from which
+    /// `session_context` was built
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_turns: Option<Vec<SessionTurn>>,
+}
+
+fn parse_params(params: &str) -> Option<InferParams> {
+    serde_json::from_str(params).ok()
+}
+
+fn parse_attestation(attestation: &str) -> Option<InferAttestation> {
+    serde_json::from_str(attestation).ok()
+}
+
+/// The context a task with `context` is run with, and the earlier turns of its session, if it
+/// continues a session whose earlier turns have all succeeded
+fn session_context(infer_db: &InferDB, context: &str) -> Option<(String, Vec<SessionTurn>)> {
+    let context = InferContext::parse(context);
+    if context.parent.is_none() {
+        return None;
+    }
+    let turns = session::session_turns(infer_db, &context).ok()?;
+    let expanded = session::expand_turns(&context, &turns);
+    Some((serde_json::to_string(&expanded).ok()?, turns))
+}
+
+/// Where an inference task stands, for clients following it while it runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferProgress {
+    pub txid: String,
+    pub status: InferStatus,
+    /// The output so far, while the task is `InProgress`; the final output once it succeeds
+    pub output: String,
+    /// How many queued tasks will run before this one, while it is queued
+    pub queue_position: Option<u64>,
+    /// When the task was queued, as UTC `YYYY-MM-DD HH:MM:SS`
+    pub create_time: Option<String>,
+    /// When the last attempt at the task started
+    pub start_time: Option<String>,
+    /// When the last attempt at the task ended
+    pub end_time: Option<String>,
+}
+
+/// A change in an inference task's status, as reported to event observers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferTaskEvent {
+    pub txid: String,
+    pub status: InferStatus,
+    pub fee: u64,
+    /// How many times the task has been started
+    pub attempts: u32,
+    /// The model requested, or once the task has run, the model that ran it
+    pub model: Option<String>,
+    pub model_digest: Option<String>,
+    /// Set once the task succeeds
+    pub output_hash: Option<String>,
+    /// Why the last attempt failed, if it did
+    pub error: Option<String>,
+    /// When the task was queued, as a unix time in seconds
+    pub create_time: Option<u64>,
+    /// When the last attempt at the task started
+    pub start_time: Option<u64>,
+    /// When the last attempt at the task ended
+    pub end_time: Option<u64>,
 }
 
+fn non_empty(s: String) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s)
+    }
+}
 
-#[allow(unused_variables)]
-pub fn infer_chain(txid: String, user_input: &str, context_messages: Option<Vec<ChatCompletionMessage>>) -> Result<InferStatus, Box<dyn error::Error>> {
-    let llm_db = db::open(db::LLM_DB_PATH)?;
-    let _ = db::sqlite_create(&llm_db, &txid.as_str(), &"", user_input, InferStatus::Created as u8)?;
+
+/// Queue an inference task.  `context` is the Infer transaction's context, as described by
+/// `InferContext`, and `fee` is the transaction's fee, which sets the task's priority.
+///
+/// A task that is already known is left alone (and its status returned), unless it was
+/// cancelled, in which case it is queued afresh.  Either way, its transaction is taken to be in
+/// the mempool, so its result is no longer an orphan (see `retention`).
+pub fn infer_chain(infer_db: &InferDB, txid: String, user_input: &str, context: &str, fee: u64) -> Result<InferStatus, Box<dyn error::Error>> {
+    let params = serde_json::to_string(&InferContext::parse(context).params)?;
+    let llm_db = infer_db.conn();
+    let status: InferStatus = db::sqlite_get(&llm_db, &txid.as_str())?.status.into();
+    if status != InferStatus::NotFound && status != InferStatus::Cancelled {
+        db::sqlite_unevict(&llm_db, &txid.as_str())?;
+        return Ok(status);
+    }
+    let _ = db::sqlite_create(&llm_db, &txid.as_str(), context, user_input, InferStatus::Created as u8, &params, fee)?;
     Ok(InferStatus::Created)
 }
 
+/// Cancel the inference task for `txid` if it hasn't finished yet, e.g. because its transaction
+/// left the mempool.  A worker that is running it will have its result discarded.  Returns
+/// whether there was anything to cancel.
+pub fn cancel(infer_db: &InferDB, txid: String) -> Result<bool, Box<dyn error::Error>> {
+    db::sqlite_cancel_llm(&infer_db.conn(), &txid.as_str())
+}
 
-pub fn query(txid: String) -> Result<InferResult, Box<dyn error::Error>> {
-    let llm_db = db::open(db::LLM_DB_PATH)?;
-    let result = db::sqlite_get(&llm_db, &txid.as_str())?;
+
+pub fn query(infer_db: &InferDB, txid: String) -> Result<InferResult, Box<dyn error::Error>> {
+    let result = db::sqlite_get(&infer_db.conn(), &txid.as_str())?;
+    let (session_context, session_turns) = match session_context(infer_db, &result.context) {
+        Some((session_context, turns)) => (Some(session_context), Some(turns)),
+        None => (None, None),
+    };
     Ok(InferResult{
         txid: result.txid,
         status:  result.status.into(),
         input: result.input,
         output: result.output,
         output_hash: result.output_hash,
+        params: parse_params(&result.params),
+        attestation: parse_attestation(&result.attestation),
+        session_context,
+        session_turns,
     })
 }
 
-pub fn query_hash(txid: String) -> Result<InferResult, Box<dyn error::Error>> {
-    let llm_db = db::open(db::LLM_DB_PATH)?;
+pub fn query_progress(infer_db: &InferDB, txid: String) -> Result<InferProgress, Box<dyn error::Error>> {
+    let llm_db = infer_db.conn();
+    let result = db::sqlite_get(&llm_db, &txid.as_str())?;
+    let queue_position = db::sqlite_queue_position(&llm_db, &txid.as_str())?;
+    Ok(InferProgress {
+        txid,
+        status: result.status.into(),
+        output: result.output,
+        queue_position,
+        create_time: non_empty(result.create_time),
+        start_time: non_empty(result.start_time),
+        end_time: non_empty(result.end_time),
+    })
+}
+
+pub fn query_hash(infer_db: &InferDB, txid: String) -> Result<InferResult, Box<dyn error::Error>> {
+    let llm_db = infer_db.conn();
     let result = db::sqlite_get(&llm_db, &txid.as_str())?;
     Ok(InferResult{
         txid: result.txid,
@@ -166,31 +314,132 @@ pub fn query_hash(txid: String) -> Result<InferResult, Box<dyn error::Error>> {
         input: "".to_string(),
         output: "".to_string(),
         output_hash: result.output_hash,
+        params: parse_params(&result.params),
+        attestation: parse_attestation(&result.attestation),
+        session_context: None,
+        session_turns: None,
     })
 }
 
-pub async fn _internal_do_infer() -> Result<(), Box<dyn error::Error>>{
-    // todo llm infer
-    // 0. open connection
-    let llm_db = db::open(db::LLM_DB_PATH)?;
+/// Up to `max` results of inferences that succeeded and whose transactions are still in the
+/// mempool, highest fee first: those a miner can put in its next block.
+pub fn query_unconfirmed_successes(infer_db: &InferDB, max: u32) -> Result<Vec<InferResult>, Box<dyn error::Error>> {
+    let rows = db::sqlite_list_unconfirmed_successes(&infer_db.conn(), max)?;
+    Ok(rows
+        .into_iter()
+        .map(|result| InferResult {
+            txid: result.txid,
+            status: result.status.into(),
+            input: result.input,
+            output: result.output,
+            output_hash: result.output_hash,
+            params: parse_params(&result.params),
+            attestation: parse_attestation(&result.attestation),
+            session_context: None,
+            session_turns: None,
+        })
+        .collect())
+}
+
+/// Remove and return up to `max` of the task status changes recorded since the last call,
+/// oldest first.  Only the most recent changes are kept until they are taken.
+pub fn take_task_events(infer_db: &InferDB, max: u32) -> Result<Vec<InferTaskEvent>, Box<dyn error::Error>> {
+    let events = db::sqlite_take_events(&infer_db.conn(), max)?;
+    Ok(events
+        .into_iter()
+        .map(|event| {
+            let params = parse_params(&event.params).unwrap_or_default();
+            InferTaskEvent {
+                txid: event.txid,
+                status: event.status.into(),
+                fee: event.fee,
+                attempts: event.attempts,
+                model: params.model,
+                model_digest: params.model_digest,
+                output_hash: non_empty(event.output_hash),
+                error: non_empty(event.error),
+                create_time: event.create_time,
+                start_time: event.start_time,
+                end_time: event.end_time,
+            }
+        })
+        .collect())
+}
+
+/// How often a running task's partial output is written to the `InferDB`
+const PARTIAL_OUTPUT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Collects the output of attempt number `attempts` at the task `txid` as it is generated, and
+/// saves it every so often so that clients can follow along.
+struct PartialOutput<'a> {
+    infer_db: &'a InferDB,
+    txid: &'a str,
+    attempts: u32,
+    output: String,
+    last_save: Instant,
+}
+
+impl<'a> PartialOutput<'a> {
+    fn new(infer_db: &'a InferDB, txid: &'a str, attempts: u32) -> PartialOutput<'a> {
+        PartialOutput {
+            infer_db,
+            txid,
+            attempts,
+            output: String::new(),
+            last_save: Instant::now(),
+        }
+    }
+
+    fn push(&mut self, token: &str) {
+        self.output.push_str(token);
+        if self.last_save.elapsed() < PARTIAL_OUTPUT_INTERVAL {
+            return;
+        }
+        self.last_save = Instant::now();
+        // best-effort; the final output is recorded regardless
+        if let Err(e) = db::sqlite_save_partial_output(
+            &self.infer_db.conn(),
+            self.txid,
+            self.attempts,
+            &self.output,
+        ) {
+            debug!("Failed to save partial inference output"; "txid" => self.txid, "err" => %e);
+        }
+    }
+}
+
+/// Run attempt number `attempts` at the inference task `txid`, returning its output and the
+/// parameters it was produced with.  The output is saved to `infer_db` as it is generated.  A
+/// task that continues a session fails until the earlier turns have succeeded.  The backend is
+/// asked to stop once `cancelled` is set.
+fn run_task(backend: &dyn InferenceBackend, infer_db: &InferDB, txid: &str, attempts: u32, user_input: &str, context: &str, cancelled: &AtomicBool) -> Result<(String, InferParams), Box<dyn error::Error>> {
+    let context = session::expand_context(infer_db, &InferContext::parse(context))?;
+    let params = context.params.resolve(backend)?;
+    let request = user_request(user_input, context.context_messages())?.with_params(&params);
+    let mut partial = PartialOutput::new(infer_db, txid, attempts);
+    let output = backend.complete_cancellable(&request, &mut |token| partial.push(token), cancelled)?;
+    Ok((output, params))
+}
+
+/// Run the next queued task to completion on this thread.  Unlike `InferWorkerPool`, this
+/// doesn't enforce a timeout, but a failed task is retried the same way.
+pub fn _internal_do_infer(infer_db: &InferDB, backend: &dyn InferenceBackend) -> Result<(), Box<dyn error::Error>>{
+    let config = InferPoolConfig::default();
     // 1. get to_do infer row
-    let row = db::sqlite_filter_to_infer(&llm_db)?;
-    // 2. do infer
-    db::sqlite_start_llm(&llm_db, row.txid.as_str(), InferStatus::InProgress as u8)?;
-    let result = infer(row.input.as_str(), None).await;
-    if !result.is_ok() {
-        db::sqlite_end_llm(&llm_db, row.txid.as_str(), "", "", InferStatus::Failure as u8)?;
-    } else {
-        let output = result.unwrap();
-        let output_hash = hex::encode(Sha256Sum::from_data(output.as_bytes()));
-        db::sqlite_end_llm(&llm_db, row.txid.as_str(), output.as_str(), output_hash.as_str(), InferStatus::Success as u8)?;
+    let Some((row, attempts)) = pool::claim_next_task(infer_db)? else {
+        return Err(Box::new(Error::new(ErrorKind::NotFound, "NO_INFER_TASK")));
+    };
+    // 2. do infer, without holding the DB lock, since the mempool and RPC share it
+    let result = run_task(backend, infer_db, &row.txid, attempts, row.input.as_str(), row.context.as_str(), &AtomicBool::new(false)).map_err(|e| e.to_string());
+    if let Err(ref e) = result {
+        warn!("Inference task failed"; "txid" => &row.txid, "backend" => backend.name(), "attempt" => attempts, "err" => %e);
     }
+    pool::record_outcome(infer_db, &config, None, &row.txid, attempts, result, false)?;
     Ok(())
 }
 
-pub fn do_infer() -> Result<(), Box<dyn error::Error>>{
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    rt.block_on(_internal_do_infer())
+pub fn do_infer(infer_db: &InferDB, backend: &dyn InferenceBackend) -> Result<(), Box<dyn error::Error>>{
+    _internal_do_infer(infer_db, backend)
 }
 
 
@@ -198,111 +447,313 @@ pub fn do_infer() -> Result<(), Box<dyn error::Error>>{
 mod tests {
     use super::*;
 
-    #[tokio::test]
-    async fn test_infer() {
+    fn test_infer_db() -> InferDB {
+        let infer_db = InferDB::open_memory().unwrap();
+        infer_chain(&infer_db, "0".to_string(), "Is the Earth round?", "", 0).unwrap();
+        infer_db
+    }
+
+    #[test]
+    fn test_infer() {
         let user_input = "Is the Earth round?";
         let context_messages = None;
 
-        let result = infer(user_input, context_messages).await;
+        let server = MockLlmServer::start();
+        let result = infer(&server.backend(), user_input, context_messages);
         assert!(result.is_ok());
 
         let response = result.unwrap();
-        println!("{}", response)
+        assert_eq!(response, infer(&MockBackend::new(), user_input, None).unwrap());
+        assert_eq!(server.requests().len(), 1);
     }
 
-    #[tokio::test]
-    async fn test_infer_with_no_userinput() {
-        let user_input = "";
-        let context_messages = None;
+    #[test]
+    fn test_infer_with_mock_backend() {
+        let backend = MockBackend::new();
+        let first = infer(&backend, "Is the Earth round?", None).unwrap();
+        let second = infer(&backend, "Is the Earth round?", None).unwrap();
+        assert_eq!(first, second);
 
-        let result = infer(user_input, context_messages).await;
-        assert!(result.is_ok());
+        assert!(infer(&backend, "", None).is_err());
     }
 
-    #[tokio::test]
-    async fn test_infer_check() {
-        let user_input = "Is the Earth round?";
-        let context_messages = None;
-
-        let result = infer(user_input, context_messages).await;
-        assert!(result.is_ok());
-
-        let response = result.unwrap();
-        println!("{}", response);
-
+    #[test]
+    fn test_infer_with_no_userinput() {
+        let user_input = "";
         let context_messages = None;
-        // let response = "不相关的回答";
-        let check_result = infer_check(user_input, &response, context_messages).await;
-        assert!(check_result.is_ok());
 
-        let res = check_result.unwrap();
-        println!("res: {}", res);
+        let server = MockLlmServer::start();
+        let result = infer(&server.backend(), user_input, context_messages);
+        assert!(result.is_err());
+        // rejected before reaching the backend
+        assert!(server.requests().is_empty());
     }
 
-    #[tokio::test]
-    async fn test_random_question() {
-
-        let result = random_question().await;
+    #[test]
+    fn test_random_question() {
+        let server = MockLlmServer::with_response("Why is the sky blue?");
+        let result = random_question(&server.backend());
         assert!(result.is_ok());
 
         let response = result.unwrap();
-        println!("Question: {}", response);
+        assert_eq!(response, "Why is the sky blue?");
+        assert_eq!(server.requests()[0].body["temperature"], 0.9);
     }
 
-    #[tokio::test]
-    async fn test_infer_chain() {
+    #[test]
+    fn test_infer_chain() {
         let txid = "0".to_string();
         let user_input = "Is the Earth round?";
-        let context_messages = None;
 
-        let result = infer_chain(txid, &user_input, context_messages);
+        let infer_db = InferDB::open_memory().unwrap();
+        let result = infer_chain(&infer_db, txid, &user_input, "", 0);
         assert!(result.is_ok());
         assert!(result.unwrap() == InferStatus::Created);
     }
 
-    #[tokio::test]
-    async fn test_query() {
+    #[test]
+    fn test_query() {
         let txid = "0".to_string();
 
-        let result = query(txid);
+        let result = query(&test_infer_db(), txid);
         assert!(result.is_ok());
+        assert_eq!(result.unwrap().status, InferStatus::Created)
     }
 
-    #[tokio::test]
-    async fn test_query_hash() {
+    #[test]
+    fn test_query_hash() {
         let txid = "0".to_string();
 
-        let result = query_hash(txid);
+        let result = query_hash(&test_infer_db(), txid);
         assert!(result.is_ok());
     }
 
-    #[tokio::test]
-    async fn test_query_not_found() {
+    #[test]
+    fn test_query_not_found() {
         let txid = "1".to_string();
 
-        let result = query(txid);
+        let result = query(&test_infer_db(), txid);
         assert!(result.is_ok());
         assert_eq!(result.unwrap().status, InferStatus::NotFound)
     }
 
-    #[tokio::test]
-    async fn test_internal_do_infer() {
-        let result = _internal_do_infer().await;
+    #[test]
+    fn test_internal_do_infer() {
+        let server = MockLlmServer::start();
+        let infer_db = test_infer_db();
+        let result = _internal_do_infer(&infer_db, &server.backend());
         assert!(result.is_ok());
+
+        let result = query(&infer_db, "0".to_string()).unwrap();
+        assert_eq!(result.status, InferStatus::Success);
+        assert_eq!(
+            result.output,
+            infer(&MockBackend::new(), "Is the Earth round?", None).unwrap()
+        );
     }
 
     #[test]
     fn test_do_infer() {
-        let result = do_infer();
+        let server = MockLlmServer::start();
+        let infer_db = test_infer_db();
+        let result = do_infer(&infer_db, &server.backend());
         assert!(result.is_ok());
+        assert_eq!(query(&infer_db, "0".to_string()).unwrap().status, InferStatus::Success);
+        // the worker streams its reply
+        assert_eq!(server.requests()[0].body["stream"], true);
+    }
+
+    #[test]
+    fn test_do_infer_backend_failures() {
+        let server = MockLlmServer::start();
+        server.push_reply(MockReply::Error(500, "out of memory".to_string()));
+        let infer_db = test_infer_db();
+        do_infer(&infer_db, &server.backend()).unwrap();
+
+        let result = query(&infer_db, "0".to_string()).unwrap();
+        assert_eq!(result.status, InferStatus::Retrying);
+        assert_eq!(result.output_hash, "");
+    }
+
+    #[test]
+    fn test_do_infer_with_mock_backend() {
+        let infer_db = test_infer_db();
+        do_infer(&infer_db, &MockBackend::with_response("yes")).unwrap();
+
+        let result = query(&infer_db, "0".to_string()).unwrap();
+        assert_eq!(result.status, InferStatus::Success);
+        assert_eq!(result.output, "yes");
+        assert_eq!(
+            query_hash(&infer_db, "0".to_string()).unwrap().output_hash,
+            hex::encode(Sha256Sum::from_data(b"yes"))
+        );
+    }
+
+    #[test]
+    fn test_do_infer_records_params() {
+        let infer_db = InferDB::open_memory().unwrap();
+        let context = r#"{"messages":[{"role":"system","content":"be brief"}],"params":{"seed":42,"max_tokens":64}}"#;
+        infer_chain(&infer_db, "0".to_string(), "Is the Earth round?", context, 0).unwrap();
+
+        let queued = query(&infer_db, "0".to_string()).unwrap();
+        assert_eq!(queued.params.as_ref().unwrap().model, None);
+        assert_eq!(queued.params.as_ref().unwrap().seed, 42);
+
+        let backend = MockBackend::new();
+        do_infer(&infer_db, &backend).unwrap();
+        let result = query_hash(&infer_db, "0".to_string()).unwrap();
+        assert_eq!(result.status, InferStatus::Success);
+
+        let params = result.params.unwrap();
+        assert_eq!(params.model, Some("mock".to_string()));
+        assert_eq!(params.seed, 42);
+        assert_eq!(params.max_tokens, 64);
+        assert!(params.satisfies(&InferContext::parse(context).params));
+
+        // anyone with the same model can recompute the committed hash
+        let recomputed =
+            recompute_output_hash(&backend, "Is the Earth round?", context, &params).unwrap();
+        assert_eq!(recomputed, result.output_hash);
+    }
+
+    #[test]
+    fn test_do_infer_rejects_unavailable_model() {
+        let infer_db = InferDB::open_memory().unwrap();
+        let context = r#"{"params":{"model":"llama3.1","model_digest":"sha256:00"}}"#;
+        infer_chain(&infer_db, "0".to_string(), "Is the Earth round?", context, 0).unwrap();
+
+        do_infer(&infer_db, &MockBackend::new()).unwrap();
+        let result = query(&infer_db, "0".to_string()).unwrap();
+        assert_eq!(result.status, InferStatus::Retrying);
+        assert_eq!(result.output_hash, "");
+    }
+
+    #[test]
+    fn test_infer_chain_keeps_known_tasks() {
+        let infer_db = test_infer_db();
+        do_infer(&infer_db, &MockBackend::with_response("yes")).unwrap();
+
+        // re-submitting a finished task doesn't queue it again
+        let status = infer_chain(&infer_db, "0".to_string(), "Is the Earth round?", "", 0).unwrap();
+        assert_eq!(status, InferStatus::Success);
+        assert_eq!(query(&infer_db, "0".to_string()).unwrap().output, "yes");
+
+        // ...but a cancelled one is
+        infer_chain(&infer_db, "1".to_string(), "Is the Earth flat?", "", 0).unwrap();
+        assert!(cancel(&infer_db, "1".to_string()).unwrap());
+        assert!(!cancel(&infer_db, "1".to_string()).unwrap());
+        assert_eq!(query(&infer_db, "1".to_string()).unwrap().status, InferStatus::Cancelled);
+        let status = infer_chain(&infer_db, "1".to_string(), "Is the Earth flat?", "", 0).unwrap();
+        assert_eq!(status, InferStatus::Created);
+    }
+
+    #[test]
+    fn test_query_progress() {
+        let infer_db = InferDB::open_memory().unwrap();
+        infer_chain(&infer_db, "a".to_string(), "Is the Earth round?", "", 10).unwrap();
+        infer_chain(&infer_db, "b".to_string(), "Is the Earth flat?", "", 1).unwrap();
+
+        let progress = query_progress(&infer_db, "b".to_string()).unwrap();
+        assert_eq!(progress.status, InferStatus::Created);
+        assert_eq!(progress.queue_position, Some(1));
+        assert!(progress.create_time.is_some());
+        assert_eq!(progress.start_time, None);
+        assert_eq!(progress.end_time, None);
+
+        do_infer(&infer_db, &MockBackend::with_response("Yes, it is.")).unwrap();
+        let progress = query_progress(&infer_db, "a".to_string()).unwrap();
+        assert_eq!(progress.status, InferStatus::Success);
+        assert_eq!(progress.output, "Yes, it is.");
+        assert_eq!(progress.queue_position, None);
+        assert!(progress.start_time.is_some());
+        assert!(progress.end_time.is_some());
+        assert_eq!(query_progress(&infer_db, "b".to_string()).unwrap().queue_position, Some(0));
+
+        let progress = query_progress(&infer_db, "c".to_string()).unwrap();
+        assert_eq!(progress.status, InferStatus::NotFound);
+        assert_eq!(progress.create_time, None);
+    }
+
+    #[test]
+    fn test_take_task_events() {
+        let infer_db = InferDB::open_memory().unwrap();
+        infer_chain(&infer_db, "a".to_string(), "Is the Earth round?", "", 10).unwrap();
+        // already queued
+        infer_chain(&infer_db, "a".to_string(), "Is the Earth round?", "", 10).unwrap();
+        do_infer(&infer_db, &MockBackend::with_response("Yes, it is.")).unwrap();
+
+        let events = take_task_events(&infer_db, 10).unwrap();
+        assert_eq!(
+            events.iter().map(|e| e.status.clone()).collect::<Vec<_>>(),
+            vec![InferStatus::Created, InferStatus::InProgress, InferStatus::Success]
+        );
+        assert!(events.iter().all(|e| e.txid == "a" && e.fee == 10));
+        assert_eq!(events[0].attempts, 0);
+        assert_eq!(events[0].output_hash, None);
+        assert!(events[0].create_time.is_some());
+        assert_eq!(events[0].start_time, None);
+
+        let done = &events[2];
+        assert_eq!(done.attempts, 1);
+        assert_eq!(done.model, Some("mock".to_string()));
+        assert_eq!(done.output_hash, Some(output_hash("Yes, it is.")));
+        assert_eq!(done.error, None);
+        assert!(done.start_time.is_some());
+        assert!(done.end_time >= done.start_time);
+
+        assert!(take_task_events(&infer_db, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_run_task_saves_partial_output() {
+        /// Emits two words, and reports what had been saved before emitting the second
+        struct PausingBackend {
+            infer_db: InferDB,
+            seen: std::sync::Mutex<String>,
+        }
+
+        impl InferenceBackend for PausingBackend {
+            fn name(&self) -> &str {
+                "pausing"
+            }
+
+            fn model(&self) -> &str {
+                "mock"
+            }
+
+            fn complete(&self, _request: &CompletionRequest) -> Result<String, Box<dyn error::Error>> {
+                Ok("Yes, it is.".to_string())
+            }
+
+            fn complete_streaming(&self, _request: &CompletionRequest, on_token: &mut dyn FnMut(&str)) -> Result<String, Box<dyn error::Error>> {
+                on_token("Yes, ");
+                thread::sleep(PARTIAL_OUTPUT_INTERVAL);
+                on_token("it ");
+                *self.seen.lock().unwrap() = query(&self.infer_db, "0".to_string()).unwrap().output;
+                on_token("is.");
+                Ok("Yes, it is.".to_string())
+            }
+        }
+
+        let infer_db = test_infer_db();
+        let backend = PausingBackend {
+            infer_db: infer_db.clone(),
+            seen: std::sync::Mutex::new(String::new()),
+        };
+        do_infer(&infer_db, &backend).unwrap();
+        assert_eq!(*backend.seen.lock().unwrap(), "Yes, it ");
+        assert_eq!(query(&infer_db, "0".to_string()).unwrap().output, "Yes, it is.");
     }
 
     #[test]
     fn test_do_infer_thread() {
+        let server = MockLlmServer::start();
+        let backend = server.backend();
+        let infer_db = test_infer_db();
         let llm_thread_handle = thread::Builder::new()
             .name("test_thread".to_string())
             .spawn(move || {
-                let _ = do_infer();
+                let _ = do_infer(&infer_db, &backend);
             })
             .expect("FATAL: failed to spawn chain llm thread");
 
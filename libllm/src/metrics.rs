@@ -0,0 +1,24 @@
+//! Hooks for watching the inference pipeline.
+//!
+//! libllm doesn't export metrics itself.  Instead, the node gives its `InferWorkerPool` an
+//! `InferMetrics`, which the pool tells how long tasks wait and run, how attempts turn out, and
+//! how many jobs are running and queued.  The node's implementation feeds these to its
+//! Prometheus metrics (see `stacks::monitoring`).
+
+use std::time::Duration;
+
+use crate::InferStatus;
+
+/// Receives measurements of the worker pool as it runs
+pub trait InferMetrics: Send + Sync {
+    /// A task waited `wait` in the queue, since it was submitted or its retry became due, before
+    /// an attempt at it was started
+    fn observe_queue_wait(&self, wait: Duration);
+    /// An attempt at a task returned from the backend after `latency`, successfully or not
+    fn observe_model_latency(&self, latency: Duration);
+    /// An attempt at a task was recorded, leaving the task with `status`: `Success`, `Retrying`,
+    /// `Failure` or `TimedOut`
+    fn record_outcome(&self, status: &InferStatus);
+    /// After a round of scheduling, `in_progress` jobs are running and `pending` tasks are queued
+    fn set_jobs(&self, in_progress: u64, pending: u64);
+}
@@ -0,0 +1,443 @@
+//! An in-process, OpenAI-compatible HTTP server, so that tests can exercise `OpenAiBackend` (and
+//! the nodes and signers built on it) without a live model server.
+//!
+//! Replies can be scripted one request at a time; once the script runs out, the server answers
+//! the way `MockBackend` does, so results computed through the server and through `MockBackend`
+//! agree.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use openai::chat::{ChatCompletionMessage, ChatCompletionMessageRole};
+use serde_json::{json, Value};
+
+use crate::backend::{
+    CompletionRequest, InferenceBackend, MockBackend, OpenAiBackend, DEFAULT_TIMEOUT_MS,
+};
+
+/// How the server answers one chat-completion request
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockReply {
+    /// A completion with this content; streamed a word at a time if the client asked for a stream
+    Text(String),
+    /// This HTTP status and body
+    Error(u16, String),
+    /// A successful response whose body isn't a chat completion
+    Malformed,
+    /// A successful completion with no content
+    Empty,
+}
+
+/// A request the server received
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockRequest {
+    pub path: String,
+    pub body: Value,
+}
+
+struct MockState {
+    script: VecDeque<(MockReply, Duration)>,
+    latency: Duration,
+    fallback: MockBackend,
+    requests: Vec<MockRequest>,
+}
+
+/// An OpenAI-compatible server on a local port, serving `/chat/completions` and `/embeddings`.
+/// The server stops when this is dropped.
+pub struct MockLlmServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockLlmServer {
+    /// Start a server that answers like `MockBackend::new()`
+    pub fn start() -> MockLlmServer {
+        MockLlmServer::start_with(MockBackend::new())
+    }
+
+    /// Start a server that always answers `response`, unless scripted otherwise
+    pub fn with_response(response: &str) -> MockLlmServer {
+        MockLlmServer::start_with(MockBackend::with_response(response))
+    }
+
+    fn start_with(fallback: MockBackend) -> MockLlmServer {
+        let listener =
+            TcpListener::bind("127.0.0.1:0").expect("FATAL: failed to bind mock LLM server");
+        let addr = listener
+            .local_addr()
+            .expect("FATAL: mock LLM server has no local address");
+        let state = Arc::new(Mutex::new(MockState {
+            script: VecDeque::new(),
+            latency: Duration::ZERO,
+            fallback,
+            requests: vec![],
+        }));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let state = state.clone();
+            let shutdown = shutdown.clone();
+            thread::Builder::new()
+                .name("mock-llm-server".to_string())
+                .spawn(move || {
+                    for stream in listener.incoming() {
+                        if shutdown.load(Ordering::SeqCst) {
+                            break;
+                        }
+                        let Ok(stream) = stream else {
+                            continue;
+                        };
+                        let state = state.clone();
+                        thread::spawn(move || {
+                            if let Err(e) = handle_connection(stream, &state) {
+                                debug!("Mock LLM server: connection failed: {:?}", &e);
+                            }
+                        });
+                    }
+                })
+                .expect("FATAL: failed to spawn mock LLM server thread")
+        };
+
+        MockLlmServer {
+            addr,
+            state,
+            shutdown,
+            handle: Some(handle),
+        }
+    }
+
+    /// The base URL to point an OpenAI-compatible client at
+    pub fn base_url(&self) -> String {
+        format!("http://{}/v1/", self.addr)
+    }
+
+    /// A backend that talks to this server
+    pub fn backend(&self) -> OpenAiBackend {
+        OpenAiBackend::new(
+            &self.base_url(),
+            "mock",
+            "mock",
+            Duration::from_millis(DEFAULT_TIMEOUT_MS),
+        )
+    }
+
+    /// Answer the next unscripted chat-completion request with `reply`
+    pub fn push_reply(&self, reply: MockReply) {
+        let latency = self.lock().latency;
+        self.push_reply_after(reply, latency);
+    }
+
+    /// Answer the next unscripted chat-completion request with `reply`, after waiting `latency`
+    pub fn push_reply_after(&self, reply: MockReply, latency: Duration) {
+        self.lock().script.push_back((reply, latency));
+    }
+
+    /// Wait `latency` before answering each request that isn't scripted
+    pub fn set_latency(&self, latency: Duration) {
+        self.lock().latency = latency;
+    }
+
+    /// The requests received so far, in order
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.lock().requests.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state
+            .lock()
+            .expect("FATAL: mock LLM server state poisoned")
+    }
+}
+
+impl Drop for MockLlmServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // wake up the accept loop so it sees the flag
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Read one request from `stream`, answer it, and close the connection
+fn handle_connection(stream: TcpStream, state: &Mutex<MockState>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("/")
+        .to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line == "\r\n" || line == "\n" {
+            break;
+        }
+        if let Some(len) = line.to_lowercase().strip_prefix("content-length:") {
+            content_length = len.trim().parse().unwrap_or(0);
+        }
+    }
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body)?;
+    let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    let (reply, latency, fallback) = {
+        let mut state = state.lock().expect("FATAL: mock LLM server state poisoned");
+        state.requests.push(MockRequest {
+            path: path.clone(),
+            body: body.clone(),
+        });
+        let fallback = state.fallback.clone();
+        if path.ends_with("/chat/completions") {
+            match state.script.pop_front() {
+                Some((reply, latency)) => (Some(reply), latency, fallback),
+                None => (None, state.latency, fallback),
+            }
+        } else {
+            (None, state.latency, fallback)
+        }
+    };
+    if !latency.is_zero() {
+        thread::sleep(latency);
+    }
+
+    let mut stream = stream;
+    if path.ends_with("/chat/completions") {
+        let reply = match reply {
+            Some(reply) => reply,
+            None => match fallback.complete(&completion_request(&body)) {
+                Ok(text) => MockReply::Text(text),
+                Err(e) => MockReply::Error(500, e.to_string()),
+            },
+        };
+        let streaming = body.get("stream").and_then(Value::as_bool).unwrap_or(false);
+        write_completion(&mut stream, &body, reply, streaming)
+    } else if path.ends_with("/embeddings") {
+        let input = body.get("input").and_then(Value::as_str).unwrap_or("");
+        match fallback.embed(input) {
+            Ok(embedding) => {
+                let response = json!({
+                    "object": "list",
+                    "data": [{"object": "embedding", "index": 0, "embedding": embedding}],
+                    "model": body.get("model").cloned().unwrap_or(Value::Null),
+                });
+                write_response(&mut stream, 200, "application/json", &response.to_string())
+            }
+            Err(e) => write_response(&mut stream, 500, "text/plain", &e.to_string()),
+        }
+    } else {
+        write_response(&mut stream, 404, "text/plain", "not found")
+    }
+}
+
+/// Rebuild the `CompletionRequest` a client sent, so the fallback can answer it
+fn completion_request(body: &Value) -> CompletionRequest {
+    let messages = body
+        .get("messages")
+        .and_then(Value::as_array)
+        .map(|messages| {
+            messages
+                .iter()
+                .map(|message| ChatCompletionMessage {
+                    role: match message.get("role").and_then(Value::as_str) {
+                        Some("system") => ChatCompletionMessageRole::System,
+                        Some("assistant") => ChatCompletionMessageRole::Assistant,
+                        _ => ChatCompletionMessageRole::User,
+                    },
+                    content: message
+                        .get("content")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                    name: None,
+                    function_call: None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    CompletionRequest::new(messages)
+}
+
+fn write_completion(
+    stream: &mut TcpStream,
+    request: &Value,
+    reply: MockReply,
+    streaming: bool,
+) -> std::io::Result<()> {
+    let model = request.get("model").cloned().unwrap_or(Value::Null);
+    match reply {
+        MockReply::Error(status, body) => write_response(stream, status, "text/plain", &body),
+        MockReply::Malformed if streaming => {
+            write_response(stream, 200, "text/event-stream", "data: nope\n\n")
+        }
+        MockReply::Malformed => write_response(stream, 200, "application/json", "nope"),
+        MockReply::Empty if streaming => {
+            write_response(stream, 200, "text/event-stream", "data: [DONE]\n\n")
+        }
+        MockReply::Empty => {
+            let completion = json!({
+                "id": "mock",
+                "object": "chat.completion",
+                "created": 0,
+                "model": model,
+                "choices": [],
+            });
+            write_response(stream, 200, "application/json", &completion.to_string())
+        }
+        MockReply::Text(text) if streaming => {
+            let mut events = String::new();
+            for word in text.split_inclusive(' ') {
+                let chunk = json!({
+                    "id": "mock",
+                    "object": "chat.completion.chunk",
+                    "created": 0,
+                    "model": model,
+                    "choices": [{"index": 0, "delta": {"content": word}, "finish_reason": null}],
+                });
+                events.push_str(&format!("data: {}\n\n", chunk));
+            }
+            events.push_str("data: [DONE]\n\n");
+            write_response(stream, 200, "text/event-stream", &events)
+        }
+        MockReply::Text(text) => {
+            let completion = json!({
+                "id": "mock",
+                "object": "chat.completion",
+                "created": 0,
+                "model": model,
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": text},
+                    "finish_reason": "stop",
+                }],
+                "usage": {"prompt_tokens": 0, "completion_tokens": 0, "total_tokens": 0},
+            });
+            write_response(stream, 200, "application/json", &completion.to_string())
+        }
+    }
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    status: u16,
+    content_type: &str,
+    body: &str,
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(content: &str) -> CompletionRequest {
+        CompletionRequest::new(vec![ChatCompletionMessage {
+            role: ChatCompletionMessageRole::User,
+            content: Some(content.to_string()),
+            name: None,
+            function_call: None,
+        }])
+    }
+
+    #[test]
+    fn test_mock_server_matches_mock_backend() {
+        let server = MockLlmServer::start();
+        let backend = server.backend();
+        let request = request("Is the Earth round?");
+
+        let expected = MockBackend::new().complete(&request).unwrap();
+        assert_eq!(backend.complete(&request).unwrap(), expected);
+
+        let mut tokens = vec![];
+        let reply = backend
+            .complete_streaming(&request, &mut |token| tokens.push(token.to_string()))
+            .unwrap();
+        assert_eq!(reply, expected);
+        assert_eq!(tokens.concat(), expected);
+
+        assert_eq!(
+            backend.embed("Is the Earth round?").unwrap(),
+            MockBackend::new().embed("Is the Earth round?").unwrap()
+        );
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].path, "/v1/chat/completions");
+        assert_eq!(requests[0].body["model"], "mock");
+        assert_eq!(requests[1].body["stream"], true);
+        assert_eq!(requests[2].path, "/v1/embeddings");
+    }
+
+    #[test]
+    fn test_mock_server_scripted_replies() {
+        let server = MockLlmServer::with_response("yes");
+        let backend = server.backend();
+        let request = request("Is the Earth round?");
+
+        server.push_reply(MockReply::Text("Yes, it is.".to_string()));
+        server.push_reply(MockReply::Error(503, "overloaded".to_string()));
+        server.push_reply(MockReply::Malformed);
+        server.push_reply(MockReply::Empty);
+
+        let mut tokens = vec![];
+        let reply = backend
+            .complete_streaming(&request, &mut |token| tokens.push(token.to_string()))
+            .unwrap();
+        assert_eq!(reply, "Yes, it is.");
+        assert_eq!(tokens, vec!["Yes, ", "it ", "is."]);
+
+        let err = backend.complete(&request).unwrap_err();
+        assert!(err.to_string().contains("BACKEND_HTTP_ERROR 503"));
+        assert!(err.to_string().contains("overloaded"));
+        assert!(backend.complete(&request).is_err());
+        assert_eq!(
+            backend.complete(&request).unwrap_err().to_string(),
+            "EMPTY_RESPONSE"
+        );
+
+        // the script has run out
+        assert_eq!(backend.complete(&request).unwrap(), "yes");
+    }
+
+    #[test]
+    fn test_mock_server_latency() {
+        let server = MockLlmServer::start();
+        let request = request("Is the Earth round?");
+        let impatient = OpenAiBackend::new(
+            &server.base_url(),
+            "mock",
+            "mock",
+            Duration::from_millis(100),
+        );
+
+        server.push_reply_after(
+            MockReply::Text("too late".to_string()),
+            Duration::from_millis(1_000),
+        );
+        assert!(impatient.complete(&request).is_err());
+
+        server.set_latency(Duration::from_millis(1_000));
+        assert!(impatient.complete(&request).is_err());
+
+        server.set_latency(Duration::ZERO);
+        assert!(impatient.complete(&request).is_ok());
+    }
+}
@@ -0,0 +1,334 @@
+use std::error;
+use std::io::{Error, ErrorKind};
+
+use openai::chat::{ChatCompletionMessage, ChatCompletionMessageRole};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::InferenceBackend;
+
+pub const DEFAULT_MAX_TOKENS: u32 = 512;
+
+fn default_max_tokens() -> u32 {
+    DEFAULT_MAX_TOKENS
+}
+
+/// Sampling parameters for a consensus-critical inference.
+///
+/// Every node that runs (or re-runs) an inference task with the same parameters, against the same
+/// model weights, must get the same output.  So sampling is greedy (temperature 0), seeded, and
+/// bounded in length, and the task may pin the exact model it expects via its digest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferParams {
+    /// Model to run.  If unset, the node runs whichever model its backend is configured with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Digest of the model weights (e.g. `sha256:...` as reported by Ollama).  If set, only a
+    /// backend serving exactly these weights may run the task.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_digest: Option<String>,
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub temperature: f32,
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: u32,
+}
+
+impl Default for InferParams {
+    fn default() -> InferParams {
+        InferParams {
+            model: None,
+            model_digest: None,
+            seed: 0,
+            temperature: 0.0,
+            max_tokens: DEFAULT_MAX_TOKENS,
+        }
+    }
+}
+
+impl InferParams {
+    /// Can these parameters produce a reproducible output?
+    pub fn check_deterministic(&self) -> Result<(), String> {
+        if self.temperature != 0.0 {
+            return Err(format!(
+                "temperature must be 0 for deterministic inference, got {}",
+                self.temperature
+            ));
+        }
+        if self.max_tokens == 0 {
+            return Err("max_tokens must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    /// Can every node reproduce the output of a task with these parameters?  From epoch 3.0 the
+    /// output of an Infer transaction is carried by consensus, so on top of being deterministic,
+    /// the task must pin the model and the weights that produce it.
+    pub fn check_reproducible(&self) -> Result<(), String> {
+        self.check_deterministic()?;
+        if self.model.is_none() {
+            return Err("model must be set for reproducible inference".to_string());
+        }
+        if self.model_digest.is_none() {
+            return Err("model_digest must be set for reproducible inference".to_string());
+        }
+        Ok(())
+    }
+
+    /// Fill in the model this task will actually run on `backend`, checking that the backend
+    /// can honor everything the task pinned.  The result is what gets recorded alongside the
+    /// output, so a validator can re-run it.
+    pub fn resolve(&self, backend: &dyn InferenceBackend) -> Result<InferParams, Box<dyn error::Error>> {
+        self.check_deterministic()
+            .map_err(|msg| Error::new(ErrorKind::InvalidInput, msg))?;
+
+        if let Some(ref model) = self.model {
+            if model != backend.model() {
+                return Err(Box::new(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("MODEL_MISMATCH: task wants {}, backend runs {}", model, backend.model()),
+                )));
+            }
+        }
+        if let Some(ref digest) = self.model_digest {
+            if Some(digest.as_str()) != backend.model_digest() {
+                return Err(Box::new(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "MODEL_DIGEST_MISMATCH: task wants {}, backend has {:?}",
+                        digest,
+                        backend.model_digest()
+                    ),
+                )));
+            }
+        }
+
+        Ok(InferParams {
+            model: Some(backend.model().to_string()),
+            model_digest: backend.model_digest().map(|d| d.to_string()),
+            ..self.clone()
+        })
+    }
+
+    /// Were these (resolved) parameters a valid way to run a task that asked for `requested`?
+    pub fn satisfies(&self, requested: &InferParams) -> bool {
+        let model_ok = requested.model.is_none() || requested.model == self.model;
+        let digest_ok = requested.model_digest.is_none() || requested.model_digest == self.model_digest;
+        model_ok
+            && digest_ok
+            && self.seed == requested.seed
+            && self.temperature == requested.temperature
+            && self.max_tokens == requested.max_tokens
+    }
+}
+
+/// An STX payment from the sender of an Infer transaction to whoever provides the inference.
+/// It is made when the output is committed on-chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferPayment {
+    /// The provider's principal, as a string (e.g. `SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7`)
+    pub recipient: String,
+    /// Amount in microSTX
+    pub amount: u64,
+}
+
+/// The `context` of an Infer transaction.
+///
+/// This is either a JSON array of chat messages that precede the user input (the original
+/// format), or a JSON object `{"messages": [...], "params": {...}, "payment": {...}, "parent":
+/// "..."}` that additionally carries the task's `InferParams`, an optional `InferPayment`, and
+/// the txid of the task whose conversation it continues (see `session`).  Anything else is
+/// treated as an empty context with default parameters and no payment.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct InferContext {
+    #[serde(default)]
+    pub messages: Vec<ChatCompletionMessage>,
+    #[serde(default)]
+    pub params: InferParams,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment: Option<InferPayment>,
+    /// The hex txid of the previous turn of the session, if this task continues one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+}
+
+impl InferContext {
+    /// Parse a context, treating anything malformed as empty.  Tasks that are already queued
+    /// (or mined) were accepted this way, so they must keep being read this way.
+    pub fn parse(context: &str) -> InferContext {
+        InferContext::try_parse(context).unwrap_or_default()
+    }
+
+    /// Parse a context, explaining what is wrong with it if it is malformed.  An empty context
+    /// is valid.
+    pub fn try_parse(context: &str) -> Result<InferContext, String> {
+        if context.trim().is_empty() {
+            return Ok(InferContext::default());
+        }
+        if let Ok(messages) = serde_json::from_str::<Vec<ChatCompletionMessage>>(context) {
+            return Ok(InferContext {
+                messages,
+                ..InferContext::default()
+            });
+        }
+        serde_json::from_str(context).map_err(|e| format!("malformed context: {}", e))
+    }
+
+    /// The txid of the previous turn of the session, if any.  Fails if it isn't a txid.
+    pub fn parent_txid(&self) -> Result<Option<[u8; 32]>, String> {
+        let Some(ref parent) = self.parent else {
+            return Ok(None);
+        };
+        let bytes =
+            hex::decode(parent).map_err(|e| format!("bad session parent {}: {}", parent, e))?;
+        let txid = <[u8; 32]>::try_from(bytes)
+            .map_err(|_| format!("bad session parent {}: not a txid", parent))?;
+        Ok(Some(txid))
+    }
+
+    /// Add a message to the end of the context messages
+    pub fn push_message(&mut self, role: ChatCompletionMessageRole, content: &str) {
+        self.messages.push(ChatCompletionMessage {
+            role,
+            content: Some(content.to_string()),
+            name: None,
+            function_call: None,
+        });
+    }
+
+    /// The context messages, in the form `infer()` takes them
+    pub fn context_messages(&self) -> Option<Vec<ChatCompletionMessage>> {
+        if self.messages.is_empty() {
+            None
+        } else {
+            Some(self.messages.clone())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MockBackend;
+
+    #[test]
+    fn test_parse_context() {
+        let legacy = InferContext::parse(r#"[{"role":"system","content":"be brief"}]"#);
+        assert_eq!(legacy.messages.len(), 1);
+        assert_eq!(legacy.params, InferParams::default());
+
+        let with_params =
+            InferContext::parse(r#"{"params":{"model":"mock","seed":42,"max_tokens":64}}"#);
+        assert!(with_params.messages.is_empty());
+        assert_eq!(with_params.params.model, Some("mock".to_string()));
+        assert_eq!(with_params.params.seed, 42);
+        assert_eq!(with_params.params.max_tokens, 64);
+        assert_eq!(with_params.params.temperature, 0.0);
+
+        assert!(with_params.payment.is_none());
+
+        let with_payment = InferContext::parse(
+            r#"{"payment":{"recipient":"ST000000000000000000002AMW42H","amount":1000}}"#,
+        );
+        assert_eq!(
+            with_payment.payment,
+            Some(InferPayment {
+                recipient: "ST000000000000000000002AMW42H".to_string(),
+                amount: 1000,
+            })
+        );
+        assert_eq!(with_payment.params, InferParams::default());
+
+        assert_eq!(InferContext::parse(""), InferContext::default());
+        assert_eq!(InferContext::parse("not json"), InferContext::default());
+
+        let parent = "ab".repeat(32);
+        let with_parent = InferContext::parse(&format!(r#"{{"parent":"{}"}}"#, parent));
+        assert_eq!(with_parent.parent, Some(parent));
+        assert_eq!(with_parent.parent_txid(), Ok(Some([0xab; 32])));
+        assert_eq!(InferContext::default().parent_txid(), Ok(None));
+        assert!(InferContext::parse(r#"{"parent":"abcd"}"#).parent_txid().is_err());
+        assert!(InferContext::parse(r#"{"parent":"not hex"}"#).parent_txid().is_err());
+    }
+
+    #[test]
+    fn test_try_parse_context() {
+        assert_eq!(InferContext::try_parse(""), Ok(InferContext::default()));
+        assert!(InferContext::try_parse("not json").is_err());
+        assert!(InferContext::try_parse(r#"{"params":{"seed":"one"}}"#).is_err());
+        assert!(InferContext::try_parse(r#"[{"role":"wizard","content":"hi"}]"#).is_err());
+
+        let mut context = InferContext::try_parse(r#"{"params":{"seed":7}}"#).unwrap();
+        assert_eq!(context.params.seed, 7);
+
+        // what a typed context turns into survives a round trip
+        context.push_message(ChatCompletionMessageRole::System, "be brief");
+        context.push_message(ChatCompletionMessageRole::Assistant, "ok");
+        let json = serde_json::to_string(&context).unwrap();
+        assert_eq!(InferContext::try_parse(&json), Ok(context));
+    }
+
+    #[test]
+    fn test_resolve_params() {
+        let backend = MockBackend::new();
+
+        let resolved = InferParams::default().resolve(&backend).unwrap();
+        assert_eq!(resolved.model, Some("mock".to_string()));
+        assert!(resolved.satisfies(&InferParams::default()));
+
+        let other_model = InferParams {
+            model: Some("llama3.1".to_string()),
+            ..InferParams::default()
+        };
+        assert!(other_model.resolve(&backend).is_err());
+        assert!(!resolved.satisfies(&other_model));
+
+        let pinned = InferParams {
+            model_digest: Some("sha256:0000".to_string()),
+            ..InferParams::default()
+        };
+        assert!(pinned.resolve(&backend).is_err());
+
+        let sampled = InferParams {
+            temperature: 0.7,
+            ..InferParams::default()
+        };
+        assert!(sampled.resolve(&backend).is_err());
+
+        let reseeded = InferParams {
+            seed: 1,
+            ..InferParams::default()
+        };
+        assert!(!resolved.satisfies(&reseeded));
+    }
+
+    #[test]
+    fn test_check_reproducible() {
+        let pinned = InferParams {
+            model: Some("mock".to_string()),
+            model_digest: Some("sha256:0000".to_string()),
+            ..InferParams::default()
+        };
+        pinned.check_reproducible().unwrap();
+
+        // deterministic, but any model could run it
+        InferParams::default().check_deterministic().unwrap();
+        assert!(InferParams::default().check_reproducible().is_err());
+        let no_digest = InferParams {
+            model_digest: None,
+            ..pinned.clone()
+        };
+        assert!(no_digest.check_reproducible().is_err());
+        let no_model = InferParams {
+            model: None,
+            ..pinned.clone()
+        };
+        assert!(no_model.check_reproducible().is_err());
+
+        let sampled = InferParams {
+            temperature: 0.7,
+            ..pinned
+        };
+        assert!(sampled.check_reproducible().is_err());
+    }
+}
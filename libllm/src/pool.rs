@@ -0,0 +1,792 @@
+//! A pool of workers that run the inference tasks queued in an `InferDB`.
+//!
+//! The pool has no thread of its own: the node's inference loop calls `InferWorkerPool::tick`,
+//! which collects finished jobs, abandons jobs that overran their timeout or were cancelled, and
+//! starts queued tasks -- highest fee first -- on any idle workers.  Each job runs on its own
+//! thread, since backends block.
+//!
+//! A failed or timed-out attempt is retried with exponential backoff until the task has been
+//! attempted `max_attempts` times.  When a job is abandoned, its backend call is cancelled, but
+//! not every backend can stop one (see `InferenceBackend::complete_cancellable`), so its thread is
+//! left to finish on its own, and whatever it produces is ignored.  A job that timed out or was
+//! cancelled keeps its worker until its thread returns, so that a slow backend isn't given more
+//! than `workers` calls at once.
+//!
+//! If the pool has an attestation key, every output it records is signed with it (see
+//! `InferAttestation`).  If it has an `InferMetrics`, it reports to it as it goes.
+
+use std::collections::HashMap;
+use std::error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use stacks_common::util::get_epoch_time_secs;
+use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+
+use crate::attestation::InferAttestation;
+use crate::backend::{InferenceBackend, DEFAULT_TIMEOUT_MS};
+use crate::db::{self, InferDB, ResultRow};
+use crate::metrics::InferMetrics;
+use crate::params::InferParams;
+use crate::{output_hash, run_task, InferStatus};
+
+pub const DEFAULT_WORKERS: usize = 1;
+pub const DEFAULT_JOB_TIMEOUT_MS: u64 = 2 * DEFAULT_TIMEOUT_MS;
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+pub const DEFAULT_RETRY_BACKOFF_MS: u64 = 5_000;
+pub const DEFAULT_MAX_RETRY_BACKOFF_MS: u64 = 300_000;
+
+/// How the worker pool schedules inference tasks
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferPoolConfig {
+    /// How many tasks may run at once
+    pub workers: usize,
+    /// How long a single attempt may run before it is abandoned
+    pub job_timeout: Duration,
+    /// How many times a task is attempted before giving up on it
+    pub max_attempts: u32,
+    /// Wait after the first failed attempt; it doubles with each further failure
+    pub retry_backoff: Duration,
+    /// Upper bound on the wait between attempts
+    pub max_retry_backoff: Duration,
+}
+
+impl Default for InferPoolConfig {
+    fn default() -> InferPoolConfig {
+        InferPoolConfig {
+            workers: DEFAULT_WORKERS,
+            job_timeout: Duration::from_millis(DEFAULT_JOB_TIMEOUT_MS),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            retry_backoff: Duration::from_millis(DEFAULT_RETRY_BACKOFF_MS),
+            max_retry_backoff: Duration::from_millis(DEFAULT_MAX_RETRY_BACKOFF_MS),
+        }
+    }
+}
+
+impl InferPoolConfig {
+    /// How long to wait before the next attempt, once `attempts` attempts have failed
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let doublings = attempts.saturating_sub(1).min(31);
+        self.retry_backoff
+            .saturating_mul(1u32 << doublings)
+            .min(self.max_retry_backoff)
+    }
+}
+
+/// Take the next task off the queue and mark it `InProgress`.  Returns the task and the number of
+/// this attempt.
+pub(crate) fn claim_next_task(
+    infer_db: &InferDB,
+) -> Result<Option<(ResultRow, u32)>, Box<dyn error::Error>> {
+    let conn = infer_db.conn();
+    let Some(row) = db::sqlite_filter_to_infer(&conn, get_epoch_time_secs())? else {
+        return Ok(None);
+    };
+    if !db::sqlite_start_llm(&conn, &row.txid, InferStatus::InProgress as u8)? {
+        return Ok(None);
+    }
+    let attempts = row.attempts + 1;
+    Ok(Some((row, attempts)))
+}
+
+/// Record the outcome of attempt number `attempts` at the task `txid`: its result on success,
+/// attested with `attestation_key` if given, otherwise either a retry or, if it was the last
+/// attempt, the reason it was given up on.  Returns the task's new status, which is unchanged if
+/// the task was cancelled in the meantime.
+pub(crate) fn record_outcome(
+    infer_db: &InferDB,
+    config: &InferPoolConfig,
+    attestation_key: Option<&Secp256k1PrivateKey>,
+    txid: &str,
+    attempts: u32,
+    result: Result<(String, InferParams), String>,
+    timed_out: bool,
+) -> Result<InferStatus, Box<dyn error::Error>> {
+    let conn = infer_db.conn();
+    let (recorded, status) = match result {
+        Ok((output, params)) => {
+            let output_hash = output_hash(&output);
+            let attestation = match attestation_key {
+                Some(privk) => serde_json::to_string(&InferAttestation::sign(
+                    privk,
+                    txid,
+                    &params,
+                    &output_hash,
+                    get_epoch_time_secs(),
+                )?)?,
+                None => String::new(),
+            };
+            let params = serde_json::to_string(&params)?;
+            let recorded = db::sqlite_finish_llm(
+                &conn,
+                txid,
+                &output,
+                &output_hash,
+                InferStatus::Success as u8,
+                &params,
+                "",
+                &attestation,
+            )?;
+            (recorded, InferStatus::Success)
+        }
+        Err(e) if attempts < config.max_attempts => {
+            let next_attempt_time =
+                get_epoch_time_secs().saturating_add(config.backoff(attempts).as_secs());
+            let recorded = db::sqlite_retry_llm(&conn, txid, next_attempt_time, &e)?;
+            (recorded, InferStatus::Retrying)
+        }
+        Err(e) => {
+            let status = if timed_out {
+                InferStatus::TimedOut
+            } else {
+                InferStatus::Failure
+            };
+            // keep the parameters the task asked for
+            let params = db::sqlite_get(&conn, txid)?.params;
+            let recorded = db::sqlite_finish_llm(
+                &conn,
+                txid,
+                "",
+                "",
+                status.clone() as u8,
+                &params,
+                &e,
+                "",
+            )?;
+            (recorded, status)
+        }
+    };
+    if recorded {
+        Ok(status)
+    } else {
+        Ok(db::sqlite_get(&conn, txid)?.status.into())
+    }
+}
+
+struct RunningJob {
+    attempts: u32,
+    started: Instant,
+    /// Shared with the job's thread, which drops its clone when it returns
+    thread_alive: Arc<()>,
+    /// Set to have the backend stop the job's call
+    cancelled: Arc<AtomicBool>,
+}
+
+impl RunningJob {
+    /// Stop the job's backend call, if the backend can, and hand back the job's `thread_alive`
+    fn abandon(self) -> Arc<()> {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.thread_alive
+    }
+}
+
+struct JobOutcome {
+    txid: String,
+    attempts: u32,
+    result: Result<(String, InferParams), String>,
+    /// How long the backend took
+    elapsed: Duration,
+}
+
+/// Runs queued inference tasks on up to `InferPoolConfig::workers` threads at a time.
+pub struct InferWorkerPool {
+    infer_db: InferDB,
+    backend: Arc<dyn InferenceBackend>,
+    config: InferPoolConfig,
+    attestation_key: Option<Secp256k1PrivateKey>,
+    metrics: Option<Arc<dyn InferMetrics>>,
+    running: HashMap<String, RunningJob>,
+    /// The `thread_alive` tokens of abandoned jobs whose threads may still be running
+    abandoned: Vec<Arc<()>>,
+    outcome_sender: Sender<JobOutcome>,
+    outcome_receiver: Receiver<JobOutcome>,
+}
+
+impl InferWorkerPool {
+    /// Create a pool that runs the tasks in `infer_db` with `backend`.  Tasks left `InProgress` by
+    /// a node that stopped while running them are queued again.
+    pub fn new(
+        infer_db: InferDB,
+        backend: Arc<dyn InferenceBackend>,
+        config: InferPoolConfig,
+    ) -> Result<InferWorkerPool, Box<dyn error::Error>> {
+        let requeued = db::sqlite_requeue_in_progress(&infer_db.conn())?;
+        if requeued > 0 {
+            info!("Re-queued interrupted inference tasks"; "count" => requeued);
+        }
+        let (outcome_sender, outcome_receiver) = channel();
+        Ok(InferWorkerPool {
+            infer_db,
+            backend,
+            config,
+            attestation_key: None,
+            metrics: None,
+            running: HashMap::new(),
+            abandoned: vec![],
+            outcome_sender,
+            outcome_receiver,
+        })
+    }
+
+    pub fn config(&self) -> &InferPoolConfig {
+        &self.config
+    }
+
+    /// Sign every output recorded from now on with `attestation_key`
+    pub fn set_attestation_key(&mut self, attestation_key: Secp256k1PrivateKey) {
+        self.attestation_key = Some(attestation_key);
+    }
+
+    /// Report how the pool is doing to `metrics` from now on
+    pub fn set_metrics(&mut self, metrics: Arc<dyn InferMetrics>) {
+        self.metrics = Some(metrics);
+    }
+
+    /// Number of tasks being run right now
+    pub fn num_running(&self) -> usize {
+        self.running.len()
+    }
+
+    /// Number of workers in use: those running tasks, plus those still held by the threads of
+    /// abandoned jobs
+    pub fn num_busy(&self) -> usize {
+        let lingering = self
+            .abandoned
+            .iter()
+            .filter(|thread_alive| Arc::strong_count(thread_alive) > 1)
+            .count();
+        self.running.len() + lingering
+    }
+
+    /// Do one round of scheduling, without waiting on any job.  Returns the number of tasks
+    /// started.
+    pub fn tick(&mut self) -> Result<usize, Box<dyn error::Error>> {
+        self.collect_finished()?;
+        self.abandon_stale()?;
+        self.abandoned
+            .retain(|thread_alive| Arc::strong_count(thread_alive) > 1);
+        let started = self.start_queued()?;
+        if let Some(ref metrics) = self.metrics {
+            let pending = db::sqlite_count_queued(&self.infer_db.conn())?;
+            metrics.set_jobs(self.running.len() as u64, pending);
+        }
+        Ok(started)
+    }
+
+    /// Report the status an attempt left its task with
+    fn observe_outcome(&self, status: &InferStatus) {
+        let Some(ref metrics) = self.metrics else {
+            return;
+        };
+        match status {
+            InferStatus::Success
+            | InferStatus::Retrying
+            | InferStatus::Failure
+            | InferStatus::TimedOut => metrics.record_outcome(status),
+            // the task was cancelled in the meantime, so the attempt didn't count
+            _ => {}
+        }
+    }
+
+    /// Record the outcome of every job that has finished since the last tick.
+    fn collect_finished(&mut self) -> Result<(), Box<dyn error::Error>> {
+        while let Ok(outcome) = self.outcome_receiver.try_recv() {
+            // an abandoned job may still report back, possibly after its task was restarted
+            if self.running.get(&outcome.txid).map(|job| job.attempts) != Some(outcome.attempts) {
+                continue;
+            }
+            self.running.remove(&outcome.txid);
+            if let Err(ref e) = outcome.result {
+                warn!("Inference task failed"; "txid" => &outcome.txid, "backend" => self.backend.name(), "attempt" => outcome.attempts, "err" => %e);
+            }
+            if let Some(ref metrics) = self.metrics {
+                metrics.observe_model_latency(outcome.elapsed);
+            }
+            let status = record_outcome(
+                &self.infer_db,
+                &self.config,
+                self.attestation_key.as_ref(),
+                &outcome.txid,
+                outcome.attempts,
+                outcome.result,
+                false,
+            )?;
+            self.observe_outcome(&status);
+        }
+        Ok(())
+    }
+
+    /// Give up on jobs that were cancelled or have run for longer than the job timeout.
+    fn abandon_stale(&mut self) -> Result<(), Box<dyn error::Error>> {
+        let txids: Vec<String> = self.running.keys().cloned().collect();
+        for txid in txids {
+            let status: InferStatus = db::sqlite_get(&self.infer_db.conn(), &txid)?.status.into();
+            if status != InferStatus::InProgress {
+                debug!("Abandoning inference task"; "txid" => &txid, "status" => ?status);
+                if let Some(job) = self.running.remove(&txid) {
+                    self.abandoned.push(job.abandon());
+                }
+                continue;
+            }
+
+            let Some(job) = self.running.get(&txid) else {
+                continue;
+            };
+            if job.started.elapsed() < self.config.job_timeout {
+                continue;
+            }
+            let attempts = job.attempts;
+            if let Some(job) = self.running.remove(&txid) {
+                self.abandoned.push(job.abandon());
+            }
+            warn!("Inference task timed out"; "txid" => &txid, "attempt" => attempts, "timeout" => ?self.config.job_timeout);
+            let status = record_outcome(
+                &self.infer_db,
+                &self.config,
+                self.attestation_key.as_ref(),
+                &txid,
+                attempts,
+                Err(format!("timed out after {:?}", self.config.job_timeout)),
+                true,
+            )?;
+            self.observe_outcome(&status);
+        }
+        Ok(())
+    }
+
+    /// Start queued tasks on idle workers.
+    fn start_queued(&mut self) -> Result<usize, Box<dyn error::Error>> {
+        let mut started = 0;
+        while self.num_busy() < self.config.workers {
+            let Some((row, attempts)) = claim_next_task(&self.infer_db)? else {
+                break;
+            };
+            let txid = row.txid.clone();
+            if let Some(ref metrics) = self.metrics {
+                if let Some(wait) = db::sqlite_queue_wait(&self.infer_db.conn(), &txid)? {
+                    metrics.observe_queue_wait(Duration::from_secs(wait));
+                }
+            }
+            let backend = self.backend.clone();
+            let infer_db = self.infer_db.clone();
+            let outcome_sender = self.outcome_sender.clone();
+            let thread_alive = Arc::new(());
+            let job_thread_alive = thread_alive.clone();
+            let cancelled = Arc::new(AtomicBool::new(false));
+            let job_cancelled = cancelled.clone();
+            let spawn_res = thread::Builder::new()
+                .name(format!("infer-worker-{}", &txid))
+                .spawn(move || {
+                    let _thread_alive = job_thread_alive;
+                    let started = Instant::now();
+                    let result = run_task(
+                        backend.as_ref(),
+                        &infer_db,
+                        &row.txid,
+                        attempts,
+                        &row.input,
+                        &row.context,
+                        &job_cancelled,
+                    )
+                    .map_err(|e| e.to_string());
+                    // the pool may have been dropped
+                    let _ = outcome_sender.send(JobOutcome {
+                        txid: row.txid,
+                        attempts,
+                        result,
+                        elapsed: started.elapsed(),
+                    });
+                });
+            if let Err(e) = spawn_res {
+                warn!("Failed to start inference worker"; "txid" => &txid, "err" => %e);
+                let status = record_outcome(
+                    &self.infer_db,
+                    &self.config,
+                    self.attestation_key.as_ref(),
+                    &txid,
+                    attempts,
+                    Err(e.to_string()),
+                    false,
+                )?;
+                self.observe_outcome(&status);
+                break;
+            }
+
+            debug!("Started inference task"; "txid" => &txid, "attempt" => attempts);
+            self.running.insert(
+                txid,
+                RunningJob {
+                    attempts,
+                    started: Instant::now(),
+                    thread_alive,
+                    cancelled,
+                },
+            );
+            started += 1;
+        }
+        Ok(started)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use stacks_common::util::secp256k1::Secp256k1PublicKey;
+
+    use super::*;
+    use crate::backend::{CompletionRequest, MockBackend, SubprocessBackend};
+    use crate::{cancel, infer_chain, query};
+
+    /// Answers like `MockBackend`, but only after `delay`, and remembers what it was asked
+    struct SlowBackend {
+        delay: Duration,
+        prompts: Mutex<Vec<String>>,
+    }
+
+    impl SlowBackend {
+        fn new(delay: Duration) -> SlowBackend {
+            SlowBackend {
+                delay,
+                prompts: Mutex::new(vec![]),
+            }
+        }
+    }
+
+    impl InferenceBackend for SlowBackend {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        fn model(&self) -> &str {
+            "mock"
+        }
+
+        fn complete(&self, request: &CompletionRequest) -> Result<String, Box<dyn error::Error>> {
+            let prompt = request.last_user_content().unwrap_or("").to_string();
+            self.prompts.lock().unwrap().push(prompt);
+            thread::sleep(self.delay);
+            MockBackend::new().complete(request)
+        }
+    }
+
+    fn test_config() -> InferPoolConfig {
+        InferPoolConfig {
+            workers: 1,
+            job_timeout: Duration::from_secs(60),
+            max_attempts: 2,
+            retry_backoff: Duration::ZERO,
+            max_retry_backoff: Duration::ZERO,
+        }
+    }
+
+    fn tick_until(pool: &mut InferWorkerPool, infer_db: &InferDB, txid: &str, status: InferStatus) {
+        for _ in 0..1000 {
+            pool.tick().unwrap();
+            if query(infer_db, txid.to_string()).unwrap().status == status {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("Task {} never reached {:?}", txid, status);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let config = InferPoolConfig {
+            retry_backoff: Duration::from_secs(5),
+            max_retry_backoff: Duration::from_secs(60),
+            ..InferPoolConfig::default()
+        };
+        assert_eq!(config.backoff(1), Duration::from_secs(5));
+        assert_eq!(config.backoff(2), Duration::from_secs(10));
+        assert_eq!(config.backoff(3), Duration::from_secs(20));
+        assert_eq!(config.backoff(5), Duration::from_secs(60));
+        assert_eq!(config.backoff(u32::MAX), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_runs_highest_fee_first() {
+        let infer_db = InferDB::open_memory().unwrap();
+        infer_chain(&infer_db, "a".to_string(), "low", "", 1).unwrap();
+        infer_chain(&infer_db, "b".to_string(), "high", "", 10).unwrap();
+        infer_chain(&infer_db, "c".to_string(), "medium", "", 5).unwrap();
+
+        let backend = Arc::new(SlowBackend::new(Duration::ZERO));
+        let mut pool = InferWorkerPool::new(infer_db.clone(), backend.clone(), test_config()).unwrap();
+        tick_until(&mut pool, &infer_db, "a", InferStatus::Success);
+
+        assert_eq!(*backend.prompts.lock().unwrap(), vec!["high", "medium", "low"]);
+        for txid in ["a", "b", "c"] {
+            let result = query(&infer_db, txid.to_string()).unwrap();
+            assert_eq!(result.status, InferStatus::Success);
+            assert_eq!(result.output_hash, output_hash(&result.output));
+        }
+    }
+
+    #[test]
+    fn test_runs_up_to_workers_at_once() {
+        let infer_db = InferDB::open_memory().unwrap();
+        for txid in ["a", "b", "c"] {
+            infer_chain(&infer_db, txid.to_string(), txid, "", 0).unwrap();
+        }
+
+        let config = InferPoolConfig {
+            workers: 2,
+            ..test_config()
+        };
+        let backend = Arc::new(SlowBackend::new(Duration::from_millis(200)));
+        let mut pool = InferWorkerPool::new(infer_db.clone(), backend, config).unwrap();
+        assert_eq!(pool.tick().unwrap(), 2);
+        assert_eq!(pool.num_running(), 2);
+        assert_eq!(pool.tick().unwrap(), 0);
+        assert_eq!(query(&infer_db, "c".to_string()).unwrap().status, InferStatus::Created);
+
+        tick_until(&mut pool, &infer_db, "c", InferStatus::Success);
+    }
+
+    #[test]
+    fn test_retries_then_fails() {
+        let infer_db = InferDB::open_memory().unwrap();
+        let context = r#"{"params":{"model":"llama3.1"}}"#;
+        infer_chain(&infer_db, "a".to_string(), "Is the Earth round?", context, 0).unwrap();
+
+        let mut pool =
+            InferWorkerPool::new(infer_db.clone(), Arc::new(MockBackend::new()), test_config())
+                .unwrap();
+        // with no backoff, the retry starts as soon as the first attempt is collected
+        tick_until(&mut pool, &infer_db, "a", InferStatus::Failure);
+
+        let row = db::sqlite_get(&infer_db.conn(), "a").unwrap();
+        assert_eq!(row.attempts, 2);
+        assert!(row.error.contains("MODEL_MISMATCH"));
+        assert_eq!(row.output_hash, "");
+        assert_eq!(pool.num_running(), 0);
+    }
+
+    #[test]
+    fn test_retry_waits_for_backoff() {
+        let infer_db = InferDB::open_memory().unwrap();
+        infer_chain(&infer_db, "a".to_string(), "Is the Earth round?", "", 0).unwrap();
+        {
+            let conn = infer_db.conn();
+            assert!(db::sqlite_start_llm(&conn, "a", InferStatus::InProgress as u8).unwrap());
+            let next_attempt_time = get_epoch_time_secs() + 3600;
+            assert!(db::sqlite_retry_llm(&conn, "a", next_attempt_time, "busy").unwrap());
+        }
+
+        let mut pool =
+            InferWorkerPool::new(infer_db.clone(), Arc::new(MockBackend::new()), test_config())
+                .unwrap();
+        assert_eq!(pool.tick().unwrap(), 0);
+        assert_eq!(query(&infer_db, "a".to_string()).unwrap().status, InferStatus::Retrying);
+    }
+
+    #[test]
+    fn test_times_out() {
+        let infer_db = InferDB::open_memory().unwrap();
+        infer_chain(&infer_db, "a".to_string(), "Is the Earth round?", "", 0).unwrap();
+
+        let config = InferPoolConfig {
+            job_timeout: Duration::from_millis(50),
+            max_attempts: 1,
+            ..test_config()
+        };
+        let backend = Arc::new(SlowBackend::new(Duration::from_millis(500)));
+        let mut pool = InferWorkerPool::new(infer_db.clone(), backend, config).unwrap();
+        tick_until(&mut pool, &infer_db, "a", InferStatus::TimedOut);
+        assert_eq!(pool.num_running(), 0);
+
+        // the late result is ignored
+        thread::sleep(Duration::from_millis(600));
+        pool.tick().unwrap();
+        let result = query(&infer_db, "a".to_string()).unwrap();
+        assert_eq!(result.status, InferStatus::TimedOut);
+        assert_eq!(result.output, "");
+    }
+
+    #[test]
+    fn test_timed_out_job_holds_its_worker() {
+        let infer_db = InferDB::open_memory().unwrap();
+        infer_chain(&infer_db, "a".to_string(), "Is the Earth round?", "", 10).unwrap();
+        infer_chain(&infer_db, "b".to_string(), "Is the Earth flat?", "", 0).unwrap();
+
+        let config = InferPoolConfig {
+            job_timeout: Duration::from_millis(50),
+            max_attempts: 1,
+            ..test_config()
+        };
+        let backend = Arc::new(SlowBackend::new(Duration::from_millis(300)));
+        let mut pool = InferWorkerPool::new(infer_db.clone(), backend, config).unwrap();
+        tick_until(&mut pool, &infer_db, "a", InferStatus::TimedOut);
+
+        // "a"'s backend call is still running, so "b" has to wait for it
+        assert_eq!(pool.num_running(), 0);
+        assert_eq!(pool.num_busy(), 1);
+        assert_eq!(pool.tick().unwrap(), 0);
+        assert_eq!(query(&infer_db, "b".to_string()).unwrap().status, InferStatus::Created);
+
+        thread::sleep(Duration::from_millis(400));
+        assert_eq!(pool.num_busy(), 0);
+        assert_eq!(pool.tick().unwrap(), 1);
+        assert_eq!(query(&infer_db, "b".to_string()).unwrap().status, InferStatus::InProgress);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timed_out_subprocess_is_killed() {
+        let infer_db = InferDB::open_memory().unwrap();
+        infer_chain(&infer_db, "a".to_string(), "Is the Earth round?", "", 0).unwrap();
+
+        let config = InferPoolConfig {
+            job_timeout: Duration::from_millis(50),
+            max_attempts: 1,
+            ..test_config()
+        };
+        let backend = Arc::new(SubprocessBackend::new(
+            "sh",
+            vec!["-c".into(), "sleep 30".into()],
+            "mock",
+        ));
+        let mut pool = InferWorkerPool::new(infer_db.clone(), backend, config).unwrap();
+        tick_until(&mut pool, &infer_db, "a", InferStatus::TimedOut);
+
+        // the program is killed, so its worker is freed long before it would have exited
+        for _ in 0..500 {
+            if pool.num_busy() == 0 {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("The timed-out program was never killed");
+    }
+
+    #[test]
+    fn test_cancel_running_task() {
+        let infer_db = InferDB::open_memory().unwrap();
+        infer_chain(&infer_db, "a".to_string(), "Is the Earth round?", "", 0).unwrap();
+        infer_chain(&infer_db, "b".to_string(), "Is the Earth flat?", "", 0).unwrap();
+
+        let backend = Arc::new(SlowBackend::new(Duration::from_millis(200)));
+        let mut pool = InferWorkerPool::new(infer_db.clone(), backend, test_config()).unwrap();
+        assert_eq!(pool.tick().unwrap(), 1);
+        let running = if query(&infer_db, "a".to_string()).unwrap().status
+            == InferStatus::InProgress
+        {
+            "a"
+        } else {
+            "b"
+        };
+        assert!(cancel(&infer_db, running.to_string()).unwrap());
+
+        // the cancelled task's backend call is still running, so it keeps its worker
+        assert_eq!(pool.tick().unwrap(), 0);
+        assert_eq!(pool.num_running(), 0);
+        assert_eq!(pool.num_busy(), 1);
+
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(pool.num_busy(), 0);
+        assert_eq!(pool.tick().unwrap(), 1);
+        assert_eq!(pool.num_running(), 1);
+        let result = query(&infer_db, running.to_string()).unwrap();
+        assert_eq!(result.status, InferStatus::Cancelled);
+        assert_eq!(result.output, "");
+    }
+
+    #[test]
+    fn test_attests_outputs() {
+        let infer_db = InferDB::open_memory().unwrap();
+        infer_chain(&infer_db, "a".to_string(), "Is the Earth round?", "", 0).unwrap();
+
+        let privk = Secp256k1PrivateKey::new();
+        let mut pool =
+            InferWorkerPool::new(infer_db.clone(), Arc::new(MockBackend::new()), test_config())
+                .unwrap();
+        pool.set_attestation_key(privk.clone());
+        tick_until(&mut pool, &infer_db, "a", InferStatus::Success);
+
+        let result = query(&infer_db, "a".to_string()).unwrap();
+        let attestation = result.attestation.clone().unwrap();
+        let public_key = attestation.check_result(&result).unwrap();
+        assert_eq!(
+            public_key.to_bytes_compressed(),
+            Secp256k1PublicKey::from_private(&privk).to_bytes_compressed()
+        );
+        assert_eq!(attestation.model, Some("mock".to_string()));
+    }
+
+    #[derive(Default)]
+    struct RecordingMetrics {
+        queue_waits: Mutex<Vec<Duration>>,
+        latencies: Mutex<Vec<Duration>>,
+        outcomes: Mutex<Vec<InferStatus>>,
+        jobs: Mutex<Option<(u64, u64)>>,
+    }
+
+    impl InferMetrics for RecordingMetrics {
+        fn observe_queue_wait(&self, wait: Duration) {
+            self.queue_waits.lock().unwrap().push(wait);
+        }
+
+        fn observe_model_latency(&self, latency: Duration) {
+            self.latencies.lock().unwrap().push(latency);
+        }
+
+        fn record_outcome(&self, status: &InferStatus) {
+            self.outcomes.lock().unwrap().push(status.clone());
+        }
+
+        fn set_jobs(&self, in_progress: u64, pending: u64) {
+            *self.jobs.lock().unwrap() = Some((in_progress, pending));
+        }
+    }
+
+    #[test]
+    fn test_reports_metrics() {
+        let infer_db = InferDB::open_memory().unwrap();
+        infer_chain(&infer_db, "a".to_string(), "Is the Earth round?", "", 10).unwrap();
+        let context = r#"{"params":{"model":"llama3.1"}}"#;
+        infer_chain(&infer_db, "b".to_string(), "Is the Earth flat?", context, 5).unwrap();
+
+        let metrics = Arc::new(RecordingMetrics::default());
+        let mut pool =
+            InferWorkerPool::new(infer_db.clone(), Arc::new(MockBackend::new()), test_config())
+                .unwrap();
+        pool.set_metrics(metrics.clone());
+
+        // "a" runs first, and "b" waits its turn
+        assert_eq!(pool.tick().unwrap(), 1);
+        assert_eq!(*metrics.jobs.lock().unwrap(), Some((1, 1)));
+
+        tick_until(&mut pool, &infer_db, "b", InferStatus::Failure);
+        assert_eq!(*metrics.jobs.lock().unwrap(), Some((0, 0)));
+        assert_eq!(
+            *metrics.outcomes.lock().unwrap(),
+            vec![
+                InferStatus::Success,
+                InferStatus::Retrying,
+                InferStatus::Failure
+            ]
+        );
+        assert_eq!(metrics.queue_waits.lock().unwrap().len(), 3);
+        assert_eq!(metrics.latencies.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_requeues_interrupted_tasks() {
+        let infer_db = InferDB::open_memory().unwrap();
+        infer_chain(&infer_db, "a".to_string(), "Is the Earth round?", "", 0).unwrap();
+        assert!(claim_next_task(&infer_db).unwrap().is_some());
+        assert_eq!(query(&infer_db, "a".to_string()).unwrap().status, InferStatus::InProgress);
+
+        let mut pool =
+            InferWorkerPool::new(infer_db.clone(), Arc::new(MockBackend::new()), test_config())
+                .unwrap();
+        assert_eq!(query(&infer_db, "a".to_string()).unwrap().status, InferStatus::Retrying);
+        tick_until(&mut pool, &infer_db, "a", InferStatus::Success);
+        assert_eq!(db::sqlite_get(&infer_db.conn(), "a").unwrap().attempts, 2);
+    }
+}
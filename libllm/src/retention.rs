@@ -0,0 +1,198 @@
+//! Retention of inference results.
+//!
+//! Without pruning, an `InferDB` keeps every task it was ever asked to run.  A result is only
+//! needed while its transaction may still be mined -- to mine or validate the block that
+//! includes it -- and while later turns of its session may be run on it.  So:
+//!
+//! * a result whose transaction was confirmed is kept until `confirmed_blocks` blocks have been
+//!   built on top of the highest block it was confirmed in.  Since a reorg may re-mine the
+//!   transaction in another fork, this should be deeper than any reorg the node expects to see.
+//!   Confirming a later turn of a session keeps the turns before it too.
+//! * a result whose transaction left the mempool unconfirmed is an orphan.  It is kept for
+//!   `orphan_secs` in case the transaction comes back (e.g. it is re-broadcast, or a fork it
+//!   was mined in is abandoned), and then pruned.
+//! * any other result belongs to a transaction still in the mempool, and is kept.
+//!
+//! Whatever the above says, a result is kept as long as any result that continues its session
+//! is, since the later turn can't be run (or re-run, after a reorg) without it.
+//!
+//! A successful result is never replaced by a re-submission of its transaction (see
+//! `db::sqlite_create`), since it may already be committed on-chain.
+
+use std::error;
+
+use crate::db::{self, InferDB};
+use crate::params::InferContext;
+use crate::session::MAX_INFER_SESSION_DEPTH;
+
+/// About two weeks of Bitcoin blocks
+pub const DEFAULT_CONFIRMED_RETENTION_BLOCKS: u64 = 2_016;
+pub const DEFAULT_ORPHAN_RETENTION_SECS: u64 = 24 * 60 * 60;
+
+/// How long inference results are kept
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferRetentionConfig {
+    /// How many blocks a confirmed transaction's result is kept for after its confirmation
+    pub confirmed_blocks: u64,
+    /// How long (in seconds) a result is kept after its transaction left the mempool unconfirmed
+    pub orphan_secs: u64,
+}
+
+impl Default for InferRetentionConfig {
+    fn default() -> InferRetentionConfig {
+        InferRetentionConfig {
+            confirmed_blocks: DEFAULT_CONFIRMED_RETENTION_BLOCKS,
+            orphan_secs: DEFAULT_ORPHAN_RETENTION_SECS,
+        }
+    }
+}
+
+/// Record that the transaction of task `txid` was confirmed in a block at `height`, along with
+/// the earlier turns of its session.  Returns false if this node has no such task.
+pub fn confirm(infer_db: &InferDB, txid: &str, height: u64) -> Result<bool, Box<dyn error::Error>> {
+    let conn = infer_db.conn();
+    if !db::sqlite_confirm(&conn, txid, height)? {
+        return Ok(false);
+    }
+    let mut next = InferContext::parse(&db::sqlite_get(&conn, txid)?.context).parent;
+    for _ in 0..MAX_INFER_SESSION_DEPTH {
+        let Some(parent) = next else {
+            break;
+        };
+        if !db::sqlite_confirm(&conn, &parent, height)? {
+            break;
+        }
+        next = InferContext::parse(&db::sqlite_get(&conn, &parent)?.context).parent;
+    }
+    Ok(true)
+}
+
+/// Record that the transaction of task `txid` left the mempool at unix time `now`.  Unless it
+/// was confirmed, its result is an orphan from then on.
+pub fn evict(infer_db: &InferDB, txid: &str, now: u64) -> Result<bool, Box<dyn error::Error>> {
+    db::sqlite_evict(&infer_db.conn(), txid, now)
+}
+
+/// Prune the results that `config` no longer keeps, given that the canonical Stacks tip is at
+/// `tip_height` and it is unix time `now`.  Returns how many were pruned.
+pub fn prune(
+    infer_db: &InferDB,
+    config: &InferRetentionConfig,
+    tip_height: u64,
+    now: u64,
+) -> Result<usize, Box<dyn error::Error>> {
+    db::sqlite_prune(
+        &infer_db.conn(),
+        tip_height.checked_sub(config.confirmed_blocks),
+        now.checked_sub(config.orphan_secs),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{do_infer, infer_chain, query, InferStatus, MockBackend};
+
+    fn status(infer_db: &InferDB, txid: &str) -> InferStatus {
+        query(infer_db, txid.to_string()).unwrap().status
+    }
+
+    #[test]
+    fn test_prune() {
+        let infer_db = InferDB::open_memory().unwrap();
+        let backend = MockBackend::new();
+        let config = InferRetentionConfig {
+            confirmed_blocks: 10,
+            orphan_secs: 60,
+        };
+        let (mined, orphan, pending) = ("01".repeat(32), "02".repeat(32), "03".repeat(32));
+        for txid in [&mined, &orphan, &pending] {
+            infer_chain(&infer_db, txid.clone(), "Is the Earth round?", "", 0).unwrap();
+            do_infer(&infer_db, &backend).unwrap();
+        }
+
+        assert!(confirm(&infer_db, &mined, 100).unwrap());
+        assert!(evict(&infer_db, &orphan, 1000).unwrap());
+        assert_eq!(prune(&infer_db, &config, 109, 1059).unwrap(), 0);
+
+        // the orphan's transaction came back, and left again
+        infer_chain(&infer_db, orphan.clone(), "Is the Earth round?", "", 0).unwrap();
+        assert_eq!(prune(&infer_db, &config, 109, 1060).unwrap(), 0);
+        assert!(evict(&infer_db, &orphan, 1030).unwrap());
+        assert_eq!(prune(&infer_db, &config, 109, 1089).unwrap(), 0);
+        assert_eq!(prune(&infer_db, &config, 109, 1090).unwrap(), 1);
+        assert_eq!(status(&infer_db, &orphan), InferStatus::NotFound);
+
+        // a reorg re-mined the transaction higher up
+        assert!(confirm(&infer_db, &mined, 105).unwrap());
+        assert_eq!(prune(&infer_db, &config, 110, 2000).unwrap(), 0);
+        assert_eq!(status(&infer_db, &mined), InferStatus::Success);
+        assert_eq!(prune(&infer_db, &config, 115, 2000).unwrap(), 1);
+        assert_eq!(status(&infer_db, &mined), InferStatus::NotFound);
+
+        assert_eq!(status(&infer_db, &pending), InferStatus::Success);
+        assert!(!confirm(&infer_db, &mined, 120).unwrap());
+    }
+
+    #[test]
+    fn test_confirm_keeps_session() {
+        let infer_db = InferDB::open_memory().unwrap();
+        let backend = MockBackend::new();
+        let config = InferRetentionConfig {
+            confirmed_blocks: 10,
+            orphan_secs: 60,
+        };
+        let (first, second) = ("01".repeat(32), "02".repeat(32));
+        infer_chain(&infer_db, first.clone(), "Is the Earth round?", "", 0).unwrap();
+        do_infer(&infer_db, &backend).unwrap();
+        let context = serde_json::to_string(&InferContext {
+            parent: Some(first.clone()),
+            ..InferContext::default()
+        })
+        .unwrap();
+        infer_chain(&infer_db, second.clone(), "Why?", &context, 0).unwrap();
+        do_infer(&infer_db, &backend).unwrap();
+
+        assert!(confirm(&infer_db, &first, 100).unwrap());
+        assert!(confirm(&infer_db, &second, 105).unwrap());
+        assert_eq!(prune(&infer_db, &config, 110, 0).unwrap(), 0);
+        assert_eq!(status(&infer_db, &first), InferStatus::Success);
+        assert_eq!(prune(&infer_db, &config, 115, 0).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_prune_keeps_live_session() {
+        let infer_db = InferDB::open_memory().unwrap();
+        let backend = MockBackend::new();
+        let config = InferRetentionConfig {
+            confirmed_blocks: 10,
+            orphan_secs: 60,
+        };
+        let (first, second, third) = ("01".repeat(32), "02".repeat(32), "03".repeat(32));
+        let continuing = |parent: &str| {
+            serde_json::to_string(&InferContext {
+                parent: Some(parent.to_string()),
+                ..InferContext::default()
+            })
+            .unwrap()
+        };
+        infer_chain(&infer_db, first.clone(), "Is the Earth round?", "", 0).unwrap();
+        do_infer(&infer_db, &backend).unwrap();
+        infer_chain(&infer_db, second.clone(), "Why?", &continuing(&first), 0).unwrap();
+        do_infer(&infer_db, &backend).unwrap();
+        assert!(confirm(&infer_db, &first, 100).unwrap());
+        assert!(confirm(&infer_db, &second, 100).unwrap());
+
+        // a third turn is still in the mempool, so the whole session is kept
+        infer_chain(&infer_db, third.clone(), "Are you sure?", &continuing(&second), 0).unwrap();
+        assert_eq!(prune(&infer_db, &config, 200, 0).unwrap(), 0);
+        assert_eq!(status(&infer_db, &first), InferStatus::Success);
+        assert_eq!(status(&infer_db, &second), InferStatus::Success);
+
+        // until it is an orphan too
+        assert!(evict(&infer_db, &third, 1000).unwrap());
+        assert_eq!(prune(&infer_db, &config, 200, 1059).unwrap(), 0);
+        assert_eq!(prune(&infer_db, &config, 200, 1060).unwrap(), 3);
+        assert_eq!(status(&infer_db, &first), InferStatus::NotFound);
+    }
+}
@@ -0,0 +1,336 @@
+//! Multi-turn inference sessions.
+//!
+//! An inference task may continue the conversation of an earlier one, its parent, by naming it in
+//! `InferContext::parent`, instead of re-sending the whole conversation.  The node rebuilds the
+//! conversation from the inputs and outputs in its `InferDB`: the parent's conversation, then the
+//! parent's user input and output, then the task's own context messages, and finally its user
+//! input.
+//!
+//! Each turn of a session committed on-chain carries a session hash, which chains the hashes of
+//! its user input, context and output onto its parent's session hash.  Given the transcript of a
+//! conversation, anyone can recompute the chain and check it against the last turn's.
+
+use std::error;
+use std::io::{Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+use stacks_common::util::hash::Sha256Sum;
+
+use crate::db::{self, InferDB};
+use crate::params::InferContext;
+use crate::{output_hash, ChatCompletionMessageRole, InferStatus};
+
+/// How many earlier turns a session may have
+pub const MAX_INFER_SESSION_DEPTH: usize = 32;
+
+/// The session hash of a turn whose user input was `user_input`, whose context was `context`,
+/// and whose output hashed to `output_hash`, following the turn with session hash
+/// `parent_session_hash` (if any)
+pub fn session_hash(
+    parent_session_hash: Option<&[u8]>,
+    user_input: &str,
+    context: &str,
+    output_hash: &[u8],
+) -> [u8; 32] {
+    let mut preimage = Vec::with_capacity(96 + output_hash.len());
+    preimage.extend_from_slice(parent_session_hash.unwrap_or(&[0u8; 32]));
+    preimage.extend_from_slice(Sha256Sum::from_data(user_input.as_bytes()).as_bytes());
+    preimage.extend_from_slice(Sha256Sum::from_data(context.as_bytes()).as_bytes());
+    preimage.extend_from_slice(output_hash);
+    Sha256Sum::from_data(&preimage).0
+}
+
+/// An earlier turn of a session, as its Infer transaction and inference result recorded it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionTurn {
+    pub txid: String,
+    pub input: String,
+    /// The turn's own context, as its transaction sent it
+    pub context: String,
+    pub output: String,
+}
+
+/// The earlier turns of the session a task with `context` continues, oldest first.  Fails if an
+/// earlier turn isn't known to `infer_db` or hasn't succeeded (yet), or if the session is too
+/// long.
+pub fn session_turns(
+    infer_db: &InferDB,
+    context: &InferContext,
+) -> Result<Vec<SessionTurn>, Box<dyn error::Error>> {
+    // most recent first
+    let mut turns = vec![];
+    let mut next = context.parent.clone();
+    while let Some(txid) = next {
+        if turns.len() >= MAX_INFER_SESSION_DEPTH {
+            return Err(Box::new(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "SESSION_TOO_LONG: more than {} earlier turns",
+                    MAX_INFER_SESSION_DEPTH
+                ),
+            )));
+        }
+        let row = db::sqlite_get(&infer_db.conn(), &txid)?;
+        let status: InferStatus = row.status.into();
+        if status != InferStatus::Success {
+            return Err(Box::new(Error::new(
+                ErrorKind::NotFound,
+                format!("SESSION_PARENT_NOT_READY: turn {} is {:?}", txid, status),
+            )));
+        }
+        next = InferContext::parse(&row.context).parent;
+        turns.push(SessionTurn {
+            txid,
+            input: row.input,
+            context: row.context,
+            output: row.output,
+        });
+    }
+    turns.reverse();
+    Ok(turns)
+}
+
+/// The context a task with `context` is run with, given the earlier `turns` of its session
+/// (oldest first): its messages are preceded by the conversation so far.
+pub fn expand_turns(context: &InferContext, turns: &[SessionTurn]) -> InferContext {
+    let mut expanded = InferContext {
+        messages: vec![],
+        ..context.clone()
+    };
+    for turn in turns.iter() {
+        expanded
+            .messages
+            .extend(InferContext::parse(&turn.context).messages);
+        expanded.push_message(ChatCompletionMessageRole::User, &turn.input);
+        expanded.push_message(ChatCompletionMessageRole::Assistant, &turn.output);
+    }
+    expanded.messages.extend(context.messages.iter().cloned());
+    expanded
+}
+
+/// The context a task with `context` is run with: if it continues a session, its messages are
+/// preceded by the conversation so far.  Fails if an earlier turn isn't known to `infer_db` or
+/// hasn't succeeded (yet), or if the session is too long.
+pub fn expand_context(
+    infer_db: &InferDB,
+    context: &InferContext,
+) -> Result<InferContext, Box<dyn error::Error>> {
+    let turns = session_turns(infer_db, context)?;
+    Ok(expand_turns(context, &turns))
+}
+
+/// Check that `turns` (oldest first) are the whole session up to and including the turn
+/// `parent`: the first turn starts the session, and each later turn continues the one before it.
+/// Returns the session hash of `parent` that the turns chain to, which is only as trustworthy as
+/// the turns until it is checked against the one committed on-chain.
+pub fn chain_session_turns(turns: &[SessionTurn], parent: &str) -> Result<[u8; 32], String> {
+    if turns.len() > MAX_INFER_SESSION_DEPTH {
+        return Err(format!(
+            "more than {} earlier turns",
+            MAX_INFER_SESSION_DEPTH
+        ));
+    }
+    let mut previous: Option<(&str, [u8; 32])> = None;
+    for turn in turns.iter() {
+        let turn_parent = InferContext::parse(&turn.context).parent;
+        if turn_parent.as_deref() != previous.map(|(txid, _)| txid) {
+            return Err(format!(
+                "turn {} does not continue {:?}",
+                &turn.txid,
+                previous.map(|(txid, _)| txid)
+            ));
+        }
+        let output_hash_bytes = hex::decode(output_hash(&turn.output))
+            .map_err(|e| format!("bad output hash of turn {}: {}", &turn.txid, e))?;
+        let hash = session_hash(
+            previous.as_ref().map(|(_, hash)| &hash[..]),
+            &turn.input,
+            &turn.context,
+            &output_hash_bytes,
+        );
+        previous = Some((&turn.txid, hash));
+    }
+    match previous {
+        Some((txid, hash)) if txid == parent => Ok(hash),
+        Some((txid, _)) => Err(format!("the last turn is {}, not {}", txid, parent)),
+        None => Err("no earlier turns".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{do_infer, infer_chain, output_hash, query, MockBackend};
+
+    fn contents(context: &InferContext) -> Vec<(ChatCompletionMessageRole, String)> {
+        context
+            .messages
+            .iter()
+            .map(|message| (message.role, message.content.clone().unwrap_or_default()))
+            .collect()
+    }
+
+    fn continue_from(parent: &str) -> String {
+        serde_json::to_string(&InferContext {
+            parent: Some(parent.to_string()),
+            ..InferContext::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_expand_context() {
+        let infer_db = InferDB::open_memory().unwrap();
+        let first = "01".repeat(32);
+        let second = "02".repeat(32);
+        infer_chain(
+            &infer_db,
+            first.clone(),
+            "Is the Earth round?",
+            r#"[{"role":"system","content":"be brief"}]"#,
+            0,
+        )
+        .unwrap();
+        infer_chain(&infer_db, second.clone(), "Why?", &continue_from(&first), 0).unwrap();
+
+        let mut third = InferContext::parse(&continue_from(&second));
+        // the parent hasn't run yet
+        assert!(expand_context(&infer_db, &third).is_err());
+
+        let backend = MockBackend::new();
+        do_infer(&infer_db, &backend).unwrap();
+        do_infer(&infer_db, &backend).unwrap();
+        let first_output = query(&infer_db, first.clone()).unwrap().output;
+        let second_output = query(&infer_db, second.clone()).unwrap().output;
+
+        third.push_message(ChatCompletionMessageRole::System, "now be verbose");
+        let expanded = expand_context(&infer_db, &third).unwrap();
+        assert_eq!(expanded.parent, Some(second.clone()));
+        assert_eq!(
+            contents(&expanded),
+            vec![
+                (ChatCompletionMessageRole::System, "be brief".to_string()),
+                (
+                    ChatCompletionMessageRole::User,
+                    "Is the Earth round?".to_string()
+                ),
+                (ChatCompletionMessageRole::Assistant, first_output),
+                (ChatCompletionMessageRole::User, "Why?".to_string()),
+                (ChatCompletionMessageRole::Assistant, second_output),
+                (
+                    ChatCompletionMessageRole::System,
+                    "now be verbose".to_string()
+                ),
+            ]
+        );
+
+        // a task outside any session is run as-is
+        let standalone = InferContext::parse(r#"[{"role":"system","content":"be brief"}]"#);
+        assert_eq!(expand_context(&infer_db, &standalone).unwrap(), standalone);
+
+        let orphan = InferContext::parse(&continue_from(&"03".repeat(32)));
+        assert!(expand_context(&infer_db, &orphan).is_err());
+    }
+
+    #[test]
+    fn test_session_too_long() {
+        let infer_db = InferDB::open_memory().unwrap();
+        let backend = MockBackend::new();
+        let mut parent: Option<String> = None;
+        for i in 0..=MAX_INFER_SESSION_DEPTH {
+            let txid = format!("{:064x}", i);
+            let context = parent.as_deref().map(continue_from).unwrap_or_default();
+            infer_chain(&infer_db, txid.clone(), "And then?", &context, 0).unwrap();
+            do_infer(&infer_db, &backend).unwrap();
+            parent = Some(txid);
+        }
+        let context = InferContext::parse(&continue_from(parent.as_ref().unwrap()));
+        assert!(expand_context(&infer_db, &context)
+            .unwrap_err()
+            .to_string()
+            .contains("SESSION_TOO_LONG"));
+    }
+
+    #[test]
+    fn test_chain_session_turns() {
+        let infer_db = InferDB::open_memory().unwrap();
+        let backend = MockBackend::new();
+        let first = "01".repeat(32);
+        let second = "02".repeat(32);
+        infer_chain(&infer_db, first.clone(), "Is the Earth round?", "", 0).unwrap();
+        infer_chain(&infer_db, second.clone(), "Why?", &continue_from(&first), 0).unwrap();
+        do_infer(&infer_db, &backend).unwrap();
+        do_infer(&infer_db, &backend).unwrap();
+
+        let third = InferContext::parse(&continue_from(&second));
+        let turns = session_turns(&infer_db, &third).unwrap();
+        assert_eq!(
+            turns
+                .iter()
+                .map(|turn| turn.txid.as_str())
+                .collect::<Vec<_>>(),
+            vec![first.as_str(), second.as_str()]
+        );
+        assert_eq!(
+            expand_turns(&third, &turns),
+            expand_context(&infer_db, &third).unwrap()
+        );
+
+        // the chain ends in the hash each turn was committed with
+        let first_hash = session_hash(
+            None,
+            "Is the Earth round?",
+            "",
+            &hex::decode(output_hash(&turns[0].output)).unwrap(),
+        );
+        let second_hash = session_hash(
+            Some(&first_hash),
+            "Why?",
+            &continue_from(&first),
+            &hex::decode(output_hash(&turns[1].output)).unwrap(),
+        );
+        assert_eq!(chain_session_turns(&turns, &second), Ok(second_hash));
+        assert_eq!(chain_session_turns(&turns[..1], &first), Ok(first_hash));
+
+        // a forged output chains to another hash
+        let mut forged = turns.clone();
+        forged[0].output = "No.".to_string();
+        assert_ne!(chain_session_turns(&forged, &second), Ok(second_hash));
+
+        // turns must be the whole session, in order, up to the parent
+        assert!(chain_session_turns(&turns[1..], &second).is_err());
+        assert!(chain_session_turns(&turns, &first).is_err());
+        let reversed: Vec<_> = turns.iter().rev().cloned().collect();
+        assert!(chain_session_turns(&reversed, &first).is_err());
+        assert!(chain_session_turns(&[], &second).is_err());
+    }
+
+    #[test]
+    fn test_session_hash() {
+        let output = output_hash("Yes.");
+        let output = hex::decode(output).unwrap();
+        let first = session_hash(None, "Is the Earth round?", "", &output);
+        assert_eq!(
+            first,
+            session_hash(None, "Is the Earth round?", "", &output)
+        );
+        assert_ne!(first, session_hash(None, "Is the Earth flat?", "", &output));
+        assert_ne!(
+            first,
+            session_hash(None, "Is the Earth round?", "[]", &output)
+        );
+        assert_ne!(
+            first,
+            session_hash(Some(&[1u8; 32]), "Is the Earth round?", "", &output)
+        );
+
+        // each turn commits to the ones before it
+        let second = session_hash(Some(&first), "Why?", "", &output);
+        let mut other_first = first;
+        other_first[0] ^= 1;
+        assert_ne!(
+            second,
+            session_hash(Some(&other_first), "Why?", "", &output)
+        );
+    }
+}
@@ -117,6 +117,44 @@ impl StacksEpochId {
     pub fn supports_pox_missed_slot_unlocks(&self) -> bool {
         self < &StacksEpochId::Epoch25
     }
+
+    /// Does this epoch accept versioned Infer transactions, with long prompts and typed chat
+    /// context?  Before 3.0, only the original Infer payload is valid.
+    pub fn supports_versioned_infer(&self) -> bool {
+        self >= &StacksEpochId::Epoch30
+    }
+
+    /// Are Infer transactions and `infer-result?` charged, against `cost_stx_infer` and
+    /// `cost_infer_result`?  Those cost functions are only defined in .costs-4, which is
+    /// instantiated at the start of 3.0.
+    pub fn charges_infer_costs(&self) -> bool {
+        self >= &StacksEpochId::Epoch30
+    }
+
+    /// Are committed inference results recorded in the Clarity DB, for Clarity 3 contracts to
+    /// read with `infer-result?`?
+    pub fn records_infer_results(&self) -> bool {
+        self >= &StacksEpochId::Epoch30
+    }
+
+    /// Can an Infer transaction pay its inference provider?  Before 3.0, a payment in the
+    /// context is ignored.
+    pub fn supports_infer_payments(&self) -> bool {
+        self >= &StacksEpochId::Epoch30
+    }
+
+    /// Can an Infer transaction continue the session of an earlier one?  From 3.0 on, every
+    /// committed inference also records its session hash.
+    pub fn supports_infer_sessions(&self) -> bool {
+        self >= &StacksEpochId::Epoch30
+    }
+
+    /// Does a block carry the outputs of its Infer transactions, in an InferOutputs transaction
+    /// that every node checks them against?  Before 3.0, each node committed the output hash it
+    /// found in its own InferDB.
+    pub fn carries_infer_outputs(&self) -> bool {
+        self >= &StacksEpochId::Epoch30
+    }
 }
 
 impl std::fmt::Display for StacksEpochId {
@@ -391,6 +391,15 @@ impl<'a, 'state, W: Write> HttpChunkedTransferWriter<'a, 'state, W> {
         to_copy
     }
 
+    /// Send out any buffered data as a chunk of its own, without ending the stream.  This is for
+    /// streams whose data trickles in, and which can't wait for a whole chunk's worth.
+    pub fn flush_buffered(&mut self) -> io::Result<()> {
+        if !self.state.corked && !self.state.chunk_buf.is_empty() {
+            self.flush_chunk()?;
+        }
+        Ok(())
+    }
+
     pub fn cork(&mut self) {
         // block future flushes from sending trailing empty chunks -- we're done sending
         self.state.corked = true;
@@ -83,6 +83,9 @@ pub enum ClientError {
     /// Stacks node does not support a feature we need
     #[error("Stacks node does not support a required feature: {0}")]
     UnsupportedStacksFeature(String),
+    /// An inference result was not attested by the node that should have produced it
+    #[error("Invalid inference attestation: {0}")]
+    InvalidInferAttestation(String),
 }
 
 /// Retry a function F with an exponential backoff and notification on transient failure
@@ -516,6 +519,7 @@ pub(crate) mod tests {
             sign_timeout: config.sign_timeout,
             tx_fee_ustx: config.tx_fee_ustx,
             db_path: config.db_path.clone(),
+            infer_verifier: config.infer_verifier.clone(),
         }
     }
 
@@ -18,7 +18,8 @@ use std::net::SocketAddr;
 use blockstack_lib::burnchains::Txid;
 use blockstack_lib::chainstate::nakamoto::NakamotoBlock;
 use blockstack_lib::chainstate::stacks::boot::{
-    NakamotoSignerEntry, SIGNERS_VOTING_FUNCTION_NAME, SIGNERS_VOTING_NAME,
+    NakamotoSignerEntry, RegisteredInferModel, INFER_MODELS_NAME, SIGNERS_VOTING_FUNCTION_NAME,
+    SIGNERS_VOTING_NAME,
 };
 use blockstack_lib::chainstate::stacks::{
     StacksTransaction, StacksTransactionSigner, TransactionAnchorMode, TransactionAuth,
@@ -27,6 +28,7 @@ use blockstack_lib::chainstate::stacks::{
 };
 use blockstack_lib::net::api::callreadonly::CallReadOnlyResponse;
 use blockstack_lib::net::api::getaccount::AccountEntryResponse;
+use blockstack_lib::net::api::getinferresult::RPCInferResultData;
 use blockstack_lib::net::api::getinfo::RPCPeerInfoData;
 use blockstack_lib::net::api::getpoxinfo::RPCPoxInfoData;
 use blockstack_lib::net::api::getstackers::GetStackersResponse;
@@ -34,6 +36,7 @@ use blockstack_lib::net::api::postblock_proposal::NakamotoBlockProposal;
 use blockstack_lib::util_lib::boot::{boot_code_addr, boot_code_id};
 use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
 use clarity::vm::{ClarityName, ContractName, Value as ClarityValue};
+use libllm::{InferResult, InferStatus};
 use reqwest::header::AUTHORIZATION;
 use serde_json::json;
 use slog::slog_debug;
@@ -42,8 +45,8 @@ use stacks_common::consts::{CHAIN_ID_MAINNET, CHAIN_ID_TESTNET};
 use stacks_common::debug;
 use stacks_common::types::chainstate::{StacksAddress, StacksPrivateKey, StacksPublicKey};
 use stacks_common::types::StacksEpochId;
+use stacks_common::util::hash::hex_bytes;
 use wsts::curve::point::{Compressed, Point};
-use libllm::InferResult;
 
 use crate::client::{retry_with_exponential_backoff, ClientError};
 use crate::config::GlobalConfig;
@@ -253,11 +256,13 @@ impl StacksClient {
         Ok(())
     }
 
-    /// Retrieve the infer result for the given transaction ID
+    /// Retrieve the infer result for the given transaction ID.  A successful result must carry
+    /// a valid attestation by `miner_key`.
     pub fn get_infer_res_with_retry(
         &self,
         txid: String,
-        miner_endpoint: String
+        miner_endpoint: String,
+        miner_key: &StacksPublicKey,
     ) -> Result<InferResult, ClientError> {
         let send_request = || {
             self.stacks_node_client
@@ -271,9 +276,64 @@ impl StacksClient {
             return Err(ClientError::RequestFailure(response.status()));
         }
         let result = response.json::<InferResult>()?;
+        if result.status == InferStatus::Success {
+            Self::check_infer_attestation(&result, miner_key)?;
+        }
         Ok(result)
     }
 
+    /// Retrieve the session hash the Infer transaction `txid` was committed with, as of this
+    /// signer's stacks node's chain tip.  None if it was not committed (with one).
+    pub fn get_committed_infer_session_hash(
+        &self,
+        txid: &str,
+    ) -> Result<Option<Vec<u8>>, ClientError> {
+        let send_request = || {
+            self.stacks_node_client
+                .get(self.infer_res_path(txid, &self.http_origin))
+                .send()
+                .map_err(backoff::Error::transient)
+        };
+
+        let response = retry_with_exponential_backoff(send_request)?;
+        if !response.status().is_success() {
+            return Err(ClientError::RequestFailure(response.status()));
+        }
+        let result = response.json::<RPCInferResultData>()?;
+        result
+            .session_hash
+            .map(|session_hash| {
+                hex_bytes(&session_hash).map_err(|e| {
+                    ClientError::UnexpectedResponseFormat(format!(
+                        "bad session hash of tx {txid}: {e}"
+                    ))
+                })
+            })
+            .transpose()
+    }
+
+    /// Check that an inference result is attested by `miner_key`
+    fn check_infer_attestation(
+        result: &InferResult,
+        miner_key: &StacksPublicKey,
+    ) -> Result<(), ClientError> {
+        let attestation = result.attestation.as_ref().ok_or_else(|| {
+            ClientError::InvalidInferAttestation(format!("tx {} is not attested", &result.txid))
+        })?;
+        let attester = attestation
+            .check_result(result)
+            .map_err(ClientError::InvalidInferAttestation)?;
+        if attester.to_bytes_compressed() != miner_key.to_bytes_compressed() {
+            return Err(ClientError::InvalidInferAttestation(format!(
+                "tx {} is attested by {}, not the miner {}",
+                &result.txid,
+                attester.to_hex(),
+                miner_key.to_hex()
+            )));
+        }
+        Ok(())
+    }
+
     /// Retrieve the approved DKG aggregate public key for the given reward cycle
     pub fn get_approved_aggregate_key(
         &self,
@@ -295,6 +355,32 @@ impl StacksClient {
         )
     }
 
+    /// Retrieve the model registered under `model_id` in the `.infer-models` boot contract
+    pub fn get_registered_infer_model(
+        &self,
+        model_id: &str,
+    ) -> Result<Option<RegisteredInferModel>, ClientError> {
+        let function_name = ClarityName::from("get-model");
+        let models_contract_id = boot_code_id(INFER_MODELS_NAME, self.mainnet);
+        let Ok(model_id_value) = ClarityValue::string_ascii_from_bytes(model_id.into()) else {
+            return Ok(None);
+        };
+        let value = self.read_only_contract_call(
+            &models_contract_id.issuer.into(),
+            &models_contract_id.name,
+            &function_name,
+            &[model_id_value],
+        )?;
+        let inner_data = value.expect_optional()?;
+        inner_data.map_or_else(
+            || Ok(None),
+            |model| {
+                let model = RegisteredInferModel::try_from_tuple(model_id, model.expect_tuple()?)?;
+                Ok(Some(model))
+            },
+        )
+    }
+
     /// Retrieve the current consumed weight for the given reward cycle and DKG round
     pub fn get_round_vote_weight(
         &self,
@@ -697,6 +783,8 @@ mod tests {
         NakamotoSignerEntry, PoxStartCycleInfo, RewardSet,
     };
     use blockstack_lib::chainstate::stacks::ThresholdSignature;
+    use clarity::vm::types::TupleData;
+    use libllm::{InferAttestation, InferParams};
     use rand::thread_rng;
     use rand_core::RngCore;
     use stacks_common::bitvec::BitVec;
@@ -859,6 +947,136 @@ mod tests {
         assert!(res.is_none());
     }
 
+    #[test]
+    fn get_registered_infer_model_should_succeed() {
+        let model = TupleData::from_data(vec![
+            (
+                "provider".into(),
+                ClarityValue::Principal(StacksAddress::burn_address(false).into()),
+            ),
+            (
+                "weights-digest".into(),
+                ClarityValue::string_ascii_from_bytes(b"sha256:01".to_vec()).unwrap(),
+            ),
+            (
+                "endpoint".into(),
+                ClarityValue::string_utf8_from_bytes(b"http://localhost:11434".to_vec()).unwrap(),
+            ),
+            ("max-tokens".into(), ClarityValue::UInt(1024)),
+            ("seeded".into(), ClarityValue::Bool(true)),
+            ("active".into(), ClarityValue::Bool(true)),
+            ("registered-at".into(), ClarityValue::UInt(7)),
+        ])
+        .unwrap();
+        let response =
+            build_read_only_response(&ClarityValue::some(ClarityValue::Tuple(model)).unwrap());
+        let mock = MockServerClient::new();
+        let h = spawn(move || mock.client.get_registered_infer_model("llama3.1"));
+        write_response(mock.server, response.as_bytes());
+        let model = h.join().unwrap().unwrap().unwrap();
+        assert_eq!(model.model_id, "llama3.1");
+        assert_eq!(model.weights_digest, "sha256:01");
+        assert_eq!(model.endpoint, "http://localhost:11434");
+        assert_eq!(model.max_tokens, 1024);
+        assert_eq!(model.registered_at, 7);
+
+        let response = build_read_only_response(&ClarityValue::none());
+        let mock = MockServerClient::new();
+        let h = spawn(move || mock.client.get_registered_infer_model("llama3.1"));
+        write_response(mock.server, response.as_bytes());
+        assert!(h.join().unwrap().unwrap().is_none());
+    }
+
+    #[test]
+    fn get_infer_res_checks_attestation() {
+        let miner_sk = StacksPrivateKey::new();
+        let miner_pk = StacksPublicKey::from_private(&miner_sk);
+        let params = InferParams {
+            model: Some("mock".to_string()),
+            ..InferParams::default()
+        };
+        let output_hash = libllm::output_hash("Yes.");
+        let result = |attester: Option<&StacksPrivateKey>| InferResult {
+            txid: "01".to_string(),
+            status: InferStatus::Success,
+            input: "Is the Earth round?".to_string(),
+            output: "Yes.".to_string(),
+            output_hash: output_hash.clone(),
+            params: Some(params.clone()),
+            attestation: attester.map(|sk| {
+                InferAttestation::sign(sk, "01", &params, &output_hash, 1_700_000_000).unwrap()
+            }),
+            session_context: None,
+            session_turns: None,
+        };
+        let fetch = |result: InferResult| {
+            let response = format!(
+                "HTTP/1.1 200 OK\n\n{}",
+                serde_json::to_string(&result).unwrap()
+            );
+            let mock = MockServerClient::new();
+            let endpoint = format!("http://{}", mock.server.local_addr().unwrap());
+            let miner_pk = miner_pk.clone();
+            let h = spawn(move || {
+                mock.client
+                    .get_infer_res_with_retry("01".to_string(), endpoint, &miner_pk)
+            });
+            write_response(mock.server, response.as_bytes());
+            h.join().unwrap()
+        };
+
+        let res = fetch(result(Some(&miner_sk))).unwrap();
+        assert_eq!(res.output, "Yes.");
+
+        // unattested, or attested by someone other than the miner
+        assert!(matches!(
+            fetch(result(None)),
+            Err(ClientError::InvalidInferAttestation(_))
+        ));
+        assert!(matches!(
+            fetch(result(Some(&StacksPrivateKey::new()))),
+            Err(ClientError::InvalidInferAttestation(_))
+        ));
+
+        // attested, but not to this output
+        let mut forged = result(Some(&miner_sk));
+        forged.output = "No.".to_string();
+        forged.output_hash = libllm::output_hash("No.");
+        assert!(matches!(
+            fetch(forged),
+            Err(ClientError::InvalidInferAttestation(_))
+        ));
+    }
+
+    #[test]
+    fn get_committed_infer_session_hash_should_succeed() {
+        let fetch = |session_hash: Option<&str>| {
+            let mut result = json!({
+                "txid": "01",
+                "status": "Success",
+                "input": "Why?",
+                "output": "Because.",
+                "output_hash": libllm::output_hash("Because."),
+            });
+            if let Some(session_hash) = session_hash {
+                result["session_hash"] = json!(session_hash);
+            }
+            let response = format!("HTTP/1.1 200 OK\n\n{result}");
+            let mock = MockServerClient::new();
+            let h = spawn(move || mock.client.get_committed_infer_session_hash("01"));
+            write_response(mock.server, response.as_bytes());
+            h.join().unwrap()
+        };
+
+        let session_hash = "ab".repeat(32);
+        assert_eq!(fetch(Some(&session_hash)).unwrap(), Some(vec![0xab; 32]));
+        assert_eq!(fetch(None).unwrap(), None);
+        assert!(matches!(
+            fetch(Some("not hex")),
+            Err(ClientError::UnexpectedResponseFormat(_))
+        ));
+    }
+
     #[test]
     fn parse_valid_aggregate_public_key_should_succeed() {
         let mock = MockServerClient::new();
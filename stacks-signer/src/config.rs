@@ -18,9 +18,14 @@ use std::fmt::Display;
 use std::fs;
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 use blockstack_lib::chainstate::stacks::TransactionVersion;
+use libllm::backend::{
+    InferenceBackend, OpenAiBackend, DEFAULT_API_KEY, DEFAULT_BASE_URL, DEFAULT_MODEL,
+    DEFAULT_TIMEOUT_MS,
+};
 use libsigner::SignerEntries;
 use serde::Deserialize;
 use stacks_common::address::{
@@ -32,6 +37,7 @@ use stacks_common::types::PrivateKey;
 use wsts::curve::scalar::Scalar;
 
 use crate::signer::SignerSlotID;
+use crate::verifier::{InferVerifier, VerificationStrategy, DEFAULT_SIMILARITY_THRESHOLD};
 
 const EVENT_TIMEOUT_MS: u64 = 5000;
 // Default transaction fee in microstacks (if unspecificed in the config file)
@@ -111,6 +117,66 @@ impl Network {
     }
 }
 
+/// How the signer verifies the inference results in proposed blocks
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferVerifierConfig {
+    /// The verification strategy to use
+    pub strategy: VerificationStrategy,
+    /// Base URL of the OpenAI-compatible endpoint used to re-run or judge inferences
+    pub base_url: String,
+    /// API key for the endpoint
+    pub api_key: String,
+    /// The model to run
+    pub model: String,
+    /// Digest of the model weights the endpoint serves, if pinned
+    pub model_digest: Option<String>,
+    /// The model to compute embeddings with, if not `model`
+    pub embedding_model: Option<String>,
+    /// The minimum cosine similarity accepted by the embedding strategy
+    pub similarity_threshold: f64,
+    /// Timeout for each request to the endpoint
+    pub timeout: Duration,
+}
+
+impl Default for InferVerifierConfig {
+    fn default() -> Self {
+        Self {
+            strategy: VerificationStrategy::ExactHash,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            api_key: DEFAULT_API_KEY.to_string(),
+            model: DEFAULT_MODEL.to_string(),
+            model_digest: None,
+            embedding_model: None,
+            similarity_threshold: DEFAULT_SIMILARITY_THRESHOLD,
+            timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+        }
+    }
+}
+
+impl InferVerifierConfig {
+    /// Instantiate the inference backend the verifier runs on
+    pub fn make_backend(&self) -> Arc<dyn InferenceBackend> {
+        let mut backend =
+            OpenAiBackend::new(&self.base_url, &self.api_key, &self.model, self.timeout);
+        if let Some(ref digest) = self.model_digest {
+            backend = backend.with_model_digest(digest);
+        }
+        if let Some(ref embedding_model) = self.embedding_model {
+            backend = backend.with_embedding_model(embedding_model);
+        }
+        Arc::new(backend)
+    }
+
+    /// Instantiate the verifier described by this config
+    pub fn make_verifier(&self) -> InferVerifier {
+        InferVerifier::from_strategy(
+            self.strategy,
+            self.make_backend(),
+            self.similarity_threshold,
+        )
+    }
+}
+
 /// The Configuration info needed for an individual signer per reward cycle
 #[derive(Debug, Clone)]
 pub struct SignerConfig {
@@ -148,6 +214,8 @@ pub struct SignerConfig {
     pub tx_fee_ustx: u64,
     /// The path to the signer's database file
     pub db_path: PathBuf,
+    /// How to verify the inference results in proposed blocks
+    pub infer_verifier: InferVerifierConfig,
 }
 
 /// The parsed configuration for the signer
@@ -183,6 +251,10 @@ pub struct GlobalConfig {
     pub auth_password: String,
     /// The path to the signer's database file
     pub db_path: PathBuf,
+    /// How to verify the inference results in proposed blocks
+    pub infer_verifier: InferVerifierConfig,
+    /// Where to serve Prometheus metrics, if at all
+    pub metrics_endpoint: Option<SocketAddr>,
 }
 
 /// Internal struct for loading up the config file
@@ -215,6 +287,26 @@ struct RawConfigFile {
     pub auth_password: String,
     /// The path to the signer's database file or :memory: for an in-memory database
     pub db_path: String,
+    /// How to verify inference results in proposed blocks. One of "exact_hash" (the default),
+    /// "embedding", or "judge".
+    pub infer_verifier: Option<String>,
+    /// The minimum cosine similarity the "embedding" verifier accepts
+    pub infer_similarity_threshold: Option<f64>,
+    /// Base URL of the OpenAI-compatible endpoint used to verify inferences
+    pub llm_base_url: Option<String>,
+    /// API key for the inference endpoint
+    pub llm_api_key: Option<String>,
+    /// The model to verify inferences with
+    pub llm_model: Option<String>,
+    /// Digest of the model weights the inference endpoint serves
+    pub llm_model_digest: Option<String>,
+    /// The model to compute embeddings with, if not `llm_model`
+    pub llm_embedding_model: Option<String>,
+    /// Timeout (in millisecs) for each request to the inference endpoint
+    pub llm_timeout_ms: Option<u64>,
+    /// Where to serve Prometheus metrics. Only used if the signer was built with the
+    /// `monitoring_prom` feature.
+    pub metrics_endpoint: Option<String>,
 }
 
 impl RawConfigFile {
@@ -292,6 +384,57 @@ impl TryFrom<RawConfigFile> for GlobalConfig {
         let sign_timeout = raw_data.sign_timeout_ms.map(Duration::from_millis);
         let db_path = raw_data.db_path.into();
 
+        let metrics_endpoint = match raw_data.metrics_endpoint {
+            Some(metrics_endpoint) => Some(
+                metrics_endpoint
+                    .to_socket_addrs()
+                    .map_err(|_| {
+                        ConfigError::BadField(
+                            "metrics_endpoint".to_string(),
+                            metrics_endpoint.clone(),
+                        )
+                    })?
+                    .next()
+                    .ok_or_else(|| {
+                        ConfigError::BadField(
+                            "metrics_endpoint".to_string(),
+                            metrics_endpoint.clone(),
+                        )
+                    })?,
+            ),
+            None => None,
+        };
+
+        let mut infer_verifier = InferVerifierConfig::default();
+        if let Some(strategy) = raw_data.infer_verifier {
+            infer_verifier.strategy = strategy
+                .parse()
+                .map_err(|_| ConfigError::BadField("infer_verifier".to_string(), strategy))?;
+        }
+        if let Some(threshold) = raw_data.infer_similarity_threshold {
+            if !(threshold > 0.0 && threshold <= 1.0) {
+                return Err(ConfigError::BadField(
+                    "infer_similarity_threshold".to_string(),
+                    threshold.to_string(),
+                ));
+            }
+            infer_verifier.similarity_threshold = threshold;
+        }
+        if let Some(base_url) = raw_data.llm_base_url {
+            infer_verifier.base_url = base_url;
+        }
+        if let Some(api_key) = raw_data.llm_api_key {
+            infer_verifier.api_key = api_key;
+        }
+        if let Some(model) = raw_data.llm_model {
+            infer_verifier.model = model;
+        }
+        infer_verifier.model_digest = raw_data.llm_model_digest;
+        infer_verifier.embedding_model = raw_data.llm_embedding_model;
+        if let Some(timeout_ms) = raw_data.llm_timeout_ms {
+            infer_verifier.timeout = Duration::from_millis(timeout_ms);
+        }
+
         Ok(Self {
             node_host: raw_data.node_host,
             endpoint,
@@ -308,6 +451,8 @@ impl TryFrom<RawConfigFile> for GlobalConfig {
             tx_fee_ustx: raw_data.tx_fee_ustx.unwrap_or(TX_FEE_USTX),
             auth_password: raw_data.auth_password,
             db_path,
+            infer_verifier,
+            metrics_endpoint,
         })
     }
 }
@@ -347,6 +492,7 @@ Public key: {public_key}
 Network: {network}
 Database path: {db_path}
 DKG transaction fee: {tx_fee} uSTX
+Inference verifier: {infer_strategy} ({infer_model} at {infer_base_url})
 "#,
             node_host = self.node_host,
             endpoint = self.endpoint,
@@ -354,7 +500,10 @@ DKG transaction fee: {tx_fee} uSTX
             public_key = StacksPublicKey::from_private(&self.stacks_private_key).to_hex(),
             network = self.network,
             db_path = self.db_path.to_str().unwrap_or_default(),
-            tx_fee = tx_fee
+            tx_fee = tx_fee,
+            infer_strategy = self.infer_verifier.strategy,
+            infer_model = self.infer_verifier.model,
+            infer_base_url = self.infer_verifier.base_url,
         )
     }
 }
@@ -462,8 +611,68 @@ Public key: 03bc489f27da3701d9f9e577c88de5567cf4023111b7577042d55cde4d823a3505
 Network: testnet
 Database path: :memory:
 DKG transaction fee: 0.01 uSTX
+Inference verifier: exact_hash (llama3.1 at http://localhost:11434/v1/)
 "#
             )
         );
     }
+
+    #[test]
+    fn test_infer_verifier_config() {
+        let config = GlobalConfig::load_from_file("./src/tests/conf/signer-0.toml").unwrap();
+        assert_eq!(config.infer_verifier, InferVerifierConfig::default());
+
+        let config_toml = fs::read_to_string("./src/tests/conf/signer-0.toml").unwrap();
+        let config = GlobalConfig::load_from_str(&format!(
+            r#"{config_toml}
+infer_verifier = "embedding"
+infer_similarity_threshold = 0.9
+llm_base_url = "http://127.0.0.1:8000/v1"
+llm_model = "qwen2"
+llm_embedding_model = "nomic-embed-text"
+llm_timeout_ms = 5000
+"#
+        ))
+        .unwrap();
+        let verifier = config.infer_verifier;
+        assert_eq!(verifier.strategy, VerificationStrategy::Embedding);
+        assert_eq!(verifier.similarity_threshold, 0.9);
+        assert_eq!(verifier.base_url, "http://127.0.0.1:8000/v1");
+        assert_eq!(verifier.api_key, DEFAULT_API_KEY);
+        assert_eq!(verifier.model, "qwen2");
+        assert_eq!(verifier.model_digest, None);
+        assert_eq!(verifier.embedding_model, Some("nomic-embed-text".to_string()));
+        assert_eq!(verifier.timeout, Duration::from_millis(5000));
+        assert_eq!(
+            verifier.make_verifier().strategy(),
+            VerificationStrategy::Embedding
+        );
+
+        assert!(GlobalConfig::load_from_str(&format!(
+            "{config_toml}\ninfer_verifier = \"llm\"\n"
+        ))
+        .is_err());
+        assert!(GlobalConfig::load_from_str(&format!(
+            "{config_toml}\ninfer_similarity_threshold = 1.5\n"
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_metrics_endpoint_config() {
+        let config = GlobalConfig::load_from_file("./src/tests/conf/signer-0.toml").unwrap();
+        assert_eq!(config.metrics_endpoint, None);
+
+        let config_toml = fs::read_to_string("./src/tests/conf/signer-0.toml").unwrap();
+        let config = GlobalConfig::load_from_str(&format!(
+            "{config_toml}\nmetrics_endpoint = \"127.0.0.1:9154\"\n"
+        ))
+        .unwrap();
+        assert_eq!(config.metrics_endpoint, Some("127.0.0.1:9154".parse().unwrap()));
+
+        assert!(GlobalConfig::load_from_str(&format!(
+            "{config_toml}\nmetrics_endpoint = \"not an address\"\n"
+        ))
+        .is_err());
+    }
 }
@@ -28,9 +28,13 @@ pub mod client;
 pub mod config;
 /// The coordinator selector for the signer
 pub mod coordinator;
+/// Prometheus metrics for the signer
+pub mod monitoring;
 /// The primary runloop for the signer
 pub mod runloop;
 /// The signer module for processing events
 pub mod signer;
 /// The state module for the signer
 pub mod signerdb;
+/// The verifier for inference results in proposed blocks
+pub mod verifier;
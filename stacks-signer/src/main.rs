@@ -49,6 +49,7 @@ use stacks_signer::cli::{
     GetLatestChunkArgs, PutChunkArgs, RunDkgArgs, RunSignerArgs, SignArgs, StackerDBArgs,
 };
 use stacks_signer::config::{build_signer_config_tomls, GlobalConfig};
+use stacks_signer::monitoring::start_serving_monitoring_metrics;
 use stacks_signer::runloop::{RunLoop, RunLoopCommand};
 use stacks_signer::signer::Command as SignerCommand;
 use tracing_subscriber::prelude::*;
@@ -86,6 +87,11 @@ fn spawn_running_signer(path: &PathBuf) -> SpawnedSigner {
     let config = GlobalConfig::try_from(path).unwrap();
     let endpoint = config.endpoint;
     info!("Starting signer with config: {}", config);
+    if let Some(metrics_endpoint) = config.metrics_endpoint {
+        if let Err(e) = start_serving_monitoring_metrics(metrics_endpoint) {
+            error!("Failed to start serving metrics at {metrics_endpoint}: {e:?}");
+        }
+    }
     let (cmd_send, cmd_recv) = channel();
     let (res_send, res_recv) = channel();
     let ev = SignerEventReceiver::new(config.network.is_mainnet());
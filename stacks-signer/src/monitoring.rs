@@ -0,0 +1,86 @@
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::net::SocketAddr;
+
+pub use blockstack_lib::monitoring::increment_infer_check_block_rejections_counter;
+use slog::slog_warn;
+use stacks_common::warn;
+
+/// Serve the signer's Prometheus metrics at `bind_address`, from a thread of their own.
+#[cfg(feature = "monitoring_prom")]
+pub fn start_serving_monitoring_metrics(bind_address: SocketAddr) -> std::io::Result<()> {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::Duration;
+
+    use blockstack_lib::prometheus::{gather, Encoder, TextEncoder};
+    use slog::{slog_debug, slog_info};
+    use stacks_common::{debug, info};
+
+    /// How long a client may take to send its request or read the answer, so that a slow or idle
+    /// one can't hold up the others
+    const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn serve(mut stream: TcpStream) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+        // whatever was asked for, the answer is the metrics
+        let mut request = [0u8; 1024];
+        let _ = stream.read(&mut request)?;
+
+        let encoder = TextEncoder::new();
+        let mut body = vec![];
+        encoder
+            .encode(&gather(), &mut body)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            encoder.format_type(),
+            body.len()
+        )?;
+        stream.write_all(&body)?;
+        stream.flush()
+    }
+
+    let listener = TcpListener::bind(bind_address)?;
+    info!(
+        "Prometheus monitoring: server listening on http://{}",
+        listener.local_addr()?
+    );
+    thread::Builder::new()
+        .name("signer-metrics".into())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                let result = stream.and_then(|stream| {
+                    debug!("Handle Prometheus polling ({:?})", stream.peer_addr());
+                    serve(stream)
+                });
+                if let Err(e) = result {
+                    warn!("Prometheus monitoring: failed to serve metrics: {e:?}");
+                }
+            }
+        })?;
+    Ok(())
+}
+
+/// Serve the signer's Prometheus metrics at `bind_address`, from a thread of their own.
+#[cfg(not(feature = "monitoring_prom"))]
+pub fn start_serving_monitoring_metrics(bind_address: SocketAddr) -> std::io::Result<()> {
+    warn!("Attempted to start monitoring service at bind_address = {bind_address}, but stacks-signer was built without `monitoring_prom` feature.");
+    Ok(())
+}
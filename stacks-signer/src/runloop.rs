@@ -214,6 +214,7 @@ impl RunLoop {
             sign_timeout: self.config.sign_timeout,
             tx_fee_ustx: self.config.tx_fee_ustx,
             db_path: self.config.db_path.clone(),
+            infer_verifier: self.config.infer_verifier.clone(),
         })
     }
 
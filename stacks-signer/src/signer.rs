@@ -13,25 +13,26 @@
 //
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
 use std::sync::mpsc::Sender;
 use std::time::Instant;
 
+use blockstack_lib::burnchains::Txid;
 use blockstack_lib::chainstate::burn::ConsensusHashExtensions;
 use blockstack_lib::chainstate::nakamoto::signer_set::NakamotoSigners;
 use blockstack_lib::chainstate::nakamoto::{NakamotoBlock, NakamotoBlockVote};
 use blockstack_lib::chainstate::stacks::boot::SIGNERS_VOTING_FUNCTION_NAME;
-use blockstack_lib::chainstate::stacks::{StacksTransaction, TransactionPayload};
+use blockstack_lib::chainstate::stacks::{InferOutput, StacksTransaction, TransactionPayload};
 use blockstack_lib::net::api::postblock_proposal::{BlockValidateResponse, ValidateRejectCode};
 use hashbrown::HashSet;
+use libllm::{InferContext, InferResult};
 use libsigner::{
     BlockProposalSigners, BlockRejection, BlockResponse, MessageSlotID, RejectCode, SignerEvent,
     SignerMessage,
 };
 use serde_derive::{Deserialize, Serialize};
 use slog::{slog_debug, slog_error, slog_info, slog_warn};
-use tokio::runtime::Runtime;
 use stacks_common::codec::{read_next, StacksMessageCodec};
 use stacks_common::types::chainstate::{ConsensusHash, StacksAddress};
 use stacks_common::types::StacksEpochId;
@@ -53,7 +54,9 @@ use wsts::v2;
 use crate::client::{retry_with_exponential_backoff, ClientError, StackerDB, StacksClient};
 use crate::config::SignerConfig;
 use crate::coordinator::CoordinatorSelector;
+use crate::monitoring::increment_infer_check_block_rejections_counter;
 use crate::signerdb::SignerDb;
+use crate::verifier::{check_carried_output, task_session_hash, InferTask, InferVerifier};
 
 /// The signer StackerDB slot ID, purposefully wrapped to prevent conflation with SignerID
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, PartialOrd, Ord)]
@@ -172,6 +175,8 @@ pub struct Signer {
     pub db_path: PathBuf,
     /// SignerDB for state management
     pub signer_db: SignerDb,
+    /// Verifies the inference results in proposed blocks
+    pub infer_verifier: InferVerifier,
 }
 
 impl std::fmt::Display for Signer {
@@ -301,6 +306,7 @@ impl From<SignerConfig> for Signer {
             miner_key: None,
             db_path: signer_config.db_path,
             signer_db,
+            infer_verifier: signer_config.infer_verifier.make_verifier(),
         }
     }
 }
@@ -796,6 +802,28 @@ impl Signer {
         Some(block_info)
     }
 
+    /// If an Infer transaction names a model, check that the miner ran the weights registered
+    /// for it in the `.infer-models` boot contract
+    fn check_infer_model(
+        stacks_client: &StacksClient,
+        context: &str,
+        infer_res: &InferResult,
+    ) -> Result<(), String> {
+        let requested = InferContext::parse(context).params;
+        let Some(model_id) = requested.model else {
+            return Ok(());
+        };
+        let model = stacks_client
+            .get_registered_infer_model(&model_id)
+            .map_err(|e| format!("failed to look up model {model_id}: {e:?}"))?
+            .ok_or_else(|| format!("model {model_id} is not registered"))?;
+        let recorded = infer_res
+            .params
+            .as_ref()
+            .ok_or_else(|| "the miner did not record the parameters it ran with".to_string())?;
+        model.check_ran(recorded)
+    }
+
     /// Verify the transactions in a block are as expected
     fn verify_block_transactions(
         &mut self,
@@ -804,49 +832,86 @@ impl Signer {
     ) -> bool {
         let mut is_infer_valid = true;
         let sig_hash = block.header.signer_signature_hash();
+        // inference outputs must be attested by the miner that proposed the block
+        let miner_key = block.header.recover_miner_pk();
+        // session hashes of the Infer transactions verified so far in this block, which later
+        // ones may continue
+        let mut session_hashes: HashMap<String, [u8; 32]> = HashMap::new();
+        // the outputs the block commits its Infer transactions to
+        let carried_outputs: HashMap<Txid, &InferOutput> = block
+            .txs
+            .iter()
+            .filter_map(|tx| match tx.payload {
+                TransactionPayload::InferOutputs(ref outputs) => Some(outputs),
+                _ => None,
+            })
+            .flatten()
+            .map(|carried| (carried.txid, carried))
+            .collect();
         match  self.signer_db.miner_endpoint_lookup(self.reward_cycle, &sig_hash) {
             Ok(Some(miner_endpoint)) => {
                 for tx in block.txs.iter() {
-                    match &tx.payload {
-                        TransactionPayload::Infer(from, input, context) => {
+                    match tx.payload.infer_request() {
+                        Some(request) => {
                             let txid = tx.txid().to_string();
-                            let infer_res = stacks_client
-                                .get_infer_res_with_retry(txid.clone(), miner_endpoint.clone());
+                            let Some(ref miner_key) = miner_key else {
+                                warn!("{self}: Failed to recover the miner's key for block {sig_hash}, so cannot check infer attestations");
+                                is_infer_valid = false;
+                                break;
+                            };
+                            let infer_res = stacks_client.get_infer_res_with_retry(
+                                txid.clone(),
+                                miner_endpoint.clone(),
+                                miner_key,
+                            );
                             match infer_res {
                                 Ok(infer_res) => {
-                                    info!("Infer res for tx {txid}: {infer_res:?}");
-                                    if !matches!(infer_res.status, libllm::InferStatus::Success) {
-                                        warn!("Infer res isn't ok for tx {txid}: {infer_res:?}");
+                                    debug!("{self}: Infer res for tx {txid}: {infer_res:?}");
+                                    if let Err(reason) = check_carried_output(carried_outputs.get(&tx.txid()).copied(), &infer_res) {
+                                        warn!("{self}: Infer check failed for tx {txid}: {reason}");
                                         is_infer_valid = false;
                                         break;
                                     }
-                                    let output = infer_res.output;
-                                    let user_input = input.to_string();
-                                    let context_str = context.to_string();
-                                    let chat_completion_message = serde_json::from_str(context_str.as_str()).unwrap_or(vec![]);
-                                    let context_messages = if chat_completion_message.is_empty() {
-                                        None
-                                    } else {
-                                        Some(chat_completion_message)
+                                    if let Err(reason) = Self::check_infer_model(stacks_client, &request.context, &infer_res) {
+                                        warn!("{self}: Infer check failed for tx {txid}: {reason}");
+                                        is_infer_valid = false;
+                                        break;
+                                    }
+                                    let task = InferTask {
+                                        txid: txid.clone(),
+                                        input: request.user_input,
+                                        context: request.context,
                                     };
-                                    let rt = Runtime::new().unwrap();
-
-                                    let result = rt.block_on(async {
-                                        let infer_check_res = libllm::infer_check(user_input.as_str(),
-                                                                                  output.as_str(),
-                                                                                  context_messages).await.unwrap_or(libllm::INFER_CHECK_FAIL);
-                                        if infer_check_res == libllm::INFER_CHECK_FAIL {
-                                            warn!("Infer check failed for tx {txid}");
-                                            false
-                                        } else {
-                                            info!("Infer check passed for tx {txid}");
-                                            true
-                                        }
-                                    });
-                                    if !result {
+                                    let parent_session_hash = match InferContext::parse(&task.context).parent {
+                                        Some(parent) => match session_hashes.get(&parent) {
+                                            Some(session_hash) => Some(session_hash.to_vec()),
+                                            None => match stacks_client.get_committed_infer_session_hash(&parent) {
+                                                Ok(session_hash) => session_hash,
+                                                Err(e) => {
+                                                    error!("{self}: Failed to get the committed session hash of tx {parent}: {e:?}");
+                                                    is_infer_valid = false;
+                                                    break;
+                                                }
+                                            },
+                                        },
+                                        None => None,
+                                    };
+                                    let verdict = self.infer_verifier.verify(&task, &infer_res, parent_session_hash.as_deref());
+                                    if verdict.passed {
+                                        info!("{self}: Infer check passed for tx {txid}"; "strategy" => %verdict.strategy, "score" => ?verdict.score);
+                                    } else {
+                                        warn!("{self}: Infer check failed for tx {txid}: {}", verdict.reason; "strategy" => %verdict.strategy, "score" => ?verdict.score);
+                                    }
+                                    if let Err(e) = self.signer_db.insert_infer_verdict(self.reward_cycle, &sig_hash, &verdict) {
+                                        error!("{self}: Failed to record infer verdict for tx {txid}: {e:?}");
+                                    }
+                                    if !verdict.passed {
                                         is_infer_valid = false;
                                         break;
                                     }
+                                    if let Some(session_hash) = task_session_hash(&task, &infer_res, parent_session_hash.as_deref()) {
+                                        session_hashes.insert(txid.clone(), session_hash);
+                                    }
                                 }
                                 Err(e) => {
                                     error!("{self}: Failed to get infer res for tx {txid}: {e:?}");
@@ -855,7 +920,7 @@ impl Signer {
                                 }
                             }
                         }
-                        _ => {} // just ignore non-infer tx
+                        None => {} // just ignore non-infer tx
                     }
                 }
             }
@@ -869,6 +934,7 @@ impl Signer {
             }
         }
         if !is_infer_valid {
+            increment_infer_check_block_rejections_counter();
             debug!("{self}: Broadcasting a block rejection due to failed infer check...");
             let block_rejection = BlockRejection::new(
                 block.header.signer_signature_hash(),
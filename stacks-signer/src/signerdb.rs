@@ -17,15 +17,16 @@
 use std::path::Path;
 
 use blockstack_lib::util_lib::db::{
-    query_row, sqlite_open, table_exists, u64_to_sql, Error as DBError,
+    query_row, query_rows, sqlite_open, table_exists, u64_to_sql, Error as DBError, FromRow,
 };
-use rusqlite::{params, Connection, Error as SqliteError, OpenFlags, NO_PARAMS};
+use rusqlite::{params, Connection, Error as SqliteError, OpenFlags, Row, NO_PARAMS};
 use slog::slog_debug;
 use stacks_common::debug;
 use stacks_common::util::hash::Sha512Trunc256Sum;
 use wsts::traits::SignerState;
 
 use crate::signer::BlockInfo;
+use crate::verifier::InferVerdict;
 
 /// This struct manages a SQLite database connection
 /// for the signer.
@@ -57,6 +58,31 @@ CREATE TABLE IF NOT EXISTS blocks_miner_endpoint (
     PRIMARY KEY (reward_cycle, signer_signature_hash)
 )";
 
+const CREATE_INFER_VERDICTS_TABLE: &'static str = "
+CREATE TABLE IF NOT EXISTS infer_verdicts (
+    reward_cycle INTEGER NOT NULL,
+    signer_signature_hash TEXT NOT NULL,
+    txid TEXT NOT NULL,
+    strategy TEXT NOT NULL,
+    passed INTEGER NOT NULL,
+    score REAL,
+    reason TEXT NOT NULL,
+    PRIMARY KEY (reward_cycle, signer_signature_hash, txid)
+)";
+
+impl FromRow<InferVerdict> for InferVerdict {
+    fn from_row<'a>(row: &'a Row) -> Result<InferVerdict, DBError> {
+        let strategy: String = row.get("strategy")?;
+        Ok(InferVerdict {
+            txid: row.get("txid")?,
+            strategy: strategy.parse().map_err(|_| DBError::ParseError)?,
+            passed: row.get("passed")?,
+            score: row.get("score")?,
+            reason: row.get("reason")?,
+        })
+    }
+}
+
 impl SignerDb {
     /// Create a new `SignerState` instance.
     /// This will create a new SQLite database at the given path
@@ -84,6 +110,10 @@ impl SignerDb {
             self.db.execute(CREATE_BLOCKS_MINER_ENDPOINT_TABLE, NO_PARAMS)?;
         }
 
+        if !table_exists(&self.db, "infer_verdicts")? {
+            self.db.execute(CREATE_INFER_VERDICTS_TABLE, NO_PARAMS)?;
+        }
+
         Ok(())
     }
 
@@ -215,6 +245,47 @@ impl SignerDb {
         )?;
         Ok(())
     }
+
+    /// Fetch the inference verdicts recorded for the given block, ordered by txid
+    /// `hash` is the `signer_signature_hash` of the block.
+    pub fn infer_verdicts_lookup(
+        &self,
+        reward_cycle: u64,
+        hash: &Sha512Trunc256Sum,
+    ) -> Result<Vec<InferVerdict>, DBError> {
+        query_rows(
+            &self.db,
+            "SELECT txid, strategy, passed, score, reason FROM infer_verdicts WHERE reward_cycle = ? AND signer_signature_hash = ? ORDER BY txid",
+            params![&u64_to_sql(reward_cycle)?, hash.to_string()],
+        )
+    }
+
+    /// Record the verdict on one of the given block's inference results
+    /// `hash` is the `signer_signature_hash` of the block.
+    pub fn insert_infer_verdict(
+        &mut self,
+        reward_cycle: u64,
+        hash: &Sha512Trunc256Sum,
+        verdict: &InferVerdict,
+    ) -> Result<(), DBError> {
+        debug!(
+            "Inserting infer verdict: reward_cycle = {reward_cycle}, sighash = {hash}, txid = {}, strategy = {}, passed = {}",
+            verdict.txid, verdict.strategy, verdict.passed
+        );
+        self.db.execute(
+            "INSERT OR REPLACE INTO infer_verdicts (reward_cycle, signer_signature_hash, txid, strategy, passed, score, reason) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                &u64_to_sql(reward_cycle)?,
+                hash.to_string(),
+                &verdict.txid,
+                verdict.strategy.to_string(),
+                verdict.passed,
+                verdict.score,
+                &verdict.reason
+            ],
+        )?;
+        Ok(())
+    }
 }
 
 fn try_deserialize<T>(s: Option<String>) -> Result<Option<T>, DBError>
@@ -228,6 +299,7 @@ where
 }
 
 #[cfg(test)]
+/// Create a fresh signer DB at `db_path`, removing any existing one
 pub fn test_signer_db(db_path: &str) -> SignerDb {
     use std::fs;
 
@@ -257,6 +329,7 @@ mod tests {
     use wsts::traits::PartyState;
 
     use super::*;
+    use crate::verifier::VerificationStrategy;
 
     fn _wipe_db(db_path: &PathBuf) {
         if fs::metadata(db_path).is_ok() {
@@ -397,6 +470,58 @@ mod tests {
         assert_eq!(block_info.vote, Some(vote));
     }
 
+    #[test]
+    fn test_infer_verdicts() {
+        let db_path = tmp_db_path();
+        let mut db = SignerDb::new(db_path).expect("Failed to create signer db");
+        let reward_cycle = 7;
+        let (_, block) = create_block();
+        let hash = block.header.signer_signature_hash();
+
+        let passed = InferVerdict {
+            txid: "01".to_string(),
+            strategy: VerificationStrategy::Embedding,
+            passed: true,
+            score: Some(0.97),
+            reason: "similarity 0.9700 >= threshold 0.95".to_string(),
+        };
+        let failed = InferVerdict {
+            txid: "02".to_string(),
+            strategy: VerificationStrategy::ExactHash,
+            passed: false,
+            score: None,
+            reason: "re-execution produced output hash 00".to_string(),
+        };
+        db.insert_infer_verdict(reward_cycle, &hash, &failed)
+            .expect("Unable to insert verdict into db");
+        db.insert_infer_verdict(reward_cycle, &hash, &passed)
+            .expect("Unable to insert verdict into db");
+
+        assert_eq!(
+            db.infer_verdicts_lookup(reward_cycle, &hash).unwrap(),
+            vec![passed.clone(), failed]
+        );
+        assert!(db
+            .infer_verdicts_lookup(reward_cycle + 1, &hash)
+            .unwrap()
+            .is_empty());
+
+        // re-verifying a result replaces its verdict
+        let rejudged = InferVerdict {
+            txid: "02".to_string(),
+            strategy: VerificationStrategy::Judge,
+            passed: true,
+            score: Some(0.8),
+            reason: "answers it".to_string(),
+        };
+        db.insert_infer_verdict(reward_cycle, &hash, &rejudged)
+            .expect("Unable to insert verdict into db");
+        assert_eq!(
+            db.infer_verdicts_lookup(reward_cycle, &hash).unwrap(),
+            vec![passed, rejudged]
+        );
+    }
+
     #[test]
     fn test_write_signer_state() {
         let db_path = tmp_db_path();
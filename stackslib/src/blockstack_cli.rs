@@ -32,19 +32,21 @@ use blockstack_lib::burnchains::bitcoin::address::{
 };
 use blockstack_lib::burnchains::Address;
 use blockstack_lib::chainstate::stacks::{
-    StacksBlock, StacksBlockHeader, StacksMicroblock, StacksPrivateKey, StacksPublicKey,
-    StacksTransaction, StacksTransactionSigner, TokenTransferMemo, TransactionAnchorMode,
-    TransactionAuth, TransactionContractCall, TransactionPayload, TransactionSmartContract,
-    TransactionSpendingCondition, TransactionVersion, C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
-    C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+    InferRequest, StacksBlock, StacksBlockHeader, StacksMicroblock, StacksPrivateKey,
+    StacksPublicKey, StacksTransaction, StacksTransactionSigner, TokenTransferMemo,
+    TransactionAnchorMode, TransactionAuth, TransactionContractCall, TransactionInfer,
+    TransactionPayload, TransactionSmartContract, TransactionSpendingCondition, TransactionVersion,
+    C32_ADDRESS_VERSION_MAINNET_SINGLESIG, C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
 };
 use blockstack_lib::clarity_cli::vm_execute;
 use blockstack_lib::core::{CHAIN_ID_MAINNET, CHAIN_ID_TESTNET};
 use blockstack_lib::net::Error as NetError;
-use blockstack_lib::util_lib::strings::StacksString;
+use blockstack_lib::util_lib::strings::{InferLPString, StacksString};
 use clarity::vm::errors::{Error as ClarityError, RuntimeErrorType};
 use clarity::vm::types::PrincipalData;
 use clarity::vm::{ClarityName, ClarityVersion, ContractName, Value};
+use libllm::InferContext;
+use serde_json::json;
 use stacks_common::address::{b58, AddressHashMode};
 use stacks_common::codec::{Error as CodecError, StacksMessageCodec};
 use stacks_common::types::chainstate::StacksAddress;
@@ -62,6 +64,7 @@ This CLI has these methods:
   contract-call      used to generate and sign a contract-call transaction
   generate-sk        used to generate a secret key for transaction signing
   token-transfer     used to generate and sign a transfer transaction
+  infer              used to generate and sign an Infer transaction
   addresses          used to get both Bitcoin and Stacks addresses from a private key
   decode-tx          used to decode a hex-encoded transaction into a human-readable representation
   decode-header      used to decode a hex-encoded Stacks header into a human-readable representation
@@ -131,6 +134,34 @@ is that the miner chooses, but you can decide which with the following options:
   --block-only       indicates to mine this transaction only in a block
 ";
 
+const INFER_USAGE: &str = "blockstack-cli (options) infer [origin-secret-key-hex] [fee-rate] [nonce] [prompt] [context-file]
+
+The infer command generates and signs an Infer transaction, which asks for [prompt] to be run
+through a language model, and commits to its output. If successful, this command outputs the
+hex string encoding of the transaction to stdout, and exits with code 0.
+
+The context file, if given, is JSON with the chat messages that precede the prompt and the
+inference parameters (or `-` to read it from stdin), e.g.
+
+   {\"messages\": [{\"role\": \"system\", \"content\": \"be brief\"}],
+    \"params\": {\"seed\": 42, \"temperature\": 0.0, \"max_tokens\": 64}}
+
+To continue the conversation of an earlier Infer transaction, give its txid as the context's
+\"parent\"; the node runs the prompt after the conversation so far.
+
+By default, this generates a versioned Infer transaction, which can be mined from epoch 3.0 on.
+The original Infer transaction, whose prompt and context are each at most 128 bytes, can be
+generated instead with the following option:
+
+  --legacy           indicates to generate the original Infer transaction
+
+An Infer transaction can be mined in a Stacks block, a Stacks microblock, or either.  The default
+is that the miner chooses, but you can decide which with the following options:
+
+  --microblock-only  indicates to mine this transaction only in a microblock
+  --block-only       indicates to mine this transaction only in a block
+";
+
 const GENERATE_USAGE: &str = "blockstack-cli (options) generate-sk
 
 This method generates a secret key, outputting the hex encoding of the
@@ -569,6 +600,81 @@ fn handle_token_transfer(
     Ok(to_hex(&signed_tx_bytes))
 }
 
+fn handle_infer(
+    args_slice: &[String],
+    version: TransactionVersion,
+    chain_id: u32,
+) -> Result<String, CliError> {
+    let mut args = args_slice.to_vec();
+    if args.len() >= 1 && args[0] == "-h" {
+        return Err(CliError::Message(format!("USAGE:\n {}", INFER_USAGE)));
+    }
+    let legacy = if let Some(ix) = args.iter().position(|x| x == "--legacy") {
+        args.remove(ix);
+        true
+    } else {
+        false
+    };
+    let anchor_mode = parse_anchor_mode(&mut args, INFER_USAGE)?;
+    if args.len() != 4 && args.len() != 5 {
+        return Err(CliError::Message(format!(
+            "Incorrect argument count supplied \n\nUSAGE:\n {}",
+            INFER_USAGE
+        )));
+    }
+
+    let sk_origin = StacksPrivateKey::from_hex(&args[0])?;
+    let tx_fee = args[1].parse()?;
+    let nonce = args[2].parse()?;
+    let prompt = &args[3];
+    let context = match args.get(4).map(|s| s.as_str()) {
+        Some("-") => {
+            let mut buffer = String::new();
+            io::stdin().read_to_string(&mut buffer)?;
+            buffer
+        }
+        Some(context_file) => fs::read_to_string(context_file)?,
+        None => String::new(),
+    };
+
+    let pk_origin = StacksPublicKey::from_private(&sk_origin);
+    let from = PrincipalData::from(StacksAddress::p2pkh(
+        version == TransactionVersion::Mainnet,
+        &pk_origin,
+    ));
+    let payload = if legacy {
+        let user_input = InferLPString::try_from(prompt.as_str()).map_err(CliError::Message)?;
+        let context = InferLPString::try_from(context.trim()).map_err(CliError::Message)?;
+        TransactionPayload::Infer(from, user_input, context)
+    } else {
+        let infer_context = InferContext::try_parse(&context).map_err(CliError::Message)?;
+        let infer = TransactionInfer::from_infer_context(from, prompt, &infer_context)
+            .map_err(CliError::Message)?;
+        TransactionPayload::VersionedInfer(infer)
+    };
+    payload
+        .infer_request()
+        .expect("BUG: Infer payload without an Infer request")
+        .validate()
+        .map_err(|e| CliError::Message(format!("Invalid Infer request: {}", e)))?;
+
+    let mut unsigned_tx =
+        make_standard_single_sig_tx(version, chain_id, payload, &pk_origin, nonce, tx_fee);
+    unsigned_tx.anchor_mode = anchor_mode;
+
+    let mut unsigned_tx_bytes = vec![];
+    unsigned_tx
+        .consensus_serialize(&mut unsigned_tx_bytes)
+        .expect("FATAL: invalid transaction");
+    let signed_tx = sign_transaction_single_sig_standard(&to_hex(&unsigned_tx_bytes), &sk_origin)?;
+
+    let mut signed_tx_bytes = vec![];
+    signed_tx
+        .consensus_serialize(&mut signed_tx_bytes)
+        .expect("FATAL: invalid signed transaction");
+    Ok(to_hex(&signed_tx_bytes))
+}
+
 fn generate_secret_key(args: &[String], version: TransactionVersion) -> Result<String, CliError> {
     if args.len() >= 1 && args[0] == "-h" {
         return Err(CliError::Message(format!("USAGE:\n {}", GENERATE_USAGE)));
@@ -663,7 +769,19 @@ fn decode_transaction(args: &[String], _version: TransactionVersion) -> Result<S
     let mut debug_cursor = LogReader::from_reader(&mut cursor);
 
     match StacksTransaction::consensus_deserialize(&mut debug_cursor) {
-        Ok(tx) => Ok(serde_json::to_string(&tx).expect("Failed to serialize transaction to JSON")),
+        Ok(tx) => {
+            let Some(request) = tx.payload.infer_request() else {
+                return Ok(
+                    serde_json::to_string(&tx).expect("Failed to serialize transaction to JSON")
+                );
+            };
+            // spell out the inference, since the legacy payload's strings serialize as bytes
+            let mut tx_json =
+                serde_json::to_value(&tx).expect("Failed to serialize transaction to JSON");
+            tx_json["infer"] = infer_request_json(&request);
+            Ok(serde_json::to_string_pretty(&tx_json)
+                .expect("Failed to serialize transaction to JSON"))
+        }
         Err(e) => {
             let mut ret = String::new();
             ret.push_str(&format!("Failed to decode transaction: {:?}\n", &e));
@@ -677,6 +795,20 @@ fn decode_transaction(args: &[String], _version: TransactionVersion) -> Result<S
     }
 }
 
+/// The inference an Infer transaction asks for, as JSON.  A context that doesn't parse is shown
+/// as-is, along with what is wrong with it.
+fn infer_request_json(request: &InferRequest) -> serde_json::Value {
+    let context = match InferContext::try_parse(&request.context) {
+        Ok(context) => serde_json::to_value(&context).expect("Failed to serialize Infer context"),
+        Err(e) => json!({ "error": e, "raw": request.context }),
+    };
+    json!({
+        "from": request.from.to_string(),
+        "prompt": request.user_input,
+        "context": context,
+    })
+}
+
 fn decode_header(args: &[String], _version: TransactionVersion) -> Result<String, CliError> {
     if (args.len() >= 1 && args[0] == "-h") || args.len() != 1 {
         return Err(CliError::Message(format!(
@@ -868,6 +1000,7 @@ fn main_handler(mut argv: Vec<String>) -> Result<String, CliError> {
             }
             "publish" => handle_contract_publish(args, tx_version, chain_id),
             "token-transfer" => handle_token_transfer(args, tx_version, chain_id),
+            "infer" => handle_infer(args, tx_version, chain_id),
             "generate-sk" => generate_secret_key(args, tx_version),
             "addresses" => get_addresses(args, tx_version),
             "decode-tx" => decode_transaction(args, tx_version),
@@ -1166,6 +1299,116 @@ mod test {
         );
     }
 
+    #[test]
+    fn simple_infer() {
+        let context_file = format!("/tmp/infer-context-{}.json", rand::random::<u64>());
+        fs::write(
+            &context_file,
+            r#"{"messages": [{"role": "system", "content": "be brief"}],
+                "params": {"seed": 42, "temperature": 0.0, "max_tokens": 64}}"#,
+        )
+        .unwrap();
+
+        let infer_args = [
+            "--testnet",
+            "infer",
+            "043ff5004e3d695060fa48ac94c96049b8c14ef441c50a184a6a3875d2a000f3",
+            "1",
+            "0",
+            "Is the Earth round?",
+            &context_file,
+        ];
+        let tx_hex = main_handler(to_string_vec(&infer_args)).unwrap();
+        let tx = StacksTransaction::consensus_deserialize(&mut &hex_bytes(&tx_hex).unwrap()[..])
+            .unwrap();
+        let TransactionPayload::VersionedInfer(ref infer) = tx.payload else {
+            panic!("Expected a versioned Infer payload");
+        };
+        assert_eq!(infer.prompt.as_str(), "Is the Earth round?");
+        assert_eq!(infer.messages.len(), 1);
+        assert_eq!(infer.messages[0].content.as_str(), "be brief");
+        assert_eq!(infer.seed, 42);
+        assert_eq!(infer.max_tokens, 64);
+        assert_eq!(
+            infer.from,
+            PrincipalData::from(tx.origin_address()),
+            "the sender asks for the inference"
+        );
+
+        let decoded: serde_json::Value =
+            serde_json::from_str(&main_handler(to_string_vec(&["decode-tx", &tx_hex])).unwrap())
+                .unwrap();
+        assert_eq!(decoded["infer"]["prompt"], "Is the Earth round?");
+        assert_eq!(decoded["infer"]["context"]["params"]["seed"], 42);
+        assert_eq!(
+            decoded["infer"]["context"]["messages"][0]["content"],
+            "be brief"
+        );
+
+        // the original payload carries the context as-is
+        let legacy_args = [
+            "infer",
+            "043ff5004e3d695060fa48ac94c96049b8c14ef441c50a184a6a3875d2a000f3",
+            "1",
+            "0",
+            "--legacy",
+            "Is the Earth round?",
+        ];
+        let tx_hex = main_handler(to_string_vec(&legacy_args)).unwrap();
+        let decoded: serde_json::Value =
+            serde_json::from_str(&main_handler(to_string_vec(&["decode-tx", &tx_hex])).unwrap())
+                .unwrap();
+        assert_eq!(decoded["infer"]["prompt"], "Is the Earth round?");
+        assert_eq!(decoded["infer"]["context"]["messages"], json!([]));
+
+        let long_prompt = "Is the Earth round? ".repeat(10);
+        let legacy_args = [
+            "infer",
+            "043ff5004e3d695060fa48ac94c96049b8c14ef441c50a184a6a3875d2a000f3",
+            "1",
+            "0",
+            "--legacy",
+            &long_prompt,
+        ];
+        assert!(
+            format!("{}", main_handler(to_string_vec(&legacy_args)).unwrap_err())
+                .contains("at most 128 are allowed")
+        );
+
+        // sampling must be greedy, so the output can be reproduced
+        fs::write(&context_file, r#"{"params": {"temperature": 0.7}}"#).unwrap();
+        let infer_args = [
+            "infer",
+            "043ff5004e3d695060fa48ac94c96049b8c14ef441c50a184a6a3875d2a000f3",
+            "1",
+            "0",
+            "Is the Earth round?",
+            &context_file,
+        ];
+        assert!(
+            format!("{}", main_handler(to_string_vec(&infer_args)).unwrap_err())
+                .contains("temperature must be 0")
+        );
+
+        fs::write(&context_file, "not json").unwrap();
+        assert!(
+            format!("{}", main_handler(to_string_vec(&infer_args)).unwrap_err())
+                .contains("malformed context")
+        );
+        fs::remove_file(&context_file).unwrap();
+
+        let infer_args = [
+            "infer",
+            "043ff5004e3d695060fa48ac94c96049b8c14ef441c50a184a6a3875d2a000f3",
+            "1",
+            "0",
+        ];
+        assert!(
+            format!("{}", main_handler(to_string_vec(&infer_args)).unwrap_err())
+                .contains("Incorrect argument count")
+        );
+    }
+
     #[test]
     fn simple_addresses() {
         let addr_args = [
@@ -26,6 +26,7 @@ use clarity::vm::costs::ExecutionCost;
 use clarity::vm::database::BurnStateDB;
 use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
 use clarity::vm::Value;
+use libllm::{InferDB, InferRetentionConfig};
 use stacks_common::bitvec::BitVec;
 use stacks_common::types::chainstate::{
     BlockHeaderHash, BurnchainHeaderHash, PoxId, SortitionId, StacksBlockId,
@@ -78,7 +79,7 @@ use crate::cost_estimates::{CostEstimator, FeeEstimator, PessimisticEstimator};
 use crate::monitoring::{
     increment_contract_calls_processed, increment_stx_blocks_processed_counter,
 };
-use crate::net::atlas::{AtlasConfig, AtlasDB, AttachmentInstance};
+use crate::net::atlas::{AtlasConfig, AtlasDB, Attachment, AttachmentInstance};
 use crate::util_lib::db::{DBConn, DBTx, Error as DBError};
 
 pub mod comm;
@@ -202,6 +203,8 @@ pub struct ChainsCoordinatorConfig {
     /// true: always wait for canonical anchor blocks, even if it stalls the chain
     /// false: proceed to process new chain history even if we're missing an anchor block.
     pub require_affirmed_anchor_blocks: bool,
+    /// How long inference results are kept once their transactions are confirmed or evicted
+    pub infer_retention: InferRetentionConfig,
 }
 
 impl ChainsCoordinatorConfig {
@@ -209,6 +212,7 @@ impl ChainsCoordinatorConfig {
         ChainsCoordinatorConfig {
             always_use_affirmation_maps: false,
             require_affirmed_anchor_blocks: true,
+            infer_retention: InferRetentionConfig::default(),
         }
     }
 }
@@ -2881,15 +2885,61 @@ impl<
         )
     }
 
-    /// Process any Atlas attachment events and forward them to the Atlas subsystem
+    /// Process any Atlas attachment events and forward them to the Atlas subsystem.
+    /// Inference outputs committed to by the block are published as attachments too.  From 3.0
+    /// the output the block carries is offered to Atlas directly; before then, the one `infer_db`
+    /// holds, if this node ran the task.
     pub fn process_atlas_attachment_events(
         atlas_db: Option<&mut AtlasDB>,
         atlas_config: &AtlasConfig,
+        infer_db: Option<&InferDB>,
         block_receipt: &StacksEpochReceipt,
         canonical_stacks_tip_height: u64,
     ) {
         let mut attachments_instances = HashSet::new();
+        let mut infer_outputs = vec![];
+        // from 3.0, an Infer transaction's output is the one the block carries, whatever this
+        // node's own InferDB says
+        let carries_infer_outputs = block_receipt.evaluated_epoch.carries_infer_outputs();
+        let carried_outputs: HashMap<_, _> = block_receipt
+            .tx_receipts
+            .iter()
+            .filter_map(|receipt| match receipt.transaction {
+                TransactionOrigin::Stacks(ref transaction) => match transaction.payload {
+                    TransactionPayload::InferOutputs(ref outputs) => Some(outputs),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .flatten()
+            .map(|carried| (carried.txid, carried.output.as_str()))
+            .collect();
         for receipt in block_receipt.tx_receipts.iter() {
+            if let Some(attachment_instance) = AttachmentInstance::try_new_from_infer_receipt(
+                receipt,
+                block_receipt.header.index_block_hash(),
+                block_receipt.header.stacks_block_height,
+                Some(canonical_stacks_tip_height),
+            ) {
+                let output = if carries_infer_outputs {
+                    carried_outputs
+                        .get(&attachment_instance.tx_id)
+                        .map(|output| output.to_string())
+                } else {
+                    infer_db
+                        .and_then(|infer_db| {
+                            libllm::query(infer_db, attachment_instance.tx_id.to_hex()).ok()
+                        })
+                        .filter(|result| result.status == libllm::InferStatus::Success)
+                        .map(|result| result.output)
+                };
+                let output = output
+                    .map(|output| Attachment::new(output.into_bytes()))
+                    .filter(|attachment| attachment.hash() == attachment_instance.content_hash);
+                infer_outputs.extend(output);
+                attachments_instances.insert(attachment_instance);
+                continue;
+            }
             if let TransactionOrigin::Stacks(ref transaction) = receipt.transaction {
                 if let TransactionPayload::ContractCall(ref contract_call) = transaction.payload {
                     let contract_id = contract_call.to_clarity_contract_id();
@@ -2924,6 +2974,15 @@ impl<
                 "stacks_height" => block_receipt.header.stacks_block_height,
             );
             if let Some(atlas_db) = atlas_db {
+                for output in infer_outputs.iter() {
+                    if let Err(e) = atlas_db.insert_uninstantiated_attachment(output) {
+                        warn!(
+                            "Atlas: Error writing inference output to DB";
+                            "err" => ?e,
+                            "content_hash" => %output.hash(),
+                        );
+                    }
+                }
                 for new_attachment in attachments_instances.into_iter() {
                     if let Err(e) = atlas_db.queue_attachment_instance(&new_attachment) {
                         warn!(
@@ -2941,6 +3000,46 @@ impl<
         }
     }
 
+    /// Record which inference results the block's Infer transactions confirmed, and prune the
+    /// results that the retention policy no longer keeps, now that the canonical Stacks tip is at
+    /// `canonical_stacks_tip_height`.
+    pub fn process_infer_retention(
+        &self,
+        block_receipt: &StacksEpochReceipt,
+        canonical_stacks_tip_height: u64,
+    ) {
+        let infer_db = &self.chain_state_db.infer_db;
+        let block_height = block_receipt.header.stacks_block_height;
+        for receipt in block_receipt.tx_receipts.iter() {
+            let TransactionOrigin::Stacks(ref transaction) = receipt.transaction else {
+                continue;
+            };
+            if !matches!(
+                transaction.payload,
+                TransactionPayload::Infer(..) | TransactionPayload::VersionedInfer(..)
+            ) {
+                continue;
+            }
+            let txid = transaction.txid();
+            if let Err(e) = libllm::retention::confirm(infer_db, &txid.to_hex(), block_height) {
+                warn!("Failed to record inference result confirmation";
+                      "txid" => %txid,
+                      "err" => %e);
+            }
+        }
+        match libllm::retention::prune(
+            infer_db,
+            &self.config.infer_retention,
+            canonical_stacks_tip_height,
+            get_epoch_time_secs(),
+        ) {
+            Ok(0) => {}
+            Ok(pruned) => debug!("Pruned {pruned} inference results";
+                                 "canonical_stacks_tip_height" => canonical_stacks_tip_height),
+            Err(e) => warn!("Failed to prune inference results"; "err" => %e),
+        }
+    }
+
     /// Replay any existing Stacks blocks we have that arose on a different PoX fork.
     /// This is best-effort -- if a block isn't found or can't be loaded, it's skipped.
     fn replay_stacks_blocks(
@@ -3250,6 +3349,11 @@ impl<
                     Self::process_atlas_attachment_events(
                         self.atlas_db.as_mut(),
                         &self.atlas_config,
+                        Some(&self.chain_state_db.infer_db),
+                        &block_receipt,
+                        new_canonical_block_snapshot.canonical_stacks_tip_height,
+                    );
+                    self.process_infer_retention(
                         &block_receipt,
                         new_canonical_block_snapshot.canonical_stacks_tip_height,
                     );
@@ -24,9 +24,11 @@ use clarity::vm::clarity::TransactionConnection;
 use clarity::vm::costs::{ExecutionCost, LimitedCostTracker};
 use clarity::vm::database::BurnStateDB;
 use clarity::vm::errors::Error as InterpreterError;
-use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
+use clarity::vm::events::{STXEventType, STXInferEventData, StacksTransactionEvent};
+use clarity::vm::types::{BuffData, PrincipalData, QualifiedContractIdentifier};
 use clarity::vm::{ClarityVersion, Value};
 use lazy_static::lazy_static;
+use libllm::InferDB;
 use rand::RngCore;
 use rusqlite::Connection;
 use stacks_common::address::AddressHashMode;
@@ -39,7 +41,7 @@ use stacks_common::types::chainstate::{
     TrieHash, VRFSeed,
 };
 use stacks_common::types::StacksPublicKeyBuffer;
-use stacks_common::util::hash::{to_hex, Hash160};
+use stacks_common::util::hash::{to_hex, Hash160, Sha256Sum};
 use stacks_common::util::secp256k1::MessageSignature;
 use stacks_common::util::vrf::*;
 use stacks_common::{address, types, util};
@@ -62,13 +64,16 @@ use crate::chainstate::stacks::boot::{
 };
 use crate::chainstate::stacks::db::accounts::MinerReward;
 use crate::chainstate::stacks::db::{ClarityTx, StacksChainState, StacksHeaderInfo};
+use crate::chainstate::stacks::events::StacksTransactionReceipt;
 use crate::chainstate::stacks::miner::BlockBuilder;
 use crate::chainstate::stacks::*;
 use crate::clarity_vm::clarity::ClarityConnection;
 use crate::core::*;
+use crate::cost_estimates::tests::common::make_block_receipt;
 use crate::monitoring::increment_stx_blocks_processed_counter;
+use crate::net::atlas::{AtlasConfig, AtlasDB, Attachment};
 use crate::util_lib::boot::{boot_code_addr, boot_code_id};
-use crate::util_lib::strings::StacksString;
+use crate::util_lib::strings::{InferLPString, InferText, StacksString};
 use crate::{chainstate, core};
 
 lazy_static! {
@@ -6908,3 +6913,104 @@ fn test_check_chainstate_db_versions() {
             .unwrap()
     );
 }
+
+#[test]
+fn test_atlas_publishes_carried_infer_outputs() {
+    let privk = StacksPrivateKey::from_hex(
+        "6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001",
+    )
+    .unwrap();
+    let auth = TransactionAuth::from_p2pkh(&privk).unwrap();
+    let sender = auth.origin().address_testnet().to_account_principal();
+    let tx_infer = StacksTransaction::new(
+        TransactionVersion::Testnet,
+        auth.clone(),
+        TransactionPayload::Infer(
+            sender.clone(),
+            InferLPString::try_from("Is the Earth round?").unwrap(),
+            InferLPString::try_from("").unwrap(),
+        ),
+    );
+    let txid = tx_infer.txid().to_hex();
+
+    // this node ran the task, but got another output than the one the block carries
+    let infer_db = InferDB::open_memory().unwrap();
+    libllm::infer_chain(&infer_db, txid.clone(), "Is the Earth round?", "", 0).unwrap();
+    libllm::do_infer(&infer_db, &libllm::MockBackend::with_response("No.")).unwrap();
+
+    let carried = "Yes, the Earth is round.";
+    let tx_outputs = StacksTransaction::new(
+        TransactionVersion::Testnet,
+        auth,
+        TransactionPayload::InferOutputs(vec![InferOutput {
+            txid: tx_infer.txid(),
+            model: None,
+            model_digest: None,
+            output: InferText::try_from(carried).unwrap(),
+        }]),
+    );
+    let infer_event =
+        StacksTransactionEvent::STXEvent(STXEventType::STXInferEvent(STXInferEventData {
+            sender,
+            infer_out_hash: BuffData {
+                data: Sha256Sum::from_data(carried.as_bytes()).as_bytes().to_vec(),
+            },
+            session: None,
+        }));
+    let mut infer_receipt = StacksTransactionReceipt::from_infer(
+        tx_infer,
+        vec![infer_event],
+        Value::okay_true(),
+        ExecutionCost::zero(),
+    );
+    infer_receipt.tx_index = 1;
+    let outputs_receipt =
+        StacksTransactionReceipt::from_infer_outputs(tx_outputs, ExecutionCost::zero());
+    let attachment = Attachment::new(carried.as_bytes().to_vec());
+
+    // from 3.0, the carried output is published, whatever this node ran
+    let mut block_receipt = make_block_receipt(vec![outputs_receipt, infer_receipt]);
+    block_receipt.evaluated_epoch = StacksEpochId::Epoch30;
+    let atlas_config = AtlasConfig::new(false);
+    let mut atlas_db = AtlasDB::connect_memory(atlas_config.clone()).unwrap();
+    ChainsCoordinator::<
+        NullEventDispatcher,
+        (),
+        OnChainRewardSetProvider<NullEventDispatcher>,
+        (),
+        (),
+        BitcoinIndexer,
+    >::process_atlas_attachment_events(
+        Some(&mut atlas_db),
+        &atlas_config,
+        Some(&infer_db),
+        &block_receipt,
+        1,
+    );
+    assert_eq!(
+        atlas_db
+            .find_uninstantiated_attachment(&attachment.hash())
+            .unwrap(),
+        Some(attachment.clone())
+    );
+    assert_eq!(atlas_db.count_uninstantiated_attachments().unwrap(), 1);
+
+    // before 3.0, only an output this node ran that matches the committed hash is
+    block_receipt.evaluated_epoch = StacksEpochId::Epoch25;
+    let mut atlas_db = AtlasDB::connect_memory(atlas_config.clone()).unwrap();
+    ChainsCoordinator::<
+        NullEventDispatcher,
+        (),
+        OnChainRewardSetProvider<NullEventDispatcher>,
+        (),
+        (),
+        BitcoinIndexer,
+    >::process_atlas_attachment_events(
+        Some(&mut atlas_db),
+        &atlas_config,
+        Some(&infer_db),
+        &block_receipt,
+        1,
+    );
+    assert_eq!(atlas_db.count_uninstantiated_attachments().unwrap(), 0);
+}
@@ -648,9 +648,11 @@ impl<
             Self::process_atlas_attachment_events(
                 self.atlas_db.as_mut(),
                 &self.atlas_config,
+                Some(&self.chain_state_db.infer_db),
                 &block_receipt,
                 canonical_stacks_block_height,
             );
+            self.process_infer_retention(&block_receipt, canonical_stacks_block_height);
 
             // update cost estimator
             if let Some(ref mut estimator) = self.cost_estimator {
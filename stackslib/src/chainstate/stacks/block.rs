@@ -599,6 +599,18 @@ impl StacksBlock {
                     return false;
                 }
             }
+            if let TransactionPayload::VersionedInfer(..) = &tx.payload {
+                if !epoch_id.supports_versioned_infer() {
+                    error!("Versioned Infer transaction not supported in epoch {}", epoch_id; "txid" => %tx.txid());
+                    return false;
+                }
+            }
+            if let TransactionPayload::InferOutputs(..) = &tx.payload {
+                if !epoch_id.carries_infer_outputs() {
+                    error!("InferOutputs transaction not supported in epoch {}", epoch_id; "txid" => %tx.txid());
+                    return false;
+                }
+            }
         }
         return true;
     }
@@ -947,6 +959,7 @@ mod test {
     use crate::net::codec::test::*;
     use crate::net::codec::*;
     use crate::net::*;
+    use crate::util_lib::strings::InferText;
 
     #[test]
     fn codec_stacks_block_ecvrf_proof() {
@@ -1796,6 +1809,33 @@ mod test {
             TransactionPayload::TenureChange(tenure_change_payload),
         );
 
+        let tx_versioned_infer = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            origin_auth.clone(),
+            TransactionPayload::VersionedInfer(TransactionInfer {
+                from: stx_address.into(),
+                prompt: InferText::try_from("Is the Earth round?").unwrap(),
+                messages: vec![],
+                model: None,
+                model_digest: None,
+                seed: 0,
+                max_tokens: 64,
+                payment: None,
+                parent: None,
+            }),
+        );
+
+        let tx_infer_outputs = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            origin_auth.clone(),
+            TransactionPayload::InferOutputs(vec![InferOutput {
+                txid: tx_versioned_infer.txid(),
+                model: None,
+                model_digest: None,
+                output: InferText::try_from("Yes.").unwrap(),
+            }]),
+        );
+
         let dup_txs = vec![
             tx_coinbase.clone(),
             tx_transfer.clone(),
@@ -1810,6 +1850,8 @@ mod test {
         let nakamoto_coinbase = vec![tx_coinbase_proof.clone()];
         let tenure_change_tx = vec![tx_tenure_change.clone()];
         let nakamoto_txs = vec![tx_coinbase_proof.clone(), tx_tenure_change.clone()];
+        let versioned_infer = vec![tx_versioned_infer.clone()];
+        let infer_outputs = vec![tx_infer_outputs.clone(), tx_versioned_infer.clone()];
 
         assert!(!StacksBlock::validate_transactions_unique(&dup_txs));
         assert!(!StacksBlock::validate_transactions_network(
@@ -1863,6 +1905,22 @@ mod test {
             &nakamoto_txs,
             StacksEpochId::Epoch21
         ));
+        assert!(!StacksBlock::validate_transactions_static_epoch(
+            &versioned_infer,
+            StacksEpochId::Epoch25
+        ));
+        assert!(StacksBlock::validate_transactions_static_epoch(
+            &versioned_infer,
+            StacksEpochId::Epoch30
+        ));
+        assert!(!StacksBlock::validate_transactions_static_epoch(
+            &infer_outputs,
+            StacksEpochId::Epoch25
+        ));
+        assert!(StacksBlock::validate_transactions_static_epoch(
+            &infer_outputs,
+            StacksEpochId::Epoch30
+        ));
     }
 
     // TODO:
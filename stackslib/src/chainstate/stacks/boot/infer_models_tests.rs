@@ -0,0 +1,273 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2024 Stacks Open Internet Foundation
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use clarity::vm::clarity::TransactionConnection;
+use clarity::vm::test_util::{TEST_BURN_STATE_DB, TEST_HEADER_DB};
+use clarity::vm::types::{PrincipalData, StandardPrincipalData};
+use clarity::vm::Value;
+use libllm::InferParams;
+use stacks_common::consts::CHAIN_ID_TESTNET;
+use stacks_common::types::chainstate::StacksBlockId;
+
+use super::{RegisteredInferModel, INFER_MODELS_NAME};
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::index::ClarityMarfTrieId;
+use crate::chainstate::stacks::Error;
+use crate::clarity_vm::clarity::{ClarityBlockConnection, ClarityInstance};
+use crate::clarity_vm::database::marf::MarfedKV;
+use crate::util_lib::boot::boot_code_id;
+
+fn registered_model() -> RegisteredInferModel {
+    RegisteredInferModel {
+        model_id: "llama3.1".into(),
+        provider: StandardPrincipalData::transient().into(),
+        weights_digest: "sha256:01".into(),
+        endpoint: "http://localhost:11434".into(),
+        max_tokens: 1024,
+        seeded: false,
+        active: true,
+        registered_at: 1,
+    }
+}
+
+fn model_params(model: &str, digest: Option<&str>) -> InferParams {
+    InferParams {
+        model: Some(model.into()),
+        model_digest: digest.map(|d| d.into()),
+        ..InferParams::default()
+    }
+}
+
+#[test]
+fn check_request() {
+    let model = registered_model();
+    model
+        .check_request(&model_params("llama3.1", None))
+        .unwrap();
+    model
+        .check_request(&model_params("llama3.1", Some("sha256:01")))
+        .unwrap();
+
+    // pinned to other weights
+    assert!(model
+        .check_request(&model_params("llama3.1", Some("sha256:02")))
+        .is_err());
+
+    // too many tokens
+    let mut params = model_params("llama3.1", None);
+    params.max_tokens = 1025;
+    assert!(model.check_request(&params).is_err());
+
+    // seeded, but the model doesn't support seeds
+    let mut params = model_params("llama3.1", None);
+    params.seed = 7;
+    assert!(model.check_request(&params).is_err());
+
+    let seeded = RegisteredInferModel {
+        seeded: true,
+        ..registered_model()
+    };
+    seeded.check_request(&params).unwrap();
+
+    // no longer active
+    let inactive = RegisteredInferModel {
+        active: false,
+        ..registered_model()
+    };
+    assert!(inactive
+        .check_request(&model_params("llama3.1", None))
+        .is_err());
+}
+
+#[test]
+fn check_ran() {
+    let model = registered_model();
+    model
+        .check_ran(&model_params("llama3.1", Some("sha256:01")))
+        .unwrap();
+
+    // other weights, or unknown weights
+    assert!(model
+        .check_ran(&model_params("llama3.1", Some("sha256:02")))
+        .is_err());
+    assert!(model.check_ran(&model_params("llama3.1", None)).is_err());
+
+    // another model with the same weights
+    assert!(model
+        .check_ran(&model_params("llama3", Some("sha256:01")))
+        .is_err());
+}
+
+fn call_infer_models(
+    conn: &mut ClarityBlockConnection,
+    sender: &PrincipalData,
+    function: &str,
+    args: &[Value],
+) -> Value {
+    let contract_id = boot_code_id(INFER_MODELS_NAME, false);
+    conn.as_transaction(|tx| {
+        tx.run_contract_call(sender, None, &contract_id, function, args, |_, _| false)
+    })
+    .unwrap()
+    .0
+}
+
+fn ascii(s: &str) -> Value {
+    Value::string_ascii_from_bytes(s.as_bytes().to_vec()).unwrap()
+}
+
+fn utf8(s: &str) -> Value {
+    Value::string_utf8_from_bytes(s.as_bytes().to_vec()).unwrap()
+}
+
+#[test]
+fn register_and_look_up_models() {
+    let marf = MarfedKV::temporary();
+    let mut clarity_instance = ClarityInstance::new(false, CHAIN_ID_TESTNET, marf);
+    let provider: PrincipalData = StandardPrincipalData::transient().into();
+    let other: PrincipalData = StandardPrincipalData(26, [1; 20]).into();
+
+    let mut conn = clarity_instance.begin_test_genesis_block(
+        &StacksBlockId::sentinel(),
+        &StacksBlockId([0 as u8; 32]),
+        &TEST_HEADER_DB,
+        &TEST_BURN_STATE_DB,
+    );
+
+    // not deployed before Epoch 3.0
+    assert_eq!(
+        StacksChainState::get_registered_infer_model(&mut conn, false, "llama3.1").unwrap(),
+        None
+    );
+    StacksChainState::check_infer_model(&mut conn, false, &model_params("llama3.1", None)).unwrap();
+
+    // .costs-4 and .infer-models
+    let receipts = conn.initialize_epoch_3_0().unwrap();
+    assert_eq!(receipts.len(), 2);
+
+    // must be registered from now on
+    assert_eq!(
+        StacksChainState::get_registered_infer_model(&mut conn, false, "llama3.1").unwrap(),
+        None
+    );
+    assert!(matches!(
+        StacksChainState::check_infer_model(&mut conn, false, &model_params("llama3.1", None)),
+        Err(Error::InvalidStacksTransaction(..))
+    ));
+    // unless no model is named
+    assert_eq!(
+        StacksChainState::check_infer_model(&mut conn, false, &InferParams::default()).unwrap(),
+        None
+    );
+
+    let register_args = [
+        ascii("llama3.1"),
+        ascii("sha256:01"),
+        utf8("http://localhost:11434 \u{1F999}"),
+        Value::UInt(1024),
+        Value::Bool(false),
+    ];
+    assert_eq!(
+        call_infer_models(&mut conn, &provider, "register-model", &register_args),
+        Value::okay_true()
+    );
+    // already registered
+    assert_eq!(
+        call_infer_models(&mut conn, &other, "register-model", &register_args),
+        Value::err_uint(1)
+    );
+
+    let model = StacksChainState::get_registered_infer_model(&mut conn, false, "llama3.1")
+        .unwrap()
+        .unwrap();
+    assert_eq!(model.provider, provider);
+    assert_eq!(model.weights_digest, "sha256:01");
+    assert_eq!(model.endpoint, "http://localhost:11434 \u{1F999}");
+    assert_eq!(model.max_tokens, 1024);
+    assert!(!model.seeded);
+    assert!(model.active);
+    assert_eq!(
+        StacksChainState::check_infer_model(&mut conn, false, &model_params("llama3.1", None))
+            .unwrap(),
+        Some(model)
+    );
+
+    // only the provider can change the model
+    let update_args = [
+        ascii("llama3.1"),
+        utf8("https://example.com"),
+        Value::UInt(2048),
+        Value::Bool(true),
+    ];
+    assert_eq!(
+        call_infer_models(&mut conn, &other, "update-model", &update_args),
+        Value::err_uint(3)
+    );
+    assert_eq!(
+        call_infer_models(&mut conn, &provider, "update-model", &update_args),
+        Value::okay_true()
+    );
+    let model = StacksChainState::get_registered_infer_model(&mut conn, false, "llama3.1")
+        .unwrap()
+        .unwrap();
+    assert_eq!(model.weights_digest, "sha256:01");
+    assert_eq!(model.endpoint, "https://example.com");
+    assert_eq!(model.max_tokens, 2048);
+    assert!(model.seeded);
+
+    let deactivate_args = [ascii("llama3.1"), Value::Bool(false)];
+    assert_eq!(
+        call_infer_models(&mut conn, &other, "set-model-active", &deactivate_args),
+        Value::err_uint(3)
+    );
+    assert_eq!(
+        call_infer_models(&mut conn, &provider, "set-model-active", &deactivate_args),
+        Value::okay_true()
+    );
+    assert!(matches!(
+        StacksChainState::check_infer_model(&mut conn, false, &model_params("llama3.1", None)),
+        Err(Error::InvalidStacksTransaction(..))
+    ));
+
+    // unknown and malformed models
+    assert_eq!(
+        call_infer_models(&mut conn, &provider, "update-model", &{
+            let mut args = update_args.clone();
+            args[0] = ascii("llama2");
+            args
+        }),
+        Value::err_uint(2)
+    );
+    assert_eq!(
+        call_infer_models(&mut conn, &provider, "register-model", &{
+            let mut args = register_args.clone();
+            args[0] = ascii("llama2");
+            args[3] = Value::UInt(0);
+            args
+        }),
+        Value::err_uint(4)
+    );
+    assert_eq!(
+        StacksChainState::get_registered_infer_model(&mut conn, false, &"x".repeat(65)).unwrap(),
+        None
+    );
+    assert_eq!(
+        StacksChainState::get_registered_infer_model(&mut conn, false, "llama\u{1F999}").unwrap(),
+        None
+    );
+
+    conn.commit_block();
+}
@@ -31,11 +31,12 @@ use clarity::vm::events::StacksTransactionEvent;
 use clarity::vm::representations::{ClarityName, ContractName};
 use clarity::vm::types::TypeSignature::UIntType;
 use clarity::vm::types::{
-    PrincipalData, QualifiedContractIdentifier, SequenceData, StandardPrincipalData, TupleData,
-    TypeSignature, Value,
+    CharType, PrincipalData, QualifiedContractIdentifier, SequenceData, StandardPrincipalData,
+    TupleData, TypeSignature, Value,
 };
 use clarity::vm::{ClarityVersion, Environment, SymbolicExpression};
 use lazy_static::lazy_static;
+use libllm::InferParams;
 use serde::Deserialize;
 use stacks_common::address::AddressHashMode;
 use stacks_common::codec::StacksMessageCodec;
@@ -68,6 +69,7 @@ pub const BOOT_CODE_LOCKUP: &'static str = std::include_str!("lockup.clar");
 pub const BOOT_CODE_COSTS: &'static str = std::include_str!("costs.clar");
 pub const BOOT_CODE_COSTS_2: &'static str = std::include_str!("costs-2.clar");
 pub const BOOT_CODE_COSTS_3: &'static str = std::include_str!("costs-3.clar");
+pub const BOOT_CODE_COSTS_4: &'static str = std::include_str!("costs-4.clar");
 pub const BOOT_CODE_COSTS_2_TESTNET: &'static str = std::include_str!("costs-2-testnet.clar");
 pub const BOOT_CODE_COST_VOTING_MAINNET: &'static str = std::include_str!("cost-voting.clar");
 pub const BOOT_CODE_BNS: &'static str = std::include_str!("bns.clar");
@@ -92,10 +94,14 @@ pub const SIGNERS_BODY: &'static str = std::include_str!("signers.clar");
 pub const SIGNERS_DB_0_BODY: &'static str = std::include_str!("signers-0-xxx.clar");
 pub const SIGNERS_DB_1_BODY: &'static str = std::include_str!("signers-1-xxx.clar");
 pub const SIGNERS_VOTING_BODY: &'static str = std::include_str!("signers-voting.clar");
+pub const INFER_MODELS_NAME: &'static str = "infer-models";
+pub const INFER_MODELS_BODY: &'static str = std::include_str!("infer-models.clar");
+pub const INFER_MODEL_ID_MAX_LEN: usize = 64;
 
 pub const COSTS_1_NAME: &'static str = "costs";
 pub const COSTS_2_NAME: &'static str = "costs-2";
 pub const COSTS_3_NAME: &'static str = "costs-3";
+pub const COSTS_4_NAME: &'static str = "costs-4";
 /// This contract name is used in testnet **only** to lookup an initial
 ///  setting for the pox-4 aggregate key. This contract should contain a `define-read-only`
 ///  function called `aggregate-key` with zero arguments which returns a (buff 33)
@@ -286,6 +292,91 @@ impl RewardSetData {
     }
 }
 
+/// A model registered in the `.infer-models` boot contract
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RegisteredInferModel {
+    pub model_id: String,
+    pub provider: PrincipalData,
+    pub weights_digest: String,
+    pub endpoint: String,
+    pub max_tokens: u128,
+    pub seeded: bool,
+    pub active: bool,
+    pub registered_at: u128,
+}
+
+impl RegisteredInferModel {
+    /// Decode an entry of the `models` map
+    pub fn try_from_tuple(
+        model_id: &str,
+        tuple: TupleData,
+    ) -> Result<RegisteredInferModel, VmError> {
+        let endpoint = match tuple.get("endpoint")? {
+            Value::Sequence(SequenceData::String(CharType::UTF8(data))) => {
+                String::from_utf8(data.data.concat()).ok()
+            }
+            _ => None,
+        }
+        .ok_or_else(|| {
+            VmError::from(InterpreterError::Expect(
+                "Registered model endpoint is not a string-utf8".into(),
+            ))
+        })?;
+        Ok(RegisteredInferModel {
+            model_id: model_id.to_string(),
+            provider: tuple.get("provider")?.to_owned().expect_principal()?,
+            weights_digest: tuple.get("weights-digest")?.to_owned().expect_ascii()?,
+            endpoint,
+            max_tokens: tuple.get("max-tokens")?.to_owned().expect_u128()?,
+            seeded: tuple.get("seeded")?.to_owned().expect_bool()?,
+            active: tuple.get("active")?.to_owned().expect_bool()?,
+            registered_at: tuple.get("registered-at")?.to_owned().expect_u128()?,
+        })
+    }
+
+    /// Can an Infer transaction ask this model for an inference with these parameters?
+    pub fn check_request(&self, requested: &InferParams) -> Result<(), String> {
+        if !self.active {
+            return Err(format!("model {} is not active", &self.model_id));
+        }
+        if let Some(ref digest) = requested.model_digest {
+            if digest != &self.weights_digest {
+                return Err(format!(
+                    "model {} is registered with weights {}, not {}",
+                    &self.model_id, &self.weights_digest, digest
+                ));
+            }
+        }
+        if u128::from(requested.max_tokens) > self.max_tokens {
+            return Err(format!(
+                "model {} allows at most {} tokens, but {} were requested",
+                &self.model_id, self.max_tokens, requested.max_tokens
+            ));
+        }
+        if requested.seed != 0 && !self.seeded {
+            return Err(format!("model {} does not support seeds", &self.model_id));
+        }
+        Ok(())
+    }
+
+    /// Were these (resolved) parameters, recorded when a task ran, a run of this model?
+    pub fn check_ran(&self, recorded: &InferParams) -> Result<(), String> {
+        if recorded.model.as_deref() != Some(self.model_id.as_str()) {
+            return Err(format!(
+                "ran model {:?}, not {}",
+                &recorded.model, &self.model_id
+            ));
+        }
+        if recorded.model_digest.as_deref() != Some(self.weights_digest.as_str()) {
+            return Err(format!(
+                "ran weights {:?}, but model {} is registered with {}",
+                &recorded.model_digest, &self.model_id, &self.weights_digest
+            ));
+        }
+        Ok(())
+    }
+}
+
 impl StacksChainState {
     /// Return the MARF key used to store whether or not a given PoX
     ///  cycle's "start" has been handled by the Stacks fork yet. This
@@ -1343,11 +1434,71 @@ impl StacksChainState {
         };
         Ok(aggregate_public_key)
     }
+
+    /// Look up a model in the `.infer-models` boot contract.  Returns None if it is not
+    /// registered, or if the contract is not deployed yet (i.e. before Epoch 3.0).
+    pub fn get_registered_infer_model<T: ClarityConnection>(
+        clarity_tx: &mut T,
+        mainnet: bool,
+        model_id: &str,
+    ) -> Result<Option<RegisteredInferModel>, Error> {
+        let key = match Value::string_ascii_from_bytes(model_id.as_bytes().to_vec()) {
+            Ok(key) if model_id.len() <= INFER_MODEL_ID_MAX_LEN => key,
+            // can't be in the map
+            _ => return Ok(None),
+        };
+        let contract_id = boot::boot_code_id(INFER_MODELS_NAME, mainnet);
+        let epoch = clarity_tx.get_epoch();
+        let entry = clarity_tx
+            .with_clarity_db_readonly(|db| {
+                match db.fetch_entry_unknown_descriptor(&contract_id, "models", &key, &epoch) {
+                    Ok(entry) => Ok(Some(entry)),
+                    Err(VmError::Unchecked(CheckErrors::NoSuchContract(_)))
+                    | Err(VmError::Unchecked(CheckErrors::NoSuchMap(_))) => Ok(None),
+                    Err(e) => Err(ClarityError::Interpreter(e)),
+                }
+            })
+            .map_err(Error::ClarityError)?;
+
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+        match entry.expect_optional()? {
+            Some(tuple) => Ok(Some(RegisteredInferModel::try_from_tuple(
+                model_id,
+                tuple.expect_tuple()?,
+            )?)),
+            None => Ok(None),
+        }
+    }
+
+    /// From Epoch 3.0, an Infer transaction that names a model must name one registered in
+    /// `.infer-models`, and ask it for parameters it supports.  Returns the registered model,
+    /// if the transaction names one.
+    pub fn check_infer_model<T: ClarityConnection>(
+        clarity_tx: &mut T,
+        mainnet: bool,
+        requested: &InferParams,
+    ) -> Result<Option<RegisteredInferModel>, Error> {
+        let Some(ref model_id) = requested.model else {
+            return Ok(None);
+        };
+        if !clarity_tx.get_epoch().supports_versioned_infer() {
+            return Ok(None);
+        }
+        let model = Self::get_registered_infer_model(clarity_tx, mainnet, model_id)?
+            .ok_or_else(|| format!("model {} is not registered", model_id))
+            .and_then(|model| model.check_request(requested).map(|_| model))
+            .map_err(|msg| Error::InvalidStacksTransaction(msg, false))?;
+        Ok(Some(model))
+    }
 }
 
 #[cfg(test)]
 pub mod contract_tests;
 #[cfg(test)]
+pub mod infer_models_tests;
+#[cfg(test)]
 pub mod pox_2_tests;
 #[cfg(test)]
 pub mod pox_3_tests;
@@ -129,6 +129,7 @@ pub enum MemPoolRejection {
     BadAddressVersionByte,
     NoCoinbaseViaMempool,
     NoTenureChangeViaMempool,
+    NoInferOutputsViaMempool,
     NoSuchChainTip(ConsensusHash, BlockHeaderHash),
     ConflictingNonceInMempool,
     TooMuchChaining {
@@ -143,6 +144,23 @@ pub enum MemPoolRejection {
     DBError(db_error),
     EstimatorError(EstimatorError),
     TemporarilyBlacklisted,
+    /// An Infer transaction whose request can't be run, e.g. because its context is malformed
+    BadInferRequest(String),
+    /// An Infer transaction whose fee doesn't meet the node's minimum fee rate per prompt byte
+    /// (actual, expected)
+    InferFeeTooLow(u64, u64),
+    /// An Infer transaction whose user input is larger than the node takes (actual, max)
+    InferPromptTooLarge(u64, u64),
+    /// An Infer transaction whose context is larger than the node takes (actual, max)
+    InferContextTooLarge(u64, u64),
+    /// The origin has sent as many Infer transactions as the node takes from it for now
+    InferRateLimited {
+        origin: StacksAddress,
+        max_txs: u32,
+        window_secs: u64,
+    },
+    /// An Infer transaction the node's content filter won't run
+    InferContentRejected(String),
     Other(String),
 }
 
@@ -288,6 +306,7 @@ impl MemPoolRejection {
             BadAddressVersionByte => ("BadAddressVersionByte", None),
             NoCoinbaseViaMempool => ("NoCoinbaseViaMempool", None),
             NoTenureChangeViaMempool => ("NoTenureChangeViaMempool", None),
+            NoInferOutputsViaMempool => ("NoInferOutputsViaMempool", None),
             // this should never happen via the RPC interface
             NoSuchChainTip(..) => ("ServerFailureNoSuchChainTip", None),
             DBError(e) => (
@@ -295,6 +314,38 @@ impl MemPoolRejection {
                 Some(json!({"message": e.to_string()})),
             ),
             TemporarilyBlacklisted => ("TemporarilyBlacklisted", None),
+            BadInferRequest(s) => ("BadInferRequest", Some(json!({ "message": s }))),
+            InferFeeTooLow(actual, expected) => (
+                "InferFeeTooLow",
+                Some(json!({
+                    "expected": expected,
+                    "actual": actual})),
+            ),
+            InferPromptTooLarge(actual, max) => (
+                "InferPromptTooLarge",
+                Some(json!({
+                    "max": max,
+                    "actual": actual})),
+            ),
+            InferContextTooLarge(actual, max) => (
+                "InferContextTooLarge",
+                Some(json!({
+                    "max": max,
+                    "actual": actual})),
+            ),
+            InferRateLimited {
+                origin,
+                max_txs,
+                window_secs,
+            } => (
+                "InferRateLimited",
+                Some(json!({
+                    "message": "Too many Infer transactions from this origin",
+                    "principal": origin.to_string(),
+                    "max_txs": max_txs,
+                    "window_secs": window_secs})),
+            ),
+            InferContentRejected(s) => ("InferContentRejected", Some(json!({ "message": s }))),
             Other(s) => ("ServerFailureOther", Some(json!({ "message": s }))),
         };
         let mut result = json!({
@@ -6504,7 +6555,7 @@ impl StacksChainState {
 
     /// Is the given address version currently supported?
     /// NOTE: not consensus-critical; only used for mempool admission
-    fn is_valid_address_version(mainnet: bool, version: u8) -> bool {
+    pub(crate) fn is_valid_address_version(mainnet: bool, version: u8) -> bool {
         if mainnet {
             version == C32_ADDRESS_VERSION_MAINNET_SINGLESIG
                 || version == C32_ADDRESS_VERSION_MAINNET_MULTISIG
@@ -6753,8 +6804,40 @@ impl StacksChainState {
 
         // 6: payload-specific checks
         match &tx.payload {
-            TransactionPayload::Infer(from, user_input, context) => {
-                // TODO do some infer check
+            TransactionPayload::Infer(..) | TransactionPayload::VersionedInfer(..) => {
+                if let TransactionPayload::VersionedInfer(..) = &tx.payload {
+                    if !clarity_connection.get_epoch().supports_versioned_infer() {
+                        return Err(MemPoolRejection::Other(
+                            "Versioned Infer transactions are not supported in this epoch"
+                                .to_string(),
+                        ));
+                    }
+                }
+                let request = tx
+                    .payload
+                    .infer_request()
+                    .expect("BUG: Infer payload without an Infer request");
+                let context = request
+                    .validate()
+                    .map_err(MemPoolRejection::BadInferRequest)?;
+                // the block will carry the output, so every node must be able to reproduce it
+                if clarity_connection.get_epoch().carries_infer_outputs() {
+                    context
+                        .params
+                        .check_reproducible()
+                        .map_err(MemPoolRejection::BadInferRequest)?;
+                }
+                StacksChainState::check_infer_model(
+                    clarity_connection,
+                    chainstate_config.mainnet,
+                    &context.params,
+                )
+                .map_err(|e| match e {
+                    Error::InvalidStacksTransaction(msg, _) => {
+                        MemPoolRejection::BadInferRequest(msg)
+                    }
+                    e => MemPoolRejection::Other(e.to_string()),
+                })?;
             }
             TransactionPayload::TokenTransfer(addr, amount, _memo) => {
                 // version byte matches?
@@ -6894,6 +6977,9 @@ impl StacksChainState {
             TransactionPayload::TenureChange(..) => {
                 return Err(MemPoolRejection::NoTenureChangeViaMempool)
             }
+            TransactionPayload::InferOutputs(..) => {
+                return Err(MemPoolRejection::NoInferOutputsViaMempool)
+            }
         };
 
         Ok(())
@@ -15,7 +15,7 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::btree_map::Entry;
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::prelude::*;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
@@ -35,6 +35,7 @@ use clarity::vm::representations::{ClarityName, ContractName};
 use clarity::vm::types::TupleData;
 use clarity::vm::{SymbolicExpression, Value};
 use lazy_static::lazy_static;
+use libllm::InferDB;
 use rusqlite::types::ToSql;
 use rusqlite::{Connection, OpenFlags, OptionalExtension, Row, Transaction, NO_PARAMS};
 use serde::de::Error as de_Error;
@@ -45,7 +46,7 @@ use stacks_common::util;
 use stacks_common::util::hash::{hex_bytes, to_hex};
 
 use crate::burnchains::bitcoin::address::{BitcoinAddress, LegacyBitcoinAddress};
-use crate::burnchains::{Address, Burnchain, BurnchainParameters, PoxConstants};
+use crate::burnchains::{Address, Burnchain, BurnchainParameters, PoxConstants, Txid};
 use crate::chainstate::burn::db::sortdb::{BlockHeaderCache, SortitionDB, SortitionDBConn, *};
 use crate::chainstate::burn::operations::{
     DelegateStxOp, StackStxOp, TransferStxOp, VoteForAggregateKeyOp,
@@ -123,6 +124,8 @@ pub struct StacksChainState {
     pub clarity_state_index_path: String, // path to clarity MARF
     pub clarity_state_index_root: String, // path to dir containing clarity MARF and side-store
     pub root_path: String,
+    /// inference tasks and their results, stored alongside the chainstate
+    pub infer_db: InferDB,
     pub unconfirmed_state: Option<UnconfirmedState>,
     pub fault_injection: StacksChainStateFaults,
     marf_opts: Option<MARFOpenOpts>,
@@ -470,6 +473,13 @@ pub type StacksDBConn<'a> = IndexDBConn<'a, (), StacksBlockId>;
 pub struct ClarityTx<'a, 'b> {
     block: ClarityBlockConnection<'a, 'b>,
     pub config: DBConfig,
+    /// where Infer transactions mined before 3.0 look up their results.  Boot code runs without
+    /// one.
+    pub infer_db: Option<InferDB>,
+    /// the outputs carried by the block's InferOutputs transactions so far, by the txid of the
+    /// Infer transaction each is the output of.  From 3.0 on, Infer transactions only take their
+    /// outputs from here.
+    pub infer_outputs: HashMap<Txid, InferOutput>,
 }
 
 impl<'a, 'b> ClarityConnection for ClarityTx<'a, 'b> {
@@ -599,6 +609,7 @@ pub struct ChainstateTx<'a> {
     pub blocks_path: String,
     pub tx: StacksDBTx<'a>,
     pub root_path: String,
+    pub infer_db: InferDB,
 }
 
 impl<'a> ChainstateTx<'a> {
@@ -607,12 +618,14 @@ impl<'a> ChainstateTx<'a> {
         blocks_path: String,
         root_path: String,
         config: DBConfig,
+        infer_db: InferDB,
     ) -> ChainstateTx<'a> {
         ChainstateTx {
             config,
             blocks_path,
             tx,
             root_path,
+            infer_db,
         }
     }
 
@@ -1262,6 +1275,7 @@ impl StacksChainState {
                         &boot_code_smart_contract,
                         &boot_code_account,
                         ASTRules::PrecheckSize,
+                        None,
                     )
                 })?;
                 receipts.push(tx_receipt);
@@ -1791,6 +1805,14 @@ impl StacksChainState {
 
         let state_index = StacksChainState::open_db(mainnet, chain_id, &header_index_root)?;
 
+        let infer_db_path = InferDB::db_path(path_str);
+        let infer_db = InferDB::open(&infer_db_path).map_err(|e| {
+            error!("Failed to open inference DB";
+                   "path" => &infer_db_path,
+                   "err" => %e);
+            Error::DBError(db_error::Other(e.to_string()))
+        })?;
+
         let vm_state = MarfedKV::open(
             &clarity_state_index_root,
             Some(&StacksBlockHeader::make_index_block_hash(
@@ -1813,6 +1835,7 @@ impl StacksChainState {
             clarity_state_index_path: clarity_state_index_marf,
             clarity_state_index_root: clarity_state_index_root,
             root_path: path_str.to_string(),
+            infer_db,
             unconfirmed_state: None,
             fault_injection: StacksChainStateFaults::new(),
             marf_opts: marf_opts,
@@ -1870,8 +1893,13 @@ impl StacksChainState {
         let clarity_instance = &mut self.clarity_state;
         let inner_tx = StacksDBTx::new(&mut self.state_index, ());
 
-        let chainstate_tx =
-            ChainstateTx::new(inner_tx, blocks_path, self.root_path.clone(), config);
+        let chainstate_tx = ChainstateTx::new(
+            inner_tx,
+            blocks_path,
+            self.root_path.clone(),
+            config,
+            self.infer_db.clone(),
+        );
 
         Ok((chainstate_tx, clarity_instance))
     }
@@ -1974,6 +2002,7 @@ impl StacksChainState {
         let conf = chainstate_tx.config.clone();
         StacksChainState::inner_clarity_tx_begin(
             conf,
+            Some(chainstate_tx.infer_db.clone()),
             chainstate_tx,
             clarity_instance,
             burn_dbconn,
@@ -1997,6 +2026,7 @@ impl StacksChainState {
         let conf = self.config();
         StacksChainState::inner_clarity_tx_begin(
             conf,
+            Some(self.infer_db.clone()),
             &self.state_index,
             &mut self.clarity_state,
             burn_dbconn,
@@ -2059,6 +2089,8 @@ impl StacksChainState {
         ClarityTx {
             block: inner_clarity_tx,
             config: conf,
+            infer_db: None,
+            infer_outputs: HashMap::new(),
         }
     }
 
@@ -2187,6 +2219,7 @@ impl StacksChainState {
     /// Begin an unconfirmed VM transaction, if there's no other open transaction for it.
     pub fn chainstate_begin_unconfirmed<'a, 'b>(
         conf: DBConfig,
+        infer_db: Option<InferDB>,
         headers_db: &'b dyn HeadersDB,
         clarity_instance: &'a mut ClarityInstance,
         burn_dbconn: &'b dyn BurnStateDB,
@@ -2196,6 +2229,8 @@ impl StacksChainState {
         ClarityTx {
             block: inner_clarity_tx,
             config: conf,
+            infer_db,
+            infer_outputs: HashMap::new(),
         }
     }
 
@@ -2213,6 +2248,7 @@ impl StacksChainState {
 
             Some(StacksChainState::chainstate_begin_unconfirmed(
                 conf,
+                Some(self.infer_db.clone()),
                 &self.state_index,
                 &mut unconfirmed.clarity_inst,
                 burn_dbconn,
@@ -2227,6 +2263,7 @@ impl StacksChainState {
     /// Create a Clarity VM database transaction
     fn inner_clarity_tx_begin<'a, 'b>(
         conf: DBConfig,
+        infer_db: Option<InferDB>,
         headers_db: &'b dyn HeadersDB,
         clarity_instance: &'a mut ClarityInstance,
         burn_dbconn: &'b dyn BurnStateDB,
@@ -2272,6 +2309,8 @@ impl StacksChainState {
         ClarityTx {
             block: inner_clarity_tx,
             config: conf,
+            infer_db,
+            infer_outputs: HashMap::new(),
         }
     }
 
@@ -2326,6 +2365,8 @@ impl StacksChainState {
         ClarityTx {
             block: inner_clarity_tx,
             config: conf,
+            infer_db: None,
+            infer_outputs: HashMap::new(),
         }
     }
 
@@ -2380,6 +2421,8 @@ impl StacksChainState {
         ClarityTx {
             block: inner_clarity_tx,
             config: conf,
+            infer_db: None,
+            infer_outputs: HashMap::new(),
         }
     }
 
@@ -29,8 +29,9 @@ use clarity::vm::contexts::{AssetMap, AssetMapEntry, Environment};
 use clarity::vm::contracts::Contract;
 use clarity::vm::costs::cost_functions::ClarityCostFunction;
 use clarity::vm::costs::{cost_functions, runtime_cost, CostTracker, ExecutionCost};
-use clarity::vm::database::{ClarityBackingStore, ClarityDatabase};
+use clarity::vm::database::{ClarityBackingStore, ClarityDatabase, InferResultRecord};
 use clarity::vm::errors::Error as InterpreterError;
+use clarity::vm::events::InferSessionLink;
 use clarity::vm::representations::{ClarityName, ContractName};
 use clarity::vm::types::serialization::SerializationError as ClaritySerializationError;
 use clarity::vm::types::{
@@ -38,6 +39,7 @@ use clarity::vm::types::{
     StacksAddressExtensions as ClarityStacksAddressExt, StandardPrincipalData, TupleData,
     TypeSignature, Value,
 };
+use libllm::{InferContext, InferCost, InferDB, InferPayment};
 use stacks_common::util::hash::to_hex;
 
 use crate::chainstate::burn::db::sortdb::*;
@@ -100,6 +102,8 @@ impl StacksTransactionReceipt {
         }
     }
 
+    /// `cost` includes the charge for the inference itself, and `events` any payment to the
+    /// inference provider.
     pub fn from_infer(
         tx: StacksTransaction,
         events: Vec<StacksTransactionEvent>,
@@ -334,6 +338,24 @@ impl StacksTransactionReceipt {
         }
     }
 
+    pub fn from_infer_outputs(
+        tx: StacksTransaction,
+        cost: ExecutionCost,
+    ) -> StacksTransactionReceipt {
+        StacksTransactionReceipt {
+            transaction: tx.into(),
+            events: vec![],
+            post_condition_aborted: false,
+            result: Value::okay_true(),
+            stx_burned: 0,
+            contract_analysis: None,
+            execution_cost: cost,
+            microblock_header: None,
+            tx_index: 0,
+            vm_error: None,
+        }
+    }
+
     pub fn is_coinbase_tx(&self) -> bool {
         if let TransactionOrigin::Stacks(ref transaction) = self.transaction {
             if let TransactionPayload::Coinbase(..) = transaction.payload {
@@ -381,6 +403,15 @@ impl From<TransactionNonceMismatch> for MemPoolRejection {
     }
 }
 
+/// What the Infer transactions in a block are checked against
+#[derive(Clone, Copy)]
+pub struct InferSource<'a> {
+    /// The node's own InferDB, whose output hashes Infer transactions mined before 3.0 commit
+    pub infer_db: Option<&'a InferDB>,
+    /// The outputs carried by the block's InferOutputs transactions so far (see `ClarityTx`)
+    pub outputs: &'a HashMap<Txid, InferOutput>,
+}
+
 pub enum ClarityRuntimeTxError {
     Acceptable {
         error: clarity_error,
@@ -983,6 +1014,7 @@ impl StacksChainState {
         tx: &StacksTransaction,
         origin_account: &StacksAccount,
         ast_rules: ASTRules,
+        infer: Option<InferSource>,
     ) -> Result<StacksTransactionReceipt, Error> {
         match tx.payload {
             TransactionPayload::TokenTransfer(ref addr, ref amount, ref memo) => {
@@ -1456,7 +1488,12 @@ impl StacksChainState {
                 let receipt = StacksTransactionReceipt::from_tenure_change(tx.clone());
                 Ok(receipt)
             }
-            TransactionPayload::Infer(ref from, ref userInput, ref context) => {
+            TransactionPayload::Infer(..) | TransactionPayload::VersionedInfer(..) => {
+                let request = tx
+                    .payload
+                    .infer_request()
+                    .expect("BUG: Infer payload without an Infer request");
+                let from = request.from;
                 if tx.post_conditions.len() > 0 {
                     let msg = format!("Invalid Stacks transaction: Infer transactions do not support post-conditions");
                     warn!("{}", &msg);
@@ -1464,53 +1501,340 @@ impl StacksChainState {
                     return Err(Error::InvalidStacksTransaction(msg, false));
                 }
 
-                let infer_res = libllm::query_hash(tx.txid().to_hex());
-
-                if let Ok(res) = infer_res {
-                    return match res.status {
-                        libllm::InferStatus::Success => {
-                            let infer_output_hash_bytes = hex::decode(res.output_hash)
-                                .map_err(|e| Error::InvalidStacksTransaction(e.to_string(), false))?;
-                            let cost_before = clarity_tx.cost_so_far();
-                            let (value, _asset_map, events) = clarity_tx.run_stx_infer(
-                                from,
-                                &BuffData {
-                                    data: infer_output_hash_bytes,
-                                },
-                            )
-                                .map_err(Error::ClarityError)?;
-
-                            let mut total_cost = clarity_tx.cost_so_far();
-                            total_cost
-                                .sub(&cost_before)
-                                .expect("BUG: total block cost decreased");
-
-                            let receipt = StacksTransactionReceipt::from_infer(
-                                tx.clone(),
-                                events,
-                                value,
-                                total_cost,
-                            );
-                            Ok(receipt)
-                        }
-                        libllm::InferStatus::NotFound => { //  theoretically, this should not happen
-                            let msg = format!("Infer task not found,res:{:?}", res);
-                            warn!("{}", &msg);
-                            Err(Error::InferTaskNotSuccess)
-                        }
-                        libllm::InferStatus::Created | libllm::InferStatus::InProgress |  libllm::InferStatus::Failure => {
-                            let msg = format!("Infer failed,res:{:?}", res);
-                            warn!("{}", &msg);
-                            Err(Error::InferTaskNotSuccess)
+                if !clarity_tx.get_epoch().carries_infer_outputs() {
+                    let infer_db = infer.and_then(|infer| infer.infer_db);
+                    return StacksChainState::process_infer_payload_from_db(
+                        clarity_tx, tx, from, infer_db,
+                    );
+                }
+
+                // the output must be reproducible, so that signers can check it
+                let context_str = request.context;
+                let infer_context = InferContext::parse(&context_str);
+                let parent_txid = infer_context.parent_txid().map_err(|e| {
+                    let msg = format!("Invalid Infer transaction {}: {}", tx.txid(), e);
+                    warn!("{}", &msg);
+                    Error::InvalidStacksTransaction(msg, false)
+                })?;
+                let requested_params = infer_context.params;
+                if let Err(e) = requested_params.check_reproducible() {
+                    let msg = format!("Invalid Infer transaction {}: {}", tx.txid(), e);
+                    warn!("{}", &msg);
+                    return Err(Error::InvalidStacksTransaction(msg, false));
+                }
+
+                let mainnet = clarity_tx.is_mainnet();
+                let registered_model =
+                    StacksChainState::check_infer_model(clarity_tx, mainnet, &requested_params)
+                        .map_err(|e| match e {
+                            Error::InvalidStacksTransaction(msg, _) => {
+                                let msg =
+                                    format!("Invalid Infer transaction {}: {}", tx.txid(), msg);
+                                warn!("{}", &msg);
+                                Error::InvalidStacksTransaction(msg, false)
+                            }
+                            e => e,
+                        })?;
+
+                let payment = match infer_context.payment {
+                    Some(payment) => Some(
+                        StacksChainState::check_infer_payment(tx, from, &payment).map_err(
+                            |msg| {
+                                let msg =
+                                    format!("Invalid Infer transaction {}: {}", tx.txid(), msg);
+                                warn!("{}", &msg);
+                                Error::InvalidStacksTransaction(msg, false)
+                            },
+                        )?,
+                    ),
+                    None => None,
+                };
+
+                // a session may only be continued from a turn committed in this fork
+                let parent = match parent_txid {
+                    Some(ref parent_txid) => Some(
+                        StacksChainState::check_infer_session_parent(clarity_tx, parent_txid)
+                            .map_err(|msg| {
+                                let msg =
+                                    format!("Invalid Infer transaction {}: {}", tx.txid(), msg);
+                                warn!("{}", &msg);
+                                Error::InvalidStacksTransaction(msg, false)
+                            })?,
+                    ),
+                    None => None,
+                };
+
+                // the output is the one the block carries, whatever this node's own InferDB says
+                let Some(carried) = infer.and_then(|infer| infer.outputs.get(&tx.txid())) else {
+                    let msg = format!(
+                        "Invalid Infer transaction {}: the block does not carry its output",
+                        tx.txid()
+                    );
+                    warn!("{}", &msg);
+                    return Err(Error::InvalidStacksTransaction(msg, false));
+                };
+                let output = carried.output.as_str();
+                let recorded_params = carried.params(&requested_params);
+                if !recorded_params.satisfies(&requested_params) {
+                    let msg = format!(
+                        "Invalid Infer transaction {}: output was not produced with the requested parameters",
+                        tx.txid()
+                    );
+                    warn!("{}", &msg;
+                          "requested" => ?requested_params,
+                          "recorded" => ?recorded_params);
+                    return Err(Error::InvalidStacksTransaction(msg, false));
+                }
+                if let Some(ref model) = registered_model {
+                    if let Err(reason) = model.check_ran(&recorded_params) {
+                        let msg = format!(
+                            "Invalid Infer transaction {}: output was not produced by registered model {}: {}",
+                            tx.txid(),
+                            &model.model_id,
+                            reason
+                        );
+                        warn!("{}", &msg);
+                        return Err(Error::InvalidStacksTransaction(msg, false));
+                    }
+                }
+
+                let infer_output_hash_bytes =
+                    hex::decode(libllm::output_hash(output)).expect("BUG: output hash is not hex");
+                let infer_cost = InferCost::new(&request.user_input, &context_str, output);
+                let session_hash = libllm::session::session_hash(
+                    parent
+                        .as_ref()
+                        .and_then(|parent| parent.session_hash.as_deref()),
+                    &request.user_input,
+                    &context_str,
+                    &infer_output_hash_bytes,
+                );
+                let session = InferSessionLink {
+                    parent_txid: parent_txid.map(|txid| BuffData {
+                        data: txid.to_vec(),
+                    }),
+                    session_hash: BuffData {
+                        data: session_hash.to_vec(),
+                    },
+                };
+
+                let cost_before = clarity_tx.cost_so_far();
+                let (value, _asset_map, events) = clarity_tx
+                    .run_stx_infer(
+                        from,
+                        &BuffData {
+                            data: infer_output_hash_bytes.clone(),
+                        },
+                        infer_cost.cost_units(),
+                        payment
+                            .as_ref()
+                            .map(|(recipient, amount)| (recipient, *amount)),
+                        Some(&session),
+                    )
+                    .map_err(Error::ClarityError)?;
+
+                // Clarity 3 contracts can read the result with `infer-result?`
+                let mut record = InferResultRecord::new(infer_output_hash_bytes, output);
+                record.session_hash = Some(session.session_hash.data);
+                clarity_tx
+                    .with_clarity_db(|db| Ok(db.set_infer_result(&tx.txid().0, &record)?))
+                    .map_err(Error::ClarityError)?;
+
+                let mut total_cost = clarity_tx.cost_so_far();
+                total_cost
+                    .sub(&cost_before)
+                    .expect("BUG: total block cost decreased");
+
+                let receipt =
+                    StacksTransactionReceipt::from_infer(tx.clone(), events, value, total_cost);
+                Ok(receipt)
+            }
+            TransactionPayload::InferOutputs(ref outputs) => {
+                if tx.post_conditions.len() > 0 {
+                    let msg = format!("Invalid Stacks transaction: InferOutputs transactions do not support post-conditions");
+                    warn!("{}", &msg);
+
+                    return Err(Error::InvalidStacksTransaction(msg, false));
+                }
+
+                if let Some(ref infer) = infer {
+                    StacksChainState::check_infer_outputs_unique(tx, outputs, infer)?;
+                }
+
+                let cost_before = clarity_tx.cost_so_far();
+                let cost = StacksChainState::infer_outputs_cost(tx, outputs);
+                if let Err(e) = clarity_tx.charge_cost(cost.clone()) {
+                    return match e {
+                        clarity_error::CostError(cost_after, budget) => {
+                            warn!("Block compute budget exceeded: if included, this will invalidate a block"; "txid" => %tx.txid(), "cost" => %cost_after, "budget" => %budget);
+                            Err(Error::CostOverflowError(cost_before, cost_after, budget))
                         }
+                        e => Err(Error::ClarityError(e)),
                     };
-                } else { // failed to query infer task status
-                    let msg = format!("Infer failed,res:{:?}", infer_res.err());
-                    warn!("{}", &msg);
-                    Err(Error::NetError(net_error::DeserializeError(msg)))
                 }
+
+                let receipt = StacksTransactionReceipt::from_infer_outputs(tx.clone(), cost);
+                Ok(receipt)
+            }
+        }
+    }
+
+    /// What it costs a block to carry an InferOutputs transaction.  The carried outputs are
+    /// stored with the block, so the transaction is charged like a write of its own size.
+    fn infer_outputs_cost(tx: &StacksTransaction, outputs: &[InferOutput]) -> ExecutionCost {
+        ExecutionCost {
+            write_length: tx.serialize_to_vec().len() as u64,
+            write_count: outputs.len() as u64,
+            read_length: 0,
+            read_count: 0,
+            runtime: 0,
+        }
+    }
+
+    /// Check that the `outputs` an InferOutputs transaction carries are each for a different Infer
+    /// transaction, and that the block has not already carried an output for any of them.
+    fn check_infer_outputs_unique(
+        tx: &StacksTransaction,
+        outputs: &[InferOutput],
+        infer: &InferSource,
+    ) -> Result<(), Error> {
+        let mut txids = HashSet::new();
+        for output in outputs.iter() {
+            if infer.outputs.contains_key(&output.txid) || !txids.insert(&output.txid) {
+                let msg = format!(
+                    "Invalid InferOutputs transaction {}: carries another output of Infer transaction {}",
+                    tx.txid(),
+                    &output.txid
+                );
+                warn!("{}", &msg);
+                return Err(Error::InvalidStacksTransaction(msg, false));
             }
         }
+        Ok(())
+    }
+
+    /// Process an Infer transaction mined before 3.0.  Blocks did not carry inference outputs
+    /// then, so the transaction commits the output hash of its task in this node's own InferDB,
+    /// which must have run it.  None of the checks that later epochs apply to Infer transactions
+    /// apply here, and neither its inference nor its payment (if any) is charged.
+    fn process_infer_payload_from_db(
+        clarity_tx: &mut ClarityTransactionConnection,
+        tx: &StacksTransaction,
+        from: &PrincipalData,
+        infer_db: Option<&InferDB>,
+    ) -> Result<StacksTransactionReceipt, Error> {
+        let Some(infer_db) = infer_db else {
+            let msg = format!(
+                "Cannot process Infer transaction {} without an inference DB",
+                tx.txid()
+            );
+            warn!("{}", &msg);
+            return Err(Error::InvalidStacksTransaction(msg, false));
+        };
+
+        let infer_res = libllm::query_hash(infer_db, tx.txid().to_hex());
+
+        if let Ok(res) = infer_res {
+            return match res.status {
+                libllm::InferStatus::Success => {
+                    let infer_output_hash_bytes = hex::decode(res.output_hash)
+                        .map_err(|e| Error::InvalidStacksTransaction(e.to_string(), false))?;
+                    let cost_before = clarity_tx.cost_so_far();
+                    let (value, _asset_map, events) = clarity_tx
+                        .run_stx_infer(
+                            from,
+                            &BuffData {
+                                data: infer_output_hash_bytes,
+                            },
+                            0,
+                            None,
+                            None,
+                        )
+                        .map_err(Error::ClarityError)?;
+
+                    let mut total_cost = clarity_tx.cost_so_far();
+                    total_cost
+                        .sub(&cost_before)
+                        .expect("BUG: total block cost decreased");
+
+                    let receipt =
+                        StacksTransactionReceipt::from_infer(tx.clone(), events, value, total_cost);
+                    Ok(receipt)
+                }
+                libllm::InferStatus::NotFound => {
+                    //  theoretically, this should not happen
+                    let msg = format!("Infer task not found,res:{:?}", res);
+                    warn!("{}", &msg);
+                    Err(Error::InferTaskNotSuccess)
+                }
+                libllm::InferStatus::Created
+                | libllm::InferStatus::InProgress
+                | libllm::InferStatus::Retrying
+                | libllm::InferStatus::Failure
+                | libllm::InferStatus::TimedOut
+                | libllm::InferStatus::Cancelled => {
+                    let msg = format!("Infer failed,res:{:?}", res);
+                    warn!("{}", &msg);
+                    Err(Error::InferTaskNotSuccess)
+                }
+            };
+        } else {
+            // failed to query infer task status
+            let msg = format!("Infer failed,res:{:?}", infer_res.err());
+            warn!("{}", &msg);
+            Err(Error::NetError(net_error::DeserializeError(msg)))
+        }
+    }
+
+    /// Check the STX payment an Infer transaction attaches to its context, and decode its
+    /// recipient.  The payment is drawn from the payload's `from` principal, so it must be the
+    /// transaction's origin.
+    pub fn check_infer_payment(
+        tx: &StacksTransaction,
+        from: &PrincipalData,
+        payment: &InferPayment,
+    ) -> Result<(PrincipalData, u128), String> {
+        if *from != PrincipalData::from(tx.origin_address()) {
+            return Err("an infer payment must be made by the transaction's origin".to_string());
+        }
+        let recipient = PrincipalData::parse(&payment.recipient)
+            .map_err(|e| format!("bad infer payment recipient: {:?}", e))?;
+        if recipient == *from {
+            return Err("infer payment recipient is the sender".to_string());
+        }
+        if payment.amount == 0 {
+            return Err("infer payment amount must be positive".to_string());
+        }
+        if !StacksChainState::is_valid_address_version(tx.is_mainnet(), recipient.version()) {
+            return Err("infer payment recipient is on a different network".to_string());
+        }
+        Ok((recipient, u128::from(payment.amount)))
+    }
+
+    /// Look up the committed result of `parent_txid`, the turn an Infer transaction continues.
+    /// Sessions are only supported from epoch 3.0 on, and can only continue an inference whose
+    /// session hash was committed.
+    pub fn check_infer_session_parent<T: ClarityConnection>(
+        clarity_tx: &mut T,
+        parent_txid: &[u8; 32],
+    ) -> Result<InferResultRecord, String> {
+        if !clarity_tx.get_epoch().supports_infer_sessions() {
+            return Err("infer sessions are not supported in this epoch".to_string());
+        }
+        let record = clarity_tx
+            .with_clarity_db_readonly(|db| db.get_infer_result(parent_txid))
+            .map_err(|e| format!("failed to load infer session parent: {:?}", e))?;
+        match record {
+            Some(record) if record.session_hash.is_some() => Ok(record),
+            Some(_) => Err(format!(
+                "infer session parent {} has no session hash",
+                to_hex(parent_txid)
+            )),
+            None => Err(format!(
+                "infer session parent {} was not committed",
+                to_hex(parent_txid)
+            )),
+        }
     }
 
     /// Deduce the Clarity version to run
@@ -1553,8 +1877,20 @@ impl StacksChainState {
                 return Err(Error::InvalidStacksTransaction(msg, false));
             }
         }
+        if clarity_version == ClarityVersion::Clarity3 {
+            // requires 3.0 and higher
+            if clarity_block.get_epoch() < StacksEpochId::Epoch30 {
+                let msg = format!("Invalid transaction {}: asks for Clarity3, but not in Stacks epoch 3.0 or later", tx.txid());
+                warn!("{}", &msg);
+                return Err(Error::InvalidStacksTransaction(msg, false));
+            }
+        }
 
-        let mut transaction = clarity_block.connection().start_transaction_processing();
+        let mut transaction = clarity_block.block.start_transaction_processing();
+        let infer = InferSource {
+            infer_db: clarity_block.infer_db.as_ref(),
+            outputs: &clarity_block.infer_outputs,
+        };
 
         let fee = tx.get_tx_fee();
         let tx_receipt = if epoch >= StacksEpochId::Epoch21 {
@@ -1575,6 +1911,7 @@ impl StacksChainState {
                 tx,
                 &origin_account,
                 ast_rules,
+                Some(infer),
             )?;
 
             // update the account nonces
@@ -1603,6 +1940,7 @@ impl StacksChainState {
                 tx,
                 &origin_account,
                 ast_rules,
+                Some(infer),
             )?;
 
             let new_payer_account = StacksChainState::get_payer_account(&mut transaction, tx);
@@ -1629,6 +1967,15 @@ impl StacksChainState {
             .commit()
             .map_err(|e| Error::InvalidStacksTransaction(e.to_string(), false))?;
 
+        // the Infer transactions that follow in the block are checked against these
+        if let TransactionPayload::InferOutputs(ref outputs) = tx.payload {
+            for output in outputs.iter() {
+                clarity_block
+                    .infer_outputs
+                    .insert(output.txid.clone(), output.clone());
+            }
+        }
+
         Ok((fee, tx_receipt))
     }
 }
@@ -1637,6 +1984,7 @@ impl StacksChainState {
 pub mod test {
     use clarity::vm::clarity::TransactionConnection;
     use clarity::vm::contracts::Contract;
+    use clarity::vm::events::STXEventType;
     use clarity::vm::representations::{ClarityName, ContractName};
     use clarity::vm::test_util::{UnitTestBurnStateDB, TEST_BURN_STATE_DB};
     use clarity::vm::tests::TEST_HEADER_DB;
@@ -1653,6 +2001,7 @@ pub mod test {
     use crate::chainstate::stacks::index::*;
     use crate::chainstate::stacks::{Error, *};
     use crate::chainstate::*;
+    use crate::util_lib::strings::{InferLPString, InferText};
 
     pub const TestBurnStateDB_20: UnitTestBurnStateDB = UnitTestBurnStateDB {
         epoch_id: StacksEpochId::Epoch20,
@@ -1747,6 +2096,7 @@ pub mod test {
                 stx_balance: STXBalance::Unlocked { amount: 100 },
             },
             ASTRules::PrecheckSize,
+            None,
         )
         .unwrap();
 
@@ -1887,6 +2237,354 @@ pub mod test {
         }
     }
 
+    fn make_infer_lp_string(s: &str) -> InferLPString {
+        let mut bytes = vec![s.len() as u8];
+        bytes.extend_from_slice(s.as_bytes());
+        InferLPString::consensus_deserialize(&mut &bytes[..]).unwrap()
+    }
+
+    #[test]
+    fn process_infer_transaction_payment_before_30() {
+        let mut chainstate = instantiate_chainstate(false, 0x80000000, function_name!());
+
+        let privk = StacksPrivateKey::from_hex(
+            "6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001",
+        )
+        .unwrap();
+        let auth = TransactionAuth::from_p2pkh(&privk).unwrap();
+        let addr = auth.origin().address_testnet();
+        let recv_addr = StacksAddress {
+            version: C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            bytes: Hash160([0xff; 20]),
+        };
+
+        let user_input = "Is the Earth round?";
+        let context = format!(
+            r#"{{"payment":{{"recipient":"{}","amount":123}}}}"#,
+            &recv_addr
+        );
+        let mut tx_infer = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth.clone(),
+            TransactionPayload::Infer(
+                addr.to_account_principal(),
+                make_infer_lp_string(user_input),
+                make_infer_lp_string(&context),
+            ),
+        );
+        tx_infer.chain_id = 0x80000000;
+        tx_infer.post_condition_mode = TransactionPostConditionMode::Allow;
+        tx_infer.set_tx_fee(0);
+
+        let mut signer = StacksTransactionSigner::new(&tx_infer);
+        signer.sign_origin(&privk).unwrap();
+        let signed_tx = signer.get_tx().unwrap();
+
+        // this node already ran the inference
+        let output = "Yes, the Earth is round.";
+        let params = libllm::InferParams {
+            model: Some("mock".to_string()),
+            ..libllm::InferParams::default()
+        };
+        {
+            let params = serde_json::to_string(&params).unwrap();
+            let conn = chainstate.infer_db.conn();
+            let txid = signed_tx.txid().to_hex();
+            libllm::db::sqlite_create(&conn, &txid, &context, user_input, 1, "", 0).unwrap();
+            libllm::db::sqlite_end_llm(
+                &conn,
+                &txid,
+                output,
+                &libllm::output_hash(output),
+                libllm::InferStatus::Success as u8,
+                &params,
+            )
+            .unwrap();
+        }
+
+        // neither the payment nor the inference itself is charged before 3.0
+        for (dbi, burn_db) in PRE_21_DBS.iter().enumerate() {
+            let mut conn = chainstate.block_begin(
+                burn_db,
+                &FIRST_BURNCHAIN_CONSENSUS_HASH,
+                &FIRST_STACKS_BLOCK_HASH,
+                &ConsensusHash([(dbi + 1) as u8; 20]),
+                &BlockHeaderHash([(dbi + 1) as u8; 32]),
+            );
+
+            conn.connection().as_transaction(|tx| {
+                StacksChainState::account_credit(tx, &addr.to_account_principal(), 223)
+            });
+
+            let (fee, receipt) = StacksChainState::process_transaction(
+                &mut conn,
+                &signed_tx,
+                false,
+                ASTRules::PrecheckSize,
+            )
+            .unwrap();
+            assert_eq!(fee, 0);
+
+            let account_after =
+                StacksChainState::get_account(&mut conn, &addr.to_account_principal());
+            assert_eq!(account_after.nonce, 1);
+            assert_eq!(account_after.stx_balance.amount_unlocked(), 223);
+
+            let recv_account_after =
+                StacksChainState::get_account(&mut conn, &recv_addr.to_account_principal());
+            assert_eq!(recv_account_after.stx_balance.amount_unlocked(), 0);
+
+            // only the commitment is in the receipt
+            assert_eq!(receipt.events.len(), 1);
+            assert!(matches!(
+                receipt.events[0],
+                StacksTransactionEvent::STXEvent(STXEventType::STXInferEvent(..))
+            ));
+
+            conn.commit_block();
+        }
+    }
+
+    #[test]
+    fn process_infer_transaction_session_before_30() {
+        let mut chainstate = instantiate_chainstate(false, 0x80000000, function_name!());
+
+        let privk = StacksPrivateKey::from_hex(
+            "6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001",
+        )
+        .unwrap();
+        let auth = TransactionAuth::from_p2pkh(&privk).unwrap();
+        let addr = auth.origin().address_testnet();
+
+        let parent_txid = [0xab; 32];
+        let context = format!(r#"{{"parent":"{}"}}"#, to_hex(&parent_txid));
+        let mut tx_infer = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth.clone(),
+            TransactionPayload::Infer(
+                addr.to_account_principal(),
+                make_infer_lp_string("Why?"),
+                make_infer_lp_string(&context),
+            ),
+        );
+        tx_infer.chain_id = 0x80000000;
+        tx_infer.post_condition_mode = TransactionPostConditionMode::Allow;
+        tx_infer.set_tx_fee(0);
+
+        let mut signer = StacksTransactionSigner::new(&tx_infer);
+        signer.sign_origin(&privk).unwrap();
+        let signed_tx = signer.get_tx().unwrap();
+        let output = "Because.";
+
+        for (dbi, burn_db) in ALL_BURN_DBS.iter().enumerate() {
+            let mut conn = chainstate.block_begin(
+                burn_db,
+                &FIRST_BURNCHAIN_CONSENSUS_HASH,
+                &FIRST_STACKS_BLOCK_HASH,
+                &ConsensusHash([(dbi + 1) as u8; 20]),
+                &BlockHeaderHash([(dbi + 1) as u8; 32]),
+            );
+
+            let err =
+                StacksChainState::check_infer_session_parent(&mut conn, &parent_txid).unwrap_err();
+            assert!(err.contains("not supported"), "{}", err);
+
+            // this node hasn't run the inference yet
+            match StacksChainState::process_transaction(
+                &mut conn,
+                &signed_tx,
+                false,
+                ASTRules::PrecheckSize,
+            ) {
+                Err(Error::InferTaskNotSuccess) => {}
+                res => panic!("Unexpected result {:?}", res.map(|(fee, _)| fee)),
+            }
+
+            {
+                let infer_db = conn.infer_db.as_ref().unwrap();
+                let conn = infer_db.conn();
+                let txid = signed_tx.txid().to_hex();
+                libllm::db::sqlite_create(&conn, &txid, &context, "Why?", 1, "", 0).unwrap();
+                libllm::db::sqlite_end_llm(
+                    &conn,
+                    &txid,
+                    output,
+                    &libllm::output_hash(output),
+                    libllm::InferStatus::Success as u8,
+                    "",
+                )
+                .unwrap();
+            }
+
+            // the parent is not checked, and the turn is not chained onto a session
+            let (_fee, receipt) = StacksChainState::process_transaction(
+                &mut conn,
+                &signed_tx,
+                false,
+                ASTRules::PrecheckSize,
+            )
+            .unwrap();
+            assert_eq!(receipt.events.len(), 1);
+            match receipt.events[0] {
+                StacksTransactionEvent::STXEvent(STXEventType::STXInferEvent(ref event)) => {
+                    assert!(event.session.is_none())
+                }
+                ref event => panic!("Unexpected event {:?}", event),
+            }
+
+            conn.commit_block();
+        }
+    }
+
+    #[test]
+    fn process_infer_transaction_carried_output() {
+        let privk = StacksPrivateKey::from_hex(
+            "6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001",
+        )
+        .unwrap();
+        let auth = TransactionAuth::from_p2pkh(&privk).unwrap();
+        let addr = auth.origin().address_testnet();
+
+        let tx_infer = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth.clone(),
+            TransactionPayload::Infer(
+                addr.to_account_principal(),
+                make_infer_lp_string("Is the Earth round?"),
+                make_infer_lp_string(""),
+            ),
+        );
+        let output = |txid: Txid, output: &str| InferOutput {
+            txid,
+            model: Some(InferText::try_from("mock").unwrap()),
+            model_digest: None,
+            output: InferText::try_from(output).unwrap(),
+        };
+        let tx_outputs = |outputs: Vec<InferOutput>| {
+            let mut tx = StacksTransaction::new(
+                TransactionVersion::Testnet,
+                auth.clone(),
+                TransactionPayload::InferOutputs(outputs),
+            );
+            tx.anchor_mode = TransactionAnchorMode::OnChainOnly;
+            tx
+        };
+        let outputs = HashMap::new();
+        let source = |outputs| InferSource {
+            infer_db: None,
+            outputs,
+        };
+
+        // an output may only be carried once per block
+        let carried = output(tx_infer.txid(), "Yes.");
+        let tx = tx_outputs(vec![carried.clone()]);
+        StacksChainState::check_infer_outputs_unique(&tx, &[carried.clone()], &source(&outputs))
+            .unwrap();
+        StacksChainState::check_infer_outputs_unique(
+            &tx,
+            &[carried.clone(), carried.clone()],
+            &source(&outputs),
+        )
+        .unwrap_err();
+
+        let mut outputs = HashMap::new();
+        outputs.insert(carried.txid.clone(), carried.clone());
+        StacksChainState::check_infer_outputs_unique(&tx, &[carried.clone()], &source(&outputs))
+            .unwrap_err();
+        let other = output(Txid([0x01; 32]), "No.");
+        StacksChainState::check_infer_outputs_unique(&tx, &[other.clone()], &source(&outputs))
+            .unwrap();
+
+        // carrying outputs is charged by size
+        let cost = StacksChainState::infer_outputs_cost(&tx, &[carried.clone()]);
+        assert_eq!(cost.write_length, tx.serialize_to_vec().len() as u64);
+        assert_eq!(cost.write_count, 1);
+        let long = output(tx_infer.txid(), &"a".repeat(1000));
+        let tx_long = tx_outputs(vec![long.clone(), other.clone()]);
+        let long_cost = StacksChainState::infer_outputs_cost(&tx_long, &[long, other]);
+        assert!(long_cost.write_length > cost.write_length + 1000);
+        assert_eq!(long_cost.write_count, 2);
+
+        // the recorded parameters are the requested ones, with the carried model
+        let requested = libllm::InferParams::default();
+        let params = carried.params(&requested);
+        assert_eq!(params.model.as_deref(), Some("mock"));
+        assert!(params.satisfies(&requested));
+    }
+
+    #[test]
+    fn process_infer_transaction_invalid_payment() {
+        let privk = StacksPrivateKey::from_hex(
+            "6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001",
+        )
+        .unwrap();
+        let auth = TransactionAuth::from_p2pkh(&privk).unwrap();
+        let addr = auth.origin().address_testnet();
+        let recv_addr = StacksAddress {
+            version: C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+            bytes: Hash160([0xff; 20]),
+        };
+        let tx = StacksTransaction::new(
+            TransactionVersion::Testnet,
+            auth,
+            TransactionPayload::Infer(
+                addr.to_account_principal(),
+                make_infer_lp_string("Is the Earth round?"),
+                make_infer_lp_string(""),
+            ),
+        );
+        let payment = |recipient: String, amount: u64| InferPayment { recipient, amount };
+
+        let (recipient, amount) = StacksChainState::check_infer_payment(
+            &tx,
+            &addr.to_account_principal(),
+            &payment(recv_addr.to_string(), 123),
+        )
+        .unwrap();
+        assert_eq!(recipient, recv_addr.to_account_principal());
+        assert_eq!(amount, 123);
+
+        // someone else's funds
+        assert!(StacksChainState::check_infer_payment(
+            &tx,
+            &recv_addr.to_account_principal(),
+            &payment(addr.to_string(), 123),
+        )
+        .is_err());
+        // paying oneself
+        assert!(StacksChainState::check_infer_payment(
+            &tx,
+            &addr.to_account_principal(),
+            &payment(addr.to_string(), 123),
+        )
+        .is_err());
+        // nothing to pay
+        assert!(StacksChainState::check_infer_payment(
+            &tx,
+            &addr.to_account_principal(),
+            &payment(recv_addr.to_string(), 0),
+        )
+        .is_err());
+        // not a principal
+        assert!(StacksChainState::check_infer_payment(
+            &tx,
+            &addr.to_account_principal(),
+            &payment("the miner".to_string(), 123),
+        )
+        .is_err());
+        // mainnet recipient
+        let mainnet_addr = StacksAddress {
+            version: C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
+            bytes: Hash160([0xff; 20]),
+        };
+        assert!(StacksChainState::check_infer_payment(
+            &tx,
+            &addr.to_account_principal(),
+            &payment(mainnet_addr.to_string(), 123),
+        )
+        .is_err());
+    }
+
     #[test]
     fn process_token_transfer_stx_transaction_invalid() {
         let mut chainstate = instantiate_chainstate(false, 0x80000000, function_name!());
@@ -267,6 +267,7 @@ impl UnconfirmedState {
             // leading to a node crash.
             let mut clarity_tx = StacksChainState::chainstate_begin_unconfirmed(
                 db_config,
+                Some(chainstate.infer_db.clone()),
                 &headers_db_conn,
                 &mut self.clarity_inst,
                 burn_dbconn,
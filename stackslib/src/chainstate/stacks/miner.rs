@@ -18,7 +18,8 @@ use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::ThreadId;
-use std::{cmp, fs, mem};
+use std::time::Duration;
+use std::{cmp, fs, mem, thread};
 
 use clarity::vm::analysis::{CheckError, CheckErrors};
 use clarity::vm::ast::errors::ParseErrors;
@@ -33,10 +34,10 @@ use stacks_common::types::chainstate::{
     BlockHeaderHash, BurnchainHeaderHash, StacksAddress, StacksBlockId, StacksWorkScore, TrieHash,
 };
 use stacks_common::types::StacksPublicKeyBuffer;
-use stacks_common::util::get_epoch_time_ms;
 use stacks_common::util::hash::{MerkleTree, Sha512Trunc256Sum};
 use stacks_common::util::secp256k1::{MessageSignature, Secp256k1PrivateKey};
 use stacks_common::util::vrf::*;
+use stacks_common::util::{get_epoch_time_ms, get_epoch_time_secs};
 
 use crate::burnchains::{Burnchain, PrivateKey, PublicKey};
 use crate::chainstate::burn::db::sortdb::{SortitionDB, SortitionDBConn, SortitionHandleTx};
@@ -1178,6 +1179,7 @@ impl<'a> StacksMicroblockBuilder<'a> {
         let mut block_limit_hit = BlockLimitFunction::NO_LIMIT_HIT;
 
         mem_pool.reset_nonce_cache()?;
+        mem_pool.reset_infer_skips();
         let stacks_epoch_id = clarity_tx.get_epoch();
         let block_limit = clarity_tx
             .block_limit()
@@ -2142,9 +2144,38 @@ impl StacksBlockBuilder {
         Ok(builder)
     }
 
+    /// If the mempool walks so far passed over Infer transactions whose inferences are still
+    /// pending (and not past their deadline), wait a little for them, unless it is already
+    /// `wait_deadline` (in ms).  Returns whether it waited, i.e. whether the mempool is worth
+    /// walking again.
+    fn wait_for_pending_inferences(
+        mempool: &MemPoolDB,
+        settings: &MemPoolWalkSettings,
+        wait_deadline: u128,
+    ) -> bool {
+        let now_secs = get_epoch_time_secs();
+        let pending = mempool.infer_skips().iter().any(|skip| {
+            skip.readiness() == InferReadiness::Pending
+                && !skip.is_expired(now_secs, settings.infer_pending_deadline_secs)
+        });
+        let now = get_epoch_time_ms();
+        if !pending || now >= wait_deadline {
+            return false;
+        }
+        let wait_ms = cmp::min(wait_deadline - now, u128::from(INFER_WAIT_POLL_MS));
+        debug!("Miner waiting {wait_ms}ms for pending inferences");
+        thread::sleep(Duration::from_millis(wait_ms as u64));
+        true
+    }
+
     /// Select transactions for block inclusion from the mempool.
     /// Applies them to the ongoing ClarityTx.
     /// If invalid transactions are encountered, they are dropped from the mempool.
+    /// Infer transactions are only mined once their inferences are done.  Once there is nothing
+    /// else to mine, the miner waits for pending inferences for up to `infer_wait_ms`, and
+    /// afterwards drops those whose inferences failed or missed their deadline.  From 3.0 on,
+    /// they are only mined if one of `initial_txs` carries their outputs, and the miner doesn't
+    /// wait.
     /// Returns whether or not the miner got blocked, as well as the gathered tx events
     pub fn select_and_apply_transactions<B: BlockBuilder>(
         epoch_tx: &mut ClarityTx,
@@ -2175,6 +2206,7 @@ impl StacksBlockBuilder {
         }
 
         mempool.reset_nonce_cache()?;
+        mempool.reset_infer_skips();
         mempool.estimate_tx_rates(100, &block_limit, &stacks_epoch_id)?;
 
         let mut block_limit_hit = BlockLimitFunction::NO_LIMIT_HIT;
@@ -2187,6 +2219,10 @@ impl StacksBlockBuilder {
         let mut infer_tx_retry_set = HashSet::new();
 
         let deadline = ts_start + u128::from(max_miner_time_ms);
+        let infer_wait_deadline = cmp::min(
+            deadline,
+            ts_start + u128::from(mempool_settings.infer_wait_ms),
+        );
         let mut num_txs = 0;
         let mut blocked = false;
 
@@ -2265,6 +2301,23 @@ impl StacksBlockBuilder {
                             }
                         }
 
+                        // from 3.0 on, an Infer transaction is only valid in a block that
+                        // carries its output, and this block's outputs are already chosen
+                        if stacks_epoch_id.carries_infer_outputs()
+                            && txinfo.tx.payload.infer_request().is_some()
+                            && !epoch_tx.infer_outputs.contains_key(&txinfo.tx.txid())
+                        {
+                            considered.insert(txinfo.tx.txid());
+                            return Ok(Some(
+                                TransactionResult::skipped(
+                                    &txinfo.tx,
+                                    "Infer transaction output is not carried by this block."
+                                        .to_string(),
+                                )
+                                .convert_to_event(),
+                            ));
+                        }
+
                         considered.insert(txinfo.tx.txid());
                         num_considered += 1;
 
@@ -2373,18 +2426,56 @@ impl StacksBlockBuilder {
                 }
 
                 if num_considered == 0 {
-                    break;
+                    // all that may be left are Infer transactions still waiting on inferences.
+                    // From 3.0 on, those can't join this block once they're done anyway.
+                    if blocked
+                        || block_limit_hit != BlockLimitFunction::NO_LIMIT_HIT
+                        || stacks_epoch_id.carries_infer_outputs()
+                        || !Self::wait_for_pending_inferences(
+                            mempool,
+                            &mempool_settings,
+                            infer_wait_deadline,
+                        )
+                    {
+                        break;
+                    }
                 }
             }
             debug!("Block transaction selection finished (parent height {}): {} transactions selected ({} considered)", &tip_height, num_txs, considered.len());
             intermediate_result
         };
 
+        // Infer transactions that were passed over: drop those that won't be ready in time
+        let now_secs = get_epoch_time_secs();
+        let mut infer_expired = vec![];
+        let mut infer_failed = vec![];
+        let mut infer_pending = 0;
+        for skip in mempool.infer_skips() {
+            if skip.readiness() == InferReadiness::Failed {
+                infer_failed.push(skip.txid);
+            } else if skip.is_expired(now_secs, mempool_settings.infer_pending_deadline_secs) {
+                infer_expired.push(skip.txid);
+            } else {
+                infer_pending += 1;
+            }
+        }
+        if infer_pending > 0 || !infer_expired.is_empty() || !infer_failed.is_empty() {
+            info!("Infer transactions not ready for block assembly";
+                  "parent_height" => tip_height,
+                  "pending" => infer_pending,
+                  "expired" => infer_expired.len(),
+                  "failed" => infer_failed.len());
+        }
+
         mempool.drop_txs(&invalidated_txs)?;
+        mempool.drop_txs(&infer_expired)?;
+        mempool.drop_txs(&infer_failed)?;
 
         if let Some(observer) = event_observer {
             observer.mempool_txs_dropped(invalidated_txs, MemPoolDropReason::TOO_EXPENSIVE);
             observer.mempool_txs_dropped(to_drop_and_blacklist, MemPoolDropReason::PROBLEMATIC);
+            observer.mempool_txs_dropped(infer_expired, MemPoolDropReason::INFER_EXPIRED);
+            observer.mempool_txs_dropped(infer_failed, MemPoolDropReason::INFER_FAILED);
         }
 
         if let Err(e) = result {
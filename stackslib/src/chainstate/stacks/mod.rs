@@ -27,6 +27,7 @@ use clarity::vm::types::{
     PrincipalData, QualifiedContractIdentifier, StandardPrincipalData, Value,
 };
 use clarity::vm::ClarityVersion;
+use libllm::{InferContext, InferParams, InferPayment};
 use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
 use rusqlite::{Error as RusqliteError, ToSql};
 use serde::{Deserialize, Serialize};
@@ -57,7 +58,7 @@ use crate::chainstate::stacks::index::Error as marf_error;
 use crate::clarity_vm::clarity::Error as clarity_error;
 use crate::net::Error as net_error;
 use crate::util_lib::db::{DBConn, Error as db_error};
-use crate::util_lib::strings::{InferLPString, StacksString};
+use crate::util_lib::strings::{InferLPString, InferText, StacksString};
 
 pub mod address;
 pub mod auth;
@@ -772,6 +773,216 @@ impl TenureChangePayload {
     }
 }
 
+define_u8_enum!(
+/// Who speaks a message in the context of a versioned Infer transaction
+InferMessageRole {
+    System = 0,
+    User = 1,
+    Assistant = 2
+});
+
+impl From<InferMessageRole> for libllm::ChatCompletionMessageRole {
+    fn from(role: InferMessageRole) -> Self {
+        match role {
+            InferMessageRole::System => libllm::ChatCompletionMessageRole::System,
+            InferMessageRole::User => libllm::ChatCompletionMessageRole::User,
+            InferMessageRole::Assistant => libllm::ChatCompletionMessageRole::Assistant,
+        }
+    }
+}
+
+impl TryFrom<libllm::ChatCompletionMessageRole> for InferMessageRole {
+    type Error = String;
+
+    fn try_from(role: libllm::ChatCompletionMessageRole) -> Result<Self, Self::Error> {
+        match role {
+            libllm::ChatCompletionMessageRole::System => Ok(InferMessageRole::System),
+            libllm::ChatCompletionMessageRole::User => Ok(InferMessageRole::User),
+            libllm::ChatCompletionMessageRole::Assistant => Ok(InferMessageRole::Assistant),
+            other => Err(format!("unsupported message role {:?}", other)),
+        }
+    }
+}
+
+/// A chat message that precedes the prompt of a versioned Infer transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferMessage {
+    pub role: InferMessageRole,
+    pub content: InferText,
+}
+
+/// Most chat messages a versioned Infer transaction may carry
+pub const MAX_INFER_MESSAGES: u32 = 256;
+
+/// An STX payment to the provider of an inference, made when the output is committed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferPaymentInfo {
+    pub recipient: PrincipalData,
+    pub amount: u64,
+}
+
+/// The payload of a versioned Infer transaction.
+///
+/// Unlike the original Infer payload, whose user input and JSON context are each at most 128
+/// bytes, the prompt and messages are u32-length-prefixed, and the context is typed and
+/// consensus-serialized, so a malformed one can't be mined.  Sampling is always greedy
+/// (temperature 0), so that every node gets the same output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionInfer {
+    /// The principal the inference is for
+    pub from: PrincipalData,
+    pub prompt: InferText,
+    /// Chat messages preceding the prompt, oldest first
+    pub messages: Vec<InferMessage>,
+    /// Model to run, if the sender cares which
+    pub model: Option<InferText>,
+    /// Digest of the model weights to run, if the sender cares which
+    pub model_digest: Option<InferText>,
+    pub seed: u64,
+    pub max_tokens: u32,
+    pub payment: Option<InferPaymentInfo>,
+    /// The Infer transaction whose conversation this one continues, if any
+    pub parent: Option<Txid>,
+}
+
+impl TransactionInfer {
+    /// The context of this inference, in the form `libllm::InferContext::parse` reads
+    pub fn to_infer_context(&self) -> InferContext {
+        let mut context = InferContext {
+            params: InferParams {
+                model: self.model.as_ref().map(|model| model.to_string()),
+                model_digest: self.model_digest.as_ref().map(|digest| digest.to_string()),
+                seed: self.seed,
+                temperature: 0.0,
+                max_tokens: self.max_tokens,
+            },
+            payment: self.payment.as_ref().map(|payment| InferPayment {
+                recipient: payment.recipient.to_string(),
+                amount: payment.amount,
+            }),
+            parent: self.parent.as_ref().map(|txid| txid.to_hex()),
+            ..InferContext::default()
+        };
+        for message in self.messages.iter() {
+            context.push_message(message.role.into(), message.content.as_str());
+        }
+        context
+    }
+
+    /// Build the payload that asks for `prompt`, with a context in the form
+    /// `libllm::InferContext::parse` reads.  Fails if the context can't be carried by a versioned
+    /// Infer payload (e.g. if it doesn't ask for greedy sampling, or has a function message).
+    pub fn from_infer_context(
+        from: PrincipalData,
+        prompt: &str,
+        context: &InferContext,
+    ) -> Result<TransactionInfer, String> {
+        context.params.check_deterministic()?;
+        if context.messages.len() > MAX_INFER_MESSAGES as usize {
+            return Err(format!(
+                "too many messages: {} > {}",
+                context.messages.len(),
+                MAX_INFER_MESSAGES
+            ));
+        }
+        let messages = context
+            .messages
+            .iter()
+            .map(|message| {
+                Ok(InferMessage {
+                    role: InferMessageRole::try_from(message.role)?,
+                    content: InferText::try_from(message.content.as_deref().unwrap_or(""))?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let payment = match context.payment {
+            Some(ref payment) => Some(InferPaymentInfo {
+                recipient: PrincipalData::parse(&payment.recipient)
+                    .map_err(|e| format!("bad payment recipient: {:?}", e))?,
+                amount: payment.amount,
+            }),
+            None => None,
+        };
+        let parent = context.parent_txid()?.map(Txid);
+        Ok(TransactionInfer {
+            from,
+            prompt: InferText::try_from(prompt)?,
+            messages,
+            model: context
+                .params
+                .model
+                .as_deref()
+                .map(InferText::try_from)
+                .transpose()?,
+            model_digest: context
+                .params
+                .model_digest
+                .as_deref()
+                .map(InferText::try_from)
+                .transpose()?,
+            seed: context.params.seed,
+            max_tokens: context.params.max_tokens,
+            payment,
+            parent,
+        })
+    }
+}
+
+/// The output of the inference an Infer transaction asks for, as the miner of its block commits
+/// it.  Every node checks the Infer transaction against this, rather than against its own
+/// InferDB.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferOutput {
+    /// The Infer transaction this is the output of
+    pub txid: Txid,
+    /// Model that produced the output
+    pub model: Option<InferText>,
+    /// Digest of the model weights that produced the output
+    pub model_digest: Option<InferText>,
+    pub output: InferText,
+}
+
+impl InferOutput {
+    /// The parameters the output was produced with, given those `requested`: the sampling
+    /// parameters are the requested ones, since any others would not have been reproducible.
+    pub fn params(&self, requested: &InferParams) -> InferParams {
+        InferParams {
+            model: self.model.as_ref().map(|model| model.to_string()),
+            model_digest: self.model_digest.as_ref().map(|digest| digest.to_string()),
+            ..requested.clone()
+        }
+    }
+}
+
+/// Most outputs an InferOutputs transaction may carry
+pub const MAX_INFER_OUTPUTS: u32 = 256;
+
+/// The inference an Infer transaction asks for, whichever payload it uses
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferRequest<'a> {
+    /// The principal the inference is for
+    pub from: &'a PrincipalData,
+    pub user_input: String,
+    /// The context, as JSON that `libllm::InferContext::parse` reads.  This is what the node's
+    /// InferDB records, and what the inference is charged for.
+    pub context: String,
+}
+
+impl<'a> InferRequest<'a> {
+    /// Check that the request can be run: that it has a prompt, and that its context is
+    /// well-formed, asks for a reproducible output, and names its session parent (if any) by
+    /// txid.  Returns the parsed context.
+    pub fn validate(&self) -> Result<InferContext, String> {
+        if self.user_input.is_empty() {
+            return Err("empty user input".to_string());
+        }
+        let context = InferContext::try_parse(&self.context)?;
+        context.params.check_deterministic()?;
+        context.parent_txid()?;
+        Ok(context)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TransactionPayload {
     TokenTransfer(PrincipalData, u64, TokenTransferMemo),
@@ -782,6 +993,9 @@ pub enum TransactionPayload {
     Coinbase(CoinbasePayload, Option<PrincipalData>, Option<VRFProof>),
     TenureChange(TenureChangePayload),
     Infer(PrincipalData, InferLPString, InferLPString), // inferUserAddress, userInput, context
+    VersionedInfer(TransactionInfer),
+    // the outputs of the Infer transactions that follow it in the block
+    InferOutputs(Vec<InferOutput>),
 }
 
 impl TransactionPayload {
@@ -809,6 +1023,26 @@ impl TransactionPayload {
                 TenureChangeCause::Extended => "TenureChange(Extension)",
             },
             TransactionPayload::Infer(..) => "Infer",
+            TransactionPayload::VersionedInfer(..) => "Infer(Versioned)",
+            TransactionPayload::InferOutputs(..) => "InferOutputs",
+        }
+    }
+
+    /// The inference this payload asks for, if it is an Infer payload
+    pub fn infer_request(&self) -> Option<InferRequest> {
+        match self {
+            TransactionPayload::Infer(from, user_input, context) => Some(InferRequest {
+                from,
+                user_input: user_input.to_string(),
+                context: context.to_string(),
+            }),
+            TransactionPayload::VersionedInfer(infer) => Some(InferRequest {
+                from: &infer.from,
+                user_input: infer.prompt.to_string(),
+                context: serde_json::to_string(&infer.to_infer_context())
+                    .expect("FATAL: failed to serialize Infer context"),
+            }),
+            _ => None,
         }
     }
 }
@@ -825,7 +1059,11 @@ define_u8_enum!(TransactionPayloadID {
     TenureChange = 7,
     // has a VRF proof, and may have an alt principal
     NakamotoCoinbase = 8,
-    Infer = 9
+    Infer = 9,
+    // u32-length-prefixed prompt, and typed context
+    VersionedInfer = 10,
+    // miner-authored; carries the outputs of the block's Infer transactions
+    InferOutputs = 11
 });
 
 /// Encoding of an asset type identifier
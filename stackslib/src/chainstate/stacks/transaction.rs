@@ -22,7 +22,9 @@ use clarity::vm::representations::{ClarityName, ContractName};
 use clarity::vm::types::serialization::SerializationError as clarity_serialization_error;
 use clarity::vm::types::{QualifiedContractIdentifier, StandardPrincipalData};
 use clarity::vm::{ClarityVersion, SymbolicExpression, SymbolicExpressionType, Value};
-use stacks_common::codec::{read_next, write_next, Error as codec_error, StacksMessageCodec};
+use stacks_common::codec::{
+    read_next, read_next_at_most, write_next, Error as codec_error, StacksMessageCodec,
+};
 use stacks_common::types::chainstate::StacksAddress;
 use stacks_common::types::StacksPublicKeyBuffer;
 use stacks_common::util::hash::{to_hex, MerkleHashFunc, MerkleTree, Sha512Trunc256Sum};
@@ -120,6 +122,7 @@ fn ClarityVersion_consensus_serialize<W: Write>(
     match *version {
         ClarityVersion::Clarity1 => write_next(fd, &1u8)?,
         ClarityVersion::Clarity2 => write_next(fd, &2u8)?,
+        ClarityVersion::Clarity3 => write_next(fd, &3u8)?,
     }
     Ok(())
 }
@@ -131,6 +134,7 @@ fn ClarityVersion_consensus_deserialize<R: Read>(
     match version_byte {
         1u8 => Ok(ClarityVersion::Clarity1),
         2u8 => Ok(ClarityVersion::Clarity2),
+        3u8 => Ok(ClarityVersion::Clarity3),
         _ => Err(codec_error::DeserializeError(format!(
             "Unrecognized ClarityVersion byte {}",
             &version_byte
@@ -216,6 +220,129 @@ impl StacksMessageCodec for TenureChangePayload {
     }
 }
 
+impl StacksMessageCodec for InferMessageRole {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &self.to_u8())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<InferMessageRole, codec_error> {
+        let byte: u8 = read_next(fd)?;
+        InferMessageRole::from_u8(byte).ok_or_else(|| {
+            codec_error::DeserializeError(format!("Unrecognized InferMessageRole byte {byte}"))
+        })
+    }
+}
+
+impl StacksMessageCodec for InferMessage {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &self.role)?;
+        write_next(fd, &self.content)
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<InferMessage, codec_error> {
+        Ok(InferMessage {
+            role: read_next(fd)?,
+            content: read_next(fd)?,
+        })
+    }
+}
+
+impl StacksMessageCodec for InferPaymentInfo {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &self.recipient)?;
+        write_next(fd, &self.amount)
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<InferPaymentInfo, codec_error> {
+        Ok(InferPaymentInfo {
+            recipient: read_next(fd)?,
+            amount: read_next(fd)?,
+        })
+    }
+}
+
+/// Optional fields of a versioned Infer payload are encoded as a 0x00 byte if absent, or a 0x01
+/// byte followed by the value if present.
+fn write_infer_option<W: Write, T: StacksMessageCodec>(
+    fd: &mut W,
+    value_opt: &Option<T>,
+) -> Result<(), codec_error> {
+    match value_opt {
+        None => write_next(fd, &0u8),
+        Some(value) => {
+            write_next(fd, &1u8)?;
+            write_next(fd, value)
+        }
+    }
+}
+
+fn read_infer_option<R: Read, T: StacksMessageCodec>(fd: &mut R) -> Result<Option<T>, codec_error> {
+    let flag: u8 = read_next(fd)?;
+    match flag {
+        0 => Ok(None),
+        1 => Ok(Some(read_next(fd)?)),
+        _ => Err(codec_error::DeserializeError(format!(
+            "Failed to parse Infer payload -- invalid option flag {flag}"
+        ))),
+    }
+}
+
+impl StacksMessageCodec for TransactionInfer {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &self.from)?;
+        write_next(fd, &self.prompt)?;
+        write_next(fd, &self.messages)?;
+        write_infer_option(fd, &self.model)?;
+        write_infer_option(fd, &self.model_digest)?;
+        write_next(fd, &self.seed)?;
+        write_next(fd, &self.max_tokens)?;
+        write_infer_option(fd, &self.payment)?;
+        write_infer_option(fd, &self.parent)
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<TransactionInfer, codec_error> {
+        let mut bound_read = BoundReader::from_reader(fd, u64::from(MAX_TRANSACTION_LEN));
+        let from = read_next(&mut bound_read)?;
+        let prompt = read_next(&mut bound_read)?;
+        let messages = read_next_at_most(&mut bound_read, MAX_INFER_MESSAGES)?;
+        let model = read_infer_option(&mut bound_read)?;
+        let model_digest = read_infer_option(&mut bound_read)?;
+        let seed = read_next(&mut bound_read)?;
+        let max_tokens = read_next(&mut bound_read)?;
+        let payment = read_infer_option(&mut bound_read)?;
+        let parent = read_infer_option(&mut bound_read)?;
+        Ok(TransactionInfer {
+            from,
+            prompt,
+            messages,
+            model,
+            model_digest,
+            seed,
+            max_tokens,
+            payment,
+            parent,
+        })
+    }
+}
+
+impl StacksMessageCodec for InferOutput {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &self.txid)?;
+        write_infer_option(fd, &self.model)?;
+        write_infer_option(fd, &self.model_digest)?;
+        write_next(fd, &self.output)
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<InferOutput, codec_error> {
+        Ok(InferOutput {
+            txid: read_next(fd)?,
+            model: read_infer_option(fd)?,
+            model_digest: read_infer_option(fd)?,
+            output: read_next(fd)?,
+        })
+    }
+}
+
 impl StacksMessageCodec for TransactionPayload {
     fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
         match self {
@@ -289,6 +416,14 @@ impl StacksMessageCodec for TransactionPayload {
                 write_next(fd, userInput)?;
                 write_next(fd, context)?;
             }
+            TransactionPayload::VersionedInfer(infer) => {
+                write_next(fd, &(TransactionPayloadID::VersionedInfer as u8))?;
+                infer.consensus_serialize(fd)?;
+            }
+            TransactionPayload::InferOutputs(outputs) => {
+                write_next(fd, &(TransactionPayloadID::InferOutputs as u8))?;
+                write_next(fd, outputs)?;
+            }
         }
         Ok(())
     }
@@ -387,6 +522,16 @@ impl StacksMessageCodec for TransactionPayload {
                 let context: InferLPString = read_next(fd)?;
                 TransactionPayload::Infer(principal, user_input, context)
             }
+            TransactionPayloadID::VersionedInfer => {
+                let payload: TransactionInfer = read_next(fd)?;
+                TransactionPayload::VersionedInfer(payload)
+            }
+            TransactionPayloadID::InferOutputs => {
+                let mut bound_read = BoundReader::from_reader(fd, u64::from(MAX_TRANSACTION_LEN));
+                let outputs: Vec<InferOutput> =
+                    read_next_at_most(&mut bound_read, MAX_INFER_OUTPUTS)?;
+                TransactionPayload::InferOutputs(outputs)
+            }
         };
 
         Ok(payload)
@@ -1245,6 +1390,7 @@ mod test {
     use crate::net::codec::test::check_codec_and_corruption;
     use crate::net::codec::*;
     use crate::net::*;
+    use crate::util_lib::strings::InferText;
 
     fn corrupt_auth_field(
         corrupt_auth_fields: &TransactionAuth,
@@ -1715,6 +1861,17 @@ mod test {
             TransactionPayload::Infer(ref addr, ref user_input, ref context) => {
                 TransactionPayload::Infer(addr.clone(), user_input.clone(), context.clone())
             }
+            TransactionPayload::VersionedInfer(ref infer) => {
+                TransactionPayload::VersionedInfer(TransactionInfer {
+                    seed: infer.seed.wrapping_add(1),
+                    ..infer.clone()
+                })
+            }
+            TransactionPayload::InferOutputs(ref outputs) => {
+                let mut corrupt_outputs = outputs.clone();
+                corrupt_outputs.pop();
+                TransactionPayload::InferOutputs(corrupt_outputs)
+            }
         };
         assert!(corrupt_tx_payload.txid() != signed_tx.txid());
 
@@ -3188,6 +3345,180 @@ mod test {
         );
     }
 
+    #[test]
+    fn tx_stacks_transaction_payload_versioned_infer() {
+        let from = PrincipalData::from(StacksAddress {
+            version: 1,
+            bytes: Hash160([0xff; 20]),
+        });
+        let recipient = PrincipalData::from(StacksAddress {
+            version: 1,
+            bytes: Hash160([0xee; 20]),
+        });
+        // too long for the original Infer payload
+        let prompt = "Is the Earth round? ".repeat(20);
+
+        let infer = TransactionInfer {
+            from: from.clone(),
+            prompt: InferText::try_from(prompt.as_str()).unwrap(),
+            messages: vec![
+                InferMessage {
+                    role: InferMessageRole::System,
+                    content: InferText::try_from("be brief").unwrap(),
+                },
+                InferMessage {
+                    role: InferMessageRole::Assistant,
+                    content: InferText::try_from("ok").unwrap(),
+                },
+            ],
+            model: Some(InferText::try_from("llama3").unwrap()),
+            model_digest: None,
+            seed: 42,
+            max_tokens: 64,
+            payment: Some(InferPaymentInfo {
+                recipient: recipient.clone(),
+                amount: 1000,
+            }),
+            parent: Some(Txid([0x07; 32])),
+        };
+
+        // wire encoding of the same
+        let mut infer_bytes = vec![TransactionPayloadID::VersionedInfer as u8];
+        from.consensus_serialize(&mut infer_bytes).unwrap();
+        infer_bytes.extend_from_slice(&(prompt.len() as u32).to_be_bytes());
+        infer_bytes.extend_from_slice(prompt.as_bytes());
+        infer_bytes.extend_from_slice(&2u32.to_be_bytes());
+        infer_bytes.push(InferMessageRole::System as u8);
+        infer_bytes.extend_from_slice(&8u32.to_be_bytes());
+        infer_bytes.extend_from_slice("be brief".as_bytes());
+        infer_bytes.push(InferMessageRole::Assistant as u8);
+        infer_bytes.extend_from_slice(&2u32.to_be_bytes());
+        infer_bytes.extend_from_slice("ok".as_bytes());
+        infer_bytes.push(1);
+        infer_bytes.extend_from_slice(&6u32.to_be_bytes());
+        infer_bytes.extend_from_slice("llama3".as_bytes());
+        infer_bytes.push(0);
+        infer_bytes.extend_from_slice(&42u64.to_be_bytes());
+        infer_bytes.extend_from_slice(&64u32.to_be_bytes());
+        infer_bytes.push(1);
+        recipient.consensus_serialize(&mut infer_bytes).unwrap();
+        infer_bytes.extend_from_slice(&1000u64.to_be_bytes());
+        infer_bytes.push(1);
+        infer_bytes.extend_from_slice(&[0x07; 32]);
+
+        let payload = TransactionPayload::VersionedInfer(infer.clone());
+        check_codec_and_corruption::<TransactionPayload>(&payload, &infer_bytes);
+
+        // what the node runs
+        let request = payload.infer_request().unwrap();
+        assert_eq!(request.from, &from);
+        assert_eq!(request.user_input, prompt);
+        let context = request.validate().unwrap();
+        assert_eq!(context, infer.to_infer_context());
+        assert_eq!(context.messages.len(), 2);
+        assert_eq!(context.messages[0].content, Some("be brief".to_string()));
+        assert_eq!(context.params.model, Some("llama3".to_string()));
+        assert_eq!(context.params.seed, 42);
+        assert_eq!(context.params.max_tokens, 64);
+        assert_eq!(context.params.temperature, 0.0);
+        assert_eq!(context.payment.unwrap().recipient, recipient.to_string());
+        assert_eq!(context.parent, Some("07".repeat(32)));
+
+        // unknown roles and option flags are rejected
+        let role_offset = 1 + from.serialize_to_vec().len() + 4 + prompt.len() + 4;
+        let mut bad_role = infer_bytes.clone();
+        bad_role[role_offset] = 3;
+        assert!(
+            TransactionPayload::consensus_deserialize(&mut &bad_role[..])
+                .unwrap_err()
+                .to_string()
+                .contains("InferMessageRole")
+        );
+
+        let flag_offset = role_offset + (1 + 4 + 8) + (1 + 4 + 2);
+        let mut bad_flag = infer_bytes.clone();
+        bad_flag[flag_offset] = 2;
+        assert!(
+            TransactionPayload::consensus_deserialize(&mut &bad_flag[..])
+                .unwrap_err()
+                .to_string()
+                .contains("invalid option flag")
+        );
+
+        // a request that can't produce a reproducible output is invalid
+        let no_tokens = TransactionPayload::VersionedInfer(TransactionInfer {
+            max_tokens: 0,
+            ..infer.clone()
+        });
+        assert!(no_tokens.infer_request().unwrap().validate().is_err());
+        let no_prompt = TransactionPayload::VersionedInfer(TransactionInfer {
+            prompt: InferText::try_from("").unwrap(),
+            ..infer.clone()
+        });
+        assert!(no_prompt.infer_request().unwrap().validate().is_err());
+    }
+
+    #[test]
+    fn tx_stacks_transaction_payload_infer_outputs() {
+        let outputs = vec![
+            InferOutput {
+                txid: Txid([0x01; 32]),
+                model: Some(InferText::try_from("llama3").unwrap()),
+                model_digest: Some(InferText::try_from("sha256:01").unwrap()),
+                output: InferText::try_from("Yes.").unwrap(),
+            },
+            InferOutput {
+                txid: Txid([0x02; 32]),
+                model: None,
+                model_digest: None,
+                output: InferText::try_from("").unwrap(),
+            },
+        ];
+
+        // wire encoding of the same
+        let mut outputs_bytes = vec![TransactionPayloadID::InferOutputs as u8];
+        outputs_bytes.extend_from_slice(&2u32.to_be_bytes());
+        outputs_bytes.extend_from_slice(&[0x01; 32]);
+        outputs_bytes.push(1);
+        outputs_bytes.extend_from_slice(&6u32.to_be_bytes());
+        outputs_bytes.extend_from_slice("llama3".as_bytes());
+        outputs_bytes.push(1);
+        outputs_bytes.extend_from_slice(&9u32.to_be_bytes());
+        outputs_bytes.extend_from_slice("sha256:01".as_bytes());
+        outputs_bytes.extend_from_slice(&4u32.to_be_bytes());
+        outputs_bytes.extend_from_slice("Yes.".as_bytes());
+        outputs_bytes.extend_from_slice(&[0x02; 32]);
+        outputs_bytes.push(0);
+        outputs_bytes.push(0);
+        outputs_bytes.extend_from_slice(&0u32.to_be_bytes());
+
+        let payload = TransactionPayload::InferOutputs(outputs.clone());
+        check_codec_and_corruption::<TransactionPayload>(&payload, &outputs_bytes);
+        assert!(payload.infer_request().is_none());
+
+        // the sampling parameters are always the requested ones
+        let requested = libllm::InferParams {
+            seed: 42,
+            max_tokens: 64,
+            ..libllm::InferParams::default()
+        };
+        let params = outputs[0].params(&requested);
+        assert_eq!(params.model, Some("llama3".to_string()));
+        assert_eq!(params.model_digest, Some("sha256:01".to_string()));
+        assert_eq!(params.seed, 42);
+        assert_eq!(params.max_tokens, 64);
+        assert!(params.satisfies(&requested));
+
+        // too many outputs
+        let mut too_many_bytes = vec![TransactionPayloadID::InferOutputs as u8];
+        too_many_bytes.extend_from_slice(&(MAX_INFER_OUTPUTS + 1).to_be_bytes());
+        for i in 0..=MAX_INFER_OUTPUTS {
+            too_many_bytes.extend_from_slice(&[i as u8; 32]);
+            too_many_bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        }
+        assert!(TransactionPayload::consensus_deserialize(&mut &too_many_bytes[..]).is_err());
+    }
+
     #[test]
     fn tx_stacks_transaction_payload_invalid() {
         let hello_contract_call = "hello-contract-call";
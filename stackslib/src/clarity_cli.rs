@@ -21,6 +21,8 @@ use std::str::FromStr;
 use std::{env, fs, io, process};
 
 use clarity::vm::coverage::CoverageReporter;
+use clarity::vm::debugger::{BreakpointSpec, Debugger};
+use clarity::vm::profiler::{CostMetric, CostProfiler};
 use lazy_static::lazy_static;
 use rand::Rng;
 use rusqlite::types::ToSql;
@@ -33,7 +35,7 @@ use stacks_common::consts::{CHAIN_ID_MAINNET, CHAIN_ID_TESTNET};
 use stacks_common::types::chainstate::{
     BlockHeaderHash, BurnchainHeaderHash, ConsensusHash, StacksAddress, StacksBlockId, VRFSeed, *,
 };
-use stacks_common::util::hash::{bytes_to_hex, Hash160, Sha512Trunc256Sum};
+use stacks_common::util::hash::{bytes_to_hex, hex_bytes, Hash160, Sha512Trunc256Sum};
 use stacks_common::util::{get_epoch_time_ms, log};
 
 use crate::burnchains::{Address, PoxConstants, Txid};
@@ -46,7 +48,9 @@ use crate::chainstate::stacks::boot::{
 use crate::chainstate::stacks::index::storage::TrieFileStorage;
 use crate::chainstate::stacks::index::{ClarityMarfTrieId, MarfTrieId};
 use crate::clarity::vm::analysis::contract_interface_builder::build_contract_interface;
+use crate::clarity::vm::analysis::cost_bound_checker::CostBoundChecker;
 use crate::clarity::vm::analysis::errors::{CheckError, CheckResult};
+use crate::clarity::vm::analysis::lint_checker::LintChecker;
 use crate::clarity::vm::analysis::{AnalysisDatabase, ContractAnalysis};
 use crate::clarity::vm::ast::{build_ast_with_rules, ASTRules};
 use crate::clarity::vm::contexts::{AssetMap, GlobalContext, OwnedEnvironment};
@@ -55,7 +59,9 @@ use crate::clarity::vm::database::{
     BurnStateDB, ClarityDatabase, HeadersDB, STXBalance, SqliteConnection, NULL_BURN_STATE_DB,
 };
 use crate::clarity::vm::errors::{Error, InterpreterResult, RuntimeErrorType};
-use crate::clarity::vm::types::{OptionalData, PrincipalData, QualifiedContractIdentifier};
+use crate::clarity::vm::types::{
+    BuffData, OptionalData, PrincipalData, QualifiedContractIdentifier,
+};
 use crate::clarity::vm::{
     analysis, ast, eval_all, ClarityVersion, ContractContext, ContractName, SymbolicExpression,
     SymbolicExpressionType, Value,
@@ -113,6 +119,12 @@ where command is one of:
 
   initialize         to initialize a local VM state database.
   check              to typecheck a potential contract definition.
+                     With `--lint`, also warn about likely mistakes, such as unchecked
+                     responses and unused definitions.
+                     With `--cost_bounds`, also report the worst-case cost of calling each
+                     public and read-only function.  This is a single number per function,
+                     for arguments as long as their types allow; it is not a function of the
+                     actual argument lengths, so shorter arguments may cost much less.
   launch             to launch a initialize a new contract in the local state database.
   eval               to evaluate (in read-only mode) a program in a given contract context.
   eval_at_chaintip   like `eval`, but does not advance to a new block.
@@ -120,7 +132,15 @@ where command is one of:
                      must be passed eval string via stdin.
   eval_raw           to typecheck and evaluate an expression without a contract or database context.
   repl               to typecheck and evaluate expressions in a stdin/stdout loop.
+                     `eval`, `eval_at_chaintip`, `execute` and `repl` accept `--debug` to
+                     step through evaluation interactively.
+                     `eval`, `eval_at_chaintip`, `launch` and `execute` accept `--profile <file>`
+                     to write the cost of each function call as collapsed stacks, or as a
+                     speedscope profile if <file> ends in `.json`.
   execute            to execute a public function of a defined contract.
+  infer              to commit a mock inference output, as an Infer transaction would.
+  make_lcov          to merge the coverage recorded with `--c <folder>` into an LCOV file,
+                     with `--html <file>` also writing an annotated HTML report.
   generate_address   to generate a random Stacks public address for testing purposes.
 ",
         invoked_by
@@ -232,6 +252,7 @@ fn run_analysis<C: ClarityStorage>(
     header_db: &CLIHeadersDB,
     marf_kv: &mut C,
     save_contract: bool,
+    build_type_map: bool,
 ) -> Result<ContractAnalysis, (CheckError, LimitedCostTracker)> {
     let mainnet = header_db.is_mainnet();
     let clarity_version = ClarityVersion::default_for_epoch(DEFAULT_CLI_EPOCH);
@@ -255,8 +276,8 @@ fn run_analysis<C: ClarityStorage>(
         cost_track,
         DEFAULT_CLI_EPOCH,
         clarity_version,
-        // no type map data is used in the clarity_cli
-        false,
+        // the type map is only used to compute cost bounds
+        build_type_map,
     )
 }
 
@@ -417,6 +438,8 @@ fn with_env_costs<F, R>(
     header_db: &CLIHeadersDB,
     marf: &mut WritableMarfStore,
     coverage: Option<&mut CoverageReporter>,
+    debugger: Option<&mut Debugger>,
+    profiler: Option<&mut CostProfiler>,
     f: F,
 ) -> (R, ExecutionCost)
 where
@@ -445,6 +468,12 @@ where
     if let Some(coverage) = coverage {
         vm_env.add_eval_hook(coverage);
     }
+    if let Some(debugger) = debugger {
+        vm_env.add_eval_hook(debugger);
+    }
+    if let Some(profiler) = profiler {
+        vm_env.add_eval_hook(profiler);
+    }
     let result = f(&mut vm_env);
     let cost = vm_env.get_cost_total();
     (result, cost)
@@ -485,9 +514,17 @@ fn save_coverage(
 ) {
     match (coverage_folder, coverage) {
         (Some(coverage_folder), Some(coverage)) => {
-            let mut coverage_file = PathBuf::from(coverage_folder);
-            coverage_file.push(&format!("{}_{}", prefix, get_epoch_time_ms()));
+            // runs are merged by `make_lcov`, so never overwrite an earlier run's file
+            let time = get_epoch_time_ms();
+            let mut coverage_file = PathBuf::from(&coverage_folder);
+            coverage_file.push(&format!("{}_{}", prefix, time));
             coverage_file.set_extension("clarcov");
+            let mut run = 1;
+            while coverage_file.exists() {
+                coverage_file.set_file_name(&format!("{}_{}_{}", prefix, time, run));
+                coverage_file.set_extension("clarcov");
+                run += 1;
+            }
 
             coverage
                 .to_file(&coverage_file)
@@ -499,6 +536,15 @@ fn save_coverage(
     }
 }
 
+fn save_profile(profile: Option<(String, CostMetric)>, profiler: Option<CostProfiler>) {
+    if let (Some((profile_file, metric)), Some(profiler)) = (profile, profiler) {
+        friendly_expect(
+            profiler.to_file(&profile_file, metric),
+            "Cost profile generation failure",
+        );
+    }
+}
+
 struct CLIHeadersDB {
     db_path: String,
     conn: Connection,
@@ -735,7 +781,7 @@ impl HeadersDB for CLIHeadersDB {
 fn get_eval_input(invoked_by: &str, args: &[String]) -> EvalInput {
     if args.len() < 3 || args.len() > 4 {
         eprintln!(
-            "Usage: {} {} [--costs] [contract-identifier] (program.clar) [vm-state.db]",
+            "Usage: {} {} [--costs] [--debug] [--break <line|contract:line|function>] [--watch <expr>] [--profile <file>] [--profile-metric <metric>] [contract-identifier] (program.clar) [vm-state.db]",
             invoked_by, args[0]
         );
         panic_test!();
@@ -820,6 +866,57 @@ fn consume_arg(
     }
 }
 
+/// Consume the `--debug`, `--break <spec>` and `--watch <expr>` switches, and build the step
+/// debugger they ask for, if any.  With breakpoints, execution runs until the first one is hit;
+/// otherwise it pauses at the first expression.
+fn consume_debugger_args(args: &mut Vec<String>) -> Option<Debugger> {
+    let debug = matches!(consume_arg(args, &["--debug"], false), Ok(Some(_)));
+    let mut breakpoints = vec![];
+    while let Ok(Some(spec)) = consume_arg(args, &["--break"], true) {
+        breakpoints.push(spec);
+    }
+    let mut watches = vec![];
+    while let Ok(Some(expr)) = consume_arg(args, &["--watch"], true) {
+        watches.push(expr);
+    }
+    if !debug && breakpoints.is_empty() && watches.is_empty() {
+        return None;
+    }
+
+    let mut debugger = Debugger::stdio();
+    for spec in breakpoints.iter() {
+        let parsed = friendly_expect(spec.parse::<BreakpointSpec>(), "Invalid breakpoint");
+        if matches!(parsed, BreakpointSpec::Line { .. }) && !cfg!(feature = "developer-mode") {
+            eprintln!(
+                "Warning: line breakpoint {} will not be hit; line numbers require the `developer-mode` feature",
+                spec
+            );
+        }
+        friendly_expect(debugger.add_breakpoint(spec), "Invalid breakpoint");
+    }
+    if !breakpoints.is_empty() {
+        debugger.run_to_breakpoint();
+    }
+    for expr in watches.iter() {
+        debugger.add_watch(expr);
+    }
+    Some(debugger)
+}
+
+/// Consume the `--profile <file>` and `--profile-metric <metric>` switches.  Returns the file to
+/// write a cost profile to, if any, and the metric to write when it's in collapsed-stack form.
+fn consume_profile_args(args: &mut Vec<String>) -> Option<(String, CostMetric)> {
+    let profile_file = match consume_arg(args, &["--profile"], true) {
+        Ok(profile_file) => profile_file,
+        Err(_) => None,
+    };
+    let metric = match consume_arg(args, &["--profile-metric"], true) {
+        Ok(Some(metric)) => friendly_expect(metric.parse::<CostMetric>(), "Invalid cost metric"),
+        _ => CostMetric::Runtime,
+    };
+    profile_file.map(|profile_file| (profile_file, metric))
+}
+
 /// This function uses Clarity1 to parse the boot code.
 fn install_boot_code<C: ClarityStorage>(header_db: &CLIHeadersDB, marf: &mut C) {
     let mainnet = header_db.is_mainnet();
@@ -1077,7 +1174,7 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
         "check" => {
             if args.len() < 2 {
                 eprintln!(
-                    "Usage: {} {} [program-file.clar] [--contract_id CONTRACT_ID] [--output_analysis] [--costs] [--testnet] (vm-state.db)",
+                    "Usage: {} {} [program-file.clar] [--contract_id CONTRACT_ID] [--output_analysis] [--costs] [--lint] [--cost_bounds] [--testnet] (vm-state.db)",
                     invoked_by, args[0]
                 );
                 panic_test!();
@@ -1112,6 +1209,19 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                 false
             };
 
+            let lint = if let Ok(Some(_)) = consume_arg(&mut argv, &["--lint"], false) {
+                true
+            } else {
+                false
+            };
+
+            let cost_bounds = if let Ok(Some(_)) = consume_arg(&mut argv, &["--cost_bounds"], false)
+            {
+                true
+            } else {
+                false
+            };
+
             // NOTE: ignored if we're using a DB
             let mut testnet_given = false;
             let mainnet = if let Ok(Some(_)) = consume_arg(&mut argv, &["--testnet"], false) {
@@ -1157,8 +1267,14 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                     );
 
                     let result = at_chaintip(&argv[2], marf_kv, |mut marf| {
-                        let result =
-                            run_analysis(&contract_id, &mut ast, &header_db, &mut marf, false);
+                        let result = run_analysis(
+                            &contract_id,
+                            &mut ast,
+                            &header_db,
+                            &mut marf,
+                            false,
+                            cost_bounds,
+                        );
                         (marf, result)
                     });
                     result
@@ -1173,6 +1289,7 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                         &header_db,
                         &mut analysis_marf,
                         false,
+                        cost_bounds,
                     )
                 }
             };
@@ -1195,17 +1312,22 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                 "message": "Checks passed."
             });
 
-            add_costs(
-                &mut result,
-                costs,
-                contract_analysis.take_contract_cost_tracker().get_total(),
-            );
+            let mut cost_tracker = contract_analysis.take_contract_cost_tracker();
+            add_costs(&mut result, costs, cost_tracker.get_total());
 
             if output_analysis {
                 result["analysis"] =
                     serde_json::to_value(&build_contract_interface(&contract_analysis).unwrap())
                         .unwrap();
             }
+            if lint {
+                let warnings = LintChecker::run(&contract_analysis);
+                result["warnings"] = serde_json::to_value(&warnings).unwrap();
+            }
+            if cost_bounds {
+                let bounds = CostBoundChecker::run(&contract_analysis, &mut cost_tracker);
+                result["cost_bounds"] = serde_json::to_value(&bounds).unwrap();
+            }
             (0, Some(result))
         }
         "repl" => {
@@ -1215,6 +1337,7 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
             } else {
                 true
             };
+            let mut debugger = consume_debugger_args(&mut argv);
             let mut marf = MemoryBackingStore::new();
             let mut vm_env = OwnedEnvironment::new_free(
                 mainnet,
@@ -1222,6 +1345,9 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                 marf.as_clarity_db(),
                 DEFAULT_CLI_EPOCH,
             );
+            if let Some(debugger) = debugger.as_mut() {
+                vm_env.add_eval_hook(debugger);
+            }
             let mut placeholder_context = ContractContext::new(
                 QualifiedContractIdentifier::transient(),
                 ClarityVersion::Clarity2,
@@ -1348,6 +1474,9 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
             } else {
                 false
             };
+            let mut debugger = consume_debugger_args(&mut argv);
+            let profile = consume_profile_args(&mut argv);
+            let mut profiler = profile.as_ref().map(|_| CostProfiler::new());
 
             let evalInput = get_eval_input(invoked_by, &argv);
             let vm_filename = if argv.len() == 3 { &argv[2] } else { &argv[3] };
@@ -1364,8 +1493,14 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
             );
 
             let (_, _, result_and_cost) = in_block(header_db, marf_kv, |header_db, mut marf| {
-                let result_and_cost =
-                    with_env_costs(mainnet, &header_db, &mut marf, None, |vm_env| {
+                let result_and_cost = with_env_costs(
+                    mainnet,
+                    &header_db,
+                    &mut marf,
+                    None,
+                    debugger.as_mut(),
+                    profiler.as_mut(),
+                    |vm_env| {
                         vm_env
                             .get_exec_environment(None, None, &mut placeholder_context)
                             .eval_read_only_with_rules(
@@ -1373,9 +1508,11 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                                 &evalInput.content,
                                 ASTRules::PrecheckSize,
                             )
-                    });
+                    },
+                );
                 (header_db, marf, result_and_cost)
             });
+            save_profile(profile, profiler);
 
             match result_and_cost {
                 (Ok(result), cost) => {
@@ -1416,6 +1553,9 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
             } else {
                 None
             };
+            let mut debugger = consume_debugger_args(&mut argv);
+            let profile = consume_profile_args(&mut argv);
+            let mut profiler = profile.as_ref().map(|_| CostProfiler::new());
 
             let evalInput = get_eval_input(invoked_by, &argv);
             let vm_filename = if argv.len() == 3 { &argv[2] } else { &argv[3] };
@@ -1442,6 +1582,8 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                     &header_db,
                     &mut marf,
                     coverage.as_mut(),
+                    debugger.as_mut(),
+                    profiler.as_mut(),
                     |vm_env| {
                         vm_env
                             .get_exec_environment(None, None, &mut placeholder_context)
@@ -1456,6 +1598,7 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
 
                 (marf, (result, cost))
             });
+            save_profile(profile, profiler);
 
             match result_and_cost {
                 (Ok(result), cost) => {
@@ -1529,7 +1672,7 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
             );
             let result_and_cost = at_block(chain_tip, marf_kv, |mut marf| {
                 let result_and_cost =
-                    with_env_costs(mainnet, &header_db, &mut marf, None, |vm_env| {
+                    with_env_costs(mainnet, &header_db, &mut marf, None, None, None, |vm_env| {
                         vm_env
                             .get_exec_environment(None, None, &mut placeholder_context)
                             .eval_read_only_with_rules(
@@ -1590,9 +1733,11 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                 } else {
                     false
                 };
+            let profile = consume_profile_args(&mut argv);
+            let mut profiler = profile.as_ref().map(|_| CostProfiler::new());
             if argv.len() < 4 {
                 eprintln!(
-                    "Usage: {} {} [--costs] [--assets] [--output_analysis] [contract-identifier] [contract-definition.clar] [vm-state.db]",
+                    "Usage: {} {} [--costs] [--assets] [--output_analysis] [--profile <file>] [--profile-metric <metric>] [contract-identifier] [contract-definition.clar] [vm-state.db]",
                     invoked_by, argv[0]
                 );
                 panic_test!();
@@ -1650,8 +1795,14 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
             };
             let (_, _, analysis_result_and_cost) =
                 in_block(header_db, marf_kv, |header_db, mut marf| {
-                    let analysis_result =
-                        run_analysis(&contract_identifier, &mut ast, &header_db, &mut marf, true);
+                    let analysis_result = run_analysis(
+                        &contract_identifier,
+                        &mut ast,
+                        &header_db,
+                        &mut marf,
+                        true,
+                        false,
+                    );
                     match analysis_result {
                         Err(e) => (header_db, marf, Err(e)),
                         Ok(analysis) => {
@@ -1660,6 +1811,8 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                                 &header_db,
                                 &mut marf,
                                 coverage.as_mut(),
+                                None,
+                                profiler.as_mut(),
                                 |vm_env| {
                                     vm_env.initialize_versioned_contract(
                                         contract_identifier,
@@ -1675,6 +1828,7 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                         }
                     }
                 });
+            save_profile(profile, profiler);
 
             match analysis_result_and_cost {
                 Ok((contract_analysis, (Ok((_x, asset_map, events)), cost))) => {
@@ -1740,9 +1894,12 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
             } else {
                 false
             };
+            let mut debugger = consume_debugger_args(&mut argv);
+            let profile = consume_profile_args(&mut argv);
+            let mut profiler = profile.as_ref().map(|_| CostProfiler::new());
 
             if argv.len() < 5 {
-                eprintln!("Usage: {} {} [--costs] [--assets] [vm-state.db] [contract-identifier] [public-function-name] [sender-address] [args...]", invoked_by, argv[0]);
+                eprintln!("Usage: {} {} [--costs] [--assets] [--debug] [--break <line|contract:line|function>] [--watch <expr>] [--profile <file>] [--profile-metric <metric>] [vm-state.db] [contract-identifier] [public-function-name] [sender-address] [args...]", invoked_by, argv[0]);
                 panic_test!();
             }
 
@@ -1798,6 +1955,8 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                     &header_db,
                     &mut marf,
                     coverage.as_mut(),
+                    debugger.as_mut(),
+                    profiler.as_mut(),
                     |vm_env| {
                         vm_env.execute_transaction(
                             sender,
@@ -1811,6 +1970,7 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                 let (result, cost) = result_and_cost;
                 (header_db, marf, (result, cost))
             });
+            save_profile(profile, profiler);
 
             match result_and_cost {
                 (Ok((x, asset_map, events)), cost) => {
@@ -1872,11 +2032,126 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
                 }
             }
         }
+        "infer" => {
+            let mut argv: Vec<String> = args.into_iter().map(|x| x.clone()).collect();
+            let costs = if let Ok(Some(_)) = consume_arg(&mut argv, &["--costs"], false) {
+                true
+            } else {
+                false
+            };
+            let assets = if let Ok(Some(_)) = consume_arg(&mut argv, &["--assets"], false) {
+                true
+            } else {
+                false
+            };
+
+            if argv.len() != 5 && argv.len() != 7 {
+                eprintln!("Usage: {} {} [--costs] [--assets] [vm-state.db] [sender-address] [output-hash-hex] [cost-units] [[payment-recipient] [payment-amount]]", invoked_by, argv[0]);
+                panic_test!();
+            }
+
+            let vm_filename = &argv[1];
+            let header_db =
+                friendly_expect(CLIHeadersDB::resume(vm_filename), "Failed to open CLI DB");
+            let marf_kv = friendly_expect(
+                MarfedKV::open(vm_filename, None, None),
+                "Failed to open VM database.",
+            );
+            let mainnet = header_db.is_mainnet();
+
+            let sender = {
+                if let Ok(sender) = PrincipalData::parse_standard_principal(&argv[2]) {
+                    PrincipalData::Standard(sender)
+                } else {
+                    eprintln!("Unexpected result parsing sender: {}", &argv[2]);
+                    panic_test!();
+                }
+            };
+            let output_hash = friendly_expect(
+                hex_bytes(&argv[3]),
+                "Failed to parse output hash: must be a hex string.",
+            );
+            if output_hash.len() != 32 {
+                eprintln!("Output hash must be 32 bytes, got {}", output_hash.len());
+                panic_test!();
+            }
+            let cost_units: u64 = friendly_expect(argv[4].parse(), "Failed to parse cost units.");
+            let payment = if argv.len() == 7 {
+                let recipient = friendly_expect(
+                    PrincipalData::parse(&argv[5]),
+                    "Failed to parse payment recipient.",
+                );
+                let amount: u128 =
+                    friendly_expect(argv[6].parse(), "Failed to parse payment amount.");
+                Some((recipient, amount))
+            } else {
+                None
+            };
+
+            let (_, _, result_and_cost) = in_block(header_db, marf_kv, |header_db, mut marf| {
+                let result_and_cost =
+                    with_env_costs(mainnet, &header_db, &mut marf, None, None, None, |vm_env| {
+                        vm_env.stx_infer(
+                            &sender,
+                            &BuffData { data: output_hash },
+                            cost_units,
+                            payment
+                                .as_ref()
+                                .map(|(recipient, amount)| (recipient, *amount)),
+                            None,
+                        )
+                    });
+                (header_db, marf, result_and_cost)
+            });
+
+            match result_and_cost {
+                (Ok((x, asset_map, events)), cost) => {
+                    let mut result = json!({
+                        "message": "Inference output committed.",
+                        "output": serde_json::to_value(&x).unwrap(),
+                        "success": true,
+                    });
+
+                    add_serialized_output(&mut result, x);
+                    add_costs(&mut result, costs, cost);
+                    add_assets(&mut result, assets, asset_map);
+
+                    let events_json: Vec<_> = events
+                        .into_iter()
+                        .map(|event| event.json_serialize(0, &Txid([0u8; 32]), true).unwrap())
+                        .collect();
+
+                    result["events"] = serde_json::Value::Array(events_json);
+                    (0, Some(result))
+                }
+                (Err(error), cost) => {
+                    let mut result = json!({
+                        "error": {
+                            "runtime": "Inference commitment error.",
+                            "error": serde_json::to_value(&format!("{}", error)).unwrap()
+                        },
+                        "success": false,
+                    });
+                    add_costs(&mut result, costs, cost);
+                    (1, Some(result))
+                }
+            }
+        }
         "make_lcov" => {
+            let mut argv: Vec<String> = args.into_iter().map(|x| x.clone()).collect();
+            let html_output_file = consume_arg(&mut argv, &["--html"], true).unwrap_or(None);
+            if argv.len() < 3 {
+                eprintln!(
+                    "Usage: {} {} [--html <report.html>] [coverage-folder] [lcov-output-file]",
+                    invoked_by, argv[0]
+                );
+                panic_test!();
+            }
+
             let mut register_files = vec![];
             let mut coverage_files = vec![];
-            let coverage_folder = &args[1];
-            let lcov_output_file = &args[2];
+            let coverage_folder = &argv[1];
+            let lcov_output_file = &argv[2];
             for folder_entry in
                 fs::read_dir(coverage_folder).expect("Failed to read the coverage folder")
             {
@@ -1893,6 +2168,10 @@ pub fn invoke_command(invoked_by: &str, args: &[String]) -> (i32, Option<serde_j
             }
             CoverageReporter::produce_lcov(lcov_output_file, &register_files, &coverage_files)
                 .expect("Failed to produce an lcov output");
+            if let Some(html_output_file) = html_output_file {
+                CoverageReporter::produce_html(&html_output_file, &register_files, &coverage_files)
+                    .expect("Failed to produce an HTML coverage report");
+            }
             (0, None)
         }
         _ => {
@@ -1953,6 +2232,92 @@ mod test {
         assert_eq!(exit, 0);
     }
 
+    #[test]
+    fn test_infer() {
+        let db_name = format!("/tmp/db_{}", rand::thread_rng().gen::<i32>());
+        let json_name = format!("/tmp/test-alloc_{}.json", rand::thread_rng().gen::<i32>());
+
+        fs::write(
+            &json_name,
+            r#"
+[ { "principal": "S1G2081040G2081040G2081040G208105NK8PE5",
+    "amount": 1000 } ]
+"#,
+        )
+        .unwrap();
+
+        let invoked = invoke_command(
+            "test",
+            &["initialize".to_string(), json_name.clone(), db_name.clone()],
+        );
+        assert_eq!(invoked.0, 0);
+
+        let output_hash = "ab".repeat(32);
+        let invoked = invoke_command(
+            "test",
+            &[
+                "infer".to_string(),
+                "--costs".to_string(),
+                db_name.clone(),
+                "S1G2081040G2081040G2081040G208105NK8PE5".to_string(),
+                output_hash.clone(),
+                "100".to_string(),
+                "SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR".to_string(),
+                "300".to_string(),
+            ],
+        );
+        let exit = invoked.0;
+        let result = invoked.1.unwrap();
+
+        assert_eq!(exit, 0);
+        assert_eq!(
+            result["output_serialized"],
+            format!("070200000020{}", output_hash)
+        );
+        assert!(result["costs"] != json!(null));
+        let events = result["events"].as_array().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["type"], "stx_transfer_event");
+        assert_eq!(events[0]["stx_transfer_event"]["amount"], "300");
+        assert_eq!(events[1]["type"], "stx_infer_event");
+
+        // the payment is more than the sender has left
+        let invoked = invoke_command(
+            "test",
+            &[
+                "infer".to_string(),
+                db_name.clone(),
+                "S1G2081040G2081040G2081040G208105NK8PE5".to_string(),
+                output_hash.clone(),
+                "100".to_string(),
+                "SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR".to_string(),
+                "1000".to_string(),
+            ],
+        );
+        let exit = invoked.0;
+        let result = invoked.1.unwrap();
+
+        assert_eq!(exit, 1);
+        assert_eq!(result["success"], false);
+
+        // no payment
+        let invoked = invoke_command(
+            "test",
+            &[
+                "infer".to_string(),
+                db_name,
+                "S1G2081040G2081040G2081040G208105NK8PE5".to_string(),
+                output_hash,
+                "0".to_string(),
+            ],
+        );
+        let exit = invoked.0;
+        let result = invoked.1.unwrap();
+
+        assert_eq!(exit, 0);
+        assert_eq!(result["events"].as_array().unwrap().len(), 1);
+    }
+
     #[test]
     fn test_init_mainnet() {
         let db_name = format!("/tmp/db_{}", rand::thread_rng().gen::<i32>());
@@ -2363,4 +2728,263 @@ mod test {
                 })
         );
     }
+
+    #[test]
+    fn test_check_lint() {
+        let clar_name = format!("/tmp/lint_{}.clar", rand::thread_rng().gen::<i32>());
+        fs::write(
+            &clar_name,
+            r#"
+(define-constant unused u1)
+(define-public (pay (amount uint))
+  (let ((result (stx-transfer? amount tx-sender 'S1G2081040G2081040G2081040G208105NK8PE5)))
+    (ok amount)))
+"#,
+        )
+        .unwrap();
+
+        // warnings don't fail the check
+        let invoked = invoke_command("test", &["check".to_string(), clar_name.clone()]);
+        assert_eq!(invoked.0, 0);
+        assert_eq!(invoked.1.unwrap()["warnings"], json!(null));
+
+        let invoked = invoke_command(
+            "test",
+            &["check".to_string(), "--lint".to_string(), clar_name],
+        );
+        let exit = invoked.0;
+        let result = invoked.1.unwrap();
+
+        assert_eq!(exit, 0);
+        assert_eq!(result["message"], "Checks passed.");
+        let warnings = result["warnings"].as_array().unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0]["level"], "Warning");
+        assert_eq!(
+            warnings[0]["message"],
+            "the response returned by `stx-transfer?` is never checked"
+        );
+        assert_eq!(warnings[1]["message"], "constant `unused` is never used");
+    }
+
+    #[test]
+    fn test_check_cost_bounds() {
+        let clar_name = format!("/tmp/bounds_{}.clar", rand::thread_rng().gen::<i32>());
+        fs::write(
+            &clar_name,
+            r#"
+(define-private (add (i uint) (acc uint)) (+ acc i))
+(define-read-only (sum-small (items (list 10 uint)))
+  (fold add items u0))
+(define-read-only (sum-large (items (list 100 uint)))
+  (fold add items u0))
+(define-trait payer ((pay (uint) (response uint uint))))
+(define-public (forward (to <payer>) (amount uint))
+  (contract-call? to pay amount))
+"#,
+        )
+        .unwrap();
+
+        let invoked = invoke_command(
+            "test",
+            &["check".to_string(), "--cost_bounds".to_string(), clar_name],
+        );
+        let exit = invoked.0;
+        let result = invoked.1.unwrap();
+
+        assert_eq!(exit, 0);
+        let bounds = &result["cost_bounds"];
+        // private functions are only bounded as part of their callers
+        assert_eq!(bounds["add"], json!(null));
+
+        let small = bounds["sum-small"]["worst_case"]["runtime"]
+            .as_u64()
+            .unwrap();
+        let large = bounds["sum-large"]["worst_case"]["runtime"]
+            .as_u64()
+            .unwrap();
+        assert!(small > 0);
+        assert!(large > small);
+        assert_eq!(bounds["sum-small"]["unbounded_calls"], json!(null));
+
+        assert_eq!(bounds["forward"]["unbounded_calls"], json!(["to.pay"]));
+    }
+
+    #[test]
+    fn test_profile() {
+        let db_name = format!("/tmp/db_{}", rand::thread_rng().gen::<i32>());
+        let clar_name = format!("/tmp/bank_{}.clar", rand::thread_rng().gen::<i32>());
+        let collapsed_name = format!("/tmp/profile_{}.folded", rand::thread_rng().gen::<i32>());
+        let speedscope_name = format!("/tmp/profile_{}.json", rand::thread_rng().gen::<i32>());
+
+        fs::write(
+            &clar_name,
+            r#"
+(define-map balances uint uint)
+(define-private (credit (i uint) (acc uint))
+  (begin
+    (map-set balances i (+ acc i))
+    (+ acc (default-to u0 (map-get? balances i)))))
+(define-public (run)
+  (ok (fold credit (list u1 u2 u3) u0)))
+"#,
+        )
+        .unwrap();
+
+        invoke_command("test", &["initialize".to_string(), db_name.clone()]);
+        let invoked = invoke_command(
+            "test",
+            &[
+                "launch".to_string(),
+                "S1G2081040G2081040G2081040G208105NK8PE5.bank".to_string(),
+                clar_name,
+                db_name.clone(),
+            ],
+        );
+        assert_eq!(invoked.0, 0);
+
+        let invoked = invoke_command(
+            "test",
+            &[
+                "execute".to_string(),
+                "--costs".to_string(),
+                "--profile".to_string(),
+                collapsed_name.clone(),
+                db_name.clone(),
+                "S1G2081040G2081040G2081040G208105NK8PE5.bank".to_string(),
+                "run".to_string(),
+                "S1G2081040G2081040G2081040G208105NK8PE5".to_string(),
+            ],
+        );
+        let exit = invoked.0;
+        let result = invoked.1.unwrap();
+        assert_eq!(exit, 0);
+        assert_eq!(
+            result["output_serialized"],
+            "07010000000000000000000000000000000c"
+        );
+
+        // each line is a call stack and its self cost; together they can't exceed the total
+        let collapsed = fs::read_to_string(&collapsed_name).unwrap();
+        let mut runtime = 0;
+        for line in collapsed.lines() {
+            let (stack, cost) = line.rsplit_once(' ').unwrap();
+            assert!(stack.starts_with("ok (S1G2081040G2081040G2081040G208105NK8PE5.bank"));
+            runtime += cost.parse::<u64>().unwrap();
+        }
+        assert!(runtime > 0);
+        assert!(runtime <= result["costs"]["runtime"].as_u64().unwrap());
+        assert!(collapsed.contains(";fold ("));
+        assert!(collapsed.contains(";map-get? ("));
+
+        let invoked = invoke_command(
+            "test",
+            &[
+                "execute".to_string(),
+                "--profile".to_string(),
+                speedscope_name.clone(),
+                db_name,
+                "S1G2081040G2081040G2081040G208105NK8PE5.bank".to_string(),
+                "run".to_string(),
+                "S1G2081040G2081040G2081040G208105NK8PE5".to_string(),
+            ],
+        );
+        assert_eq!(invoked.0, 0);
+
+        let speedscope: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&speedscope_name).unwrap()).unwrap();
+        let profiles = speedscope["profiles"].as_array().unwrap();
+        assert_eq!(profiles.len(), 5);
+        assert_eq!(profiles[0]["name"], "runtime");
+        // `map-set` makes one write per element of the list
+        assert_eq!(profiles[3]["name"], "write_count");
+        assert_eq!(profiles[3]["endValue"], 3);
+    }
+
+    #[test]
+    fn test_coverage_report() {
+        let db_name = format!("/tmp/db_{}", rand::thread_rng().gen::<i32>());
+        let clar_name = format!("/tmp/check_{}.clar", rand::thread_rng().gen::<i32>());
+        let coverage_folder = format!("/tmp/coverage_{}", rand::thread_rng().gen::<i32>());
+        let lcov_name = format!("{}.lcov", coverage_folder);
+        let html_name = format!("{}.html", coverage_folder);
+        fs::create_dir_all(&coverage_folder).unwrap();
+
+        fs::write(
+            &clar_name,
+            r#"
+(define-public (check (x uint))
+  (if (> x u1)
+    (ok x)
+    (ok u0)))
+(define-private (unused) (< u1 u2))
+"#,
+        )
+        .unwrap();
+
+        invoke_command("test", &["initialize".to_string(), db_name.clone()]);
+        let invoked = invoke_command(
+            "test",
+            &[
+                "launch".to_string(),
+                "--c".to_string(),
+                coverage_folder.clone(),
+                "S1G2081040G2081040G2081040G208105NK8PE5.check".to_string(),
+                clar_name.clone(),
+                db_name.clone(),
+            ],
+        );
+        assert_eq!(invoked.0, 0);
+
+        // each run is saved separately, and merged by `make_lcov`
+        for arg in ["u2", "u0"] {
+            let invoked = invoke_command(
+                "test",
+                &[
+                    "execute".to_string(),
+                    "--c".to_string(),
+                    coverage_folder.clone(),
+                    db_name.clone(),
+                    "S1G2081040G2081040G2081040G208105NK8PE5.check".to_string(),
+                    "check".to_string(),
+                    "S1G2081040G2081040G2081040G208105NK8PE5".to_string(),
+                    arg.to_string(),
+                ],
+            );
+            assert_eq!(invoked.0, 0);
+        }
+        let coverage_files = fs::read_dir(&coverage_folder)
+            .unwrap()
+            .filter(|entry| {
+                entry.as_ref().unwrap().path().extension() == Some(OsStr::new("clarcov"))
+            })
+            .count();
+        assert_eq!(coverage_files, 3);
+
+        let invoked = invoke_command(
+            "test",
+            &[
+                "make_lcov".to_string(),
+                "--html".to_string(),
+                html_name.clone(),
+                coverage_folder,
+                lcov_name.clone(),
+            ],
+        );
+        assert_eq!(invoked.0, 0);
+
+        let lcov = fs::read_to_string(&lcov_name).unwrap();
+        assert!(lcov.contains(&format!("SF:{}\n", clar_name)));
+        assert!(lcov.contains("FNF:2\n"));
+        assert!(lcov.contains("BRF:2\n"));
+        #[cfg(feature = "developer-mode")]
+        {
+            assert!(lcov.contains("FN:2,check\nFN:6,unused\nFNDA:2,check\nFNDA:0,unused\n"));
+            assert!(lcov.contains("BRDA:3,0,0,1\nBRDA:3,0,1,1\nBRF:2\nBRH:2\n"));
+        }
+
+        let html = fs::read_to_string(&html_name).unwrap();
+        assert!(html.contains("S1G2081040G2081040G2081040G208105NK8PE5.check"));
+        assert!(html.contains("(define-private (unused) (&lt; u1 u2))"));
+    }
 }
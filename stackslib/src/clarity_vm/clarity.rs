@@ -46,11 +46,12 @@ use crate::burnchains::{Burnchain, PoxConstants};
 use crate::chainstate::nakamoto::signer_set::NakamotoSigners;
 use crate::chainstate::stacks::boot::{
     BOOT_CODE_COSTS, BOOT_CODE_COSTS_2, BOOT_CODE_COSTS_2_TESTNET, BOOT_CODE_COSTS_3,
-    BOOT_CODE_COST_VOTING_TESTNET as BOOT_CODE_COST_VOTING, BOOT_CODE_POX_TESTNET,
-    BOOT_TEST_POX_4_AGG_KEY_CONTRACT, BOOT_TEST_POX_4_AGG_KEY_FNAME, COSTS_2_NAME, COSTS_3_NAME,
-    MINERS_NAME, POX_2_MAINNET_CODE, POX_2_NAME, POX_2_TESTNET_CODE, POX_3_MAINNET_CODE,
-    POX_3_NAME, POX_3_TESTNET_CODE, POX_4_CODE, POX_4_NAME, SIGNERS_BODY, SIGNERS_DB_0_BODY,
-    SIGNERS_DB_1_BODY, SIGNERS_NAME, SIGNERS_VOTING_BODY, SIGNERS_VOTING_NAME,
+    BOOT_CODE_COSTS_4, BOOT_CODE_COST_VOTING_TESTNET as BOOT_CODE_COST_VOTING,
+    BOOT_CODE_POX_TESTNET, BOOT_TEST_POX_4_AGG_KEY_CONTRACT, BOOT_TEST_POX_4_AGG_KEY_FNAME,
+    COSTS_2_NAME, COSTS_3_NAME, COSTS_4_NAME, INFER_MODELS_BODY, INFER_MODELS_NAME, MINERS_NAME,
+    POX_2_MAINNET_CODE, POX_2_NAME, POX_2_TESTNET_CODE, POX_3_MAINNET_CODE, POX_3_NAME,
+    POX_3_TESTNET_CODE, POX_4_CODE, POX_4_NAME, SIGNERS_BODY, SIGNERS_DB_0_BODY, SIGNERS_DB_1_BODY,
+    SIGNERS_NAME, SIGNERS_VOTING_BODY, SIGNERS_VOTING_NAME,
 };
 use crate::chainstate::stacks::db::{StacksAccount, StacksChainState};
 use crate::chainstate::stacks::events::{StacksTransactionEvent, StacksTransactionReceipt};
@@ -864,6 +865,7 @@ impl<'a, 'b> ClarityBlockConnection<'a, 'b> {
                     &costs_2_contract_tx,
                     &boot_code_account,
                     ASTRules::PrecheckSize,
+                    None,
                 )
                 .expect("FATAL: Failed to process PoX 2 contract initialization");
 
@@ -977,6 +979,7 @@ impl<'a, 'b> ClarityBlockConnection<'a, 'b> {
                     &pox_2_contract_tx,
                     &boot_code_account,
                     ASTRules::PrecheckSize,
+                    None,
                 )
                 .expect("FATAL: Failed to process PoX 2 contract initialization");
 
@@ -1048,6 +1051,7 @@ impl<'a, 'b> ClarityBlockConnection<'a, 'b> {
                     &costs_3_contract_tx,
                     &boot_code_account,
                     ASTRules::PrecheckSize,
+                    None,
                 )
                 .expect("FATAL: Failed to process costs-3 contract initialization");
 
@@ -1218,6 +1222,7 @@ impl<'a, 'b> ClarityBlockConnection<'a, 'b> {
                     &pox_3_contract_tx,
                     &boot_code_account,
                     ASTRules::PrecheckSize,
+                    None,
                 )
                 .expect("FATAL: Failed to process PoX 3 contract initialization");
 
@@ -1335,6 +1340,7 @@ impl<'a, 'b> ClarityBlockConnection<'a, 'b> {
                     &pox_4_contract_tx,
                     &boot_code_account,
                     ASTRules::PrecheckSize,
+                    None,
                 )
                 .expect("FATAL: Failed to process PoX 4 contract initialization");
 
@@ -1393,6 +1399,7 @@ impl<'a, 'b> ClarityBlockConnection<'a, 'b> {
                     &signers_contract_tx,
                     &boot_code_account,
                     ASTRules::PrecheckSize,
+                    None,
                 )
                 .expect("FATAL: Failed to process .signers contract initialization");
                 receipt
@@ -1439,6 +1446,7 @@ impl<'a, 'b> ClarityBlockConnection<'a, 'b> {
                             &signers_contract_tx,
                             &boot_code_account,
                             ASTRules::PrecheckSize,
+                            None,
                         )
                         .expect("FATAL: Failed to process .signers DB contract initialization");
                         receipt
@@ -1479,6 +1487,7 @@ impl<'a, 'b> ClarityBlockConnection<'a, 'b> {
                     &signers_contract_tx,
                     &boot_code_account,
                     ASTRules::PrecheckSize,
+                    None,
                 )
                 .expect("FATAL: Failed to process .signers-voting contract initialization");
                 receipt
@@ -1520,8 +1529,101 @@ impl<'a, 'b> ClarityBlockConnection<'a, 'b> {
                 tx_conn.epoch = StacksEpochId::Epoch30;
             });
 
+            let mainnet = self.mainnet;
+            let tx_version = if mainnet {
+                TransactionVersion::Mainnet
+            } else {
+                TransactionVersion::Testnet
+            };
+
+            let mut receipts = vec![];
+
+            let boot_code_account = self
+                .get_boot_code_account()
+                .expect("FATAL: did not get boot account");
+
+            let boot_code_address = boot_code_addr(mainnet);
+            let boot_code_auth = boot_code_tx_auth(boot_code_address);
+
+            /////////////////// .costs-4 ////////////////////////
+            let payload = TransactionPayload::SmartContract(
+                TransactionSmartContract {
+                    name: ContractName::try_from(COSTS_4_NAME)
+                        .expect("FATAL: invalid boot-code contract name"),
+                    code_body: StacksString::from_str(BOOT_CODE_COSTS_4)
+                        .expect("FATAL: invalid boot code body"),
+                },
+                Some(ClarityVersion::Clarity2),
+            );
+
+            let costs_4_contract_tx =
+                StacksTransaction::new(tx_version.clone(), boot_code_auth.clone(), payload);
+
+            let costs_4_initialization_receipt = self.as_transaction(|tx_conn| {
+                // initialize with a synthetic transaction
+                debug!("Instantiate .costs-4 contract");
+                let receipt = StacksChainState::process_transaction_payload(
+                    tx_conn,
+                    &costs_4_contract_tx,
+                    &boot_code_account,
+                    ASTRules::PrecheckSize,
+                    None,
+                )
+                .expect("FATAL: Failed to process costs-4 contract initialization");
+                receipt
+            });
+
+            if costs_4_initialization_receipt.result != Value::okay_true()
+                || costs_4_initialization_receipt.post_condition_aborted
+            {
+                panic!(
+                    "FATAL: Failure processing Costs 4 contract initialization: {:#?}",
+                    &costs_4_initialization_receipt
+                );
+            }
+            receipts.push(costs_4_initialization_receipt);
+
+            /////////////////// .infer-models ////////////////////////
+            let infer_models_contract_id = boot_code_id(INFER_MODELS_NAME, mainnet);
+            let payload = TransactionPayload::SmartContract(
+                TransactionSmartContract {
+                    name: ContractName::try_from(INFER_MODELS_NAME)
+                        .expect("FATAL: invalid boot-code contract name"),
+                    code_body: StacksString::from_str(INFER_MODELS_BODY)
+                        .expect("FATAL: invalid boot code body"),
+                },
+                Some(ClarityVersion::Clarity2),
+            );
+
+            let infer_models_contract_tx =
+                StacksTransaction::new(tx_version, boot_code_auth, payload);
+
+            let infer_models_initialization_receipt = self.as_transaction(|tx_conn| {
+                // initialize with a synthetic transaction
+                debug!("Instantiate {} contract", &infer_models_contract_id);
+                let receipt = StacksChainState::process_transaction_payload(
+                    tx_conn,
+                    &infer_models_contract_tx,
+                    &boot_code_account,
+                    ASTRules::PrecheckSize,
+                    None,
+                )
+                .expect("FATAL: Failed to process .infer-models contract initialization");
+                receipt
+            });
+
+            if infer_models_initialization_receipt.result != Value::okay_true()
+                || infer_models_initialization_receipt.post_condition_aborted
+            {
+                panic!(
+                    "FATAL: Failure processing infer-models contract initialization: {:#?}",
+                    &infer_models_initialization_receipt
+                );
+            }
+            receipts.push(infer_models_initialization_receipt);
+
             debug!("Epoch 3.0 initialized");
-            (old_cost_tracker, Ok(vec![]))
+            (old_cost_tracker, Ok(receipts))
         })
     }
 
@@ -1770,6 +1872,14 @@ impl<'a, 'b> ClarityTransactionConnection<'a, 'b> {
         }
     }
 
+    /// Charge `cost` to the block's budget for work done outside of the Clarity VM
+    pub fn charge_cost(&mut self, cost: ExecutionCost) -> Result<(), Error> {
+        match self.cost_track {
+            Some(ref mut track) => track.add_cost(cost).map_err(|e| CheckError::from(e).into()),
+            None => Ok(()),
+        }
+    }
+
     /// Evaluate a poison-microblock transaction
     pub fn run_poison_microblock(
         &mut self,
@@ -2652,6 +2762,7 @@ mod tests {
                     &tx1,
                     &account,
                     ASTRules::PrecheckSize,
+                    None,
                 )
                 .unwrap();
                 assert_eq!(receipt.post_condition_aborted, true);
@@ -2662,6 +2773,7 @@ mod tests {
                     &tx2,
                     &account,
                     ASTRules::PrecheckSize,
+                    None,
                 )
                 .unwrap();
             });
@@ -2672,6 +2784,7 @@ mod tests {
                     &tx3,
                     &account,
                     ASTRules::PrecheckSize,
+                    None,
                 )
                 .unwrap();
 
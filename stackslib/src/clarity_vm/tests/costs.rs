@@ -23,8 +23,10 @@ use clarity::vm::contexts::{
 };
 use clarity::vm::contracts::Contract;
 use clarity::vm::costs::cost_functions::ClarityCostFunction;
-use clarity::vm::costs::{ClarityCostFunctionReference, ExecutionCost, LimitedCostTracker};
-use clarity::vm::database::{ClarityDatabase, MemoryBackingStore};
+use clarity::vm::costs::{
+    ClarityCostFunctionReference, CostTracker, ExecutionCost, LimitedCostTracker,
+};
+use clarity::vm::database::{ClarityDatabase, MemoryBackingStore, INFER_RESULT_MAX_SERIALIZED_LEN};
 use clarity::vm::errors::{CheckErrors, Error, RuntimeErrorType};
 use clarity::vm::events::StacksTransactionEvent;
 use clarity::vm::functions::NativeFunctions;
@@ -158,6 +160,7 @@ pub fn get_simple_test(function: &NativeFunctions) -> &'static str {
         StxTransferMemo => r#"(stx-transfer-memo? u1 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR 0x89995432)"#,
         StxBurn => "(stx-burn? u1 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
         StxGetAccount => "(stx-account 'SZ2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKQ9H6DPR)",
+        GetInferResult => "(infer-result? 0x0000000000000000000000000000000000000000000000000000000000000000)",
         BitwiseAnd => "(bit-and 2 3)",
         BitwiseOr => "(bit-or 2 3)",
         BitwiseNot => "(bit-not 3)",
@@ -219,9 +222,18 @@ where
         tip = next_block.clone();
     }
 
+    if epoch >= StacksEpochId::Epoch30 {
+        let next_block = StacksBlockId([3 as u8; 32]);
+        let mut clarity_conn =
+            clarity_instance.begin_block(&tip, &next_block, &TEST_HEADER_DB, &TEST_BURN_STATE_DB);
+        clarity_conn.initialize_epoch_3_0().unwrap();
+        clarity_conn.commit_block();
+        tip = next_block.clone();
+    }
+
     let mut marf_kv = clarity_instance.destroy();
 
-    let mut store = marf_kv.begin(&tip, &StacksBlockId([3 as u8; 32]));
+    let mut store = marf_kv.begin(&tip, &StacksBlockId([4 as u8; 32]));
 
     to_do(OwnedEnvironment::new_max_limit(
         store.as_clarity_db(&TEST_HEADER_DB, &TEST_BURN_STATE_DB),
@@ -991,9 +1003,12 @@ fn epoch_21_test_all(use_mainnet: bool) {
 
         for (ix, f) in NativeFunctions::ALL.iter().enumerate() {
             // Note: Include Clarity2 functions for Epoch21.
-            let test = get_simple_test(f);
-            let cost = test_program_cost(test, ClarityVersion::Clarity2, &mut owned_env, ix + 1);
-            assert!(cost.exceeds(&baseline));
+            if f.get_version() <= ClarityVersion::Clarity2 {
+                let test = get_simple_test(f);
+                let cost =
+                    test_program_cost(test, ClarityVersion::Clarity2, &mut owned_env, ix + 1);
+                assert!(cost.exceeds(&baseline));
+            }
         }
     })
 }
@@ -1008,6 +1023,55 @@ fn epoch_21_test_all_testnet() {
     epoch_21_test_all(false)
 }
 
+// test each individual cost function can be correctly invoked as
+//  Clarity code executes in Epoch 3.0, against .costs-4
+fn epoch_30_test_all(use_mainnet: bool) {
+    with_owned_env(StacksEpochId::Epoch30, use_mainnet, |mut owned_env| {
+        setup_cost_tracked_test(use_mainnet, ClarityVersion::Clarity3, &mut owned_env);
+
+        let baseline = test_program_cost("1", ClarityVersion::Clarity3, &mut owned_env, 0);
+
+        for (ix, f) in NativeFunctions::ALL.iter().enumerate() {
+            let test = get_simple_test(f);
+            let cost = test_program_cost(test, ClarityVersion::Clarity3, &mut owned_env, ix + 1);
+            assert!(cost.exceeds(&baseline));
+        }
+    })
+}
+
+#[test]
+fn epoch_30_test_all_mainnet() {
+    epoch_30_test_all(true)
+}
+
+#[test]
+fn epoch_30_test_all_testnet() {
+    epoch_30_test_all(false)
+}
+
+// .costs-4 must charge for the largest infer result record that can be written and read
+#[test]
+fn epoch_30_infer_costs_cover_largest_record() {
+    with_owned_env(StacksEpochId::Epoch30, false, |mut owned_env| {
+        let ((stx_infer, infer_result), ..) = owned_env
+            .execute_in_env(
+                QualifiedContractIdentifier::transient().issuer.into(),
+                None,
+                None,
+                |env| -> Result<_, Error> {
+                    let cost_track = &mut env.global_context.cost_track;
+                    let stx_infer = cost_track.compute_cost(ClarityCostFunction::StxInfer, &[0])?;
+                    let infer_result =
+                        cost_track.compute_cost(ClarityCostFunction::InferResult, &[0])?;
+                    Ok((stx_infer, infer_result))
+                },
+            )
+            .unwrap();
+        assert!(stx_infer.write_length >= INFER_RESULT_MAX_SERIALIZED_LEN as u64);
+        assert!(infer_result.read_length >= INFER_RESULT_MAX_SERIALIZED_LEN as u64);
+    })
+}
+
 fn test_cost_contract_short_circuits(use_mainnet: bool, clarity_version: ClarityVersion) {
     let marf_kv = MarfedKV::temporary();
     let chain_id = test_only_mainnet_to_chain_id(use_mainnet);
@@ -33,7 +33,7 @@ use rusqlite::{
     NO_PARAMS,
 };
 use siphasher::sip::SipHasher;
-use libllm::{InferStatus, query_hash};
+use libllm::{InferDB, InferStatus};
 // this is SipHash-2-4
 use stacks_common::codec::{
     read_next, write_next, Error as codec_error, StacksMessageCodec, MAX_MESSAGE_LEN,
@@ -51,9 +51,10 @@ use crate::chainstate::stacks::db::blocks::MemPoolRejection;
 use crate::chainstate::stacks::db::{ClarityTx, StacksChainState};
 use crate::chainstate::stacks::events::StacksTransactionReceipt;
 use crate::chainstate::stacks::index::Error as MarfError;
-use crate::chainstate::stacks::miner::TransactionEvent;
+use crate::chainstate::stacks::miner::{TransactionEvent, TransactionResult};
 use crate::chainstate::stacks::{
-    Error as ChainstateError, StacksBlock, StacksMicroblock, StacksTransaction, TransactionPayload,
+    Error as ChainstateError, InferOutput, InferRequest, StacksBlock, StacksMicroblock,
+    StacksTransaction, TransactionPayload, MAX_INFER_OUTPUTS,
 };
 use crate::clarity_vm::clarity::ClarityConnection;
 use crate::core::{
@@ -70,6 +71,7 @@ use crate::util_lib::db::{
     tx_begin_immediate, tx_busy_handler, u64_to_sql, DBConn, DBTx, Error as db_error, Error,
     FromColumn, FromRow,
 };
+use crate::util_lib::strings::InferText;
 use crate::{cost_estimates, monitoring};
 
 // maximum number of confirmations a transaction can have before it's garbage-collected
@@ -333,12 +335,158 @@ impl MemPoolAdmitter {
     }
 }
 
+pub const DEFAULT_INFER_MIN_FEE_RATE_PER_PROMPT_BYTE: u64 = 1;
+pub const DEFAULT_INFER_MAX_PROMPT_LEN: u64 = 16 * 1024;
+pub const DEFAULT_INFER_MAX_CONTEXT_LEN: u64 = 64 * 1024;
+pub const DEFAULT_INFER_MAX_TXS_PER_ORIGIN: u32 = 10;
+pub const DEFAULT_INFER_RATE_LIMIT_WINDOW_SECS: u64 = 60;
+pub const DEFAULT_INFER_PENDING_DEADLINE_SECS: u64 = 600;
+/// How long the miner sleeps between mempool walks while it waits for pending inferences
+pub const INFER_WAIT_POLL_MS: u64 = 100;
+
+/// Which Infer transactions the mempool will take.  Every Infer transaction it admits costs the
+/// node an inference, so these are stricter than the checks for other transactions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferAdmissionConfig {
+    /// Minimum fee (in microSTX) per byte of user input and context
+    pub min_fee_rate_per_prompt_byte: u64,
+    /// Largest user input, in bytes
+    pub max_prompt_len: u64,
+    /// Largest context, in bytes, as recorded in the InferDB
+    pub max_context_len: u64,
+    /// Most Infer transactions admitted from one origin per `rate_limit_window_secs`.  0 means
+    /// no limit.
+    pub max_txs_per_origin: u32,
+    pub rate_limit_window_secs: u64,
+}
+
+impl Default for InferAdmissionConfig {
+    fn default() -> InferAdmissionConfig {
+        InferAdmissionConfig {
+            min_fee_rate_per_prompt_byte: DEFAULT_INFER_MIN_FEE_RATE_PER_PROMPT_BYTE,
+            max_prompt_len: DEFAULT_INFER_MAX_PROMPT_LEN,
+            max_context_len: DEFAULT_INFER_MAX_CONTEXT_LEN,
+            max_txs_per_origin: DEFAULT_INFER_MAX_TXS_PER_ORIGIN,
+            rate_limit_window_secs: DEFAULT_INFER_RATE_LIMIT_WINDOW_SECS,
+        }
+    }
+}
+
+/// A hook to refuse Infer transactions by what they ask, e.g. to keep a node from running
+/// prompts its operator won't serve.  Returns the reason for refusing.
+pub trait InferContentFilter: Send {
+    fn check(&self, request: &InferRequest) -> Result<(), String>;
+}
+
+/// Decides which Infer transactions the mempool admits, per its `InferAdmissionConfig` and
+/// optional `InferContentFilter`, and keeps track of how many each origin has sent lately.
+pub struct InferAdmissionPolicy {
+    config: InferAdmissionConfig,
+    content_filter: Option<Box<dyn InferContentFilter>>,
+    /// When each origin's Infer transactions were admitted, oldest first, within the window
+    admitted: HashMap<StacksAddress, VecDeque<u64>>,
+}
+
+impl InferAdmissionPolicy {
+    pub fn new(config: InferAdmissionConfig) -> InferAdmissionPolicy {
+        InferAdmissionPolicy {
+            config,
+            content_filter: None,
+            admitted: HashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &InferAdmissionConfig {
+        &self.config
+    }
+
+    pub fn set_content_filter(&mut self, content_filter: Box<dyn InferContentFilter>) {
+        self.content_filter = Some(content_filter);
+    }
+
+    /// Forget admissions that fell out of the rate-limit window as of `now`
+    fn expire(&mut self, now: u64) {
+        let cutoff = now.saturating_sub(self.config.rate_limit_window_secs);
+        self.admitted.retain(|_, times| {
+            while times.front().map(|time| *time <= cutoff).unwrap_or(false) {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+    }
+
+    /// Would the Infer transaction `tx`, asking for `request`, be admitted at unix time `now`?
+    /// Cheap checks go first, so that a rejected transaction costs the node little.
+    pub fn check(
+        &mut self,
+        tx: &StacksTransaction,
+        request: &InferRequest,
+        now: u64,
+    ) -> Result<(), MemPoolRejection> {
+        let prompt_len = request.user_input.len() as u64;
+        if prompt_len > self.config.max_prompt_len {
+            return Err(MemPoolRejection::InferPromptTooLarge(
+                prompt_len,
+                self.config.max_prompt_len,
+            ));
+        }
+        let context_len = request.context.len() as u64;
+        if context_len > self.config.max_context_len {
+            return Err(MemPoolRejection::InferContextTooLarge(
+                context_len,
+                self.config.max_context_len,
+            ));
+        }
+
+        let min_fee = self
+            .config
+            .min_fee_rate_per_prompt_byte
+            .saturating_mul(prompt_len.saturating_add(context_len));
+        if tx.get_tx_fee() < min_fee {
+            return Err(MemPoolRejection::InferFeeTooLow(tx.get_tx_fee(), min_fee));
+        }
+
+        if self.config.max_txs_per_origin > 0 {
+            self.expire(now);
+            let origin = tx.origin_address();
+            let recent = self.admitted.get(&origin).map(|times| times.len()).unwrap_or(0);
+            if recent >= self.config.max_txs_per_origin as usize {
+                return Err(MemPoolRejection::InferRateLimited {
+                    origin,
+                    max_txs: self.config.max_txs_per_origin,
+                    window_secs: self.config.rate_limit_window_secs,
+                });
+            }
+        }
+
+        if let Some(ref content_filter) = self.content_filter {
+            content_filter
+                .check(request)
+                .map_err(MemPoolRejection::InferContentRejected)?;
+        }
+        Ok(())
+    }
+
+    /// Count an Infer transaction from `origin`, admitted at unix time `now`, against its rate
+    /// limit
+    pub fn record_admitted(&mut self, origin: StacksAddress, now: u64) {
+        if self.config.max_txs_per_origin == 0 {
+            return;
+        }
+        self.admitted.entry(origin).or_default().push_back(now);
+    }
+}
+
 pub enum MemPoolDropReason {
     REPLACE_ACROSS_FORK,
     REPLACE_BY_FEE,
     STALE_COLLECT,
     TOO_EXPENSIVE,
     PROBLEMATIC,
+    /// an Infer transaction whose inference didn't finish by its deadline
+    INFER_EXPIRED,
+    /// an Infer transaction whose inference failed
+    INFER_FAILED,
 }
 
 pub struct ConsiderTransaction {
@@ -364,6 +512,8 @@ impl std::fmt::Display for MemPoolDropReason {
             MemPoolDropReason::REPLACE_ACROSS_FORK => write!(f, "ReplaceAcrossFork"),
             MemPoolDropReason::REPLACE_BY_FEE => write!(f, "ReplaceByFee"),
             MemPoolDropReason::PROBLEMATIC => write!(f, "Problematic"),
+            MemPoolDropReason::INFER_EXPIRED => write!(f, "InferExpired"),
+            MemPoolDropReason::INFER_FAILED => write!(f, "InferFailed"),
         }
     }
 }
@@ -408,6 +558,61 @@ pub struct InferTxRetryInfo {
     pub last_retry_time: u64,
 }
 
+/// How far along an Infer transaction's inference is, as far as block assembly is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InferReadiness {
+    /// The inference succeeded, so the transaction can be mined
+    Ready,
+    /// The inference is queued or running
+    Pending,
+    /// The inference failed for good
+    Failed,
+}
+
+impl InferReadiness {
+    pub fn of(status: &InferStatus) -> InferReadiness {
+        match status {
+            InferStatus::Success => InferReadiness::Ready,
+            InferStatus::Failure | InferStatus::TimedOut => InferReadiness::Failed,
+            InferStatus::Created
+            | InferStatus::InProgress
+            | InferStatus::Retrying
+            | InferStatus::NotFound
+            | InferStatus::Cancelled => InferReadiness::Pending,
+        }
+    }
+}
+
+/// An Infer transaction that a mempool walk passed over, because its inference wasn't ready
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferSkip {
+    pub txid: Txid,
+    /// When the mempool accepted the transaction
+    pub accept_time: u64,
+    /// The status of its inference task when it was passed over
+    pub status: InferStatus,
+}
+
+impl InferSkip {
+    pub fn readiness(&self) -> InferReadiness {
+        InferReadiness::of(&self.status)
+    }
+
+    /// Why the transaction was passed over, for the miner's log and event stream
+    pub fn reason(&self) -> String {
+        match self.readiness() {
+            InferReadiness::Failed => format!("Inference failed ({:?})", self.status),
+            _ => format!("Inference not ready ({:?})", self.status),
+        }
+    }
+
+    /// Has the inference been pending for `deadline_secs` or more, as of `now`?
+    pub fn is_expired(&self, now: u64, deadline_secs: u64) -> bool {
+        self.readiness() == InferReadiness::Pending
+            && now.saturating_sub(self.accept_time) >= deadline_secs
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct MemPoolTxInfo {
     pub tx: StacksTransaction,
@@ -477,6 +682,9 @@ impl FromStr for MemPoolWalkTxTypes {
             "ContractCall" => {
                 return Ok(Self::ContractCall);
             }
+            "Infer" => {
+                return Ok(Self::Infer);
+            }
             _ => {
                 return Err("Unknown mempool tx walk type");
             }
@@ -521,6 +729,12 @@ pub struct MemPoolWalkSettings {
     pub filter_origins: HashSet<StacksAddress>,
     /// infer tx max retry count
     pub infer_tx_max_retry_count: u64,
+    /// How long an Infer transaction's inference may be pending, from when the transaction
+    /// entered the mempool, before the miner drops the transaction
+    pub infer_pending_deadline_secs: u64,
+    /// How long, at most, the miner will wait for pending inferences once it has nothing else
+    /// to mine, in milliseconds.  This is bounded by the miner's mining time.
+    pub infer_wait_ms: u64,
 }
 
 impl MemPoolWalkSettings {
@@ -540,6 +754,8 @@ impl MemPoolWalkSettings {
             .collect(),
             filter_origins: HashSet::new(),
             infer_tx_max_retry_count: 5,
+            infer_pending_deadline_secs: DEFAULT_INFER_PENDING_DEADLINE_SECS,
+            infer_wait_ms: 0,
         }
     }
     pub fn zero() -> MemPoolWalkSettings {
@@ -558,6 +774,8 @@ impl MemPoolWalkSettings {
             .collect(),
             filter_origins: HashSet::new(),
             infer_tx_max_retry_count: 5,
+            infer_pending_deadline_secs: DEFAULT_INFER_PENDING_DEADLINE_SECS,
+            infer_wait_ms: 0,
         }
     }
 }
@@ -834,12 +1052,20 @@ pub struct MemPoolDB {
     metric: Box<dyn CostMetric>,
     pub blacklist_timeout: u64,
     pub blacklist_max_size: u64,
+    /// inference tasks for the Infer transactions in this mempool
+    infer_db: InferDB,
+    /// which Infer transactions to admit
+    infer_admission: InferAdmissionPolicy,
+    /// Infer transactions that mempool walks passed over since the last `reset_infer_skips()`,
+    /// because their inferences weren't ready
+    infer_skips: HashMap<Txid, InferSkip>,
 }
 
 pub struct MemPoolTx<'a> {
     tx: DBTx<'a>,
     admitter: &'a mut MemPoolAdmitter,
     bloom_counter: Option<&'a mut BloomCounter<BloomNodeHasher>>,
+    infer_db: &'a InferDB,
 }
 
 impl<'a> Deref for MemPoolTx<'a> {
@@ -860,11 +1086,13 @@ impl<'a> MemPoolTx<'a> {
         tx: DBTx<'a>,
         admitter: &'a mut MemPoolAdmitter,
         bloom_counter: &'a mut BloomCounter<BloomNodeHasher>,
+        infer_db: &'a InferDB,
     ) -> MemPoolTx<'a> {
         MemPoolTx {
             tx,
             admitter,
             bloom_counter: Some(bloom_counter),
+            infer_db,
         }
     }
 
@@ -885,6 +1113,23 @@ impl<'a> MemPoolTx<'a> {
         self.tx.commit().map_err(db_error::SqliteError)
     }
 
+    /// Cancel the inference tasks of Infer transactions that are leaving the mempool, so the
+    /// node stops spending time on them.  If they come back, the mempool walk queues them again.
+    /// Results of transactions that leave unconfirmed are pruned later, as orphans.
+    fn cancel_infer_tasks(&self, txids: &[Txid]) {
+        let now = get_epoch_time_secs();
+        for txid in txids.iter() {
+            match libllm::cancel(self.infer_db, txid.to_hex()) {
+                Ok(true) => debug!("Cancelled inference task"; "txid" => %txid),
+                Ok(false) => {}
+                Err(e) => warn!("Failed to cancel inference task"; "txid" => %txid, "err" => %e),
+            }
+            if let Err(e) = libllm::retention::evict(self.infer_db, &txid.to_hex(), now) {
+                warn!("Failed to record inference task eviction"; "txid" => %txid, "err" => %e);
+            }
+        }
+    }
+
     /// Remove all txids at the given height from the bloom counter.
     /// Used to clear out txids that are now outside the bloom counter's depth.
     fn prune_bloom_counter(&mut self, target_height: u64) -> Result<(), MemPoolRejection> {
@@ -1430,6 +1675,7 @@ impl MemPoolDB {
 
     pub fn open_db(
         db_path: &str,
+        infer_db: InferDB,
         cost_estimator: Box<dyn CostEstimator>,
         metric: Box<dyn CostMetric>,
     ) -> Result<MemPoolDB, db_error> {
@@ -1469,9 +1715,21 @@ impl MemPoolDB {
             metric,
             blacklist_timeout: DEFAULT_BLACKLIST_TIMEOUT,
             blacklist_max_size: DEFAULT_BLACKLIST_MAX_SIZE,
+            infer_db,
+            infer_admission: InferAdmissionPolicy::new(InferAdmissionConfig::default()),
+            infer_skips: HashMap::new(),
         })
     }
 
+    pub fn infer_db(&self) -> &InferDB {
+        &self.infer_db
+    }
+
+    /// Replace the policy for admitting Infer transactions
+    pub fn set_infer_admission_policy(&mut self, infer_admission: InferAdmissionPolicy) {
+        self.infer_admission = infer_admission;
+    }
+
     pub fn reopen(&self, readwrite: bool) -> Result<DBConn, db_error> {
         if let Err(e) = fs::metadata(&self.path) {
             return Err(db_error::IOError(e));
@@ -1512,7 +1770,55 @@ impl MemPoolDB {
 
         let db_path = MemPoolDB::db_path(&chainstate.root_path)?;
 
-        MemPoolDB::open_db(&db_path, cost_estimator, metric)
+        MemPoolDB::open_db(&db_path, chainstate.infer_db, cost_estimator, metric)
+    }
+
+    /// Forget which Infer transactions earlier mempool walks passed over.  Done at the start of
+    /// each block assembly.
+    pub fn reset_infer_skips(&mut self) {
+        self.infer_skips.clear();
+    }
+
+    /// The Infer transactions that mempool walks passed over since the last
+    /// `reset_infer_skips()`, and are still not ready
+    pub fn infer_skips(&self) -> Vec<InferSkip> {
+        self.infer_skips.values().cloned().collect()
+    }
+
+    /// Look up the status of the inference task of the Infer transaction `tx`, queueing the task
+    /// again if this node doesn't have it (e.g. because it was cancelled)
+    fn refresh_infer_task(infer_db: &InferDB, tx: &StacksTransaction) -> InferStatus {
+        let txid = tx.txid().to_hex();
+        let status = match libllm::query_hash(infer_db, txid.clone()) {
+            Ok(res) => res.status,
+            Err(e) => {
+                warn!("Failed to query infer task"; "txid" => &txid, "error" => %e);
+                return InferStatus::NotFound;
+            }
+        };
+        if status != InferStatus::NotFound && status != InferStatus::Cancelled {
+            return status;
+        }
+        let request = tx
+            .payload
+            .infer_request()
+            .expect("BUG: Infer payload without an Infer request");
+        match libllm::infer_chain(
+            infer_db,
+            txid.clone(),
+            request.user_input.as_str(),
+            request.context.as_str(),
+            tx.get_tx_fee(),
+        ) {
+            Ok(status) => {
+                info!("Infer task not found, submitted new infer task"; "txid" => &txid);
+                status
+            }
+            Err(e) => {
+                warn!("Infer task not found, failed to submit new infer task"; "txid" => &txid, "error" => %e);
+                status
+            }
+        }
     }
 
     #[cfg_attr(test, mutants::skip)]
@@ -1835,47 +2141,35 @@ impl MemPoolDB {
                         .txs_to_consider
                         .contains(&MemPoolWalkTxTypes::ContractCall),
                 ),
-                TransactionPayload::Infer(ref from, ref userInput, ref context) => {
-                    let mut settings_do_consider = settings
+                TransactionPayload::Infer(..) | TransactionPayload::VersionedInfer(..) => {
+                    let settings_do_consider = settings
                         .txs_to_consider
                         .contains(&MemPoolWalkTxTypes::Infer);
-                    let infer_task_res = libllm::query_hash(tx_info.tx.txid().to_hex());
-                    let infer_task_done = if let Ok(res) = infer_task_res {
-                        match res.status {
-                            InferStatus::Success => true,
-                            InferStatus::NotFound => {
-                                let txid_str = tx_info.tx.txid().to_hex();
-                                let user_input = userInput.to_string();
-                                let context_str = context.to_string();
-                                let chat_completion_message = serde_json::from_str(context_str.as_str()).unwrap_or(vec![]);
-                                let context_messages = if chat_completion_message.is_empty() {
-                                    None
-                                } else {
-                                    Some(chat_completion_message)
-                                };
-                                let submit_infer_res = libllm::infer_chain(txid_str.clone(), user_input.as_str(), context_messages);
-                                match submit_infer_res {
-                                    Ok(_) => {
-                                        let msg = format!("Infer task not found, submitted new infer task, txid:{:?}", txid_str);
-                                        info!("{}", &msg);
-                                    }
-                                    Err(e) => {
-                                        let msg = format!("Infer task not found, failed to submit new infer task, txid:{:?}, error:{:?}", txid_str, e);
-                                        warn!("{}", &msg);
-                                    }
-                                }
-                                false
+                    // only transactions whose inferences are done can be mined; the rest are
+                    // reported, and left to the miner to wait for or drop
+                    let infer_task_done = settings_do_consider && {
+                        let skip = InferSkip {
+                            txid: tx_info.tx.txid(),
+                            accept_time: tx_info.metadata.accept_time,
+                            status: Self::refresh_infer_task(&self.infer_db, &tx_info.tx),
+                        };
+                        if skip.readiness() == InferReadiness::Ready {
+                            self.infer_skips.remove(&skip.txid);
+                            true
+                        } else {
+                            // report each transaction once, and again if its status changes
+                            if self.infer_skips.get(&skip.txid) != Some(&skip) {
+                                output_events.push(
+                                    TransactionResult::skipped(&tx_info.tx, skip.reason())
+                                        .convert_to_event(),
+                                );
                             }
-                            _ => false,
+                            self.infer_skips.insert(skip.txid.clone(), skip);
+                            false
                         }
-                    } else {
-                        false
                     };
 
-                    (
-                        "Infer".to_string(),
-                        settings_do_consider && infer_task_done,
-                    )
+                    ("Infer".to_string(), infer_task_done)
                 }
                 _ => ("".to_string(), true),
             };
@@ -2010,6 +2304,7 @@ impl MemPoolDB {
             tx,
             &mut self.admitter,
             &mut self.bloom_counter,
+            &self.infer_db,
         ))
     }
 
@@ -2030,6 +2325,50 @@ impl MemPoolDB {
         )
     }
 
+    /// The outputs of the inferences this node finished for Infer transactions in its mempool,
+    /// highest fee first, for a block to carry in an InferOutputs transaction.  At most
+    /// `MAX_INFER_OUTPUTS` outputs are returned, taking up at most `max_len` bytes.
+    pub fn infer_outputs(&self, max_len: u64) -> Result<Vec<InferOutput>, db_error> {
+        let results = libllm::query_unconfirmed_successes(&self.infer_db, MAX_INFER_OUTPUTS)
+            .map_err(|e| db_error::Other(format!("Failed to list inference results: {e}")))?;
+        let mut outputs = vec![];
+        let mut len = 0;
+        for result in results.into_iter() {
+            let Ok(txid) = Txid::from_hex(&result.txid) else {
+                continue;
+            };
+            if !MemPoolDB::db_has_tx(&self.db, &txid)? {
+                continue;
+            }
+            let params = result.params.unwrap_or_default();
+            let to_text = |s: String| InferText::try_from(s);
+            let output = match (
+                params.model.map(to_text).transpose(),
+                params.model_digest.map(to_text).transpose(),
+                to_text(result.output),
+            ) {
+                (Ok(model), Ok(model_digest), Ok(output)) => InferOutput {
+                    txid,
+                    model,
+                    model_digest,
+                    output,
+                },
+                _ => {
+                    warn!("Inference result too long to carry in a block"; "txid" => %txid);
+                    continue;
+                }
+            };
+            // a smaller output further down may still fit
+            let output_len = output.serialize_to_vec().len() as u64;
+            if len + output_len > max_len {
+                continue;
+            }
+            len += output_len;
+            outputs.push(output);
+        }
+        Ok(outputs)
+    }
+
     /// Get all transactions across all tips
     #[cfg(test)]
     pub fn get_all_txs(conn: &DBConn) -> Result<Vec<MemPoolTxInfo>, db_error> {
@@ -2273,6 +2612,10 @@ impl MemPoolDB {
 
         tx.update_mempool_pager(&txid)?;
 
+        if let Some(ref prior_tx) = prior_tx {
+            tx.cancel_infer_tasks(&[prior_tx.txid.clone()]);
+        }
+
         // broadcast drop event if a tx is being replaced
         if let (Some(prior_tx), Some(event_observer)) = (prior_tx, event_observer) {
             event_observer.mempool_txs_dropped(vec![prior_tx.txid], replace_reason);
@@ -2290,9 +2633,10 @@ impl MemPoolDB {
     ) -> Result<(), db_error> {
         let args: &[&dyn ToSql] = &[&u64_to_sql(min_height)?];
 
+        let sql = "SELECT txid FROM mempool WHERE height < ?1";
+        let txids: Vec<Txid> = query_rows(tx, sql, args)?;
+        tx.cancel_infer_tasks(&txids);
         if let Some(event_observer) = event_observer {
-            let sql = "SELECT txid FROM mempool WHERE height < ?1";
-            let txids = query_rows(tx, sql, args)?;
             event_observer.mempool_txs_dropped(txids, MemPoolDropReason::STALE_COLLECT);
         }
 
@@ -2452,6 +2796,12 @@ impl MemPoolDB {
             return Err(MemPoolRejection::TemporarilyBlacklisted);
         }
 
+        // every admitted Infer transaction costs this node an inference
+        let now = get_epoch_time_secs();
+        if let Some(request) = tx.payload.infer_request() {
+            self.infer_admission.check(tx, &request, now)?;
+        }
+
         let estimator_result = cost_estimates::estimate_fee_rate(
             tx,
             self.cost_estimator.as_ref(),
@@ -2485,6 +2835,10 @@ impl MemPoolDB {
             fee_rate,
         )?;
         mempool_tx.commit().map_err(MemPoolRejection::DBError)?;
+
+        if tx.payload.infer_request().is_some() {
+            self.infer_admission.record_admitted(tx.origin_address(), now);
+        }
         Ok(())
     }
 
@@ -2701,6 +3055,7 @@ impl MemPoolDB {
     pub fn drop_txs(&mut self, txids: &[Txid]) -> Result<(), db_error> {
         let mempool_tx = self.tx_begin()?;
         MemPoolDB::inner_drop_txs(&mempool_tx, txids)?;
+        mempool_tx.cancel_infer_tasks(txids);
         mempool_tx.commit()?;
         Ok(())
     }
@@ -2717,6 +3072,7 @@ impl MemPoolDB {
 
         let mempool_tx = self.tx_begin()?;
         MemPoolDB::inner_drop_txs(&mempool_tx, txids)?;
+        mempool_tx.cancel_infer_tasks(txids);
         MemPoolDB::inner_blacklist_txs(&mempool_tx, txids, now)?;
         MemPoolDB::garbage_collect_tx_blacklist(
             &mempool_tx,
@@ -23,6 +23,7 @@ use clarity::vm::errors::{Error as ClarityError, RuntimeErrorType};
 use clarity::vm::test_util::TEST_BURN_STATE_DB;
 use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, StacksAddressExtensions};
 use clarity::vm::{ClarityName, ContractName, Value};
+use libllm::InferStatus;
 use rand::prelude::*;
 use rand::thread_rng;
 use stacks_common::address::AddressHashMode;
@@ -46,19 +47,20 @@ use crate::chainstate::stacks::db::test::{
 use crate::chainstate::stacks::db::{StacksChainState, StacksHeaderInfo};
 use crate::chainstate::stacks::events::StacksTransactionReceipt;
 use crate::chainstate::stacks::index::{MarfTrieId, TrieHashExtension};
-use crate::chainstate::stacks::miner::TransactionResult;
+use crate::chainstate::stacks::miner::{TransactionEvent, TransactionResult};
 use crate::chainstate::stacks::test::codec_all_transactions;
 use crate::chainstate::stacks::{
-    CoinbasePayload, Error as ChainstateError, SinglesigHashMode, SinglesigSpendingCondition,
-    StacksBlockHeader, StacksMicroblockHeader, StacksPrivateKey, StacksPublicKey,
-    StacksTransaction, StacksTransactionSigner, TokenTransferMemo, TransactionAnchorMode,
-    TransactionAuth, TransactionContractCall, TransactionPayload, TransactionPostConditionMode,
-    TransactionPublicKeyEncoding, TransactionSmartContract, TransactionSpendingCondition,
-    TransactionVersion, C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
+    CoinbasePayload, Error as ChainstateError, InferRequest, SinglesigHashMode,
+    SinglesigSpendingCondition, StacksBlockHeader, StacksMicroblockHeader, StacksPrivateKey,
+    StacksPublicKey, StacksTransaction, StacksTransactionSigner, TokenTransferMemo,
+    TransactionAnchorMode, TransactionAuth, TransactionContractCall, TransactionPayload,
+    TransactionPostConditionMode, TransactionPublicKeyEncoding, TransactionSmartContract,
+    TransactionSpendingCondition, TransactionVersion, C32_ADDRESS_VERSION_MAINNET_SINGLESIG,
     C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
 };
 use crate::core::mempool::{
-    db_get_all_nonces, MemPoolSyncData, MemPoolWalkSettings, MemPoolWalkTxTypes, TxTag,
+    db_get_all_nonces, InferAdmissionConfig, InferAdmissionPolicy, InferContentFilter,
+    InferReadiness, InferSkip, MemPoolSyncData, MemPoolWalkSettings, MemPoolWalkTxTypes, TxTag,
     BLOOM_COUNTER_DEPTH, BLOOM_COUNTER_ERROR_RATE, MAX_BLOOM_COUNTER_TXS,
 };
 use crate::core::{FIRST_BURNCHAIN_CONSENSUS_HASH, FIRST_STACKS_BLOCK_HASH};
@@ -66,7 +68,7 @@ use crate::net::Error as NetError;
 use crate::util_lib::bloom::test::setup_bloom_counter;
 use crate::util_lib::bloom::*;
 use crate::util_lib::db::{tx_begin_immediate, DBConn, FromRow};
-use crate::util_lib::strings::StacksString;
+use crate::util_lib::strings::{InferLPString, StacksString};
 
 const FOO_CONTRACT: &'static str = "(define-public (foo) (ok 1))
                                     (define-public (bar (x uint)) (ok x))";
@@ -2541,6 +2543,430 @@ fn test_drop_and_blacklist_txs_by_time() {
     }
 }
 
+#[test]
+fn test_drop_txs_cancels_infer_tasks() {
+    let mut chainstate = instantiate_chainstate(false, 0x80000000, function_name!());
+    let chainstate_path = chainstate_path(function_name!());
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+
+    let addr = StacksAddress {
+        version: 1,
+        bytes: Hash160([0xff; 20]),
+    };
+    let mut txids = vec![];
+    let block_height = 10;
+
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    for _ in 0..3 {
+        let pk = StacksPrivateKey::new();
+        let mut tx = StacksTransaction {
+            version: TransactionVersion::Testnet,
+            chain_id: 0x80000000,
+            auth: TransactionAuth::from_p2pkh(&pk).unwrap(),
+            anchor_mode: TransactionAnchorMode::Any,
+            post_condition_mode: TransactionPostConditionMode::Allow,
+            post_conditions: vec![],
+            payload: TransactionPayload::TokenTransfer(
+                addr.to_account_principal(),
+                123,
+                TokenTransferMemo([0u8; 34]),
+            ),
+        };
+        tx.set_tx_fee(1000);
+        tx.set_origin_nonce(0);
+
+        let txid = tx.txid();
+        let origin_addr = tx.origin_address();
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &ConsensusHash([0x1 + (block_height as u8); 20]),
+            &BlockHeaderHash([0x2 + (block_height as u8); 32]),
+            txid.clone(),
+            tx.serialize_to_vec(),
+            tx.get_tx_fee(),
+            block_height as u64,
+            &origin_addr,
+            0,
+            &origin_addr,
+            0,
+            None,
+        )
+        .unwrap();
+        txids.push(txid);
+    }
+    mempool_tx.commit().unwrap();
+
+    let infer_db = mempool.infer_db().clone();
+    for txid in txids.iter() {
+        libllm::infer_chain(&infer_db, txid.to_hex(), "Is the Earth round?", "", 1000).unwrap();
+    }
+    // the last task already finished
+    libllm::do_infer(&infer_db, &libllm::MockBackend::new()).unwrap();
+    let finished = txids
+        .iter()
+        .find(|txid| {
+            libllm::query(&infer_db, txid.to_hex()).unwrap().status == InferStatus::Success
+        })
+        .unwrap()
+        .clone();
+    let pending: Vec<_> = txids.iter().filter(|txid| **txid != finished).collect();
+
+    // dropping a tx cancels its task
+    mempool.drop_txs(&[pending[0].clone()]).unwrap();
+    assert_eq!(
+        libllm::query(&infer_db, pending[0].to_hex()).unwrap().status,
+        InferStatus::Cancelled
+    );
+    assert_eq!(
+        libllm::query(&infer_db, pending[1].to_hex()).unwrap().status,
+        InferStatus::Created
+    );
+
+    // so does garbage-collecting it, but finished tasks are left alone
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    MemPoolDB::garbage_collect(&mut mempool_tx, block_height + 1, None).unwrap();
+    mempool_tx.commit().unwrap();
+    assert_eq!(
+        libllm::query(&infer_db, pending[1].to_hex()).unwrap().status,
+        InferStatus::Cancelled
+    );
+    assert_eq!(
+        libllm::query(&infer_db, finished.to_hex()).unwrap().status,
+        InferStatus::Success
+    );
+
+    // none of them were confirmed, so their results are now orphans
+    for txid in txids.iter() {
+        let row = libllm::db::sqlite_get(&infer_db.conn(), &txid.to_hex()).unwrap();
+        assert_eq!(row.confirm_height, None);
+        assert!(row.evict_time > 0);
+    }
+}
+
+#[test]
+/// Infer transactions are only considered once their inferences succeed; until then, each walk
+/// reports them as skipped (once per status) and records them for the miner.
+fn test_iterate_candidates_infer_readiness() {
+    let mut chainstate =
+        instantiate_chainstate_with_balances(false, 0x80000000, function_name!(), vec![]);
+    let chainstate_path = chainstate_path(function_name!());
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+    let b_1 = make_block(
+        &mut chainstate,
+        ConsensusHash([0x1; 20]),
+        &(
+            FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+            FIRST_STACKS_BLOCK_HASH.clone(),
+        ),
+        1,
+        1,
+    );
+    let b_2 = make_block(&mut chainstate, ConsensusHash([0x2; 20]), &b_1, 2, 2);
+
+    let pk = StacksPrivateKey::new();
+    let tx = make_infer_tx(&pk, "Is the Earth round?", "", 1000);
+    let txid = tx.txid();
+    let origin_address = tx.origin_address();
+    let mut mempool_tx = mempool.tx_begin().unwrap();
+    MemPoolDB::try_add_tx(
+        &mut mempool_tx,
+        &mut chainstate,
+        &b_1.0,
+        &b_1.1,
+        txid.clone(),
+        tx.serialize_to_vec(),
+        tx.get_tx_fee(),
+        100,
+        &origin_address,
+        0,
+        &origin_address,
+        0,
+        None,
+    )
+    .unwrap();
+    mempool_tx.commit().unwrap();
+
+    let mempool_settings = MemPoolWalkSettings::default();
+    let mut walk = |mempool: &mut MemPoolDB, tx_events: &mut Vec<TransactionEvent>| {
+        let mut considered = vec![];
+        chainstate.with_read_only_clarity_tx(
+            &TEST_BURN_STATE_DB,
+            &StacksBlockHeader::make_index_block_hash(&b_2.0, &b_2.1),
+            |clarity_conn| {
+                mempool
+                    .iterate_candidates::<_, ChainstateError, _>(
+                        clarity_conn,
+                        tx_events,
+                        2,
+                        mempool_settings.clone(),
+                        |_, available_tx, _| {
+                            considered.push(available_tx.tx.tx.txid());
+                            Ok(None)
+                        },
+                    )
+                    .unwrap();
+            },
+        );
+        considered
+    };
+
+    // the inference is queued, so the tx is passed over and reported
+    let mut tx_events = vec![];
+    assert!(walk(&mut mempool, &mut tx_events).is_empty());
+    assert_eq!(tx_events.len(), 1);
+    match &tx_events[0] {
+        TransactionEvent::Skipped(event) => {
+            assert_eq!(event.txid, txid);
+            assert_eq!(event.error, "Inference not ready (Created)");
+        }
+        x => panic!("Expected Skipped, got {:?}", x),
+    }
+    let skips = mempool.infer_skips();
+    assert_eq!(skips.len(), 1);
+    assert_eq!(skips[0].txid, txid);
+    assert_eq!(skips[0].readiness(), InferReadiness::Pending);
+
+    // walking again doesn't report it again
+    assert!(walk(&mut mempool, &mut tx_events).is_empty());
+    assert_eq!(tx_events.len(), 1);
+
+    // a cancelled task is queued again
+    let infer_db = mempool.infer_db().clone();
+    libllm::cancel(&infer_db, txid.to_hex()).unwrap();
+    assert!(walk(&mut mempool, &mut tx_events).is_empty());
+    assert_eq!(tx_events.len(), 1);
+    assert_eq!(
+        libllm::query(&infer_db, txid.to_hex()).unwrap().status,
+        InferStatus::Created
+    );
+
+    // once the inference succeeds, the tx is considered
+    libllm::do_infer(&infer_db, &libllm::MockBackend::new()).unwrap();
+    assert_eq!(walk(&mut mempool, &mut tx_events), vec![txid]);
+    assert!(mempool.infer_skips().is_empty());
+
+    mempool.reset_infer_skips();
+    assert!(mempool.infer_skips().is_empty());
+}
+
+#[test]
+/// An output too long for what's left of the block doesn't keep smaller ones out of it
+fn test_infer_outputs_skips_outputs_that_dont_fit() {
+    let mut chainstate =
+        instantiate_chainstate_with_balances(false, 0x80000000, function_name!(), vec![]);
+    let chainstate_path = chainstate_path(function_name!());
+    let mut mempool = MemPoolDB::open_test(false, 0x80000000, &chainstate_path).unwrap();
+    let b_1 = make_block(
+        &mut chainstate,
+        ConsensusHash([0x1; 20]),
+        &(
+            FIRST_BURNCHAIN_CONSENSUS_HASH.clone(),
+            FIRST_STACKS_BLOCK_HASH.clone(),
+        ),
+        1,
+        1,
+    );
+
+    let mut txids = vec![];
+    for (prompt, fee) in [("Is the Earth round?", 2000), ("Is the Earth flat?", 1000)] {
+        let pk = StacksPrivateKey::new();
+        let tx = make_infer_tx(&pk, prompt, "", fee);
+        let txid = tx.txid();
+        let origin_address = tx.origin_address();
+        let mut mempool_tx = mempool.tx_begin().unwrap();
+        MemPoolDB::try_add_tx(
+            &mut mempool_tx,
+            &mut chainstate,
+            &b_1.0,
+            &b_1.1,
+            txid.clone(),
+            tx.serialize_to_vec(),
+            tx.get_tx_fee(),
+            1,
+            &origin_address,
+            0,
+            &origin_address,
+            0,
+            None,
+        )
+        .unwrap();
+        mempool_tx.commit().unwrap();
+        txids.push(txid);
+    }
+
+    // the higher-fee task runs first, and gets the longer output
+    let infer_db = mempool.infer_db().clone();
+    let long_output = "The Earth is round. ".repeat(25);
+    libllm::do_infer(&infer_db, &libllm::MockBackend::with_response(&long_output)).unwrap();
+    libllm::do_infer(&infer_db, &libllm::MockBackend::new()).unwrap();
+
+    let outputs = mempool.infer_outputs(u64::MAX).unwrap();
+    assert_eq!(
+        outputs.iter().map(|output| output.txid).collect::<Vec<_>>(),
+        txids
+    );
+    assert_eq!(outputs[0].output.as_str(), long_output);
+
+    let short_len = outputs[1].serialize_to_vec().len() as u64;
+    let outputs = mempool.infer_outputs(short_len).unwrap();
+    assert_eq!(
+        outputs.iter().map(|output| output.txid).collect::<Vec<_>>(),
+        vec![txids[1]]
+    );
+}
+
+#[test]
+fn test_infer_skip_deadline() {
+    let skip = |status: InferStatus| InferSkip {
+        txid: Txid([0x11; 32]),
+        accept_time: 1000,
+        status,
+    };
+    let deadline_secs = 600;
+
+    let pending = skip(InferStatus::InProgress);
+    assert_eq!(pending.readiness(), InferReadiness::Pending);
+    assert!(!pending.is_expired(1000, deadline_secs));
+    assert!(!pending.is_expired(1599, deadline_secs));
+    assert!(pending.is_expired(1600, deadline_secs));
+    // clocks may disagree
+    assert!(!pending.is_expired(0, deadline_secs));
+
+    // failed inferences don't expire; they're dropped right away
+    for status in [InferStatus::Failure, InferStatus::TimedOut] {
+        let failed = skip(status);
+        assert_eq!(failed.readiness(), InferReadiness::Failed);
+        assert!(!failed.is_expired(1600, deadline_secs));
+        assert!(failed.reason().starts_with("Inference failed"));
+    }
+
+    for status in [
+        InferStatus::Created,
+        InferStatus::Retrying,
+        InferStatus::NotFound,
+        InferStatus::Cancelled,
+    ] {
+        assert_eq!(skip(status).readiness(), InferReadiness::Pending);
+    }
+    assert_eq!(
+        skip(InferStatus::Success).readiness(),
+        InferReadiness::Ready
+    );
+}
+
+fn make_infer_tx(
+    pk: &StacksPrivateKey,
+    user_input: &str,
+    context: &str,
+    fee: u64,
+) -> StacksTransaction {
+    let mut tx = StacksTransaction {
+        version: TransactionVersion::Testnet,
+        chain_id: 0x80000000,
+        auth: TransactionAuth::from_p2pkh(pk).unwrap(),
+        anchor_mode: TransactionAnchorMode::Any,
+        post_condition_mode: TransactionPostConditionMode::Allow,
+        post_conditions: vec![],
+        payload: TransactionPayload::Infer(
+            StacksAddress {
+                version: 1,
+                bytes: Hash160([0xff; 20]),
+            }
+            .to_account_principal(),
+            InferLPString::try_from(user_input).unwrap(),
+            InferLPString::try_from(context).unwrap(),
+        ),
+    };
+    tx.set_tx_fee(fee);
+    tx
+}
+
+struct RejectWord(&'static str);
+
+impl InferContentFilter for RejectWord {
+    fn check(&self, request: &InferRequest) -> Result<(), String> {
+        if request.user_input.contains(self.0) {
+            return Err(format!("prompt mentions '{}'", self.0));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_infer_admission_policy() {
+    let mut policy = InferAdmissionPolicy::new(InferAdmissionConfig {
+        min_fee_rate_per_prompt_byte: 10,
+        max_prompt_len: 32,
+        max_context_len: 8,
+        max_txs_per_origin: 2,
+        rate_limit_window_secs: 60,
+    });
+    let pk = StacksPrivateKey::new();
+    let now = 1_000_000;
+    let check = |policy: &mut InferAdmissionPolicy, tx: &StacksTransaction, now: u64| {
+        policy.check(tx, &tx.payload.infer_request().unwrap(), now)
+    };
+
+    // 19 bytes of prompt cost at least 190 uSTX
+    let tx = make_infer_tx(&pk, "Is the Earth round?", "", 189);
+    match check(&mut policy, &tx, now) {
+        Err(MemPoolRejection::InferFeeTooLow(189, 190)) => {}
+        x => panic!("Expected InferFeeTooLow, got {:?}", x),
+    }
+    let tx = make_infer_tx(&pk, "Is the Earth round?", "", 190);
+    check(&mut policy, &tx, now).unwrap();
+
+    // ...and context is charged for too
+    let tx = make_infer_tx(&pk, "Is the Earth round?", "{}", 190);
+    match check(&mut policy, &tx, now) {
+        Err(MemPoolRejection::InferFeeTooLow(190, 210)) => {}
+        x => panic!("Expected InferFeeTooLow, got {:?}", x),
+    }
+
+    let tx = make_infer_tx(&pk, &"a".repeat(33), "", 1_000_000);
+    match check(&mut policy, &tx, now) {
+        Err(MemPoolRejection::InferPromptTooLarge(33, 32)) => {}
+        x => panic!("Expected InferPromptTooLarge, got {:?}", x),
+    }
+    let tx = make_infer_tx(&pk, "Hi", "{\"seed\": 1}", 1_000_000);
+    match check(&mut policy, &tx, now) {
+        Err(MemPoolRejection::InferContextTooLarge(11, 8)) => {}
+        x => panic!("Expected InferContextTooLarge, got {:?}", x),
+    }
+
+    // each origin gets two txs per minute
+    let tx = make_infer_tx(&pk, "Hi", "", 1000);
+    policy.record_admitted(tx.origin_address(), now);
+    check(&mut policy, &tx, now + 1).unwrap();
+    policy.record_admitted(tx.origin_address(), now + 1);
+    match check(&mut policy, &tx, now + 2) {
+        Err(MemPoolRejection::InferRateLimited {
+            origin,
+            max_txs: 2,
+            window_secs: 60,
+        }) => assert_eq!(origin, tx.origin_address()),
+        x => panic!("Expected InferRateLimited, got {:?}", x),
+    }
+    // ...but other origins are unaffected
+    let other_tx = make_infer_tx(&StacksPrivateKey::new(), "Hi", "", 1000);
+    check(&mut policy, &other_tx, now + 2).unwrap();
+    // ...and the window moves on
+    check(&mut policy, &tx, now + 60).unwrap();
+
+    // the content filter gets the last word
+    policy.set_content_filter(Box::new(RejectWord("Earth")));
+    let tx = make_infer_tx(&StacksPrivateKey::new(), "Is the Earth round?", "", 1000);
+    match check(&mut policy, &tx, now) {
+        Err(MemPoolRejection::InferContentRejected(reason)) => {
+            assert_eq!(reason, "prompt mentions 'Earth'")
+        }
+        x => panic!("Expected InferContentRejected, got {:?}", x),
+    }
+    let tx = make_infer_tx(&StacksPrivateKey::new(), "Is the Moon round?", "", 1000);
+    check(&mut policy, &tx, now).unwrap();
+}
+
 #[test]
 fn test_drop_and_blacklist_txs_by_size() {
     let mut chainstate = instantiate_chainstate(false, 0x80000000, function_name!());
@@ -316,15 +316,16 @@ fn fee_rate_and_weight_from_receipt(
             // TokenTransfers *only* contribute tx_len, and just have an empty ExecutionCost.
             metric.from_len(tx_size)
         }
-        TransactionPayload::Coinbase(..) => {
-            // Coinbase txs are "free", so they don't factor into the fee market.
+        TransactionPayload::Coinbase(..) | TransactionPayload::InferOutputs(..) => {
+            // Coinbase and InferOutputs txs are "free", so they don't factor into the fee market.
             return None;
         }
         TransactionPayload::PoisonMicroblock(..)
         | TransactionPayload::ContractCall(..)
         | TransactionPayload::SmartContract(..)
         | TransactionPayload::TenureChange(..)
-        | TransactionPayload::Infer(..) => {
+        | TransactionPayload::Infer(..)
+        | TransactionPayload::VersionedInfer(..) => {
             // These transaction payload types all "work" the same: they have associated ExecutionCosts
             // and contibute to the block length limit with their tx_len
             metric.from_cost_and_len(&tx_receipt.execution_cost, &block_limit, tx_size)
@@ -182,15 +182,16 @@ impl<M: CostMetric> FeeEstimator for ScalarFeeRateEstimator<M> {
                             tx_size,
                         )
                     }
-                    TransactionPayload::Coinbase(..) => {
-                        // Coinbase txs are "free", so they don't factor into the fee market.
+                    TransactionPayload::Coinbase(..) | TransactionPayload::InferOutputs(..) => {
+                        // Coinbase and InferOutputs txs are "free", so they don't factor into the fee market.
                         return None;
                     }
                     TransactionPayload::PoisonMicroblock(_, _)
                     | TransactionPayload::ContractCall(_)
                     | TransactionPayload::SmartContract(..)
                     | TransactionPayload::TenureChange(..)
-                    | TransactionPayload::Infer(..) => {
+                    | TransactionPayload::Infer(..)
+                    | TransactionPayload::VersionedInfer(..) => {
                         // These transaction payload types all "work" the same: they have associated ExecutionCosts
                         // and contibute to the block length limit with their tx_len
                         self.metric.from_cost_and_len(
@@ -2,6 +2,7 @@ use std::cmp;
 use std::path::Path;
 
 use clarity::vm::costs::ExecutionCost;
+use libllm::InferCost;
 use rusqlite::types::{FromSql, FromSqlError};
 use rusqlite::{
     Connection, Error as SqliteError, OptionalExtension, ToSql, Transaction as SqliteTransaction,
@@ -245,7 +246,17 @@ impl PessimisticEstimator {
             TransactionPayload::PoisonMicroblock(_, _) => "poison-ublock".to_string(),
             TransactionPayload::Coinbase(..) => "coinbase".to_string(),
             TransactionPayload::TenureChange(..) => "tenure-change".to_string(),
-            TransactionPayload::Infer(..) => "infer".to_string(),
+            TransactionPayload::InferOutputs(..) => "infer-outputs".to_string(),
+            TransactionPayload::Infer(..) | TransactionPayload::VersionedInfer(..) => {
+                // an inference costs more the more text it reads, so keep estimates for
+                // prompts of different sizes apart, bucketed by powers of two
+                let request = tx
+                    .infer_request()
+                    .expect("BUG: Infer payload without an Infer request");
+                let prompt_tokens =
+                    InferCost::new(&request.user_input, &request.context, "").cost_units();
+                format!("infer:{}", u64::BITS - prompt_tokens.leading_zeros())
+            }
         };
 
         format!("{}:{}", &tx_descriptor, field)
@@ -6,6 +6,7 @@ use clarity::vm::types::{PrincipalData, StandardPrincipalData};
 use clarity::vm::Value;
 use rand::seq::SliceRandom;
 use rand::Rng;
+use stacks_common::codec::StacksMessageCodec;
 use stacks_common::types::chainstate::{
     BlockHeaderHash, BurnchainHeaderHash, StacksAddress, StacksWorkScore, TrieHash,
 };
@@ -27,6 +28,7 @@ use crate::cost_estimates::tests::common::*;
 use crate::cost_estimates::{
     CostEstimator, EstimatorError, FeeEstimator, FeeRateEstimate, PessimisticEstimator,
 };
+use crate::util_lib::strings::InferLPString;
 
 fn instantiate_test_db() -> PessimisticEstimator {
     let mut path = env::temp_dir();
@@ -135,6 +137,31 @@ fn make_dummy_cc_payload(contract_name: &str, function_name: &str) -> Transactio
     })
 }
 
+fn make_dummy_infer_payload(user_input: &str) -> TransactionPayload {
+    let mut input_bytes = vec![user_input.len() as u8];
+    input_bytes.extend_from_slice(user_input.as_bytes());
+    TransactionPayload::Infer(
+        PrincipalData::Standard(StandardPrincipalData(0, [0; 20])),
+        InferLPString::consensus_deserialize(&mut &input_bytes[..]).unwrap(),
+        InferLPString::consensus_deserialize(&mut &[0u8][..]).unwrap(),
+    )
+}
+
+fn make_dummy_infer_tx(user_input: &str, execution_cost: ExecutionCost) -> StacksTransactionReceipt {
+    let tx = StacksTransaction::new(
+        TransactionVersion::Mainnet,
+        TransactionAuth::Standard(TransactionSpendingCondition::new_initial_sighash()),
+        make_dummy_infer_payload(user_input),
+    );
+
+    StacksTransactionReceipt::from_infer(
+        tx,
+        vec![],
+        Value::okay(Value::Bool(true)).unwrap(),
+        execution_cost,
+    )
+}
+
 #[test]
 fn test_cost_estimator_notify_block() {
     let mut estimator = instantiate_test_db();
@@ -870,3 +897,51 @@ fn test_cost_estimator_epochs_independent() {
         cost_205.clone(),
     );
 }
+
+/// Test that Infer transactions with prompts of very different sizes get separate estimates,
+/// while prompts of similar size share one.
+#[test]
+fn test_cost_estimator_infer_prompt_sizes() {
+    let short_prompt = "Is the Earth round?";
+    let long_prompt = "Why is the sky blue? ".repeat(5);
+    let short_cost = ExecutionCost {
+        write_length: 1,
+        write_count: 1,
+        read_length: 1,
+        read_count: 1,
+        runtime: 10_000,
+    };
+    let long_cost = ExecutionCost {
+        runtime: 60_000,
+        ..short_cost.clone()
+    };
+    let mut estimator = instantiate_test_db();
+
+    estimator.notify_block(
+        &vec![
+            make_dummy_infer_tx(short_prompt, short_cost.clone()),
+            make_dummy_infer_tx(&long_prompt, long_cost.clone()),
+        ],
+        &BLOCK_LIMIT_MAINNET_20,
+        &StacksEpochId::Epoch25,
+    );
+
+    assert_eq!(
+        estimator
+            .estimate_cost(
+                &make_dummy_infer_payload("Is the Moon round?"),
+                &StacksEpochId::Epoch25
+            )
+            .expect("Should be able to provide cost estimate now"),
+        short_cost,
+    );
+    assert_eq!(
+        estimator
+            .estimate_cost(
+                &make_dummy_infer_payload(&long_prompt),
+                &StacksEpochId::Epoch25
+            )
+            .expect("Should be able to provide cost estimate now"),
+        long_cost,
+    );
+}
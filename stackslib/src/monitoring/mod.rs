@@ -18,10 +18,12 @@ use std::error::Error;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Mutex;
+use std::time::Duration;
 use std::{fmt, fs};
 
 use clarity::vm::costs::ExecutionCost;
 use lazy_static::lazy_static;
+use libllm::{InferMetrics, InferStatus};
 use rusqlite::{OpenFlags, OptionalExtension};
 use stacks_common::util::get_epoch_time_secs;
 use stacks_common::util::uint::{Uint256, Uint512};
@@ -352,6 +354,67 @@ pub fn increment_contract_calls_processed() {
     prometheus::CONTRACT_CALLS_PROCESSED_COUNT.inc();
 }
 
+#[allow(unused_variables)]
+pub fn observe_infer_queue_wait(wait: Duration) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::INFER_QUEUE_WAIT_HISTOGRAM.observe(wait.as_secs_f64());
+}
+
+#[allow(unused_variables)]
+pub fn observe_infer_model_latency(latency: Duration) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::INFER_MODEL_LATENCY_HISTOGRAM.observe(latency.as_secs_f64());
+}
+
+#[allow(unused_variables)]
+pub fn increment_infer_outcome_counter(outcome: &str) {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::INFER_OUTCOMES_COUNTER_VEC
+        .with_label_values(&[outcome])
+        .inc();
+}
+
+#[allow(unused_variables)]
+pub fn update_infer_jobs(in_progress: u64, pending: u64) {
+    #[cfg(feature = "monitoring_prom")]
+    {
+        prometheus::INFER_JOBS_IN_PROGRESS_GAUGE.set(in_progress as i64);
+        prometheus::INFER_JOBS_PENDING_GAUGE.set(pending as i64);
+    }
+}
+
+pub fn increment_infer_check_block_rejections_counter() {
+    #[cfg(feature = "monitoring_prom")]
+    prometheus::INFER_CHECK_BLOCK_REJECTIONS_COUNTER.inc();
+}
+
+/// Reports the inference worker pool's metrics through the functions above
+pub struct InferPoolMonitor;
+
+impl InferMetrics for InferPoolMonitor {
+    fn observe_queue_wait(&self, wait: Duration) {
+        observe_infer_queue_wait(wait);
+    }
+
+    fn observe_model_latency(&self, latency: Duration) {
+        observe_infer_model_latency(latency);
+    }
+
+    fn record_outcome(&self, status: &InferStatus) {
+        let outcome = match status {
+            InferStatus::Success => "success",
+            InferStatus::Retrying => "retry",
+            InferStatus::TimedOut => "timeout",
+            _ => "failure",
+        };
+        increment_infer_outcome_counter(outcome);
+    }
+
+    fn set_jobs(&self, in_progress: u64, pending: u64) {
+        update_infer_jobs(in_progress, pending);
+    }
+}
+
 /// Given a value (type uint256), return value/uint256::max() as an f64 value.
 /// The precision of the percentage is determined by the input `precision_points`, which is capped
 /// at a max of 15.
@@ -254,6 +254,39 @@ lazy_static! {
         "stacks_node_miner_current_median_commitment_low",
         "Low 64 bits of a miner's median commitment over the mining commitment window."
     )).unwrap();
+
+    pub static ref INFER_QUEUE_WAIT_HISTOGRAM: Histogram = register_histogram!(histogram_opts!(
+        "stacks_node_infer_queue_wait_seconds",
+        "Time (seconds) an inference task waited in the queue before an attempt at it started",
+        vec![1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0]
+    )).unwrap();
+
+    pub static ref INFER_MODEL_LATENCY_HISTOGRAM: Histogram = register_histogram!(histogram_opts!(
+        "stacks_node_infer_model_latency_seconds",
+        "Time (seconds) the inference backend took to answer an attempt at a task",
+        vec![0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0]
+    )).unwrap();
+
+    pub static ref INFER_OUTCOMES_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
+        "stacks_node_infer_outcomes_total",
+        "Inference attempts by outcome: success, retry, failure or timeout",
+        &["outcome"]
+    ).unwrap();
+
+    pub static ref INFER_JOBS_IN_PROGRESS_GAUGE: IntGauge = register_int_gauge!(opts!(
+        "stacks_node_infer_jobs_in_progress",
+        "Number of inference tasks being run by the worker pool"
+    )).unwrap();
+
+    pub static ref INFER_JOBS_PENDING_GAUGE: IntGauge = register_int_gauge!(opts!(
+        "stacks_node_infer_jobs_pending",
+        "Number of inference tasks queued to be run, including those waiting to be retried"
+    )).unwrap();
+
+    pub static ref INFER_CHECK_BLOCK_REJECTIONS_COUNTER: IntCounter = register_int_counter!(opts!(
+        "stacks_signer_infer_check_block_rejections_total",
+        "Total number of blocks the signer rejected because an Infer transaction failed its check"
+    )).unwrap();
 }
 
 pub fn new_rpc_call_timer(path: &str) -> HistogramTimer {
@@ -14,12 +14,16 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
 use std::io::{Read, Write};
 
 use clarity::vm::analysis::contract_interface_builder::ContractInterface;
+use clarity::vm::analysis::cost_bound_checker::{CostBound, CostBoundChecker};
+use clarity::vm::analysis::{run_analysis, ContractAnalysis};
 use clarity::vm::ast::parser::v1::CLARITY_NAME_REGEX;
+use clarity::vm::ast::{build_ast_with_rules, ASTRules};
 use clarity::vm::clarity::ClarityConnection;
-use clarity::vm::costs::LimitedCostTracker;
+use clarity::vm::costs::{ExecutionCost, LimitedCostTracker};
 use clarity::vm::database::clarity_store::{make_contract_hash_key, ContractCommitment};
 use clarity::vm::database::{ClarityDatabase, STXBalance, StoreType};
 use clarity::vm::representations::{
@@ -64,6 +68,58 @@ impl RPCGetContractAbiRequestHandler {
     }
 }
 
+/// Bound the cost of calling each public and read-only function of the contract analyzed in
+/// `contract`, with the cost functions in force at `clarity_tx`'s tip.  The bounds need a type
+/// map, which isn't kept when a contract is deployed, so the contract's source is type-checked
+/// again here.  Returns `None` if that fails.
+fn contract_cost_bounds<C: ClarityConnection>(
+    clarity_tx: &mut C,
+    contract: &ContractAnalysis,
+    mainnet: bool,
+    chain_id: u32,
+) -> Option<BTreeMap<ClarityName, CostBound>> {
+    let contract_identifier = &contract.contract_identifier;
+    let epoch = clarity_tx.get_epoch();
+    let source =
+        clarity_tx.with_clarity_db_readonly(|db| db.get_contract_src(contract_identifier))?;
+    let mut cost_track = clarity_tx
+        .with_clarity_db_readonly(|db| {
+            LimitedCostTracker::new_mid_block(
+                mainnet,
+                chain_id,
+                ExecutionCost::max_value(),
+                db,
+                epoch,
+            )
+        })
+        .ok()?;
+    let contract_ast = build_ast_with_rules(
+        contract_identifier,
+        &source,
+        &mut cost_track,
+        contract.clarity_version,
+        contract.epoch,
+        ASTRules::PrecheckSize,
+    )
+    .ok()?;
+    let mut analysis = clarity_tx
+        .with_analysis_db_readonly(|db| {
+            run_analysis(
+                contract_identifier,
+                &contract_ast.expressions,
+                db,
+                false,
+                cost_track,
+                contract.epoch,
+                contract.clarity_version,
+                true,
+            )
+        })
+        .ok()?;
+    let mut cost_track = analysis.take_contract_cost_tracker();
+    Some(CostBoundChecker::run(&analysis, &mut cost_track))
+}
+
 /// Decode the HTTP request
 impl HttpRequest for RPCGetContractAbiRequestHandler {
     fn verb(&self) -> &'static str {
@@ -132,13 +188,24 @@ impl RPCRequestHandler for RPCGetContractAbiRequestHandler {
 
         let data_resp =
             node.with_node_state(|_network, sortdb, chainstate, _mempool, _rpc_args| {
+                let mainnet = chainstate.mainnet;
+                let chain_id = chainstate.chain_id;
                 chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), &tip, |clarity_tx| {
                     let epoch = clarity_tx.get_epoch();
-                    clarity_tx.with_analysis_db_readonly(|db| {
-                        db.load_contract(&contract_identifier, &epoch)
-                            .ok()?
-                            .map(|contract| contract.contract_interface)
-                    })
+                    let contract = clarity_tx.with_analysis_db_readonly(|db| {
+                        db.load_contract(&contract_identifier, &epoch).ok()
+                    })??;
+                    let mut contract_interface = contract.contract_interface.clone();
+                    if let Some(ref mut contract_interface) = contract_interface {
+                        // computed on request: the bounds aren't part of consensus, so nothing
+                        // about them is stored when the contract is deployed
+                        if let Some(cost_bounds) =
+                            contract_cost_bounds(clarity_tx, &contract, mainnet, chain_id)
+                        {
+                            contract_interface.set_cost_bounds(&cost_bounds);
+                        }
+                    }
+                    Some(contract_interface)
                 })
             });
 
@@ -1,10 +1,20 @@
-use libllm::InferStatus;
+use clarity::vm::database::InferResultRecord;
+use libllm::{InferAttestation, InferDB, InferParams, InferStatus, SessionTurn};
 use regex::{Captures, Regex};
+use stacks_common::types::chainstate::StacksBlockId;
 use stacks_common::types::net::PeerHost;
+use stacks_common::util::hash::to_hex;
+use crate::burnchains::Txid;
+use crate::chainstate::burn::db::sortdb::SortitionDB;
+use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::Error as ChainstateError;
+use crate::clarity_vm::clarity::{ClarityConnection, Error as ClarityError};
+use crate::net::atlas::{AtlasDB, INFER_OUTPUTS_NAME};
 use crate::net::http::{Error, HttpRequest, HttpRequestContents, HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload, HttpResponsePreamble, HttpServerError, parse_json};
 use crate::net::httpcore::{RPCRequestHandler, StacksHttpRequest, StacksHttpResponse};
 use crate::net::{Error as NetError, StacksNodeState};
 use crate::net::http::response::HttpResponseClone;
+use crate::util_lib::boot::boot_code_id;
 use crate::util_lib::db::{Error as db_error};
 
 /// The request to GET /v2/infer_res/{txid}
@@ -27,28 +37,249 @@ pub struct RPCInferResultData {
     pub input: String,
     pub output: String,
     pub output_hash: String,
+    /// the sampling parameters (and model) the output was produced with, so that it can be
+    /// reproduced
+    #[serde(default)]
+    pub params: Option<InferParams>,
+    /// how many queued tasks will run before this one, while it is queued
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<u64>,
+    /// when the task was queued, started, and finished (UTC `YYYY-MM-DD HH:MM:SS`), if this
+    /// node ran it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub create_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<String>,
+    /// whether the model that produced the output is the one registered under its name in
+    /// `.infer-models`, if the output was produced by a named model and the node could check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_verified: Option<bool>,
+    /// this node's signed attestation of the output, if it produced it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<InferAttestation>,
+    /// if the task continues a session, the context it was run with, including the
+    /// conversation so far
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_context: Option<String>,
+    /// if the task continues a session, the session's earlier turns, oldest first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_turns: Option<Vec<SessionTurn>>,
+    /// the session hash the task was committed with (hex), if its transaction was mined in
+    /// epoch 3.0 or later in the fork of the chain tip
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_hash: Option<String>,
 }
 
 impl RPCInferResultData {
     pub fn from_llm(
+        infer_db: &InferDB,
         tx_id: String,
     ) -> Result<RPCInferResultData, NetError> {
-        let result = libllm::query(tx_id.clone())
+        let result = libllm::query(infer_db, tx_id.clone())
             .map_err(|e| NetError::DBError(db_error::NotFoundError))?;
+        let progress = libllm::query_progress(infer_db, tx_id.clone())
+            .map_err(|_e| NetError::DBError(db_error::NotFoundError))?;
         Ok(RPCInferResultData {
             txid: tx_id,
             status: result.status,
             input: result.input,
             output: result.output,
             output_hash: result.output_hash,
+            params: result.params,
+            queue_position: progress.queue_position,
+            create_time: progress.create_time,
+            start_time: progress.start_time,
+            end_time: progress.end_time,
+            model_verified: None,
+            attestation: result.attestation,
+            session_context: result.session_context,
+            session_turns: result.session_turns,
+            session_hash: None,
         })
     }
-}
 
-#[derive(Debug, PartialEq, Clone)]
-pub struct InferResultStream {
-    /// tx id
-    pub tx_id: String,
+    /// Load an inference result from the outputs replicated by Atlas.  Only the output and the
+    /// user input are published, so the parameters are unknown.
+    pub fn from_atlas(
+        atlas_db: &AtlasDB,
+        mainnet: bool,
+        tx_id: String,
+    ) -> Result<Option<RPCInferResultData>, NetError> {
+        let Ok(txid) = Txid::from_hex(&tx_id) else {
+            return Ok(None);
+        };
+        let contract_id = boot_code_id(INFER_OUTPUTS_NAME, mainnet);
+        for instance in atlas_db.find_attachment_instances_for_tx(&contract_id, &txid)? {
+            let Some(attachment) = atlas_db.find_attachment(&instance.content_hash)? else {
+                continue;
+            };
+            let input = instance.infer_user_input().unwrap_or_default();
+            let output = String::from_utf8_lossy(&attachment.content).into_owned();
+            return Ok(Some(RPCInferResultData {
+                txid: tx_id,
+                status: InferStatus::Success,
+                input,
+                output_hash: libllm::output_hash(&output),
+                output,
+                params: None,
+                queue_position: None,
+                create_time: None,
+                start_time: None,
+                end_time: None,
+                model_verified: None,
+                attestation: None,
+                session_context: None,
+                session_turns: None,
+                session_hash: None,
+            }));
+        }
+        Ok(None)
+    }
+
+    /// Check the model that produced this result against the `.infer-models` registry
+    pub fn verify_model<T: ClarityConnection>(
+        &mut self,
+        clarity_tx: &mut T,
+        mainnet: bool,
+    ) -> Result<(), ChainstateError> {
+        if self.status != InferStatus::Success {
+            return Ok(());
+        }
+        let Some(ref params) = self.params else {
+            return Ok(());
+        };
+        let Some(ref model_id) = params.model else {
+            return Ok(());
+        };
+        let verified = StacksChainState::get_registered_infer_model(clarity_tx, mainnet, model_id)?
+            .map(|model| model.check_ran(params).is_ok())
+            .unwrap_or(false);
+        self.model_verified = Some(verified);
+        Ok(())
+    }
+
+    /// Look up the session hash the task was committed with
+    pub fn load_session_hash<T: ClarityConnection>(
+        &mut self,
+        clarity_tx: &mut T,
+    ) -> Result<(), ChainstateError> {
+        let Ok(txid) = Txid::from_hex(&self.txid) else {
+            return Ok(());
+        };
+        let record = clarity_tx
+            .with_clarity_db_readonly(|db| db.get_infer_result(&txid.0))
+            .map_err(|e| ChainstateError::ClarityError(ClarityError::Interpreter(e)))?;
+        self.session_hash = record
+            .and_then(|record| record.session_hash)
+            .map(|session_hash| to_hex(&session_hash));
+        Ok(())
+    }
+
+    /// Look up the result the task was committed with as of `tip`, if its transaction was mined
+    /// in epoch 3.0 or later, where blocks carry the outputs of their Infer transactions.
+    pub fn load_committed(
+        chainstate: &mut StacksChainState,
+        sortdb: &SortitionDB,
+        tip: &StacksBlockId,
+        tx_id: &str,
+    ) -> Result<Option<InferResultRecord>, NetError> {
+        let Ok(txid) = Txid::from_hex(tx_id) else {
+            return Ok(None);
+        };
+        let record =
+            chainstate.maybe_read_only_clarity_tx(&sortdb.index_conn(), tip, |clarity_tx| {
+                if !clarity_tx.get_epoch().carries_infer_outputs() {
+                    return Ok(None);
+                }
+                clarity_tx.with_clarity_db_readonly(|db| db.get_infer_result(&txid.0))
+            })?;
+        match record {
+            Some(record) => {
+                record.map_err(|e| NetError::ClarityError(ClarityError::Interpreter(e)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Load a committed inference result.  The output is the one its block carried: our own or
+    /// the published output if it hashes to the committed output hash, or else the recorded
+    /// output, if it was short enough to be kept.
+    fn from_committed(
+        tx_id: String,
+        record: InferResultRecord,
+        local: Result<RPCInferResultData, NetError>,
+        published: Option<RPCInferResultData>,
+    ) -> Result<RPCInferResultData, NetError> {
+        let output_hash = to_hex(&record.output_hash);
+        let local = local.ok();
+        if let Some(local) = local.as_ref() {
+            if local.status == InferStatus::Success && local.output_hash == output_hash {
+                return Ok(local.clone());
+            }
+        }
+        if let Some(published) = published.as_ref() {
+            if published.output_hash == output_hash {
+                return Ok(published.clone());
+            }
+        }
+        let Some(output) = record.output else {
+            return Err(NetError::DBError(db_error::NotFoundError));
+        };
+        // the input is the transaction's, whichever output we have for it
+        let input = local
+            .map(|local| local.input)
+            .or(published.map(|published| published.input))
+            .unwrap_or_default();
+        Ok(RPCInferResultData {
+            txid: tx_id,
+            status: InferStatus::Success,
+            input,
+            output,
+            output_hash,
+            params: None,
+            queue_position: None,
+            create_time: None,
+            start_time: None,
+            end_time: None,
+            model_verified: None,
+            attestation: None,
+            session_context: None,
+            session_turns: None,
+            session_hash: None,
+        })
+    }
+
+    /// Load an inference result.  If it was committed in epoch 3.0 or later (see
+    /// `load_committed`), this is the committed output, whatever this node ran.  Otherwise,
+    /// prefer our own, which knows the parameters, and then the published output, if there
+    /// is one.
+    pub fn load(
+        infer_db: &InferDB,
+        atlas_db: &AtlasDB,
+        mainnet: bool,
+        tx_id: String,
+        committed: Option<InferResultRecord>,
+    ) -> Result<RPCInferResultData, NetError> {
+        let local = RPCInferResultData::from_llm(infer_db, tx_id.clone());
+        let ran_here = matches!(local, Ok(ref res) if res.status == InferStatus::Success);
+        if committed.is_none() && ran_here {
+            return local;
+        }
+        let published = match RPCInferResultData::from_atlas(atlas_db, mainnet, tx_id.clone()) {
+            Ok(published) => published,
+            Err(e) => {
+                warn!("Failed to load published infer result";
+                      "txid" => %tx_id, "err" => ?e);
+                None
+            }
+        };
+        match committed {
+            Some(record) => RPCInferResultData::from_committed(tx_id, record, local, published),
+            None => published.map(Ok).unwrap_or(local),
+        }
+    }
 }
 
 /// Decode the HTTP request
@@ -104,7 +335,7 @@ impl RPCRequestHandler for RPCInferResultRequestHandler {
     fn try_handle_request(
         &mut self,
         preamble: HttpRequestPreamble,
-        _contents: HttpRequestContents,
+        contents: HttpRequestContents,
         node: &mut StacksNodeState,
     ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
         let tx_id = self
@@ -112,9 +343,38 @@ impl RPCRequestHandler for RPCInferResultRequestHandler {
             .take()
             .ok_or(NetError::SendError("Missing tx_id".to_string()))?;
 
-        let result =
-            node.with_node_state(|_network, _sortdb, _chainstate, _mempool, _rpc_args| {
-                RPCInferResultData::from_llm(tx_id.clone())
+        // the model registry and the committed session hash are checked as of the chain tip,
+        // if there is one
+        let tip = node.load_stacks_chain_tip(&preamble, &contents).ok();
+
+        let result: Result<RPCInferResultData, NetError> =
+            node.with_node_state(|network, sortdb, chainstate, _mempool, _rpc_args| {
+                let committed = match tip {
+                    Some(ref tip) => {
+                        RPCInferResultData::load_committed(chainstate, sortdb, tip, &tx_id)?
+                    }
+                    None => None,
+                };
+                let mut result = RPCInferResultData::load(
+                    &chainstate.infer_db,
+                    network.get_atlasdb(),
+                    chainstate.mainnet,
+                    tx_id.clone(),
+                    committed,
+                )?;
+                if let Some(tip) = tip {
+                    let mainnet = chainstate.mainnet;
+                    let verified = chainstate
+                        .maybe_read_only_clarity_tx(&sortdb.index_conn(), &tip, |clarity_tx| {
+                            result.verify_model(clarity_tx, mainnet)?;
+                            result.load_session_hash(clarity_tx)
+                        });
+                    if let Err(e) | Ok(Some(Err(e))) = verified {
+                        warn!("Failed to check the model or session of infer result";
+                              "txid" => %tx_id, "err" => ?e);
+                    }
+                }
+                Ok(result)
             });
 
         info!("Infer result for tx_id:{} infer_res:{:?}", tx_id, result);
@@ -0,0 +1,439 @@
+use std::time::{Duration, Instant};
+
+use libllm::{InferDB, InferStatus};
+use regex::{Captures, Regex};
+use stacks_common::codec::MAX_MESSAGE_LEN;
+use stacks_common::types::net::PeerHost;
+
+use crate::net::api::getinferresult::RPCInferResultData;
+use crate::net::http::common::parse_raw_bytes;
+use crate::net::http::{
+    Error, HttpChunkGenerator, HttpContentType, HttpRequest, HttpRequestContents,
+    HttpRequestPreamble, HttpResponse, HttpResponseContents, HttpResponsePayload,
+    HttpResponsePreamble, HttpServerError,
+};
+use crate::net::httpcore::{RPCRequestHandler, StacksHttpRequest, StacksHttpResponse};
+use crate::net::{Error as NetError, StacksNodeState};
+use crate::util_lib::db::Error as db_error;
+
+/// How often a stream checks on its task
+#[cfg(test)]
+const INFER_STREAM_POLL_INTERVAL: Duration = Duration::ZERO;
+#[cfg(not(test))]
+const INFER_STREAM_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long a stream may go without sending anything before it sends a keep-alive comment, so
+/// that neither end times out while a task waits in the queue
+const INFER_STREAM_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The request to GET /v2/infer_res/{txid}/stream
+#[derive(Clone)]
+pub struct RPCInferResultStreamRequestHandler {
+    pub tx_id: Option<String>,
+}
+
+impl RPCInferResultStreamRequestHandler {
+    pub fn new() -> Self {
+        RPCInferResultStreamRequestHandler { tx_id: None }
+    }
+}
+
+/// Sent as a `status` event whenever a task's status or queue position changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferResultStatusEvent {
+    pub txid: String,
+    pub status: InferStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub create_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<String>,
+}
+
+/// Sent as a `token` event with each piece of output.  If `reset` is set, the output sent so
+/// far is void (e.g. because the task is being retried), and `text` starts it afresh.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferResultTokenEvent {
+    pub text: String,
+    #[serde(default)]
+    pub reset: bool,
+}
+
+/// One server-sent event of an inference result stream
+#[derive(Debug, Clone, PartialEq)]
+pub enum InferResultEvent {
+    Status(InferResultStatusEvent),
+    Token(InferResultTokenEvent),
+    /// The final result, after which the stream ends
+    Done(RPCInferResultData),
+}
+
+impl InferResultEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            InferResultEvent::Status(..) => "status",
+            InferResultEvent::Token(..) => "token",
+            InferResultEvent::Done(..) => "done",
+        }
+    }
+
+    fn data(&self) -> Result<String, serde_json::Error> {
+        match self {
+            InferResultEvent::Status(ref event) => serde_json::to_string(event),
+            InferResultEvent::Token(ref event) => serde_json::to_string(event),
+            InferResultEvent::Done(ref result) => serde_json::to_string(result),
+        }
+    }
+
+    /// Encode as a server-sent event.  The data is a single line of JSON.
+    pub fn encode(&self) -> Result<Vec<u8>, serde_json::Error> {
+        Ok(format!("event: {}\ndata: {}\n\n", self.name(), self.data()?).into_bytes())
+    }
+
+    fn decode(name: &str, data: &str) -> Result<InferResultEvent, serde_json::Error> {
+        match name {
+            "status" => Ok(InferResultEvent::Status(serde_json::from_str(data)?)),
+            "token" => Ok(InferResultEvent::Token(serde_json::from_str(data)?)),
+            "done" => Ok(InferResultEvent::Done(serde_json::from_str(data)?)),
+            _ => Err(serde::de::Error::custom(format!(
+                "Unknown event type '{}'",
+                name
+            ))),
+        }
+    }
+
+    /// Decode a stream of server-sent events.  Comments (i.e. keep-alives) are skipped.
+    pub fn decode_all(body: &str) -> Result<Vec<InferResultEvent>, Error> {
+        let mut events = vec![];
+        for block in body.split("\n\n") {
+            let mut name = None;
+            let mut data = None;
+            for line in block.lines() {
+                if let Some(value) = line.strip_prefix("event:") {
+                    name = Some(value.trim());
+                } else if let Some(value) = line.strip_prefix("data:") {
+                    data = Some(value.trim());
+                }
+            }
+            let (Some(name), Some(data)) = (name, data) else {
+                continue;
+            };
+            let event = InferResultEvent::decode(name, data).map_err(|e| {
+                Error::DecodeError(format!("Failed to decode '{}' event: {:?}", name, &e))
+            })?;
+            events.push(event);
+        }
+        Ok(events)
+    }
+}
+
+/// Follows an inference task in the `InferDB`, and streams its progress as server-sent events:
+/// its status and queue position, its output as it is generated, and finally its result.
+pub struct InferResultStream {
+    /// tx id
+    pub tx_id: String,
+    infer_db: InferDB,
+    /// the result to finish with, if it is already known
+    result: Option<RPCInferResultData>,
+    /// the status and queue position last sent
+    last_status: Option<(InferStatus, Option<u64>)>,
+    /// the output sent so far
+    sent_output: String,
+    last_poll: Option<Instant>,
+    last_sent: Instant,
+    finished: bool,
+}
+
+impl InferResultStream {
+    pub fn new(infer_db: InferDB, tx_id: String) -> InferResultStream {
+        InferResultStream {
+            tx_id,
+            infer_db,
+            result: None,
+            last_status: None,
+            sent_output: String::new(),
+            last_poll: None,
+            last_sent: Instant::now(),
+            finished: false,
+        }
+    }
+
+    /// Stream `result`, which is already final (e.g. it was published via Atlas), and finish.
+    pub fn from_result(infer_db: InferDB, result: RPCInferResultData) -> InferResultStream {
+        let mut stream = InferResultStream::new(infer_db, result.txid.clone());
+        stream.result = Some(result);
+        stream
+    }
+
+    /// The events that bring a client up to date with `status` and `output`
+    fn progress_events(
+        &mut self,
+        status: InferResultStatusEvent,
+        output: &str,
+    ) -> Vec<InferResultEvent> {
+        let mut events = vec![];
+        let status_key = (status.status.clone(), status.queue_position);
+        if self.last_status.as_ref() != Some(&status_key) {
+            self.last_status = Some(status_key);
+            events.push(InferResultEvent::Status(status));
+        }
+        if output != self.sent_output {
+            let event = match output.strip_prefix(self.sent_output.as_str()) {
+                Some(delta) => InferResultTokenEvent {
+                    text: delta.to_string(),
+                    reset: false,
+                },
+                None => InferResultTokenEvent {
+                    text: output.to_string(),
+                    reset: true,
+                },
+            };
+            self.sent_output = output.to_string();
+            events.push(InferResultEvent::Token(event));
+        }
+        events
+    }
+
+    /// The events that finish the stream with `result`
+    fn finish(&mut self, result: RPCInferResultData) -> Vec<InferResultEvent> {
+        let status = InferResultStatusEvent {
+            txid: result.txid.clone(),
+            status: result.status.clone(),
+            queue_position: result.queue_position,
+            create_time: result.create_time.clone(),
+            start_time: result.start_time.clone(),
+            end_time: result.end_time.clone(),
+        };
+        let mut events = self.progress_events(status, &result.output);
+        events.push(InferResultEvent::Done(result));
+        self.finished = true;
+        events
+    }
+
+    /// Check on the task, and return the events for whatever changed
+    fn poll(&mut self) -> Result<Vec<InferResultEvent>, NetError> {
+        let progress = libllm::query_progress(&self.infer_db, self.tx_id.clone())
+            .map_err(|e| NetError::DBError(db_error::Other(e.to_string())))?;
+        if progress.status.is_finished() {
+            let result = RPCInferResultData::from_llm(&self.infer_db, self.tx_id.clone())?;
+            return Ok(self.finish(result));
+        }
+        let status = InferResultStatusEvent {
+            txid: progress.txid,
+            status: progress.status,
+            queue_position: progress.queue_position,
+            create_time: progress.create_time,
+            start_time: progress.start_time,
+            end_time: progress.end_time,
+        };
+        Ok(self.progress_events(status, &progress.output))
+    }
+}
+
+impl HttpChunkGenerator for InferResultStream {
+    fn hint_chunk_size(&self) -> usize {
+        4096
+    }
+
+    fn is_live(&self) -> bool {
+        !self.finished
+    }
+
+    #[cfg_attr(test, mutants::skip)]
+    fn generate_next_chunk(&mut self) -> Result<Vec<u8>, String> {
+        if self.finished {
+            return Ok(vec![]);
+        }
+
+        let events = if let Some(result) = self.result.take() {
+            self.finish(result)
+        } else if self
+            .last_poll
+            .map(|last_poll| last_poll.elapsed() < INFER_STREAM_POLL_INTERVAL)
+            .unwrap_or(false)
+        {
+            vec![]
+        } else {
+            self.last_poll = Some(Instant::now());
+            self.poll().map_err(|e| {
+                let msg = format!("Failed to load infer result for {}: {:?}", &self.tx_id, &e);
+                warn!("{}", &msg);
+                msg
+            })?
+        };
+
+        let mut chunk = vec![];
+        for event in events.iter() {
+            let bytes = event.encode().map_err(|e| {
+                let msg = format!("Failed to encode infer result event: {:?}", &e);
+                warn!("{}", &msg);
+                msg
+            })?;
+            chunk.extend_from_slice(&bytes);
+        }
+
+        if chunk.is_empty() && self.last_sent.elapsed() >= INFER_STREAM_KEEPALIVE_INTERVAL {
+            chunk.extend_from_slice(b": keep-alive\n\n");
+        }
+        if !chunk.is_empty() {
+            self.last_sent = Instant::now();
+        }
+        Ok(chunk)
+    }
+}
+
+/// Decode the HTTP request
+impl HttpRequest for RPCInferResultStreamRequestHandler {
+    fn verb(&self) -> &'static str {
+        "GET"
+    }
+
+    fn path_regex(&self) -> Regex {
+        Regex::new(r#"^/v2/infer_res/(?P<tx_id>[0-9a-zA-Z]+)/stream$"#).unwrap()
+    }
+
+    fn metrics_identifier(&self) -> &str {
+        "/v2/infer_res/:tx_id/stream"
+    }
+
+    /// Try to decode this request.
+    /// There's nothing to load here, so just make sure the request is well-formed.
+    fn try_parse_request(
+        &mut self,
+        preamble: &HttpRequestPreamble,
+        captures: &Captures,
+        query: Option<&str>,
+        _body: &[u8],
+    ) -> Result<HttpRequestContents, Error> {
+        if preamble.get_content_length() != 0 {
+            return Err(Error::DecodeError(
+                "Invalid Http request: expected 0-length body".to_string(),
+            ));
+        }
+
+        let tx_id = captures
+            .name("tx_id")
+            .ok_or(Error::DecodeError("Missing tx_id".to_string()))?
+            .as_str()
+            .to_string();
+
+        self.tx_id = Some(tx_id);
+
+        Ok(HttpRequestContents::new().query_string(query))
+    }
+}
+
+impl RPCRequestHandler for RPCInferResultStreamRequestHandler {
+    /// Reset internal state
+    fn restart(&mut self) {
+        self.tx_id = None;
+    }
+
+    /// Make the response
+    fn try_handle_request(
+        &mut self,
+        preamble: HttpRequestPreamble,
+        contents: HttpRequestContents,
+        node: &mut StacksNodeState,
+    ) -> Result<(HttpResponsePreamble, HttpResponseContents), NetError> {
+        let tx_id = self
+            .tx_id
+            .take()
+            .ok_or(NetError::SendError("Missing tx_id".to_string()))?;
+
+        let tip = node.load_stacks_chain_tip(&preamble, &contents).ok();
+
+        let stream_res: Result<InferResultStream, NetError> =
+            node.with_node_state(|network, sortdb, chainstate, _mempool, _rpc_args| {
+                let committed = match tip {
+                    Some(ref tip) => {
+                        RPCInferResultData::load_committed(chainstate, sortdb, tip, &tx_id)?
+                    }
+                    None => None,
+                };
+                let infer_db = chainstate.infer_db.clone();
+                let result = RPCInferResultData::load(
+                    &infer_db,
+                    network.get_atlasdb(),
+                    chainstate.mainnet,
+                    tx_id.clone(),
+                    committed,
+                )?;
+                // follow the task if it's still running here; otherwise just send the result
+                if result.status.is_finished() {
+                    Ok(InferResultStream::from_result(infer_db, result))
+                } else {
+                    Ok(InferResultStream::new(infer_db, tx_id.clone()))
+                }
+            });
+
+        let stream = match stream_res {
+            Ok(stream) => stream,
+            Err(e) => {
+                return StacksHttpResponse::new_error(
+                    &preamble,
+                    &HttpServerError::new(format!("Failed to load infer result: {:?}", &e)),
+                )
+                .try_into_contents()
+                .map_err(NetError::from);
+            }
+        };
+
+        let mut resp_preamble = HttpResponsePreamble::from_http_request_preamble(
+            &preamble,
+            200,
+            "OK",
+            None,
+            HttpContentType::EventStream,
+        );
+        resp_preamble.add_header("Cache-Control".into(), "no-cache".into());
+
+        Ok((
+            resp_preamble,
+            HttpResponseContents::from_stream(Box::new(stream)),
+        ))
+    }
+}
+
+/// Decode the HTTP response
+impl HttpResponse for RPCInferResultStreamRequestHandler {
+    fn try_parse_response(
+        &self,
+        preamble: &HttpResponsePreamble,
+        body: &[u8],
+    ) -> Result<HttpResponsePayload, Error> {
+        let bytes = parse_raw_bytes(
+            preamble,
+            body,
+            MAX_MESSAGE_LEN.into(),
+            HttpContentType::EventStream,
+        )?;
+        let text = String::from_utf8(bytes)
+            .map_err(|_e| Error::DecodeError("Event stream is not UTF-8".to_string()))?;
+        Ok(HttpResponsePayload::Text(text))
+    }
+}
+
+impl StacksHttpRequest {
+    /// Make a new request to stream an infer result
+    pub fn new_getinferresultstream(host: PeerHost, tx_id: String) -> Self {
+        StacksHttpRequest::new_for_peer(
+            host,
+            "GET".into(),
+            format!("/v2/infer_res/{}/stream", tx_id),
+            HttpRequestContents::new(),
+        )
+        .expect("Failed to construct request from infallible data")
+    }
+}
+
+impl StacksHttpResponse {
+    pub fn decode_infer_result_events(self) -> Result<Vec<InferResultEvent>, Error> {
+        let contents = self.get_http_payload_ok()?;
+        let body: String = contents.try_into()?;
+        InferResultEvent::decode_all(&body)
+    }
+}
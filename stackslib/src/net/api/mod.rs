@@ -43,6 +43,7 @@ pub mod getcontractsrc;
 pub mod getdatavar;
 pub mod getheaders;
 pub mod getinferresult;
+pub mod getinferresultstream;
 pub mod getinfo;
 pub mod getistraitimplemented;
 pub mod getmapentry;
@@ -130,6 +131,9 @@ impl StacksHttp {
         self.register_rpc_endpoint(posttransaction::RPCPostTransactionRequestHandler::new());
         self.register_rpc_endpoint(getstackers::GetStackersRequestHandler::default());
         self.register_rpc_endpoint(getinferresult::RPCInferResultRequestHandler::new());
+        self.register_rpc_endpoint(
+            getinferresultstream::RPCInferResultStreamRequestHandler::new(),
+        );
     }
 }
 
@@ -132,6 +132,16 @@ fn test_try_make_response() {
 
     let resp = response.decode_contract_abi_response().unwrap();
 
+    // cost bounds are computed for public and read-only functions
+    let get_bar = resp
+        .functions
+        .iter()
+        .find(|function| function.name == "get-bar")
+        .unwrap();
+    let cost_bound = get_bar.cost_bound.as_ref().unwrap();
+    assert!(cost_bound.worst_case.runtime > 0);
+    assert_eq!(cost_bound.worst_case.read_count, 1);
+
     // unconfirmed data
     let response = responses.remove(0);
     debug!(
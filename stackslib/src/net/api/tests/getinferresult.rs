@@ -1,5 +1,9 @@
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use clarity::vm::database::InferResultRecord;
+use stacks_common::util::hash::hex_bytes;
+use crate::burnchains::Txid;
 use crate::net::api::getinferresult;
+use crate::net::atlas::{AtlasConfig, AtlasDB};
 use crate::net::api::tests::{test_rpc, TestRPC};
 use crate::net::connection::ConnectionOptions;
 use crate::net::httpcore::{RPCRequestHandler, StacksHttp, StacksHttpRequest};
@@ -56,13 +60,81 @@ fn test_try_make_response() {
     let request = StacksHttpRequest::new_getinferresult(addr.into(), "654321".to_string());
     requests.push(request);
 
+    // query an infer result published via Atlas
+    let request = StacksHttpRequest::new_getinferresult(addr.into(), Txid([0xab; 32]).to_hex());
+    requests.push(request);
+
     let mut responses = rpc_test.run(requests);
 
     // got the infer result
     let response = responses.remove(0);
     let resp = response.decode_rpc_get_infer_result().unwrap();
 
-    assert_eq!(resp.tx_id, "123456".to_string());
+    assert_eq!(resp.txid, "123456".to_string());
 
     assert_eq!(resp.status, libllm::InferStatus::NotFound);
-}
\ No newline at end of file
+
+    let _ = responses.remove(0);
+
+    // got the published output
+    let response = responses.remove(0);
+    let resp = response.decode_rpc_get_infer_result().unwrap();
+
+    assert_eq!(resp.txid, Txid([0xab; 32]).to_hex());
+    assert_eq!(resp.status, libllm::InferStatus::Success);
+    assert_eq!(resp.input, "Is the Earth round?");
+    assert_eq!(resp.output, "Yes, the Earth is round.");
+    assert_eq!(resp.output_hash, libllm::output_hash(&resp.output));
+    // only the node that produced an output can attest to it
+    assert_eq!(resp.attestation, None);
+}
+
+#[test]
+fn test_load_committed() {
+    let infer_db = libllm::InferDB::open_memory().unwrap();
+    let atlas_db = AtlasDB::connect_memory(AtlasConfig::new(false)).unwrap();
+    let tx_id = Txid([0xcd; 32]).to_hex();
+    libllm::infer_chain(&infer_db, tx_id.clone(), "Is the Earth round?", "", 0).unwrap();
+    libllm::do_infer(
+        &infer_db,
+        &libllm::MockBackend::with_response("Yes, it is."),
+    )
+    .unwrap();
+
+    let load = |committed| {
+        getinferresult::RPCInferResultData::load(
+            &infer_db,
+            &atlas_db,
+            false,
+            tx_id.clone(),
+            committed,
+        )
+    };
+    let record = |output: &str| {
+        InferResultRecord::new(hex_bytes(&libllm::output_hash(output)).unwrap(), output)
+    };
+
+    // not committed: our own output
+    let resp = load(None).unwrap();
+    assert_eq!(resp.output, "Yes, it is.");
+    assert!(resp.params.is_some());
+
+    // committed with our own output
+    let resp = load(Some(record("Yes, it is."))).unwrap();
+    assert_eq!(resp.output, "Yes, it is.");
+    assert!(resp.params.is_some());
+
+    // committed with another output: the committed one, whatever we ran
+    let resp = load(Some(record("No."))).unwrap();
+    assert_eq!(resp.status, libllm::InferStatus::Success);
+    assert_eq!(resp.input, "Is the Earth round?");
+    assert_eq!(resp.output, "No.");
+    assert_eq!(resp.output_hash, libllm::output_hash("No."));
+    assert_eq!(resp.params, None);
+    assert_eq!(resp.attestation, None);
+
+    // committed with another output too long to be recorded, which we don't have
+    let mut long = record("No.");
+    long.output = None;
+    assert!(load(Some(long)).is_err());
+}
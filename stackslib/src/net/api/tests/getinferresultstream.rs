@@ -0,0 +1,217 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use libllm::db;
+use libllm::{InferDB, InferStatus};
+
+use crate::burnchains::Txid;
+use crate::net::api::getinferresultstream::{
+    self, InferResultEvent, InferResultStatusEvent, InferResultStream, InferResultTokenEvent,
+};
+use crate::net::api::tests::TestRPC;
+use crate::net::connection::ConnectionOptions;
+use crate::net::http::HttpChunkGenerator;
+use crate::net::httpcore::{RPCRequestHandler, StacksHttp, StacksHttpRequest};
+use crate::net::ProtocolFamily;
+
+#[test]
+fn test_try_parse_request() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+    let mut http = StacksHttp::new(addr.clone(), &ConnectionOptions::default());
+
+    let request = StacksHttpRequest::new_getinferresultstream(addr.into(), "123456".to_string());
+    let bytes = request.try_serialize().unwrap();
+
+    debug!("Request:\n{}\n", std::str::from_utf8(&bytes).unwrap());
+
+    let (parsed_preamble, offset) = http.read_preamble(&bytes).unwrap();
+    let mut handler = getinferresultstream::RPCInferResultStreamRequestHandler::new();
+    let mut parsed_request = http
+        .handle_try_parse_request(
+            &mut handler,
+            &parsed_preamble.expect_request(),
+            &bytes[offset..],
+        )
+        .unwrap();
+
+    // parsed request consumes headers that would not be in a constructed request
+    parsed_request.clear_headers();
+    let (preamble, _contents) = parsed_request.destruct();
+
+    // consumed path args
+    assert_eq!(handler.tx_id, Some("123456".to_string()));
+
+    assert_eq!(&preamble, request.preamble());
+
+    handler.restart();
+    assert!(handler.tx_id.is_none());
+}
+
+#[test]
+fn test_try_make_response() {
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 33333);
+
+    let rpc_test = TestRPC::setup(function_name!());
+    let mut requests = vec![];
+
+    // stream a task this node ran
+    let request =
+        StacksHttpRequest::new_getinferresultstream(addr.into(), Txid([0xcd; 32]).to_hex());
+    requests.push(request);
+
+    // stream an infer result published via Atlas
+    let request =
+        StacksHttpRequest::new_getinferresultstream(addr.into(), Txid([0xab; 32]).to_hex());
+    requests.push(request);
+
+    // stream a non-existent infer result
+    let request = StacksHttpRequest::new_getinferresultstream(addr.into(), "654321".to_string());
+    requests.push(request);
+
+    let mut responses = rpc_test.run(requests);
+
+    // the whole output, then the result
+    let response = responses.remove(0);
+    let mut events = response.decode_infer_result_events().unwrap();
+    assert_eq!(events.len(), 3);
+    let InferResultEvent::Status(status) = events.remove(0) else {
+        panic!("Expected a status event");
+    };
+    assert_eq!(status.status, InferStatus::Success);
+    assert_eq!(status.queue_position, None);
+    assert!(status.create_time.is_some());
+    assert!(status.start_time.is_some());
+    assert!(status.end_time.is_some());
+    assert_eq!(
+        events.remove(0),
+        InferResultEvent::Token(InferResultTokenEvent {
+            text: "Yes, it is.".to_string(),
+            reset: false,
+        })
+    );
+    let InferResultEvent::Done(result) = events.remove(0) else {
+        panic!("Expected a done event");
+    };
+    assert_eq!(result.txid, Txid([0xcd; 32]).to_hex());
+    assert_eq!(result.output, "Yes, it is.");
+    assert_eq!(result.output_hash, libllm::output_hash("Yes, it is."));
+    assert!(result.params.is_some());
+
+    // the published output
+    let response = responses.remove(0);
+    let events = response.decode_infer_result_events().unwrap();
+    assert_eq!(events.len(), 3);
+    let Some(InferResultEvent::Done(result)) = events.last() else {
+        panic!("Expected a done event");
+    };
+    assert_eq!(result.status, InferStatus::Success);
+    assert_eq!(result.output, "Yes, the Earth is round.");
+
+    // nothing to follow
+    let response = responses.remove(0);
+    let events = response.decode_infer_result_events().unwrap();
+    assert_eq!(events.len(), 2);
+    let Some(InferResultEvent::Done(result)) = events.last() else {
+        panic!("Expected a done event");
+    };
+    assert_eq!(result.status, InferStatus::NotFound);
+}
+
+/// Decode the events in the stream's next chunk
+fn next_events(stream: &mut InferResultStream) -> Vec<InferResultEvent> {
+    let chunk = stream.generate_next_chunk().unwrap();
+    InferResultEvent::decode_all(std::str::from_utf8(&chunk).unwrap()).unwrap()
+}
+
+fn status_of(event: &InferResultEvent) -> &InferResultStatusEvent {
+    match event {
+        InferResultEvent::Status(ref status) => status,
+        _ => panic!("Expected a status event, got {:?}", event),
+    }
+}
+
+fn token(text: &str, reset: bool) -> InferResultEvent {
+    InferResultEvent::Token(InferResultTokenEvent {
+        text: text.to_string(),
+        reset,
+    })
+}
+
+#[test]
+fn test_stream_follows_task() {
+    let infer_db = InferDB::open_memory().unwrap();
+    libllm::infer_chain(&infer_db, "a".to_string(), "Is the Earth round?", "", 10).unwrap();
+    libllm::infer_chain(&infer_db, "b".to_string(), "Is the Earth flat?", "", 1).unwrap();
+
+    let mut stream = InferResultStream::new(infer_db.clone(), "b".to_string());
+    assert!(stream.is_live());
+
+    // queued behind "a"
+    let events = next_events(&mut stream);
+    assert_eq!(events.len(), 1);
+    let status = status_of(&events[0]);
+    assert_eq!(status.status, InferStatus::Created);
+    assert_eq!(status.queue_position, Some(1));
+    assert!(status.create_time.is_some());
+    assert_eq!(status.start_time, None);
+
+    // nothing changed
+    assert!(stream.generate_next_chunk().unwrap().is_empty());
+    assert!(stream.is_live());
+
+    // "a" starts, so "b" moves up
+    assert!(db::sqlite_start_llm(&infer_db.conn(), "a", InferStatus::InProgress as u8).unwrap());
+    let events = next_events(&mut stream);
+    assert_eq!(events.len(), 1);
+    assert_eq!(status_of(&events[0]).queue_position, Some(0));
+
+    // "b" starts, and produces output
+    assert!(db::sqlite_start_llm(&infer_db.conn(), "b", InferStatus::InProgress as u8).unwrap());
+    assert!(db::sqlite_save_partial_output(&infer_db.conn(), "b", 1, "No, ").unwrap());
+    let events = next_events(&mut stream);
+    assert_eq!(events.len(), 2);
+    let status = status_of(&events[0]);
+    assert_eq!(status.status, InferStatus::InProgress);
+    assert_eq!(status.queue_position, None);
+    assert!(status.start_time.is_some());
+    assert_eq!(events[1], token("No, ", false));
+
+    assert!(db::sqlite_save_partial_output(&infer_db.conn(), "b", 1, "No, it").unwrap());
+    assert_eq!(next_events(&mut stream), vec![token("it", false)]);
+
+    // the attempt fails, and the retry starts over
+    assert!(db::sqlite_retry_llm(&infer_db.conn(), "b", 0, "oops").unwrap());
+    let events = next_events(&mut stream);
+    assert_eq!(events.len(), 2);
+    assert_eq!(status_of(&events[0]).status, InferStatus::Retrying);
+    assert_eq!(events[1], token("", true));
+
+    assert!(db::sqlite_start_llm(&infer_db.conn(), "b", InferStatus::InProgress as u8).unwrap());
+    assert!(db::sqlite_save_partial_output(&infer_db.conn(), "b", 2, "No.").unwrap());
+    let events = next_events(&mut stream);
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[1], token("No.", false));
+
+    // finished
+    assert!(db::sqlite_finish_llm(
+        &infer_db.conn(),
+        "b",
+        "No.",
+        &libllm::output_hash("No."),
+        InferStatus::Success as u8,
+        "",
+        "",
+        ""
+    )
+    .unwrap());
+    let events = next_events(&mut stream);
+    assert_eq!(events.len(), 2);
+    assert_eq!(status_of(&events[0]).status, InferStatus::Success);
+    let InferResultEvent::Done(ref result) = events[1] else {
+        panic!("Expected a done event");
+    };
+    assert_eq!(result.output, "No.");
+    assert!(result.end_time.is_some());
+
+    assert!(!stream.is_live());
+    assert!(stream.generate_next_chunk().unwrap().is_empty());
+}
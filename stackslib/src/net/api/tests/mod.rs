@@ -25,7 +25,7 @@ use stacks_common::types::chainstate::{
     BlockHeaderHash, ConsensusHash, StacksAddress, StacksBlockId, StacksPrivateKey, StacksPublicKey,
 };
 use stacks_common::util::get_epoch_time_secs;
-use stacks_common::util::hash::{Hash160, Sha512Trunc256Sum};
+use stacks_common::util::hash::{to_hex, Hash160, Sha512Trunc256Sum};
 use stacks_common::util::pipe::Pipe;
 
 use crate::burnchains::bitcoin::indexer::BitcoinIndexer;
@@ -40,6 +40,7 @@ use crate::chainstate::stacks::{
     TransactionAuth, TransactionPayload, TransactionPostConditionMode, TransactionVersion,
 };
 use crate::core::MemPoolDB;
+use crate::net::atlas::{INFER_OUTPUTS_INDEX_BASE, INFER_OUTPUTS_NAME};
 use crate::net::db::PeerDB;
 use crate::net::httpcore::{StacksHttpRequest, StacksHttpResponse};
 use crate::net::relay::Relayer;
@@ -49,6 +50,7 @@ use crate::net::tests::inv::nakamoto::make_nakamoto_peers_from_invs;
 use crate::net::{
     Attachment, AttachmentInstance, RPCHandlerArgs, StackerDBConfig, StacksNodeState, UrlString,
 };
+use crate::util_lib::boot::boot_code_id;
 
 mod callreadonly;
 mod getaccount;
@@ -83,6 +85,7 @@ mod postmicroblock;
 mod poststackerdbchunk;
 mod posttransaction;
 mod getinferresult;
+mod getinferresultstream;
 
 const TEST_CONTRACT: &'static str = "
     (define-trait test-trait
@@ -679,6 +682,48 @@ impl<'a> TestRPC<'a> {
             .insert_instantiated_attachment(&attachment)
             .unwrap();
 
+        // insert a fake published inference output
+        let infer_output = Attachment {
+            content: "Yes, the Earth is round.".as_bytes().to_vec(),
+        };
+        let infer_output_instance = AttachmentInstance {
+            content_hash: infer_output.hash(),
+            attachment_index: INFER_OUTPUTS_INDEX_BASE,
+            stacks_block_height: 1,
+            index_block_hash: canonical_tip.clone(),
+            metadata: format!("13{}", to_hex("Is the Earth round?".as_bytes())),
+            contract_id: boot_code_id(INFER_OUTPUTS_NAME, false),
+            tx_id: Txid([0xab; 32]),
+            canonical_stacks_tip_height: Some(1),
+        };
+        for peer in [&mut peer_1, &mut peer_2] {
+            let atlasdb = peer.network.get_atlasdb_mut();
+            atlasdb
+                .insert_initial_attachment_instance(&infer_output_instance)
+                .unwrap();
+            atlasdb
+                .insert_instantiated_attachment(&infer_output)
+                .unwrap();
+        }
+
+        // run a fake inference task to completion
+        for peer in [&mut peer_1, &mut peer_2] {
+            let infer_db = peer.chainstate().infer_db.clone();
+            libllm::infer_chain(
+                &infer_db,
+                Txid([0xcd; 32]).to_hex(),
+                "Is the Earth round?",
+                "",
+                0,
+            )
+            .unwrap();
+            libllm::do_infer(
+                &infer_db,
+                &libllm::MockBackend::with_response("Yes, it is."),
+            )
+            .unwrap();
+        }
+
         // next tip, coinbase
         let tip =
             SortitionDB::get_canonical_burn_chain_tip(&peer_1.sortdb.as_ref().unwrap().conn())
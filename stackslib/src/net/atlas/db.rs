@@ -643,6 +643,22 @@ impl AtlasDB {
         Ok(rows)
     }
 
+    /// Find the checked attachment instances that `tx_id` emitted for `contract_id`
+    pub fn find_attachment_instances_for_tx(
+        &self,
+        contract_id: &QualifiedContractIdentifier,
+        tx_id: &Txid,
+    ) -> Result<Vec<AttachmentInstance>, db_error> {
+        let qry = "SELECT * FROM attachment_instances WHERE contract_id = ?1 AND tx_id = ?2 AND status = ?3";
+        let args = rusqlite::params![
+            &contract_id.to_string(),
+            &tx_id,
+            &AttachmentInstanceStatus::Checked
+        ];
+        let rows = query_rows(&self.conn, qry, args)?;
+        Ok(rows)
+    }
+
     pub fn find_attachment(&self, content_hash: &Hash160) -> Result<Option<Attachment>, db_error> {
         let hex_content_hash = to_hex(&content_hash.0[..]);
         let qry = "SELECT content, hash FROM attachments WHERE hash = ?1 AND was_instantiated = 1"
@@ -17,6 +17,7 @@
 use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 
+use clarity::vm::events::{STXEventType, StacksTransactionEvent};
 use clarity::vm::types::{QualifiedContractIdentifier, SequenceData, TupleData, Value};
 use lazy_static::lazy_static;
 use regex::Regex;
@@ -31,7 +32,10 @@ pub use self::download::AttachmentsDownloader;
 use crate::burnchains::Txid;
 use crate::chainstate::burn::db::sortdb::SortitionDB;
 use crate::chainstate::burn::ConsensusHash;
+use crate::chainstate::stacks::events::{StacksTransactionReceipt, TransactionOrigin};
+use crate::chainstate::stacks::TransactionPayload;
 use crate::util_lib::boot::boot_code_id;
+use crate::util_lib::strings::{InferLPString, InferText};
 
 /// Implements AtlasDB and associated API. Stores information about attachments and attachment
 /// instances.
@@ -51,6 +55,13 @@ lazy_static! {
     pub static ref BNS_CHARS_REGEX: Regex = Regex::new("^([a-z0-9]|[-_])*$").unwrap();
 }
 
+/// Inference outputs are published as attachments of this (never-deployed) boot contract, so
+/// that they are replicated by Atlas like any other attachment.
+pub const INFER_OUTPUTS_NAME: &'static str = "infer-outputs";
+/// Inference output attachments are indexed from here up, by their transaction's position in
+/// the block, so their inventory pages never overlap those of contract-driven attachments.
+pub const INFER_OUTPUTS_INDEX_BASE: u32 = 1 << 31;
+
 const ATTACHMENTS_MAX_SIZE_MIN: u32 = 1_048_576;
 const MAX_UNINSTANTIATED_ATTACHMENTS_MIN: u32 = 50_000;
 const UNINSTANTIATED_ATTACHMENTS_EXPIRE_AFTER_MIN: u32 = 86_400;
@@ -104,6 +115,7 @@ impl AtlasConfig {
     pub fn new(mainnet: bool) -> AtlasConfig {
         let mut contracts = HashSet::new();
         contracts.insert(boot_code_id("bns", mainnet));
+        contracts.insert(boot_code_id(INFER_OUTPUTS_NAME, mainnet));
         AtlasConfig {
             contracts,
             attachments_max_size: ATTACHMENTS_MAX_SIZE_MIN,
@@ -239,6 +251,61 @@ impl AttachmentInstance {
         }
         None
     }
+
+    /// The attachment instance that publishes the output of the Infer transaction in `receipt`,
+    /// if it produced one.  The attachment is the output itself, so its content hash follows
+    /// from the output hash the transaction committed to.  The metadata is the transaction's
+    /// user input, consensus-serialized and hex-encoded (see `infer_user_input()`).
+    pub fn try_new_from_infer_receipt(
+        receipt: &StacksTransactionReceipt,
+        index_block_hash: StacksBlockId,
+        stacks_block_height: u64,
+        canonical_stacks_tip_height: Option<u64>,
+    ) -> Option<AttachmentInstance> {
+        let TransactionOrigin::Stacks(ref transaction) = receipt.transaction else {
+            return None;
+        };
+        let user_input = match transaction.payload {
+            TransactionPayload::Infer(_, ref user_input, _) => user_input.serialize_to_vec(),
+            TransactionPayload::VersionedInfer(ref infer) => infer.prompt.serialize_to_vec(),
+            _ => return None,
+        };
+        let output_hash = receipt.events.iter().find_map(|event| match event {
+            StacksTransactionEvent::STXEvent(STXEventType::STXInferEvent(ref event_data)) => {
+                <[u8; 32]>::try_from(&event_data.infer_out_hash.data[..]).ok()
+            }
+            _ => None,
+        })?;
+
+        Some(AttachmentInstance {
+            content_hash: Hash160::from_sha256(&output_hash),
+            attachment_index: INFER_OUTPUTS_INDEX_BASE.checked_add(receipt.tx_index)?,
+            stacks_block_height,
+            index_block_hash,
+            metadata: to_hex(&user_input),
+            contract_id: boot_code_id(INFER_OUTPUTS_NAME, transaction.is_mainnet()),
+            tx_id: transaction.txid(),
+            canonical_stacks_tip_height,
+        })
+    }
+
+    /// Decode the user input in the metadata of an instance made by
+    /// `try_new_from_infer_receipt()`.  Legacy Infer transactions carry a one-byte length
+    /// prefix, and versioned ones a four-byte prefix; a versioned prompt's leading length byte
+    /// is never the length of the rest of the metadata, so the two cannot be confused.
+    pub fn infer_user_input(&self) -> Option<String> {
+        let bytes = hex_bytes(&self.metadata).ok()?;
+        if bytes.len() == 1 + usize::from(*bytes.first()?) {
+            let user_input = InferLPString::consensus_deserialize(&mut &bytes[..]).ok()?;
+            return Some(user_input.to_string());
+        }
+        let mut cursor = &bytes[..];
+        let prompt = InferText::consensus_deserialize(&mut cursor).ok()?;
+        if !cursor.is_empty() {
+            return None;
+        }
+        Some(prompt.as_str().to_string())
+    }
 }
 
 #[cfg(test)]
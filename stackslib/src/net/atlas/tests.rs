@@ -17,28 +17,40 @@
 use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::{thread, time};
 
-use clarity::vm::types::QualifiedContractIdentifier;
-use stacks_common::types::chainstate::{BlockHeaderHash, StacksBlockId};
+use clarity::vm::costs::ExecutionCost;
+use clarity::vm::events::{STXEventType, STXInferEventData, StacksTransactionEvent};
+use clarity::vm::types::{BuffData, PrincipalData, QualifiedContractIdentifier};
+use clarity::vm::Value;
+use stacks_common::address::{AddressHashMode, C32_ADDRESS_VERSION_TESTNET_SINGLESIG};
+use stacks_common::codec::StacksMessageCodec;
+use stacks_common::types::chainstate::{
+    BlockHeaderHash, StacksAddress, StacksBlockId, StacksPrivateKey, StacksPublicKey,
+};
 use stacks_common::types::net::{PeerAddress, PeerHost};
-use stacks_common::util::hash::Hash160;
+use stacks_common::util::hash::{to_hex, Hash160, Sha256Sum};
 
 use super::download::{
     AttachmentRequest, AttachmentsBatch, AttachmentsBatchStateContext, AttachmentsInventoryRequest,
     BatchedRequestsResult, ReliabilityReport,
 };
 use super::{
-    AtlasConfig, AtlasDB, Attachment, AttachmentInstance, AttachmentPage, GetAttachmentsInvResponse,
+    AtlasConfig, AtlasDB, Attachment, AttachmentInstance, AttachmentPage, AttachmentsDownloader,
+    GetAttachmentsInvResponse, INFER_OUTPUTS_INDEX_BASE, INFER_OUTPUTS_NAME,
 };
 use crate::burnchains::Txid;
 use crate::chainstate::burn::ConsensusHash;
 use crate::chainstate::stacks::db::StacksChainState;
+use crate::chainstate::stacks::events::{StacksTransactionReceipt, TransactionOrigin};
+use crate::chainstate::stacks::{
+    StacksTransaction, TransactionAuth, TransactionInfer, TransactionPayload, TransactionVersion,
+};
 use crate::net::connection::ConnectionOptions;
 use crate::net::http::{HttpResponsePayload, HttpResponsePreamble, HttpVersion};
 use crate::net::httpcore::StacksHttpResponse;
 use crate::net::Requestable;
 use crate::util_lib::boot::boot_code_id;
 use crate::util_lib::db::u64_to_sql;
-use crate::util_lib::strings::UrlString;
+use crate::util_lib::strings::{InferLPString, InferText, UrlString};
 
 fn new_attachment_from(content: &str) -> Attachment {
     Attachment {
@@ -297,6 +309,138 @@ fn test_attachment_instance_parsing() {
     }
 }
 
+#[test]
+fn test_infer_output_attachment_instance() {
+    let privk = StacksPrivateKey::from_hex(
+        "6d430bb91222408e7706c9001cfaeb91b08c2be6d5ac95779ab52c6b431950e001",
+    )
+    .unwrap();
+    let sender = PrincipalData::from(StacksAddress::from_public_keys(
+        C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+        &AddressHashMode::SerializeP2PKH,
+        1,
+        &vec![StacksPublicKey::from_private(&privk)],
+    )
+    .unwrap());
+    let user_input = "Is the Earth round?";
+    let mut input_bytes = vec![user_input.len() as u8];
+    input_bytes.extend_from_slice(user_input.as_bytes());
+    let user_input_str = InferLPString::consensus_deserialize(&mut &input_bytes[..]).unwrap();
+    let context = InferLPString::consensus_deserialize(&mut &[0u8][..]).unwrap();
+    let tx = StacksTransaction::new(
+        TransactionVersion::Testnet,
+        TransactionAuth::from_p2pkh(&privk).unwrap(),
+        TransactionPayload::Infer(sender.clone(), user_input_str, context),
+    );
+
+    let output = "Yes, the Earth is round.";
+    let attachment = Attachment::new(output.as_bytes().to_vec());
+    let infer_event = StacksTransactionEvent::STXEvent(STXEventType::STXInferEvent(
+        STXInferEventData {
+            sender: sender.clone(),
+            infer_out_hash: BuffData {
+                data: Sha256Sum::from_data(output.as_bytes()).as_bytes().to_vec(),
+            },
+            session: None,
+        },
+    ));
+    let mut receipt = StacksTransactionReceipt::from_infer(
+        tx.clone(),
+        vec![infer_event],
+        Value::okay_true(),
+        ExecutionCost::zero(),
+    );
+    receipt.tx_index = 3;
+
+    let index_block_hash = StacksBlockId([0xab; 32]);
+    let instance = AttachmentInstance::try_new_from_infer_receipt(
+        &receipt,
+        index_block_hash.clone(),
+        7,
+        Some(8),
+    )
+    .unwrap();
+    assert_eq!(instance.content_hash, attachment.hash());
+    assert_eq!(instance.attachment_index, INFER_OUTPUTS_INDEX_BASE + 3);
+    assert_eq!(instance.contract_id, boot_code_id(INFER_OUTPUTS_NAME, false));
+    assert_eq!(instance.tx_id, tx.txid());
+    assert_eq!(instance.metadata, to_hex(&input_bytes));
+    assert_eq!(instance.infer_user_input(), Some(user_input.to_string()));
+
+    // a versioned Infer transaction's prompt has a four-byte length prefix
+    let prompt = "Is the Earth round? ".repeat(20);
+    let versioned_tx = StacksTransaction::new(
+        TransactionVersion::Testnet,
+        TransactionAuth::from_p2pkh(&privk).unwrap(),
+        TransactionPayload::VersionedInfer(TransactionInfer {
+            from: sender,
+            prompt: InferText::try_from(prompt.as_str()).unwrap(),
+            messages: vec![],
+            model: None,
+            model_digest: None,
+            seed: 0,
+            max_tokens: 64,
+            payment: None,
+            parent: None,
+        }),
+    );
+    let mut versioned_receipt = receipt.clone();
+    versioned_receipt.transaction = TransactionOrigin::Stacks(versioned_tx);
+    let versioned = AttachmentInstance::try_new_from_infer_receipt(
+        &versioned_receipt,
+        index_block_hash.clone(),
+        7,
+        Some(8),
+    )
+    .unwrap();
+    assert_eq!(versioned.content_hash, attachment.hash());
+    assert_eq!(versioned.infer_user_input(), Some(prompt));
+
+    let mut bad_metadata = versioned.clone();
+    bad_metadata.metadata.push_str("00");
+    assert_eq!(bad_metadata.infer_user_input(), None);
+
+    // no output committed, nothing to publish
+    let failed = StacksTransactionReceipt::from_infer(
+        tx.clone(),
+        vec![],
+        Value::okay_true(),
+        ExecutionCost::zero(),
+    );
+    assert!(AttachmentInstance::try_new_from_infer_receipt(
+        &failed,
+        index_block_hash.clone(),
+        7,
+        Some(8)
+    )
+    .is_none());
+
+    // once checked, the output can be found by txid
+    let mut atlas_db = AtlasDB::connect_memory(AtlasConfig::new(false)).unwrap();
+    atlas_db.insert_uninstantiated_attachment(&attachment).unwrap();
+    atlas_db.queue_attachment_instance(&instance).unwrap();
+    assert!(atlas_db
+        .find_attachment_instances_for_tx(&instance.contract_id, &instance.tx_id)
+        .unwrap()
+        .is_empty());
+    let mut downloader = AttachmentsDownloader::new(vec![]);
+    let resolved = downloader
+        .check_queued_attachment_instances(&mut atlas_db)
+        .unwrap();
+    assert_eq!(resolved.len(), 1);
+    assert_eq!(resolved[0].0.tx_id, instance.tx_id);
+    assert_eq!(resolved[0].1, attachment);
+    let found = atlas_db
+        .find_attachment_instances_for_tx(&instance.contract_id, &instance.tx_id)
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].content_hash, attachment.hash());
+    assert_eq!(
+        atlas_db.find_attachment(&found[0].content_hash).unwrap(),
+        Some(attachment)
+    );
+}
+
 #[test]
 fn test_attachments_batch_ordering() {
     // Ensuring that when batches are being queued, we are correctly dequeueing, based on the following priorities:
@@ -153,6 +153,8 @@ pub enum HttpContentType {
     Bytes,
     Text,
     JSON,
+    /// server-sent events
+    EventStream,
 }
 
 impl fmt::Display for HttpContentType {
@@ -167,6 +169,7 @@ impl HttpContentType {
             HttpContentType::Bytes => "application/octet-stream",
             HttpContentType::Text => "text/plain",
             HttpContentType::JSON => "application/json",
+            HttpContentType::EventStream => "text/event-stream",
         }
     }
 }
@@ -182,6 +185,8 @@ impl FromStr for HttpContentType {
             Ok(HttpContentType::Text)
         } else if s == "application/json" {
             Ok(HttpContentType::JSON)
+        } else if s == "text/event-stream" {
+            Ok(HttpContentType::EventStream)
         } else {
             Err(CodecError::DeserializeError(
                 "Unsupported HTTP content type".to_string(),
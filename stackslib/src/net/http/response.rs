@@ -111,6 +111,14 @@ impl HttpResponseContents {
         }
     }
 
+    /// Is this a live stream, which may produce more data later even if it has none now?
+    pub fn is_live(&self) -> bool {
+        match self {
+            Self::Stream(ref inner_stream) => inner_stream.generator.is_live(),
+            Self::RAM(..) => false,
+        }
+    }
+
     /// Write data for this to a pipe writer, which buffers it up.
     /// Return Ok(Some(..)) if there is mroe data to send.
     /// Once all data is sent, return Ok(None)
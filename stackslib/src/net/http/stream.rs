@@ -36,9 +36,18 @@ pub trait HttpChunkGenerator: Send {
     fn generate_next_chunk(&mut self) -> Result<Vec<u8>, String>;
     fn hint_chunk_size(&self) -> usize;
 
+    /// Is this stream still producing data as it happens?  A live stream may have nothing to
+    /// send right now and more to send later, so an empty chunk doesn't end it, and each chunk is
+    /// sent as soon as it is generated.  The stream ends once this returns false and an empty
+    /// chunk is generated.
+    fn is_live(&self) -> bool {
+        false
+    }
+
     /// Stream one chunk to the pipe writer.  This never blocks.
     /// Returns Ok(num-bytes > 0) if there are more chunks (i.e. the caller should call this again)
-    /// Returns Ok(0) if there are no more chunks (i.e. the caller should not call this again)
+    /// Returns Ok(0) if there are no more chunks (i.e. the caller should not call this again), or
+    /// if a live stream has nothing to send yet (i.e. the caller should try again later)
     /// Returns Err(..) on irrecoverable I/O error
     #[cfg_attr(test, mutants::skip)]
     fn stream_to(
@@ -54,6 +63,10 @@ pub trait HttpChunkGenerator: Send {
         let mut encoder = HttpChunkedTransferWriter::from_writer_state(fd, encoder_state);
 
         if chunk.is_empty() {
+            if self.is_live() {
+                // nothing to send yet
+                return Ok(0);
+            }
             // no more chunks, but be sure to cork the stream
             if !encoder.corked() {
                 encoder.flush()?;
@@ -61,6 +74,9 @@ pub trait HttpChunkGenerator: Send {
             }
         } else {
             encoder.write_all(&chunk)?;
+            if self.is_live() {
+                encoder.flush_buffered()?;
+            }
         }
 
         Ok(chunk.len() as u64)
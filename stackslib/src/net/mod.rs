@@ -2422,6 +2422,7 @@ pub mod test {
                             &boot_code_smart_contract,
                             &boot_code_account,
                             ASTRules::PrecheckSize,
+                            None,
                         )
                         .unwrap()
                     });
@@ -323,6 +323,10 @@ impl ConversationHttp {
                 if let Some(pipe_fd) = reply.inner_pipe_out() {
                     let num_written = http_response.pipe_out(pipe_fd)?;
                     if num_written == 0 {
+                        if http_response.is_live() {
+                            // no more chunks for now; poll it again on the next pass
+                            break;
+                        }
                         // no more chunks
                         drained_stream = true;
                     }
@@ -479,6 +483,14 @@ impl ConversationHttp {
         Ok(())
     }
 
+    /// Are we sending a live stream, whose data gets generated between socket events?
+    pub fn has_live_stream(&self) -> bool {
+        self.reply_streams
+            .front()
+            .map(|(_, http_response, _)| http_response.is_live())
+            .unwrap_or(false)
+    }
+
     /// Is the connection idle?
     pub fn is_idle(&self) -> bool {
         self.pending_response.is_none()
@@ -616,6 +616,15 @@ impl HttpPeer {
             if let Err(e) = convo.try_flush() {
                 info!("Broken HTTP connection {:?}: {:?}", convo, &e);
                 close.push(*event_id);
+            } else if convo.has_live_stream() {
+                // the socket won't become ready just because the stream produced more data, so
+                // send it out now
+                if let Some(client_sock) = self.sockets.get_mut(event_id) {
+                    if let Err(e) = HttpPeer::saturate_http_socket(client_sock, convo) {
+                        info!("Broken HTTP connection {:?}: {:?}", convo, &e);
+                        close.push(*event_id);
+                    }
+                }
             }
             if convo.is_drained() && !convo.is_keep_alive() {
                 // did some work, but nothing more to do and we're not keep-alive
@@ -80,6 +80,21 @@ impl StacksMessageCodec for InferLPString {
     }
 }
 
+impl TryFrom<&str> for InferLPString {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<InferLPString, String> {
+        if s.len() > CLARITY_MAX_STRING_LENGTH as usize {
+            return Err(format!(
+                "Infer string is {} bytes, but at most {} are allowed",
+                s.len(),
+                CLARITY_MAX_STRING_LENGTH
+            ));
+        }
+        Ok(InferLPString(s.as_bytes().to_vec()))
+    }
+}
+
 impl fmt::Display for InferLPString {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.write_str(String::from_utf8_lossy(&self.0).into_owned().as_str())
@@ -92,6 +107,79 @@ impl fmt::Debug for InferLPString {
     }
 }
 
+/// Longest prompt or message (in bytes) a versioned Infer transaction may carry
+pub const MAX_INFER_TEXT_LEN: u32 = 1024 * 1024;
+
+/// UTF-8 text of a versioned Infer transaction, prefixed with its length as a u32.
+/// It cannot be longer than MAX_INFER_TEXT_LEN bytes.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferText(String);
+
+impl InferText {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for InferText {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<InferText, String> {
+        if s.len() > MAX_INFER_TEXT_LEN as usize {
+            return Err(format!(
+                "Infer text is {} bytes, but at most {} are allowed",
+                s.len(),
+                MAX_INFER_TEXT_LEN
+            ));
+        }
+        Ok(InferText(s))
+    }
+}
+
+impl TryFrom<&str> for InferText {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<InferText, String> {
+        InferText::try_from(s.to_string())
+    }
+}
+
+impl StacksMessageCodec for InferText {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        write_next(fd, &(self.0.len() as u32))?;
+        fd.write_all(self.0.as_bytes())
+            .map_err(codec_error::WriteError)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<Self, codec_error> {
+        let len: u32 = read_next(fd)?;
+        if len > MAX_INFER_TEXT_LEN {
+            return Err(codec_error::DeserializeError(
+                "Failed to deserialize Infer text: too long".to_string(),
+            ));
+        }
+        let mut bytes = vec![0u8; len as usize];
+        fd.read_exact(&mut bytes).map_err(codec_error::ReadError)?;
+        let text = String::from_utf8(bytes).map_err(|_| {
+            codec_error::DeserializeError("Failed to deserialize Infer text: not UTF-8".to_string())
+        })?;
+        Ok(InferText(text))
+    }
+}
+
+impl fmt::Display for InferText {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for InferText {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", &self.0)
+    }
+}
+
 /// printable-ASCII-only string, but encodable.
 /// Note that it cannot be longer than ARRAY_MAX_LEN (4.1 billion bytes)
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
@@ -421,6 +509,35 @@ mod test {
         assert!(ContractName::consensus_deserialize(&mut &s_payload[..]).is_err());
     }
 
+    #[test]
+    fn test_infer_text_codec() {
+        // longer than an InferLPString could hold
+        let s = "Is the Earth round? ".repeat(20);
+        let infer_text = InferText::try_from(s.as_str()).unwrap();
+
+        let mut bytes = (s.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(s.as_bytes());
+        check_codec_and_corruption::<InferText>(&infer_text, &bytes);
+
+        let s = "¿Es la Tierra redonda?";
+        let mut bytes = (s.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(s.as_bytes());
+        check_codec_and_corruption::<InferText>(&InferText::try_from(s).unwrap(), &bytes);
+    }
+
+    #[test]
+    fn test_infer_text_invalid() {
+        // not UTF-8
+        let s = vec![0u8, 0, 0, 2, 0xc3, 0x28];
+        assert!(InferText::consensus_deserialize(&mut &s[..]).is_err());
+
+        // too long
+        let s = (MAX_INFER_TEXT_LEN + 1).to_be_bytes();
+        assert!(InferText::consensus_deserialize(&mut &s[..]).is_err());
+        assert!(InferText::try_from("a".repeat(MAX_INFER_TEXT_LEN as usize + 1)).is_err());
+        assert!(InferText::try_from("a".repeat(MAX_INFER_TEXT_LEN as usize)).is_ok());
+    }
+
     #[test]
     fn test_url_parse() {
         assert!(UrlString::try_from("asdfjkl;")
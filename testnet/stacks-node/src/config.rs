@@ -9,6 +9,11 @@ use std::{fs, thread};
 use clarity::vm::costs::ExecutionCost;
 use clarity::vm::types::{AssetIdentifier, PrincipalData, QualifiedContractIdentifier};
 use lazy_static::lazy_static;
+use libllm::backend::{
+    InferenceBackend, MockBackend, OpenAiBackend, SubprocessBackend, DEFAULT_API_KEY,
+    DEFAULT_BASE_URL, DEFAULT_MODEL, DEFAULT_TIMEOUT_MS,
+};
+use libllm::{InferDB, InferPoolConfig, InferRetentionConfig};
 use rand::RngCore;
 use serde::Deserialize;
 use stacks::burnchains::affirmation::AffirmationMap;
@@ -20,7 +25,10 @@ use stacks::chainstate::stacks::index::marf::MARFOpenOpts;
 use stacks::chainstate::stacks::index::storage::TrieHashCalculationMode;
 use stacks::chainstate::stacks::miner::{BlockBuilderSettings, MinerStatus};
 use stacks::chainstate::stacks::MAX_BLOCK_LEN;
-use stacks::core::mempool::{MemPoolWalkSettings, MemPoolWalkTxTypes};
+use stacks::core::mempool::{
+    InferAdmissionConfig, InferAdmissionPolicy, MemPoolWalkSettings, MemPoolWalkTxTypes,
+    DEFAULT_INFER_PENDING_DEADLINE_SECS,
+};
 use stacks::core::{
     MemPoolDB, StacksEpoch, StacksEpochExtension, StacksEpochId,
     BITCOIN_TESTNET_FIRST_BLOCK_HEIGHT, BITCOIN_TESTNET_STACKS_25_BURN_HEIGHT,
@@ -47,6 +55,7 @@ use stacks_common::util::hash::hex_bytes;
 use stacks_common::util::secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey};
 
 use crate::chain_data::MinerStats;
+use crate::keychain::Keychain;
 
 pub const DEFAULT_SATS_PER_VB: u64 = 50;
 const DEFAULT_MAX_RBF_RATE: u64 = 150; // 1.5x
@@ -66,6 +75,7 @@ pub struct ConfigFile {
     pub fee_estimation: Option<FeeEstimationConfigFile>,
     pub miner: Option<MinerConfigFile>,
     pub atlas: Option<AtlasConfigFile>,
+    pub llm: Option<LlmConfigFile>,
 }
 
 #[derive(Clone, Deserialize, Default)]
@@ -204,6 +214,175 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_load_llm_config() {
+        let config = Config::from_config_file(ConfigFile::from_str("").unwrap()).unwrap();
+        assert_eq!(config.llm, LlmConfig::default());
+
+        let config = Config::from_config_file(
+            ConfigFile::from_str(
+                r#"
+                [llm]
+                backend = "subprocess"
+                model = "my-model"
+                model_digest = "sha256:1234"
+                command = "/usr/local/bin/run-model"
+                args = ["--quiet"]
+                "#,
+            )
+            .unwrap(),
+        )
+        .expect("Expected to be able to parse llm config from file");
+        assert_eq!(config.llm.backend, InferenceBackendName::Subprocess);
+        assert_eq!(config.llm.model, "my-model");
+        assert_eq!(
+            config.llm.command,
+            Some("/usr/local/bin/run-model".to_string())
+        );
+        assert_eq!(config.llm.args, vec!["--quiet".to_string()]);
+        assert_eq!(config.make_inference_backend().model(), "my-model");
+        assert_eq!(
+            config.make_inference_backend().model_digest(),
+            Some("sha256:1234")
+        );
+
+        let config = Config::from_config_file(
+            ConfigFile::from_str(
+                r#"
+                [llm]
+                backend = "mock"
+                mock_response = "42"
+                "#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(config.make_inference_backend().name(), "mock");
+
+        let missing_command = ConfigFile::from_str(
+            r#"
+            [llm]
+            backend = "subprocess"
+            "#,
+        )
+        .unwrap();
+        assert!(Config::from_config_file(missing_command).is_err());
+
+        let bad_backend = ConfigFile::from_str(
+            r#"
+            [llm]
+            backend = "telepathy"
+            "#,
+        )
+        .unwrap();
+        assert!(Config::from_config_file(bad_backend).is_err());
+
+        let config = Config::from_config_file(
+            ConfigFile::from_str(
+                r#"
+                [llm]
+                workers = 4
+                job_timeout_ms = 60000
+                max_attempts = 5
+                retry_backoff_ms = 1000
+                "#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(config.llm.pool.workers, 4);
+        assert_eq!(config.llm.pool.job_timeout, Duration::from_secs(60));
+        assert_eq!(config.llm.pool.max_attempts, 5);
+        assert_eq!(config.llm.pool.retry_backoff, Duration::from_secs(1));
+        assert_eq!(
+            config.llm.pool.max_retry_backoff,
+            InferPoolConfig::default().max_retry_backoff
+        );
+        assert_eq!(config.llm.retention, InferRetentionConfig::default());
+
+        let config = Config::from_config_file(
+            ConfigFile::from_str(
+                r#"
+                [llm]
+                result_retention_blocks = 100
+                "#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(config.llm.retention.confirmed_blocks, 100);
+        assert_eq!(
+            config.llm.retention.orphan_secs,
+            InferRetentionConfig::default().orphan_secs
+        );
+
+        let no_workers = ConfigFile::from_str(
+            r#"
+            [llm]
+            workers = 0
+            "#,
+        )
+        .unwrap();
+        assert!(Config::from_config_file(no_workers).is_err());
+
+        let config = Config::from_config_file(
+            ConfigFile::from_str(
+                r#"
+                [llm]
+                infer_min_fee_rate_per_prompt_byte = 10
+                infer_max_prompt_len = 1024
+                infer_max_txs_per_origin = 0
+                "#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(config.llm.admission.min_fee_rate_per_prompt_byte, 10);
+        assert_eq!(config.llm.admission.max_prompt_len, 1024);
+        assert_eq!(
+            config.llm.admission.max_context_len,
+            InferAdmissionConfig::default().max_context_len
+        );
+        assert_eq!(config.llm.admission.max_txs_per_origin, 0);
+
+        let no_window = ConfigFile::from_str(
+            r#"
+            [llm]
+            infer_rate_limit_window_secs = 0
+            "#,
+        )
+        .unwrap();
+        assert!(Config::from_config_file(no_window).is_err());
+    }
+
+    #[test]
+    fn test_miner_infer_settings() {
+        let config = Config::from_config_file(
+            ConfigFile::from_str(
+                r#"
+                [miner]
+                infer_pending_deadline_secs = 60
+                infer_wait_ms = 1000
+                "#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let miner_status = Arc::new(Mutex::new(MinerStatus::make_ready(0)));
+
+        let settings = config.make_block_builder_settings(2, false, miner_status.clone());
+        assert_eq!(settings.mempool_settings.infer_pending_deadline_secs, 60);
+        assert_eq!(settings.mempool_settings.infer_wait_ms, 1000);
+        assert_eq!(
+            settings.mempool_settings.infer_tx_max_retry_count,
+            MinerConfig::default().infer_tx_max_retry_count
+        );
+
+        // microblocks don't wait for inferences
+        let settings = config.make_block_builder_settings(2, true, miner_status);
+        assert_eq!(settings.mempool_settings.infer_wait_ms, 0);
+    }
+
     #[test]
     fn should_load_affirmation_map() {
         let affirmation_string = "nnnnnnnnnnnnnnnnnnnnnnnnnnnnnnnnppnnnnnnnnnnnnnnnnnnnnnnnnpppppnnnnnnnnnnnnnnnnnnnnnnnpppppppppppppppnnnnnnnnnnnnnnnnnnnnnnnppppppppppnnnnnnnnnnnnnnnnnnnppppnnnnnnnnnnnnnnnnnnnnnnnppppppppnnnnnnnnnnnnnnnnnnnnnnnppnppnnnnnnnnnnnnnnnnnnnnnnnppppnnnnnnnnnnnnnnnnnnnnnnnnnppppppnnnnnnnnnnnnnnnnnnnnnnnnnppnnnnnnnnnnnnnnnnnnnnnnnnnpppppppnnnnnnnnnnnnnnnnnnnnnnnnnnpnnnnnnnnnnnnnnnnnnnnnnnnnpppnppppppppppppppnnppppnpa";
@@ -494,6 +673,7 @@ pub struct Config {
     pub miner: MinerConfig,
     pub estimation: FeeEstimationConfig,
     pub atlas: AtlasConfig,
+    pub llm: LlmConfig,
 }
 
 lazy_static! {
@@ -739,13 +919,15 @@ impl Config {
             .make_cost_metric()
             .unwrap_or_else(|| Box::new(UnitMetric));
 
-        MemPoolDB::open(
+        let mut mempool = MemPoolDB::open(
             self.is_mainnet(),
             self.burnchain.chain_id,
             &self.get_chainstate_path_str(),
             cost_estimator,
             metric,
-        )
+        )?;
+        mempool.set_infer_admission_policy(InferAdmissionPolicy::new(self.llm.admission.clone()));
+        Ok(mempool)
     }
 
     /// Load up a Burnchain and apply config settings to it.
@@ -938,6 +1120,7 @@ impl Config {
             burnchain: default_burnchain_config,
             miner: miner_default_config,
             estimation: default_estimator,
+            llm: default_llm_config,
             ..
         } = default;
 
@@ -1100,6 +1283,11 @@ impl Config {
             .validate()
             .map_err(|e| format!("Atlas config error: {e}"))?;
 
+        let llm = match config_file.llm {
+            Some(llm) => llm.into_config_default(default_llm_config)?,
+            None => default_llm_config,
+        };
+
         Ok(Config {
             config_path: config_file.__path,
             node,
@@ -1110,6 +1298,7 @@ impl Config {
             estimation,
             miner,
             atlas,
+            llm,
         })
     }
 
@@ -1185,6 +1374,22 @@ impl Config {
         path.to_str().expect("Unable to produce path").to_string()
     }
 
+    /// Inference tasks and results live inside the chainstate directory, so that they are
+    /// opened by the chainstate, mempool, and LLM thread alike.
+    pub fn get_infer_db_file_path(&self) -> String {
+        InferDB::db_path(&self.get_chainstate_path_str())
+    }
+
+    /// Inference outputs are attested with the key the node signs Nakamoto blocks with, so
+    /// that signers can check that the outputs in a block came from its miner.
+    pub fn get_infer_attestation_key(&self) -> Secp256k1PrivateKey {
+        self.miner.mining_key.clone().unwrap_or_else(|| {
+            Keychain::default(self.node.seed.clone())
+                .get_nakamoto_sk()
+                .clone()
+        })
+    }
+
     pub fn get_stacker_db_file_path(&self) -> String {
         let mut path = self.get_chainstate_path();
         path.set_file_name("stacker_db.sqlite");
@@ -1252,7 +1457,14 @@ impl Config {
                 candidate_retry_cache_size: miner_config.candidate_retry_cache_size,
                 txs_to_consider: miner_config.txs_to_consider,
                 filter_origins: miner_config.filter_origins,
-                infer_tx_max_retry_count: 5,
+                infer_tx_max_retry_count: miner_config.infer_tx_max_retry_count,
+                infer_pending_deadline_secs: miner_config.infer_pending_deadline_secs,
+                // microblocks are mined on a tight schedule, so don't hold them up for inferences
+                infer_wait_ms: if microblocks {
+                    0
+                } else {
+                    miner_config.infer_wait_ms
+                },
             },
             miner_status,
             confirm_microblocks: true,
@@ -1290,6 +1502,7 @@ impl std::default::Default for Config {
             estimation,
             miner: MinerConfig::default(),
             atlas: AtlasConfig::new(mainnet),
+            llm: LlmConfig::default(),
         }
     }
 }
@@ -1882,6 +2095,10 @@ impl From<FeeEstimationConfigFile> for FeeEstimationConfig {
 }
 
 impl Config {
+    pub fn make_inference_backend(&self) -> Arc<dyn InferenceBackend> {
+        self.llm.make_backend()
+    }
+
     pub fn make_cost_estimator(&self) -> Option<Box<dyn CostEstimator>> {
         let cost_estimator: Box<dyn CostEstimator> =
             match self.estimation.cost_estimator.as_ref()? {
@@ -2237,6 +2454,15 @@ pub struct MinerConfig {
     pub max_reorg_depth: u64,
     /// Amount of time while mining in nakamoto to wait for signers to respond to a proposed block
     pub wait_on_signers: Duration,
+    /// How many times an Infer transaction whose inference didn't succeed is retried before it
+    /// is dropped from the mempool
+    pub infer_tx_max_retry_count: u64,
+    /// How long an Infer transaction's inference may stay pending after the mempool accepted it,
+    /// before the miner drops the transaction
+    pub infer_pending_deadline_secs: u64,
+    /// How long the miner waits for pending inferences once it has run out of other transactions
+    /// to mine
+    pub infer_wait_ms: u64,
 }
 
 impl Default for MinerConfig {
@@ -2266,6 +2492,120 @@ impl Default for MinerConfig {
             max_reorg_depth: 3,
             // TODO: update to a sane value based on stackerdb benchmarking
             wait_on_signers: Duration::from_secs(200),
+            infer_tx_max_retry_count: 5,
+            infer_pending_deadline_secs: DEFAULT_INFER_PENDING_DEADLINE_SECS,
+            infer_wait_ms: 5_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InferenceBackendName {
+    OpenAi,
+    Mock,
+    Subprocess,
+}
+
+impl Default for InferenceBackendName {
+    fn default() -> Self {
+        InferenceBackendName::OpenAi
+    }
+}
+
+impl FromStr for InferenceBackendName {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "openai" => Ok(InferenceBackendName::OpenAi),
+            "mock" => Ok(InferenceBackendName::Mock),
+            "subprocess" => Ok(InferenceBackendName::Subprocess),
+            _ => Err(format!(
+                "llm.backend should be one of: openai, mock, subprocess (got '{}')",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LlmConfig {
+    /// Which kind of inference backend to run
+    pub backend: InferenceBackendName,
+    /// Model identifier passed to the backend
+    pub model: String,
+    /// Digest of the model weights the backend serves.  Infer transactions that pin a model
+    /// digest will only be run if it matches this.
+    pub model_digest: Option<String>,
+    /// Base URL of the OpenAI-compatible API (`openai` backend only)
+    pub base_url: String,
+    /// API key sent as a bearer token (`openai` backend only)
+    pub api_key: String,
+    /// Per-request HTTP timeout (`openai` backend only)
+    pub timeout: Duration,
+    /// Program to execute for each request (`subprocess` backend only)
+    pub command: Option<String>,
+    /// Arguments passed to `command` (`subprocess` backend only)
+    pub args: Vec<String>,
+    /// Fixed reply to return (`mock` backend only).  If unset, the mock backend answers with a
+    /// digest of the prompt.
+    pub mock_response: Option<String>,
+    /// How inference tasks are scheduled: parallelism, timeouts and retries
+    pub pool: InferPoolConfig,
+    /// Which Infer transactions the mempool admits: fees, sizes and rate limits
+    pub admission: InferAdmissionConfig,
+    /// How long inference results are kept once their transactions are confirmed or evicted
+    pub retention: InferRetentionConfig,
+}
+
+impl Default for LlmConfig {
+    fn default() -> LlmConfig {
+        LlmConfig {
+            backend: InferenceBackendName::default(),
+            model: DEFAULT_MODEL.to_string(),
+            model_digest: None,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            api_key: DEFAULT_API_KEY.to_string(),
+            timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+            command: None,
+            args: vec![],
+            mock_response: None,
+            pool: InferPoolConfig::default(),
+            admission: InferAdmissionConfig::default(),
+            retention: InferRetentionConfig::default(),
+        }
+    }
+}
+
+impl LlmConfig {
+    /// Instantiate the inference backend described by this config
+    pub fn make_backend(&self) -> Arc<dyn InferenceBackend> {
+        match self.backend {
+            InferenceBackendName::OpenAi => {
+                let backend =
+                    OpenAiBackend::new(&self.base_url, &self.api_key, &self.model, self.timeout);
+                Arc::new(match self.model_digest {
+                    Some(ref digest) => backend.with_model_digest(digest),
+                    None => backend,
+                })
+            }
+            InferenceBackendName::Mock => Arc::new(match self.mock_response {
+                Some(ref response) => MockBackend::with_response(response),
+                None => MockBackend::new(),
+            }),
+            InferenceBackendName::Subprocess => {
+                let backend = SubprocessBackend::new(
+                    self.command
+                        .as_ref()
+                        .expect("FATAL: llm.command is required for the subprocess backend"),
+                    self.args.clone(),
+                    &self.model,
+                );
+                Arc::new(match self.model_digest {
+                    Some(ref digest) => backend.with_model_digest(digest),
+                    None => backend,
+                })
+            }
         }
     }
 }
@@ -2591,6 +2931,9 @@ pub struct MinerConfigFile {
     pub filter_origins: Option<String>,
     pub max_reorg_depth: Option<u64>,
     pub wait_on_signers_ms: Option<u64>,
+    pub infer_tx_max_retry_count: Option<u64>,
+    pub infer_pending_deadline_secs: Option<u64>,
+    pub infer_wait_ms: Option<u64>,
 }
 
 impl MinerConfigFile {
@@ -2690,6 +3033,15 @@ impl MinerConfigFile {
                 .wait_on_signers_ms
                 .map(Duration::from_millis)
                 .unwrap_or(miner_default_config.wait_on_signers),
+            infer_tx_max_retry_count: self
+                .infer_tx_max_retry_count
+                .unwrap_or(miner_default_config.infer_tx_max_retry_count),
+            infer_pending_deadline_secs: self
+                .infer_pending_deadline_secs
+                .unwrap_or(miner_default_config.infer_pending_deadline_secs),
+            infer_wait_ms: self
+                .infer_wait_ms
+                .unwrap_or(miner_default_config.infer_wait_ms),
         })
     }
 }
@@ -2721,6 +3073,113 @@ impl AtlasConfigFile {
     }
 }
 
+#[derive(Clone, Deserialize, Default, Debug)]
+pub struct LlmConfigFile {
+    pub backend: Option<String>,
+    pub model: Option<String>,
+    pub model_digest: Option<String>,
+    pub base_url: Option<String>,
+    pub api_key: Option<String>,
+    pub timeout_ms: Option<u64>,
+    pub command: Option<String>,
+    pub args: Option<Vec<String>>,
+    pub mock_response: Option<String>,
+    pub workers: Option<usize>,
+    pub job_timeout_ms: Option<u64>,
+    pub max_attempts: Option<u32>,
+    pub retry_backoff_ms: Option<u64>,
+    pub max_retry_backoff_ms: Option<u64>,
+    pub infer_min_fee_rate_per_prompt_byte: Option<u64>,
+    pub infer_max_prompt_len: Option<u64>,
+    pub infer_max_context_len: Option<u64>,
+    pub infer_max_txs_per_origin: Option<u32>,
+    pub infer_rate_limit_window_secs: Option<u64>,
+    pub result_retention_blocks: Option<u64>,
+    pub orphan_result_retention_secs: Option<u64>,
+}
+
+impl LlmConfigFile {
+    fn into_config_default(self, default_llm_config: LlmConfig) -> Result<LlmConfig, String> {
+        let backend = match self.backend {
+            Some(ref backend) => backend.parse()?,
+            None => default_llm_config.backend,
+        };
+        if backend == InferenceBackendName::Subprocess && self.command.is_none() {
+            return Err("llm.command is required when llm.backend = \"subprocess\"".into());
+        }
+        if self.workers == Some(0) {
+            return Err("llm.workers must be at least 1".into());
+        }
+        if self.max_attempts == Some(0) {
+            return Err("llm.max_attempts must be at least 1".into());
+        }
+        let default_pool = default_llm_config.pool;
+        let pool = InferPoolConfig {
+            workers: self.workers.unwrap_or(default_pool.workers),
+            job_timeout: self
+                .job_timeout_ms
+                .map(Duration::from_millis)
+                .unwrap_or(default_pool.job_timeout),
+            max_attempts: self.max_attempts.unwrap_or(default_pool.max_attempts),
+            retry_backoff: self
+                .retry_backoff_ms
+                .map(Duration::from_millis)
+                .unwrap_or(default_pool.retry_backoff),
+            max_retry_backoff: self
+                .max_retry_backoff_ms
+                .map(Duration::from_millis)
+                .unwrap_or(default_pool.max_retry_backoff),
+        };
+        let default_admission = default_llm_config.admission;
+        let admission = InferAdmissionConfig {
+            min_fee_rate_per_prompt_byte: self
+                .infer_min_fee_rate_per_prompt_byte
+                .unwrap_or(default_admission.min_fee_rate_per_prompt_byte),
+            max_prompt_len: self
+                .infer_max_prompt_len
+                .unwrap_or(default_admission.max_prompt_len),
+            max_context_len: self
+                .infer_max_context_len
+                .unwrap_or(default_admission.max_context_len),
+            max_txs_per_origin: self
+                .infer_max_txs_per_origin
+                .unwrap_or(default_admission.max_txs_per_origin),
+            rate_limit_window_secs: self
+                .infer_rate_limit_window_secs
+                .unwrap_or(default_admission.rate_limit_window_secs),
+        };
+        if admission.max_txs_per_origin > 0 && admission.rate_limit_window_secs == 0 {
+            return Err("llm.infer_rate_limit_window_secs must be at least 1".into());
+        }
+        let default_retention = default_llm_config.retention;
+        let retention = InferRetentionConfig {
+            confirmed_blocks: self
+                .result_retention_blocks
+                .unwrap_or(default_retention.confirmed_blocks),
+            orphan_secs: self
+                .orphan_result_retention_secs
+                .unwrap_or(default_retention.orphan_secs),
+        };
+        Ok(LlmConfig {
+            backend,
+            model: self.model.unwrap_or(default_llm_config.model),
+            model_digest: self.model_digest.or(default_llm_config.model_digest),
+            base_url: self.base_url.unwrap_or(default_llm_config.base_url),
+            api_key: self.api_key.unwrap_or(default_llm_config.api_key),
+            timeout: self
+                .timeout_ms
+                .map(Duration::from_millis)
+                .unwrap_or(default_llm_config.timeout),
+            command: self.command.or(default_llm_config.command),
+            args: self.args.unwrap_or(default_llm_config.args),
+            mock_response: self.mock_response.or(default_llm_config.mock_response),
+            pool,
+            admission,
+            retention,
+        })
+    }
+}
+
 #[derive(Clone, Deserialize, Default, Debug, Hash, PartialEq, Eq, PartialOrd)]
 pub struct EventObserverConfigFile {
     pub endpoint: String,
@@ -2746,6 +3205,7 @@ pub enum EventKeyType {
     MinedMicroblocks,
     StackerDBChunks,
     BlockProposal,
+    InferTasks,
 }
 
 impl EventKeyType {
@@ -2778,6 +3238,10 @@ impl EventKeyType {
             return Some(EventKeyType::BlockProposal);
         }
 
+        if raw_key == "infer_tasks" {
+            return Some(EventKeyType::InferTasks);
+        }
+
         let comps: Vec<_> = raw_key.split("::").collect();
         if comps.len() == 1 {
             let split: Vec<_> = comps[0].split('.').collect();
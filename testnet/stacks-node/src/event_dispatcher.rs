@@ -12,6 +12,7 @@ use clarity::vm::costs::ExecutionCost;
 use clarity::vm::events::{FTEventType, NFTEventType, STXEventType};
 use clarity::vm::types::{AssetIdentifier, QualifiedContractIdentifier, Value};
 use http_types::{Method, Request, Url};
+use libllm::{InferDB, InferStatus, InferTaskEvent};
 use serde_json::json;
 use stacks::burnchains::{PoxConstants, Txid};
 use stacks::chainstate::burn::operations::BlockstackOperationType;
@@ -79,6 +80,11 @@ pub const PATH_BURN_BLOCK_SUBMIT: &str = "new_burn_block";
 pub const PATH_BLOCK_PROCESSED: &str = "new_block";
 pub const PATH_ATTACHMENT_PROCESSED: &str = "attachments/new";
 pub const PATH_PROPOSAL_RESPONSE: &str = "proposal_response";
+pub const PATH_NEW_INFER_TASK: &str = "new_infer_task";
+pub const PATH_INFER_TASK_UPDATE: &str = "infer_task_update";
+
+/// How many inference task events to send per call to `dispatch_infer_task_events`
+const INFER_TASK_EVENTS_BATCH: u32 = 256;
 
 pub static STACKER_DB_CHANNEL: StackerDBChannel = StackerDBChannel::new();
 
@@ -561,6 +567,30 @@ impl EventObserver {
         self.send_payload(payload, PATH_BURN_BLOCK_SUBMIT);
     }
 
+    fn send_new_infer_task(&self, payload: &serde_json::Value) {
+        self.send_payload(payload, PATH_NEW_INFER_TASK);
+    }
+
+    fn send_infer_task_update(&self, payload: &serde_json::Value) {
+        self.send_payload(payload, PATH_INFER_TASK_UPDATE);
+    }
+
+    fn make_infer_task_payload(event: &InferTaskEvent) -> serde_json::Value {
+        json!({
+            "txid": format!("0x{}", &event.txid),
+            "status": event.status,
+            "fee": event.fee,
+            "attempts": event.attempts,
+            "model": event.model,
+            "model_digest": event.model_digest,
+            "output_hash": event.output_hash,
+            "error": event.error,
+            "create_time": event.create_time,
+            "start_time": event.start_time,
+            "end_time": event.end_time,
+        })
+    }
+
     fn make_new_block_processed_payload(
         &self,
         filtered_events: Vec<(usize, &(bool, Txid, &StacksTransactionEvent))>,
@@ -679,6 +709,7 @@ pub struct EventDispatcher {
     mined_microblocks_observers_lookup: HashSet<u16>,
     stackerdb_observers_lookup: HashSet<u16>,
     block_proposal_observers_lookup: HashSet<u16>,
+    infer_task_observers_lookup: HashSet<u16>,
 }
 
 /// This struct is used specifically for receiving proposal responses.
@@ -874,6 +905,7 @@ impl EventDispatcher {
             mined_microblocks_observers_lookup: HashSet::new(),
             stackerdb_observers_lookup: HashSet::new(),
             block_proposal_observers_lookup: HashSet::new(),
+            infer_task_observers_lookup: HashSet::new(),
         }
     }
 
@@ -1331,6 +1363,45 @@ impl EventDispatcher {
         }
     }
 
+    /// Send the status changes of this node's inference tasks.  Newly queued tasks go to
+    /// `/new_infer_task`, and every later change to `/infer_task_update`.
+    pub fn process_infer_task_events(&self, events: &[InferTaskEvent]) {
+        let interested_observers = self.filter_observers(&self.infer_task_observers_lookup, true);
+
+        if interested_observers.len() < 1 {
+            return;
+        }
+
+        for event in events.iter() {
+            let payload = EventObserver::make_infer_task_payload(event);
+            for observer in interested_observers.iter() {
+                if event.status == InferStatus::Created {
+                    observer.send_new_infer_task(&payload);
+                } else {
+                    observer.send_infer_task_update(&payload);
+                }
+            }
+        }
+    }
+
+    /// Send (or, absent any interested observers, discard) the inference task status changes
+    /// recorded in `infer_db` since the last call.
+    pub fn dispatch_infer_task_events(&self, infer_db: &InferDB) {
+        loop {
+            let events = match libllm::take_task_events(infer_db, INFER_TASK_EVENTS_BATCH) {
+                Ok(events) => events,
+                Err(e) => {
+                    warn!("Failed to load inference task events: {:?}", &e);
+                    return;
+                }
+            };
+            self.process_infer_task_events(&events);
+            if events.len() < INFER_TASK_EVENTS_BATCH as usize {
+                return;
+            }
+        }
+    }
+
     pub fn process_new_attachments(&self, attachments: &Vec<(AttachmentInstance, Attachment)>) {
         let interested_observers: Vec<_> = self.registered_observers.iter().enumerate().collect();
         if interested_observers.len() < 1 {
@@ -1426,6 +1497,9 @@ impl EventDispatcher {
                 EventKeyType::BlockProposal => {
                     self.block_proposal_observers_lookup.insert(observer_index);
                 }
+                EventKeyType::InferTasks => {
+                    self.infer_task_observers_lookup.insert(observer_index);
+                }
             }
         }
 
@@ -1442,7 +1516,9 @@ mod test {
     use stacks_common::bitvec::BitVec;
     use stacks_common::types::chainstate::{BurnchainHeaderHash, StacksBlockId};
 
-    use crate::event_dispatcher::EventObserver;
+    use libllm::{InferDB, InferStatus, InferTaskEvent};
+
+    use crate::event_dispatcher::{EventDispatcher, EventObserver};
 
     #[test]
     fn build_block_processed_event() {
@@ -1501,4 +1577,51 @@ mod test {
             expected_bitvec_str
         );
     }
+
+    #[test]
+    fn build_infer_task_event() {
+        let event = InferTaskEvent {
+            txid: "ab".repeat(32),
+            status: InferStatus::Success,
+            fee: 100,
+            attempts: 1,
+            model: Some("llama3".to_string()),
+            model_digest: None,
+            output_hash: Some("cd".repeat(32)),
+            error: None,
+            create_time: Some(1700000000),
+            start_time: Some(1700000001),
+            end_time: Some(1700000003),
+        };
+        let payload = EventObserver::make_infer_task_payload(&event);
+        assert_eq!(
+            payload.get("txid").unwrap().as_str().unwrap(),
+            format!("0x{}", "ab".repeat(32))
+        );
+        assert_eq!(payload.get("status").unwrap().as_str().unwrap(), "Success");
+        assert_eq!(payload.get("model").unwrap().as_str().unwrap(), "llama3");
+        assert!(payload.get("model_digest").unwrap().is_null());
+        assert_eq!(
+            payload.get("output_hash").unwrap().as_str().unwrap(),
+            "cd".repeat(32)
+        );
+        assert_eq!(
+            payload.get("start_time").unwrap().as_u64().unwrap(),
+            1700000001
+        );
+        assert_eq!(
+            payload.get("end_time").unwrap().as_u64().unwrap(),
+            1700000003
+        );
+    }
+
+    #[test]
+    fn dispatch_infer_task_events_without_observers() {
+        let infer_db = InferDB::open_memory().unwrap();
+        libllm::infer_chain(&infer_db, "ab".repeat(32), "Is the Earth round?", "", 1).unwrap();
+
+        // nobody is listening, so the events are dropped rather than left to pile up
+        EventDispatcher::new().dispatch_infer_task_events(&infer_db);
+        assert!(libllm::take_task_events(&infer_db, 10).unwrap().is_empty());
+    }
 }
@@ -34,9 +34,9 @@ use stacks::chainstate::nakamoto::{NakamotoBlock, NakamotoChainState};
 use stacks::chainstate::stacks::boot::MINERS_NAME;
 use stacks::chainstate::stacks::db::{StacksChainState, StacksHeaderInfo};
 use stacks::chainstate::stacks::{
-    CoinbasePayload, Error as ChainstateError, StacksTransaction, StacksTransactionSigner,
-    TenureChangeCause, TenureChangePayload, ThresholdSignature, TransactionAnchorMode,
-    TransactionPayload, TransactionVersion,
+    CoinbasePayload, Error as ChainstateError, InferOutput, StacksTransaction,
+    StacksTransactionSigner, TenureChangeCause, TenureChangePayload, ThresholdSignature,
+    TransactionAnchorMode, TransactionPayload, TransactionVersion, MAX_BLOCK_LEN,
 };
 use stacks::net::stackerdb::StackerDBs;
 use stacks_common::codec::read_next;
@@ -652,6 +652,34 @@ impl BlockMinerThread {
         tx_signer.get_tx().unwrap()
     }
 
+    /// Create an InferOutputs transaction, which carries the `outputs` of the Infer transactions
+    /// the block will include.
+    fn generate_infer_outputs_tx(
+        &mut self,
+        nonce: u64,
+        outputs: Vec<InferOutput>,
+    ) -> StacksTransaction {
+        let is_mainnet = self.config.is_mainnet();
+        let chain_id = self.config.burnchain.chain_id;
+        let mut tx_auth = self.keychain.get_transaction_auth().unwrap();
+        tx_auth.set_origin_nonce(nonce);
+
+        let version = if is_mainnet {
+            TransactionVersion::Mainnet
+        } else {
+            TransactionVersion::Testnet
+        };
+
+        let mut tx =
+            StacksTransaction::new(version, tx_auth, TransactionPayload::InferOutputs(outputs));
+        tx.chain_id = chain_id;
+        tx.anchor_mode = TransactionAnchorMode::OnChainOnly;
+        let mut tx_signer = StacksTransactionSigner::new(&tx);
+        self.keychain.sign_as_origin(&mut tx_signer);
+
+        tx_signer.get_tx().unwrap()
+    }
+
     /// Load up the parent block info for mining.
     /// If there's no parent because this is the first block, then return the genesis block's info.
     /// If we can't find the parent in the DB but we expect one, return None.
@@ -806,9 +834,28 @@ impl BlockMinerThread {
             .map_err(|_| NakamotoNodeError::UnexpectedChainState)?
             .saturating_add(1);
 
-        let signer_transactions =
+        let mut signer_transactions =
             self.get_signer_transactions(&mut chain_state, &burn_db, &stackerdbs)?;
 
+        // carry the outputs of the inferences that are done, so that the block can include
+        // their Infer transactions
+        if target_epoch_id.carries_infer_outputs() {
+            let outputs = mem_pool
+                .infer_outputs(u64::from(MAX_BLOCK_LEN / 2))
+                .unwrap_or_else(|e| {
+                    warn!("Miner: failed to load inference outputs: {e:?}");
+                    vec![]
+                });
+            if !outputs.is_empty() {
+                let nonce = if tenure_start_info.coinbase_tx.is_some() {
+                    parent_block_info.coinbase_nonce + 2
+                } else {
+                    parent_block_info.coinbase_nonce
+                };
+                signer_transactions.push(self.generate_infer_outputs_tx(nonce, outputs));
+            }
+        }
+
         // build the block itself
         let (mut block, consumed, size, tx_events) = NakamotoBlockBuilder::build_nakamoto_block(
             &chain_state,
@@ -174,7 +174,7 @@ use stacks::chainstate::stacks::{
     StacksMicroblock, StacksPublicKey, StacksTransaction, StacksTransactionSigner,
     TransactionAnchorMode, TransactionPayload, TransactionVersion,
 };
-use stacks::core::mempool::MemPoolDB;
+use stacks::core::mempool::{InferAdmissionPolicy, MemPoolDB};
 use stacks::core::{FIRST_BURNCHAIN_CONSENSUS_HASH, STACKS_EPOCH_3_0_MARKER};
 use stacks::cost_estimates::metrics::{CostMetric, UnitMetric};
 use stacks::cost_estimates::{CostEstimator, FeeEstimator, UnitEstimator};
@@ -4118,7 +4118,7 @@ impl PeerThread {
             .make_cost_metric()
             .unwrap_or_else(|| Box::new(UnitMetric));
 
-        let mempool = MemPoolDB::open(
+        let mut mempool = MemPoolDB::open(
             config.is_mainnet(),
             config.burnchain.chain_id,
             &config.get_chainstate_path_str(),
@@ -4126,6 +4126,7 @@ impl PeerThread {
             metric,
         )
         .expect("Database failure opening mempool");
+        mempool.set_infer_admission_policy(InferAdmissionPolicy::new(config.llm.admission.clone()));
 
         mempool
     }
@@ -1,11 +1,12 @@
 use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::{env, thread, time};
 
 use clarity::vm::database::BurnStateDB;
 use rand::RngCore;
-use libllm::do_infer;
+use libllm::{InferDB, InferWorkerPool};
 use stacks::burnchains::bitcoin::BitcoinNetworkType;
 use stacks::burnchains::db::BurnchainDB;
 use stacks::burnchains::{PoxConstants, Txid};
@@ -33,6 +34,7 @@ use stacks::core::mempool::MemPoolDB;
 use stacks::core::{STACKS_EPOCH_2_1_MARKER, STACKS_EPOCH_3_0_MARKER};
 use stacks::cost_estimates::metrics::UnitMetric;
 use stacks::cost_estimates::UnitEstimator;
+use stacks::monitoring::InferPoolMonitor;
 use stacks::net::atlas::{AtlasConfig, AtlasDB, AttachmentInstance};
 use stacks::net::db::PeerDB;
 use stacks::net::p2p::PeerNetwork;
@@ -395,14 +397,25 @@ impl Node {
     // This function is used for helium and mocknet.
     pub fn spawn_peer_llm_thread(&mut self) {
         let config = self.config.clone();
+        let backend = config.make_inference_backend();
+        let infer_db = InferDB::open(&config.get_infer_db_file_path())
+            .expect("FATAL: failed to open inference DB");
+        debug!("Chain-llm inference backend"; "backend" => backend.name(), "model" => backend.model());
+        let mut pool = InferWorkerPool::new(infer_db.clone(), backend, config.llm.pool.clone())
+            .expect("FATAL: failed to start inference worker pool");
+        pool.set_attestation_key(config.get_infer_attestation_key());
+        pool.set_metrics(Arc::new(InferPoolMonitor));
+        let event_dispatcher = self.event_dispatcher.clone();
 
         let _llm_thread_handle = thread::Builder::new()
             .name(format!("chain-llm-{}", config.node.rpc_bind))
             .spawn(move || {
-                debug!("Chain-llm thread start!");
+                debug!("Chain-llm thread start!"; "workers" => pool.config().workers);
                 loop {
-                    debug!("Chain-llm do_infer");
-                    let _ = do_infer();
+                    if let Err(e) = pool.tick() {
+                        warn!("Chain-llm failed to schedule inference tasks"; "err" => %e);
+                    }
+                    event_dispatcher.dispatch_infer_task_events(&infer_db);
                     sleep_ms(500);
                 }
                 debug!("Chain-llm thread exit!");
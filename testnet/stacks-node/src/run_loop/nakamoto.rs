@@ -19,7 +19,7 @@ use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
 use std::{cmp, thread};
 
-use libllm::do_infer;
+use libllm::{InferDB, InferWorkerPool, InferenceBackend};
 use stacks::burnchains::bitcoin::address::{BitcoinAddress, LegacyBitcoinAddressType};
 use stacks::burnchains::{Burnchain, Error as burnchain_error};
 use stacks::chainstate::burn::db::sortdb::SortitionDB;
@@ -31,6 +31,7 @@ use stacks::chainstate::coordinator::{
 use stacks::chainstate::stacks::db::{ChainStateBootData, StacksChainState};
 use stacks::chainstate::stacks::miner::{signal_mining_blocked, signal_mining_ready, MinerStatus};
 use stacks::core::StacksEpochId;
+use stacks::monitoring::InferPoolMonitor;
 use stacks::net::atlas::{AtlasConfig, AtlasDB, Attachment};
 use stacks_common::types::PublicKey;
 use stacks_common::util::hash::Hash160;
@@ -72,6 +73,8 @@ pub struct RunLoop {
     /// instantiated (namely, so the test framework can access it).
     miner_status: Arc<Mutex<MinerStatus>>,
     monitoring_thread: Option<JoinHandle<Result<(), MonitoringError>>>,
+    /// Backend used by the chain-llm thread to run queued inference tasks
+    inference_backend: Arc<dyn InferenceBackend>,
 }
 
 impl RunLoop {
@@ -94,6 +97,7 @@ impl RunLoop {
         for observer in config.events_observers.iter() {
             event_dispatcher.register_observer(observer);
         }
+        let inference_backend = config.make_inference_backend();
 
         Self {
             config,
@@ -108,6 +112,7 @@ impl RunLoop {
             pox_watchdog_comms,
             miner_status,
             monitoring_thread,
+            inference_backend,
         }
     }
 
@@ -315,6 +320,7 @@ impl RunLoop {
                     require_affirmed_anchor_blocks: moved_config
                         .node
                         .require_affirmed_anchor_blocks,
+                    infer_retention: moved_config.llm.retention.clone(),
                     ..ChainsCoordinatorConfig::new()
                 };
                 ChainsCoordinator::run(
@@ -388,15 +394,27 @@ impl RunLoop {
         )
     }
 
-    /// In a separate thread, loop llm do_infer
+    /// Replace the inference backend the chain-llm thread will use.
+    /// Must be called before `start()`.
+    pub fn set_inference_backend(&mut self, backend: Arc<dyn InferenceBackend>) {
+        self.inference_backend = backend;
+    }
+
+    /// In a separate thread, keep the inference worker pool busy, and tell event observers how
+    /// its tasks are getting on
     fn drive_llm(
         globals: Globals,
+        mut pool: InferWorkerPool,
+        infer_db: InferDB,
+        event_dispatcher: EventDispatcher,
     ) {
-        debug!("Chain-llm thread start!");
+        debug!("Chain-llm thread start!"; "workers" => pool.config().workers);
 
         while globals.keep_running() {
-            debug!("Chain-llm do_infer");
-            let _ = do_infer();
+            if let Err(e) = pool.tick() {
+                warn!("Chain-llm failed to schedule inference tasks"; "err" => %e);
+            }
+            event_dispatcher.dispatch_infer_task_events(&infer_db);
             sleep_ms(500);
         }
 
@@ -406,13 +424,20 @@ impl RunLoop {
     /// Spawn a thread to drive llm infer
     fn spawn_llm_thread(&self, globals: Globals) -> JoinHandle<()> {
         let config = self.config.clone();
+        let backend = self.inference_backend.clone();
+        let infer_db = InferDB::open(&config.get_infer_db_file_path())
+            .expect("FATAL: failed to open inference DB");
+        debug!("Chain-llm inference backend"; "backend" => backend.name(), "model" => backend.model(), "db" => infer_db.path());
+        let mut pool = InferWorkerPool::new(infer_db.clone(), backend, config.llm.pool.clone())
+            .expect("FATAL: failed to start inference worker pool");
+        pool.set_attestation_key(config.get_infer_attestation_key());
+        pool.set_metrics(Arc::new(InferPoolMonitor));
+        let event_dispatcher = self.event_dispatcher.clone();
 
         let llm_thread_handle = thread::Builder::new()
             .name(format!("chain-llm-{}", config.node.rpc_bind))
             .stack_size(BLOCK_PROCESSOR_STACK_SIZE)
-            .spawn(move || {
-                Self::drive_llm(globals)
-            })
+            .spawn(move || Self::drive_llm(globals, pool, infer_db, event_dispatcher))
             .expect("FATAL: failed to spawn chain llm thread");
 
         llm_thread_handle
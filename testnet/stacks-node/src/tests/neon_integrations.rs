@@ -211,6 +211,7 @@ pub mod test_observer {
     pub static ATTACHMENTS: Mutex<Vec<serde_json::Value>> = Mutex::new(Vec::new());
     pub static PROPOSAL_RESPONSES: Mutex<Vec<BlockValidateResponse>> = Mutex::new(Vec::new());
     pub static STACKER_SETS: Mutex<Vec<(StacksBlockId, u64, RewardSet)>> = Mutex::new(Vec::new());
+    pub static INFER_TASK_EVENTS: Mutex<Vec<(String, serde_json::Value)>> = Mutex::new(Vec::new());
 
     async fn handle_proposal_response(
         response: serde_json::Value,
@@ -407,6 +408,22 @@ pub mod test_observer {
         Ok(warp::http::StatusCode::OK)
     }
 
+    async fn handle_new_infer_task(
+        event: serde_json::Value,
+    ) -> Result<impl warp::Reply, Infallible> {
+        let mut events = INFER_TASK_EVENTS.lock().unwrap();
+        events.push(("new_infer_task".to_string(), event));
+        Ok(warp::http::StatusCode::OK)
+    }
+
+    async fn handle_infer_task_update(
+        event: serde_json::Value,
+    ) -> Result<impl warp::Reply, Infallible> {
+        let mut events = INFER_TASK_EVENTS.lock().unwrap();
+        events.push(("infer_task_update".to_string(), event));
+        Ok(warp::http::StatusCode::OK)
+    }
+
     async fn handle_attachments(
         attachments: serde_json::Value,
     ) -> Result<impl warp::Reply, Infallible> {
@@ -466,6 +483,11 @@ pub mod test_observer {
         PROPOSAL_RESPONSES.lock().unwrap().clone()
     }
 
+    /// The inference task events received so far, each with the path it was sent to
+    pub fn get_infer_task_events() -> Vec<(String, serde_json::Value)> {
+        INFER_TASK_EVENTS.lock().unwrap().clone()
+    }
+
     /// each path here should correspond to one of the paths listed in `event_dispatcher.rs`
     async fn serve(port: u16) {
         let new_blocks = warp::path!("new_block")
@@ -516,6 +538,14 @@ pub mod test_observer {
             .and(warp::post())
             .and(warp::body::json())
             .and_then(handle_pox_stacker_set);
+        let new_infer_tasks = warp::path!("new_infer_task")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and_then(handle_new_infer_task);
+        let infer_task_updates = warp::path!("infer_task_update")
+            .and(warp::post())
+            .and(warp::body::json())
+            .and_then(handle_infer_task_update);
 
         info!("Spawning event-observer warp server");
         warp::serve(
@@ -530,7 +560,9 @@ pub mod test_observer {
                 .or(mined_nakamoto_blocks)
                 .or(new_stackerdb_chunks)
                 .or(block_proposals)
-                .or(stacker_sets),
+                .or(stacker_sets)
+                .or(new_infer_tasks)
+                .or(infer_task_updates),
         )
         .run(([127, 0, 0, 1], port))
         .await
@@ -563,6 +595,7 @@ pub mod test_observer {
         MEMTXS_DROPPED.lock().unwrap().clear();
         ATTACHMENTS.lock().unwrap().clear();
         PROPOSAL_RESPONSES.lock().unwrap().clear();
+        INFER_TASK_EVENTS.lock().unwrap().clear();
     }
 }
 
@@ -6,8 +6,9 @@ use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use std::{env, thread};
 
-use clarity::boot_util::boot_code_id;
+use clarity::boot_util::{boot_code_addr, boot_code_id};
 use clarity::vm::Value;
+use libllm::MockLlmServer;
 use libsigner::{
     BlockResponse, MessageSlotID, RejectCode, RunningSigner, Signer, SignerEventReceiver,
     SignerMessage,
@@ -19,7 +20,7 @@ use stacks::chainstate::coordinator::comm::CoordinatorChannels;
 use stacks::chainstate::nakamoto::signer_set::NakamotoSigners;
 use stacks::chainstate::nakamoto::{NakamotoBlock, NakamotoBlockHeader};
 use stacks::chainstate::stacks::boot::{
-    SIGNERS_NAME, SIGNERS_VOTING_FUNCTION_NAME, SIGNERS_VOTING_NAME,
+    INFER_MODELS_NAME, SIGNERS_NAME, SIGNERS_VOTING_FUNCTION_NAME, SIGNERS_VOTING_NAME,
 };
 use stacks::chainstate::stacks::events::StackerDBChunksEvent;
 use stacks::chainstate::stacks::miner::TransactionEvent;
@@ -29,8 +30,8 @@ use stacks::chainstate::stacks::{
     TransactionVersion,
 };
 use stacks::core::StacksEpoch;
-use stacks::net::api::postblock_proposal::BlockValidateResponse;
-use stacks::util_lib::strings::StacksString;
+use stacks::net::api::postblock_proposal::{BlockValidateResponse, ValidateRejectCode};
+use stacks::util_lib::strings::{InferLPString, StacksString};
 use stacks_common::bitvec::BitVec;
 use stacks_common::codec::StacksMessageCodec;
 use stacks_common::consts::{CHAIN_ID_TESTNET, SIGNER_SLOTS_PER_USER};
@@ -50,7 +51,9 @@ use wsts::curve::point::Point;
 use wsts::curve::scalar::Scalar;
 use wsts::state_machine::OperationResult;
 
-use crate::config::{Config as NeonConfig, EventKeyType, EventObserverConfig, InitialBalance};
+use crate::config::{
+    Config as NeonConfig, EventKeyType, EventObserverConfig, InferenceBackendName, InitialBalance,
+};
 use crate::event_dispatcher::MinedNakamotoBlockEvent;
 use crate::neon::Counters;
 use crate::run_loop::boot_nakamoto;
@@ -60,9 +63,9 @@ use crate::tests::nakamoto_integrations::{
     next_block_and_mine_commit, POX_4_DEFAULT_STACKER_BALANCE,
 };
 use crate::tests::neon_integrations::{
-    next_block_and_wait, run_until_burnchain_height, test_observer, wait_for_runloop,
+    next_block_and_wait, run_until_burnchain_height, submit_tx, test_observer, wait_for_runloop,
 };
-use crate::tests::to_addr;
+use crate::tests::{make_contract_call, serialize_sign_standard_single_sig_tx, to_addr};
 use crate::{BitcoinRegtestController, BurnchainController};
 
 // Helper struct for holding the btc and stx neon nodes
@@ -98,12 +101,23 @@ struct SignerTest {
 
 impl SignerTest {
     fn new(num_signers: usize) -> Self {
+        Self::new_with_config_modifications(num_signers, |_| {}, |_| {})
+    }
+
+    /// Like `new`, but lets the caller adjust the node's config, and append to each signer's
+    /// config TOML, before anything is started.
+    fn new_with_config_modifications<F: FnOnce(&mut NeonConfig), G: FnMut(&mut String)>(
+        num_signers: usize,
+        modify_node_config: F,
+        mut modify_signer_config: G,
+    ) -> Self {
         // Generate Signer Data
         let signer_stacks_private_keys = (0..num_signers)
             .map(|_| StacksPrivateKey::new())
             .collect::<Vec<StacksPrivateKey>>();
 
         let (mut naka_conf, _miner_account) = naka_neon_integration_conf(None);
+        modify_node_config(&mut naka_conf);
         // So the combination is... one, two, three, four, five? That's the stupidest combination I've ever heard in my life!
         // That's the kind of thing an idiot would have on his luggage!
         let password = "12345";
@@ -112,7 +126,7 @@ impl SignerTest {
         let run_stamp = rand::random();
 
         // Setup the signer and coordinator configurations
-        let signer_configs = build_signer_config_tomls(
+        let mut signer_configs = build_signer_config_tomls(
             &signer_stacks_private_keys,
             &naka_conf.node.rpc_bind,
             Some(Duration::from_millis(128)), // Timeout defaults to 5 seconds. Let's override it to 128 milliseconds.
@@ -121,6 +135,9 @@ impl SignerTest {
             run_stamp,
             3000,
         );
+        for signer_config in signer_configs.iter_mut() {
+            modify_signer_config(signer_config);
+        }
 
         let mut running_signers = Vec::new();
         let mut signer_cmd_senders = Vec::new();
@@ -804,6 +821,7 @@ fn setup_stx_btc_node(
             EventKeyType::StackerDBChunks,
             EventKeyType::BlockProposal,
             EventKeyType::MinedBlocks,
+            EventKeyType::InferTasks,
         ],
     });
 
@@ -1111,6 +1129,73 @@ pub fn find_block_response(chunk_events: Vec<StackerDBChunksEvent>) -> Option<Si
     None
 }
 
+/// The weights digest the mock model is registered and served with
+const MOCK_MODEL_DIGEST: &str = "sha256:mock";
+
+/// Register the mock model in `.infer-models` from `sender` at the node at `conf`, so that
+/// Infer transactions can ask for it
+fn submit_register_mock_model_tx(conf: &NeonConfig, sender: &StacksPrivateKey, nonce: u64) {
+    let tx = make_contract_call(
+        sender,
+        nonce,
+        10_000,
+        &boot_code_addr(false),
+        INFER_MODELS_NAME,
+        "register-model",
+        &[
+            Value::string_ascii_from_bytes("mock".as_bytes().to_vec()).unwrap(),
+            Value::string_ascii_from_bytes(MOCK_MODEL_DIGEST.as_bytes().to_vec()).unwrap(),
+            Value::string_utf8_from_bytes(vec![]).unwrap(),
+            Value::UInt(4096),
+            Value::Bool(true),
+        ],
+    );
+    submit_tx(&format!("http://{}", &conf.node.rpc_bind), &tx);
+}
+
+/// Submit an Infer transaction asking `user_input` of the mock model from `sender` to the node
+/// at `conf`, and return its txid
+fn submit_infer_tx(
+    conf: &NeonConfig,
+    sender: &StacksPrivateKey,
+    nonce: u64,
+    user_input: &str,
+) -> String {
+    let context = format!(
+        r#"{{"params":{{"model":"mock","model_digest":"{}"}}}}"#,
+        MOCK_MODEL_DIGEST
+    );
+    let payload = TransactionPayload::Infer(
+        to_addr(sender).to_account_principal(),
+        InferLPString::try_from(user_input).unwrap(),
+        InferLPString::try_from(context.as_str()).unwrap(),
+    );
+    let tx = serialize_sign_standard_single_sig_tx(payload, sender, nonce, 10_000);
+    submit_tx(&format!("http://{}", &conf.node.rpc_bind), &tx)
+}
+
+/// Wait for the node to report that the inference task for `txid` reached `status`
+fn wait_for_infer_task_status(txid: &str, status: &str, timeout: Duration) -> serde_json::Value {
+    let t_start = Instant::now();
+    loop {
+        assert!(
+            t_start.elapsed() < timeout,
+            "Timed out while waiting for inference task {txid} to reach {status}"
+        );
+        let event = test_observer::get_infer_task_events()
+            .into_iter()
+            .map(|(_path, event)| event)
+            .find(|event| {
+                event.get("txid").and_then(|txid| txid.as_str()) == Some(&format!("0x{txid}"))
+                    && event.get("status").and_then(|status| status.as_str()) == Some(status)
+            });
+        if let Some(event) = event {
+            return event;
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
 #[test]
 #[ignore]
 /// Test that a signer can respond to a miners request for a signature on a block proposal
@@ -1391,3 +1476,196 @@ fn stackerdb_sign_after_signer_reboot() {
 
     signer_test.shutdown();
 }
+
+#[test]
+#[ignore]
+/// Test that signers accept a block containing an Infer transaction whose result they can
+/// reproduce.
+///
+/// Test Setup:
+/// The test spins up an in-process OpenAI-compatible model server, five stacks signers, one miner
+/// Nakamoto node, and a corresponding bitcoind. The node and the signers all use the same model
+/// server. The stacks node is advanced to epoch 3.0 boundary to allow block signing.
+///
+/// Test Execution:
+/// A funded account submits an Infer transaction. Once the node has run the inference task, it
+/// mines a Nakamoto block, and the signers re-run the task against the model server to check it.
+///
+/// Test Assertion:
+/// The node's inference task succeeds.
+/// Signers sign the proposed block, and the Infer transaction is mined.
+/// Signers consulted the model server.
+fn stackerdb_infer_tx_accepted() {
+    if env::var("BITCOIND_TEST") != Ok("1".into()) {
+        return;
+    }
+
+    tracing_subscriber::registry()
+        .with(fmt::layer())
+        .with(EnvFilter::from_default_env())
+        .init();
+
+    info!("------------------------- Test Setup -------------------------");
+    let mock_llm = MockLlmServer::start();
+    let sender_sk = StacksPrivateKey::new();
+    let sender_addr = to_addr(&sender_sk);
+    let mut signer_test = SignerTest::new_with_config_modifications(
+        5,
+        |naka_conf| {
+            naka_conf.initial_balances.push(InitialBalance {
+                address: sender_addr.into(),
+                amount: 1_000_000,
+            });
+            naka_conf.llm.backend = InferenceBackendName::OpenAi;
+            naka_conf.llm.base_url = mock_llm.base_url();
+            naka_conf.llm.model = "mock".to_string();
+            naka_conf.llm.model_digest = Some(MOCK_MODEL_DIGEST.to_string());
+        },
+        |signer_config| {
+            signer_config.push_str(&format!(
+                "llm_base_url = \"{}\"\nllm_model = \"mock\"\nllm_model_digest = \"{}\"\n",
+                mock_llm.base_url(),
+                MOCK_MODEL_DIGEST
+            ));
+        },
+    );
+    let timeout = Duration::from_secs(200);
+    let short_timeout = Duration::from_secs(30);
+
+    let key = signer_test.boot_to_epoch_3(timeout);
+
+    info!("------------------------- Register Model -------------------------");
+    submit_register_mock_model_tx(&signer_test.running_nodes.conf, &sender_sk, 0);
+    signer_test.mine_and_verify_confirmed_naka_block(&key, timeout);
+
+    info!("------------------------- Submit Infer Transaction -------------------------");
+    let txid = submit_infer_tx(
+        &signer_test.running_nodes.conf,
+        &sender_sk,
+        1,
+        "Is the Earth round?",
+    );
+    wait_for_infer_task_status(&txid, "Success", short_timeout);
+
+    info!("------------------------- Verify Nakamoto Block Mined -------------------------");
+    let mined_block_event = signer_test.mine_and_verify_confirmed_naka_block(&key, timeout);
+    assert!(
+        mined_block_event.tx_events.iter().any(|tx_event| matches!(
+            tx_event,
+            TransactionEvent::Success(tx_success) if tx_success.txid.to_string() == txid
+        )),
+        "Miner did not include the Infer transaction in the block"
+    );
+
+    // the node streams its inference, while signers re-run it in one request
+    let requests = mock_llm.requests();
+    assert!(requests
+        .iter()
+        .any(|request| request.body["stream"] == true));
+    assert!(requests
+        .iter()
+        .any(|request| request.body.get("stream").is_none()));
+
+    signer_test.shutdown();
+}
+
+#[test]
+#[ignore]
+/// Test that signers reject a block containing an Infer transaction whose result they can't
+/// reproduce.
+///
+/// Test Setup:
+/// The test spins up two in-process OpenAI-compatible model servers, five stacks signers, one
+/// miner Nakamoto node, and a corresponding bitcoind. The node uses one model server, and the
+/// signers use the other, which answers every prompt differently. The stacks node is advanced to
+/// epoch 3.0 boundary to allow block signing.
+///
+/// Test Execution:
+/// A funded account submits an Infer transaction. Once the node has run the inference task, it
+/// proposes a Nakamoto block containing it, and the signers re-run the task to check it.
+///
+/// Test Assertion:
+/// The node's inference task succeeds.
+/// Signers reject the proposed block as containing a bad transaction.
+fn stackerdb_infer_tx_rejected() {
+    if env::var("BITCOIND_TEST") != Ok("1".into()) {
+        return;
+    }
+
+    tracing_subscriber::registry()
+        .with(fmt::layer())
+        .with(EnvFilter::from_default_env())
+        .init();
+
+    info!("------------------------- Test Setup -------------------------");
+    let miner_llm = MockLlmServer::start();
+    let signer_llm = MockLlmServer::with_response("The Earth is flat.");
+    let sender_sk = StacksPrivateKey::new();
+    let sender_addr = to_addr(&sender_sk);
+    let mut signer_test = SignerTest::new_with_config_modifications(
+        5,
+        |naka_conf| {
+            naka_conf.initial_balances.push(InitialBalance {
+                address: sender_addr.into(),
+                amount: 1_000_000,
+            });
+            naka_conf.llm.backend = InferenceBackendName::OpenAi;
+            naka_conf.llm.base_url = miner_llm.base_url();
+            naka_conf.llm.model = "mock".to_string();
+            naka_conf.llm.model_digest = Some(MOCK_MODEL_DIGEST.to_string());
+        },
+        |signer_config| {
+            signer_config.push_str(&format!(
+                "llm_base_url = \"{}\"\nllm_model = \"mock\"\nllm_model_digest = \"{}\"\n",
+                signer_llm.base_url(),
+                MOCK_MODEL_DIGEST
+            ));
+        },
+    );
+    let timeout = Duration::from_secs(200);
+    let short_timeout = Duration::from_secs(30);
+
+    let key = signer_test.boot_to_epoch_3(timeout);
+
+    info!("------------------------- Register Model -------------------------");
+    submit_register_mock_model_tx(&signer_test.running_nodes.conf, &sender_sk, 0);
+    signer_test.mine_and_verify_confirmed_naka_block(&key, timeout);
+    test_observer::clear();
+
+    info!("------------------------- Submit Infer Transaction -------------------------");
+    let txid = submit_infer_tx(
+        &signer_test.running_nodes.conf,
+        &sender_sk,
+        1,
+        "Is the Earth round?",
+    );
+    wait_for_infer_task_status(&txid, "Success", short_timeout);
+
+    info!("------------------------- Test Block Rejected -------------------------");
+    signer_test.mine_nakamoto_block(timeout);
+
+    let t_start = Instant::now();
+    let signer_message = loop {
+        assert!(
+            t_start.elapsed() < short_timeout,
+            "Timed out while waiting for signers block response stacker db event"
+        );
+
+        let nakamoto_blocks = test_observer::get_stackerdb_chunks();
+        if let Some(message) = find_block_response(nakamoto_blocks) {
+            break message;
+        }
+        thread::sleep(Duration::from_secs(1));
+    };
+    if let SignerMessage::BlockResponse(BlockResponse::Rejected(rejection)) = signer_message {
+        assert_eq!(
+            rejection.reason_code,
+            RejectCode::ValidationFailed(ValidateRejectCode::BadTransaction)
+        );
+    } else {
+        panic!("Received unexpected message: {:?}", &signer_message);
+    }
+    assert!(!signer_llm.requests().is_empty());
+
+    signer_test.shutdown();
+}